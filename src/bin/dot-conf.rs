@@ -0,0 +1,338 @@
+use dot_conf_parser::parser::conf;
+use dot_conf_parser::parser::conf::ConfMap;
+use dot_conf_parser::parser::conf::ConfValue;
+use dot_conf_parser::parser::diff::ConfDiff;
+use dot_conf_parser::parser::diff::render_value;
+use dot_conf_parser::parser::schema;
+use dot_conf_parser::parser::schema::ScaffoldStyle;
+use dot_conf_parser::show::Show;
+use dot_conf_parser::show::ShowFmt;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: dot-conf check --schema <schema> <conf>
+       dot-conf show --schema <schema> [--format conf|json|toml|debug|env] \
+                     <conf>
+       dot-conf get --schema <schema> <conf> <key>
+       dot-conf scaffold [--grouped|--annotated] <schema>
+       dot-conf diff --schema <schema> [--format text|json] <running> \
+                     <proposed>
+       dot-conf explain --schema <schema> <conf> <key>
+       dot-conf schema-compat <old schema> <new schema>";
+
+/// exit code for errors reported by the parser
+const EXIT_PARSE_ERROR: u8 = 1;
+/// exit code for invalid command line arguments
+const EXIT_USAGE: u8 = 2;
+/// exit code of `diff` when the files differ. like diff(1), `diff` reports
+/// any trouble with [`EXIT_USAGE`]
+const EXIT_DIFFERENT: u8 = 1;
+/// exit code of `schema-compat` when a change is breaking. like `diff`, it
+/// reports any trouble with [`EXIT_USAGE`]
+const EXIT_BREAKING: u8 = 1;
+
+fn main() -> ExitCode {
+	let args: Vec<String,> = std::env::args().skip(1,).collect();
+	match args.first().map(String::as_str,) {
+		Some("check",) => check(&args[1..],),
+		Some("show",) => show(&args[1..],),
+		Some("get",) => get(&args[1..],),
+		Some("scaffold",) => scaffold(&args[1..],),
+		Some("diff",) => diff(&args[1..],),
+		Some("explain",) => explain(&args[1..],),
+		Some("schema-compat",) => schema_compat(&args[1..],),
+		_ => usage(),
+	}
+}
+
+fn usage() -> ExitCode {
+	eprintln!("{USAGE}");
+	ExitCode::from(EXIT_USAGE,)
+}
+
+/// `--schema <schema>` and the positional arguments of `args`. `None` for
+/// anything else, or when the schema is missing
+fn schema_and_paths<'a,>(
+	args: &'a [String],
+	mut option: impl FnMut(&str, Option<&'a String,>,) -> bool,
+) -> Option<(&'a str, Vec<&'a str,>,),> {
+	let mut schema_path = None;
+	let mut positional = Vec::new();
+	let mut args = args.iter();
+	while let Some(arg,) = args.next() {
+		match arg.as_str() {
+			"--schema" => schema_path = Some(args.next()?.as_str(),),
+			arg if !arg.starts_with('-',) => positional.push(arg,),
+			arg if option(arg, args.next(),) => {},
+			_ => return None,
+		}
+	}
+	Some((schema_path?, positional,),)
+}
+
+/// prints every error of a conf file and the files it includes, one per line
+fn check(args: &[String],) -> ExitCode {
+	let Some((schema_path, paths,),) = schema_and_paths(args, |_, _| false,)
+	else {
+		return usage();
+	};
+	let [conf_path,] = paths.as_slice() else {
+		return usage();
+	};
+
+	let schema = match schema::parse_file(schema_path,) {
+		Ok(schema,) => schema,
+		Err(err,) => {
+			eprintln!("{schema_path}: {err}");
+			return ExitCode::from(EXIT_PARSE_ERROR,);
+		},
+	};
+	match conf::parse_file_all_errors(conf_path, &schema,) {
+		Ok(_,) => ExitCode::SUCCESS,
+		Err(errors,) => {
+			for err in errors {
+				eprintln!("{conf_path}: {err}");
+			}
+			ExitCode::from(EXIT_PARSE_ERROR,)
+		},
+	}
+}
+
+fn show(args: &[String],) -> ExitCode {
+	let mut format = Some(ShowFmt::Conf,);
+	let parsed = schema_and_paths(args, |option, value| {
+		if option != "--format" {
+			return false;
+		}
+		format = match value.map(String::as_str,) {
+			Some("conf",) => Some(ShowFmt::Conf,),
+			Some("json",) => Some(ShowFmt::Json,),
+			Some("toml",) => Some(ShowFmt::Toml,),
+			Some("debug",) => Some(ShowFmt::Debug,),
+			Some("env",) => Some(ShowFmt::Env,),
+			_ => None,
+		};
+		true
+	},);
+	let (Some((schema_path, paths,),), Some(format,),) = (parsed, format,)
+	else {
+		return usage();
+	};
+	let [conf_path,] = paths.as_slice() else {
+		return usage();
+	};
+
+	let Some(conf,) = parse_conf(conf_path, schema_path,) else {
+		return ExitCode::from(EXIT_PARSE_ERROR,);
+	};
+	conf.show_as(format,);
+	ExitCode::SUCCESS
+}
+
+/// prints the value of a key, or the entries of a section in conf format
+fn get(args: &[String],) -> ExitCode {
+	let Some((schema_path, paths,),) = schema_and_paths(args, |_, _| false,)
+	else {
+		return usage();
+	};
+	let [conf_path, key,] = paths.as_slice() else {
+		return usage();
+	};
+
+	let Some(conf,) = parse_conf(conf_path, schema_path,) else {
+		return ExitCode::from(EXIT_PARSE_ERROR,);
+	};
+	match conf.get(key,) {
+		Some(ConfValue::Scalar(value,),) => println!("{}", render_value(value)),
+		Some(ConfValue::Map(children,),) => {
+			ConfMap::from(children,).show_as(ShowFmt::Conf,)
+		},
+		None => {
+			eprintln!("{conf_path}: '{key}' is not set");
+			return ExitCode::from(EXIT_PARSE_ERROR,);
+		},
+	}
+	ExitCode::SUCCESS
+}
+
+fn scaffold(args: &[String],) -> ExitCode {
+	let mut style = ScaffoldStyle::Compact;
+	let mut schema_path = None;
+	for arg in args {
+		match arg.as_str() {
+			"--grouped" => style = ScaffoldStyle::Grouped,
+			"--annotated" => style = ScaffoldStyle::Annotated,
+			path if schema_path.is_none() && !path.starts_with('-',) => {
+				schema_path = Some(path,)
+			},
+			_ => return usage(),
+		}
+	}
+
+	let Some(schema_path,) = schema_path else {
+		return usage();
+	};
+	match schema::parse_file(schema_path,) {
+		Ok(schema,) => {
+			print!("{}", schema::scaffold(&schema, style));
+			ExitCode::SUCCESS
+		},
+		Err(err,) => {
+			eprintln!("{schema_path}: {err}");
+			ExitCode::from(EXIT_PARSE_ERROR,)
+		},
+	}
+}
+
+#[derive(PartialEq,)]
+enum DiffFormat {
+	Text,
+	Json,
+}
+
+fn diff(args: &[String],) -> ExitCode {
+	let mut schema_path = None;
+	let mut format = DiffFormat::Text;
+	let mut paths = Vec::new();
+	let mut args = args.iter();
+	while let Some(arg,) = args.next() {
+		match arg.as_str() {
+			"--schema" => match args.next() {
+				Some(path,) => schema_path = Some(path.as_str(),),
+				None => return usage(),
+			},
+			"--format" => match args.next().map(String::as_str,) {
+				Some("text",) => format = DiffFormat::Text,
+				Some("json",) => format = DiffFormat::Json,
+				_ => return usage(),
+			},
+			path if !path.starts_with('-',) => paths.push(path,),
+			_ => return usage(),
+		}
+	}
+
+	let (Some(schema_path,), [running, proposed,],) =
+		(schema_path, paths.as_slice(),)
+	else {
+		return usage();
+	};
+	let (running, proposed,) = match (
+		parse_conf(running, schema_path,),
+		parse_conf(proposed, schema_path,),
+	) {
+		(Some(running,), Some(proposed,),) => (running, proposed,),
+		_ => return ExitCode::from(EXIT_USAGE,),
+	};
+
+	let diffs = running.diff(&proposed,);
+	match format {
+		DiffFormat::Text => {
+			let color = std::io::stdout().is_terminal()
+				&& std::env::var_os("NO_COLOR",).is_none();
+			for diff in &diffs {
+				print_diff_line(diff, color,);
+			}
+		},
+		DiffFormat::Json => println!("{}", diffs_as_json(&diffs)),
+	}
+
+	if diffs.is_empty() {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::from(EXIT_DIFFERENT,)
+	}
+}
+
+/// prints every assignment of a key, oldest first, marking the one in effect
+fn explain(args: &[String],) -> ExitCode {
+	let mut schema_path = None;
+	let mut positional = Vec::new();
+	let mut args = args.iter();
+	while let Some(arg,) = args.next() {
+		match arg.as_str() {
+			"--schema" => match args.next() {
+				Some(path,) => schema_path = Some(path.as_str(),),
+				None => return usage(),
+			},
+			arg if !arg.starts_with('-',) => positional.push(arg,),
+			_ => return usage(),
+		}
+	}
+
+	let (Some(schema_path,), [conf_path, key,],) =
+		(schema_path, positional.as_slice(),)
+	else {
+		return usage();
+	};
+	let Some(conf,) = parse_conf(conf_path, schema_path,) else {
+		return ExitCode::from(EXIT_PARSE_ERROR,);
+	};
+
+	let history = conf.history_of(key,);
+	if history.is_empty() {
+		eprintln!("{conf_path}: '{key}' is not set");
+		return ExitCode::from(EXIT_PARSE_ERROR,);
+	}
+	for (idx, source,) in history.iter().enumerate() {
+		if idx + 1 == history.len() {
+			println!("{source} (effective)");
+		} else {
+			println!("{source} (overridden)");
+		}
+	}
+	ExitCode::SUCCESS
+}
+
+/// prints every difference between two schemas, one per line
+fn schema_compat(args: &[String],) -> ExitCode {
+	let [old_path, new_path,] = args else {
+		return usage();
+	};
+	if old_path.starts_with('-',) || new_path.starts_with('-',) {
+		return usage();
+	}
+
+	let parse = |path: &str| {
+		schema::parse_file(path,)
+			.inspect_err(|err| eprintln!("{path}: {err:#}"),)
+			.ok()
+	};
+	let (Some(old,), Some(new,),) = (parse(old_path,), parse(new_path,),)
+	else {
+		return ExitCode::from(EXIT_USAGE,);
+	};
+
+	let report = schema::compat_check(&old, &new,);
+	print!("{report}");
+	if report.is_compatible() {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::from(EXIT_BREAKING,)
+	}
+}
+
+/// reports errors with the verbose error format, prefixed by `path`
+fn parse_conf(path: &str, schema_path: &str,) -> Option<ConfMap,> {
+	conf::parse_file(path, schema_path,)
+		.inspect_err(|err| eprintln!("{path}: {err:#}"),)
+		.ok()
+}
+
+fn print_diff_line(diff: &ConfDiff, color: bool,) {
+	if !color {
+		println!("{diff}");
+		return;
+	}
+
+	let code = match diff {
+		ConfDiff::Added { .. } => "32",
+		ConfDiff::Removed { .. } => "31",
+		ConfDiff::Changed { .. } => "33",
+	};
+	println!("\x1b[{code}m{diff}\x1b[0m");
+}
+
+fn diffs_as_json(diffs: &[ConfDiff],) -> String {
+	let entries: Vec<String,> = diffs.iter().map(ConfDiff::to_json,).collect();
+	format!("[{}]", entries.join(",",))
+}
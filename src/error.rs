@@ -1,76 +1,1421 @@
+use crate::parser::conf::PathRequirement;
 use crate::parser::conf::SingleValueDiscriminants;
+use std::path::PathBuf;
 
 #[derive(Debug,)]
 pub enum ParseError {
 	Io(std::io::Error,),
-	/// missing `=`  or `->`
+	/// none of the `expected` delimiters, `=` in conf files and `->` in
+	/// schemas unless the parse options name others. `column` is where the
+	/// line starts. columns are 1-based and count chars, and are 0 along with
+	/// the line for errors about values which were not read from text
 	MissingDelimiter {
-		line: usize,
+		line:     usize,
+		column:   usize,
+		expected: Vec<String,>,
 	},
+	/// `column` is where the key should start
 	EmptyKey {
-		line: usize,
+		line:   usize,
+		column: usize,
 	},
+	/// `column` is right after the delimiter
 	EmptyValue {
-		line: usize,
+		line:   usize,
+		column: usize,
 	},
 	InvalidKeySegment {
 		segment: String,
 		line:    usize,
+		column:  usize,
 	},
 	/// case of declarating a certain key multiple times
 	ConflictingTypes {
-		key:  String,
-		line: usize,
+		key:    String,
+		line:   usize,
+		column: usize,
+		/// files of the two declarations, for schemas merged from several
+		origin: Option<(PathBuf, PathBuf,),>,
 	},
 	InvalidValue {
+		key:      String,
+		value:    String,
+		ty:       SingleValueDiscriminants,
+		line:     usize,
+		column:   usize,
+		/// label of the offending element of a labeled tuple
+		element:  Option<String,>,
+		/// 1-based index of the offending element of a collection, and the
+		/// number of its elements
+		position: Option<(usize, usize,),>,
+	},
+	UnknownKey {
+		key:        String,
+		lines:      Vec<usize,>,
+		/// the closest key the schema declares at the same level, when it
+		/// is close enough to be a typo
+		suggestion: Option<String,>,
+	},
+	/// number which does not parse because it is written with a thousands
+	/// separator or a decimal comma. `suggestion` is how to write it
+	LocalizedNumber {
+		key:        String,
+		value:      String,
+		ty:         SingleValueDiscriminants,
+		line:       usize,
+		separator:  Separator,
+		suggestion: String,
+	},
+	/// number outside the range declared for its key, as in
+	/// `Integer[1..65535]`, or an integer which does not fit `ty` at all. a
+	/// bound is `None` when the range is open on that side
+	OutOfRange {
 		key:   String,
 		value: String,
 		ty:    SingleValueDiscriminants,
+		min:   Option<String,>,
+		max:   Option<String,>,
 		line:  usize,
 	},
-	UnknownKey {
-		key:   String,
-		lines: Vec<usize,>,
+	/// value of a key declared `Enum(...)` which is none of its literals
+	InvalidEnumValue {
+		key:     String,
+		value:   String,
+		allowed: Vec<String,>,
+		line:    usize,
+	},
+	/// schema declaration which is malformed beyond an unknown type name
+	InvalidDeclaration {
+		key:         String,
+		declaration: String,
+		line:        usize,
+	},
+	/// schema declaration naming a type this version does not know
+	UnknownType {
+		key:  String,
+		name: String,
+		line: usize,
+	},
+	/// quoted value without its closing quote
+	UnterminatedString {
+		line: usize,
+	},
+	/// `[section]` line with nothing or no valid key between its brackets,
+	/// or without its closing bracket
+	InvalidSectionHeader {
+		line: usize,
+	},
+	/// text after the closing quote of a quoted value. `column` is 1-based
+	TrailingCharacters {
+		line:   usize,
+		column: usize,
+		text:   String,
+	},
+	/// resolver of a `@resolver(arg)` placeholder returned an error
+	ResolverFailed {
+		key:      String,
+		resolver: String,
+		reason:   String,
+	},
+	/// placeholders left after resolution, for want of a resolver
+	Unresolved {
+		keys: Vec<String,>,
+	},
+	/// keys declared by the schema but not set, for strict parsing
+	MissingKey {
+		keys: Vec<String,>,
+	},
+	/// conf file setting both a key and the key a migration renames it to
+	MigrationConflict {
+		old_key:  String,
+		old_line: usize,
+		new_key:  String,
+		new_line: usize,
+	},
+	/// line of a migrations file which is not a known rule
+	InvalidMigration {
+		rule: String,
+		line: usize,
+	},
+	/// key declared more than once where redeclaration is not allowed
+	DuplicateKey {
+		key:         String,
+		first_line:  usize,
+		second_line: usize,
+	},
+	/// `@include` of a file which is already being read
+	CircularInclude {
+		path: PathBuf,
+		line: usize,
+	},
+	/// `error` found in the included file at `path`, whose lines it refers
+	/// to
+	InFile {
+		path:  PathBuf,
+		error: Box<ParseError,>,
+	},
+	/// value set by both maps of a merge which allows no conflicts
+	MergeConflict {
+		key: String,
+	},
+	/// schema of `plugin` mounted under a prefix which equals, contains or
+	/// is contained in the one `other` was mounted under before
+	MountConflict {
+		plugin:       String,
+		prefix:       String,
+		other:        String,
+		other_prefix: String,
 	},
+	/// `Path` value failing the requirement it was validated against
+	UnmetPathRequirement {
+		key:         String,
+		path:        PathBuf,
+		requirement: PathRequirement,
+	},
+	/// value of a shape a conf map cannot hold, met while serializing into
+	/// one. `key` is empty for the value as a whole
+	Unserializable {
+		key:    String,
+		reason: String,
+	},
+	/// json `null` where a value of `key` was expected
+	NullValue {
+		key: String,
+	},
+	/// collection value with more or fewer elements than the tuple its
+	/// schema declares
+	ArityMismatch {
+		key:      String,
+		expected: usize,
+		found:    usize,
+		line:     usize,
+	},
+	/// `key += value` for a key which is not a list declared repeatable
+	NotRepeatable {
+		key:  String,
+		line: usize,
+	},
+}
+
+/// separator a [`ParseError::LocalizedNumber`] was written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq,)]
+pub enum Separator {
+	/// `1.000` or `1,000,000`
+	Thousands,
+	/// `3,14`
+	DecimalComma,
+}
+
+impl std::fmt::Display for Separator {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			Separator::Thousands => write!(f, "a thousands separator"),
+			Separator::DecimalComma => write!(f, "a decimal comma"),
+		}
+	}
+}
+
+/// category of a [`ParseError`], to branch on without matching every
+/// variant
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash,)]
+pub enum ErrorKind {
+	/// reading a file failed
+	Io,
+	/// text which is not well formed, before any schema is looked at
+	Syntax,
+	/// value which does not fit the type or constraints declared for it
+	Type,
+	/// malformed schema, or a key it does not declare
+	Schema,
+	/// two declarations, assignments or mounts which cannot both hold
+	Conflict,
+	/// key the schema requires which is not set
+	Missing,
+	/// placeholder which could not be resolved
+	Resolve,
 }
 
+/// `{}` renders a single line, embedded line breaks escaped, so errors can
+/// be logged as they are. `{:#}` appends indented notes and hints below it.
+/// both start with the [`ParseError::code`] in brackets
 impl std::fmt::Display for ParseError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		let mut headline = format!("[{}] ", self.code());
+		self.write_headline(&mut headline,)?;
+
+		if !f.alternate() {
+			return f.write_str(&single_line(&headline,),);
+		}
+
+		f.write_str(&headline,)?;
+		for detail in self.details() {
+			write!(f, "\n  {detail}")?;
+		}
+		Ok((),)
+	}
+}
+
+impl ParseError {
+	fn write_headline(
+		&self, f: &mut impl std::fmt::Write,
+	) -> std::fmt::Result {
 		match self {
 			ParseError::Io(err,) => write!(f, "I/O error: {err}"),
-			ParseError::MissingDelimiter { line, } => {
-				write!(f, "missing delimiter on line {line}")
+			ParseError::MissingDelimiter { line, column, expected, } => {
+				write!(f, "missing delimiter on line {line}, column {column}")?;
+				let quoted: Vec<String,> =
+					expected.iter().map(|sep| format!("'{sep}'"),).collect();
+				match quoted.split_last() {
+					None => Ok((),),
+					Some((last, [],),) => write!(f, "; expected {last}"),
+					Some((last, rest,),) => {
+						write!(f, "; expected {} or {last}", rest.join(", ",))
+					},
+				}
 			},
-			ParseError::EmptyKey { line, } => {
-				write!(f, "empty key on line {line}")
+			ParseError::EmptyKey { line, column, } => {
+				write!(f, "empty key on line {line}, column {column}")
 			},
-			ParseError::EmptyValue { line, } => {
-				write!(f, "empty value on line {line}")
+			ParseError::EmptyValue { line, column, } => {
+				write!(f, "empty value on line {line}, column {column}")
+			},
+			ParseError::InvalidKeySegment { segment, line, column, } => {
+				write!(
+					f,
+					"invalid key segment '{segment}' on line {line}, column \
+					 {column}"
+				)
+			},
+			ParseError::ConflictingTypes {
+				key,
+				origin: Some((first, second,),),
+				..
+			} => {
+				write!(
+					f,
+					"conflicting definitions for '{key}' in '{}' and '{}'",
+					first.display(),
+					second.display()
+				)
 			},
-			ParseError::InvalidKeySegment { segment, line, } => {
-				write!(f, "invalid key segment '{segment}' on line {line}")
+			ParseError::ConflictingTypes { key, line, column, .. } => {
+				write!(
+					f,
+					"conflicting definitions for '{key}' on line {line}, \
+					 column {column}"
+				)
 			},
-			ParseError::ConflictingTypes { key, line, } => {
-				write!(f, "conflicting definitions for '{key}' on line {line}")
+			ParseError::InvalidValue {
+				key,
+				value,
+				ty,
+				line,
+				column,
+				element: None,
+				position: Some((index, count,),),
+			} => {
+				write!(
+					f,
+					"invalid value '{value}' (element {index} of {count}) \
+					 while expecting {ty} for '{key}' on line {line}, column \
+					 {column}"
+				)
 			},
-			ParseError::InvalidValue { key, value, ty, line, } => {
+			ParseError::InvalidValue {
+				key,
+				value,
+				ty,
+				line,
+				column,
+				element: None,
+				..
+			} => {
 				write!(
 					f,
 					"invalid value '{value}' while expecting {ty} for '{key}' \
-					 on line {line}"
+					 on line {line}, column {column}"
 				)
 			},
-			ParseError::UnknownKey { key, lines, } => {
-				write!(f, "unknown key '{key}' on line {lines:?}")
+			ParseError::InvalidValue {
+				key,
+				value,
+				ty,
+				line,
+				column,
+				element: Some(element,),
+				..
+			} => {
+				write!(
+					f,
+					"invalid value '{value}' while expecting {ty} for element \
+					 '{element}' of '{key}' on line {line}, column {column}"
+				)
+			},
+			ParseError::InvalidDeclaration { key, declaration, line, } => {
+				write!(
+					f,
+					"invalid declaration '{declaration}' for '{key}' on line \
+					 {line}"
+				)
+			},
+			ParseError::UnknownType { key, name, line, } => {
+				let valid: Vec<String,> =
+					SingleValueDiscriminants::declarable()
+						.map(|kind| kind.to_string(),)
+						.collect();
+				write!(
+					f,
+					"unknown type '{name}' for '{key}' on line {line}; valid \
+					 types are: {}",
+					valid.join(", ",)
+				)
+			},
+			ParseError::UnknownKey { key, lines, suggestion, } => {
+				write!(f, "unknown key '{key}' on line {lines:?}")?;
+				match suggestion {
+					Some(suggestion,) => {
+						write!(f, "; did you mean '{suggestion}'?")
+					},
+					None => Ok((),),
+				}
+			},
+			ParseError::LocalizedNumber {
+				key,
+				value,
+				line,
+				separator,
+				suggestion,
+				..
+			} => {
+				write!(
+					f,
+					"value '{value}' for '{key}' on line {line} looks like it \
+					 uses {separator}; write {suggestion}"
+				)
+			},
+			ParseError::OutOfRange { key, value, ty, min, max, line, } => {
+				write!(
+					f,
+					"value '{value}' for '{key}' on line {line} is out of \
+					 range for {ty}[{}..{}]",
+					min.as_deref().unwrap_or_default(),
+					max.as_deref().unwrap_or_default()
+				)
+			},
+			ParseError::InvalidEnumValue { key, value, allowed, line, } => {
+				write!(
+					f,
+					"invalid value '{value}' for '{key}' on line {line}, \
+					 expected one of {}",
+					allowed.join(", ",)
+				)
+			},
+			ParseError::UnterminatedString { line, } => {
+				write!(f, "unterminated string on line {line}")
+			},
+			ParseError::InvalidSectionHeader { line, } => {
+				write!(f, "invalid section header on line {line}")
+			},
+			ParseError::TrailingCharacters { line, column, text, } => {
+				write!(
+					f,
+					"unexpected '{text}' after closing quote on line {line}, \
+					 column {column}"
+				)
+			},
+			ParseError::ResolverFailed { key, resolver, reason, } => {
+				write!(f, "resolver '{resolver}' failed for '{key}': {reason}")
+			},
+			ParseError::Unresolved { keys, } => {
+				write!(f, "unresolved placeholders for {}", keys.join(", "))
+			},
+			ParseError::MissingKey { keys, } => {
+				write!(f, "missing keys {}", keys.join(", "))
+			},
+			ParseError::MigrationConflict {
+				old_key,
+				old_line,
+				new_key,
+				new_line,
+			} => {
+				write!(
+					f,
+					"'{old_key}' on line {old_line} and its replacement \
+					 '{new_key}' on line {new_line} are both set"
+				)
+			},
+			ParseError::InvalidMigration { rule, line, } => {
+				write!(f, "invalid migration rule '{rule}' on line {line}")
+			},
+			ParseError::DuplicateKey { key, first_line, second_line, } => {
+				write!(
+					f,
+					"duplicate key '{key}' on lines {first_line} and \
+					 {second_line}"
+				)
+			},
+			ParseError::CircularInclude { path, line, } => {
+				write!(
+					f,
+					"circular include of '{}' on line {line}",
+					path.display()
+				)
+			},
+			ParseError::InFile { path, error, } => {
+				error.write_headline(f,)?;
+				write!(f, " in '{}'", path.display())
+			},
+			ParseError::MergeConflict { key, } => {
+				write!(f, "'{key}' is set by both merged maps")
+			},
+			ParseError::MountConflict {
+				plugin,
+				prefix,
+				other,
+				other_prefix,
+			} => {
+				write!(
+					f,
+					"'{plugin}' mounted at '{prefix}' collides with '{other}' \
+					 mounted at '{other_prefix}'"
+				)
+			},
+			ParseError::UnmetPathRequirement { key, path, requirement, } => {
+				write!(f, "'{}' for '{key}' must {requirement}", path.display())
+			},
+			ParseError::Unserializable { key, reason, } if key.is_empty() => {
+				write!(f, "cannot serialize into a conf map: {reason}")
+			},
+			ParseError::Unserializable { key, reason, } => {
+				write!(f, "cannot serialize '{key}' into a conf map: {reason}")
+			},
+			ParseError::NullValue { key, } => {
+				write!(f, "null value for '{key}'")
+			},
+			ParseError::ArityMismatch { key, expected, found, line, } => {
+				write!(
+					f,
+					"expected {expected} elements for '{key}' on line {line}, \
+					 found {found}"
+				)
+			},
+			ParseError::NotRepeatable { key, line, } => {
+				write!(
+					f,
+					"'+=' on line {line} appends to '{key}', which is not a \
+					 repeatable list"
+				)
 			},
 		}
 	}
+
+	/// first line of the parsed text the error points at. `None` for errors
+	/// which are not about a line, and for those inside an included file
+	pub fn line(&self,) -> Option<usize,> {
+		match self {
+			ParseError::MissingDelimiter { line, .. }
+			| ParseError::EmptyKey { line, .. }
+			| ParseError::EmptyValue { line, .. }
+			| ParseError::InvalidKeySegment { line, .. }
+			| ParseError::ConflictingTypes { line, .. }
+			| ParseError::InvalidValue { line, .. }
+			| ParseError::LocalizedNumber { line, .. }
+			| ParseError::OutOfRange { line, .. }
+			| ParseError::InvalidEnumValue { line, .. }
+			| ParseError::InvalidDeclaration { line, .. }
+			| ParseError::UnknownType { line, .. }
+			| ParseError::UnterminatedString { line, }
+			| ParseError::InvalidSectionHeader { line, }
+			| ParseError::TrailingCharacters { line, .. }
+			| ParseError::InvalidMigration { line, .. }
+			| ParseError::CircularInclude { line, .. }
+			| ParseError::ArityMismatch { line, .. }
+			| ParseError::NotRepeatable { line, .. } => Some(*line,),
+			ParseError::UnknownKey { lines, .. } => lines.iter().min().copied(),
+			ParseError::MigrationConflict { old_line, new_line, .. } => {
+				Some(*old_line.min(new_line,),)
+			},
+			ParseError::DuplicateKey { first_line, .. } => Some(*first_line,),
+			ParseError::Io(_,)
+			| ParseError::ResolverFailed { .. }
+			| ParseError::Unresolved { .. }
+			| ParseError::MissingKey { .. }
+			| ParseError::InFile { .. }
+			| ParseError::MergeConflict { .. }
+			| ParseError::MountConflict { .. }
+			| ParseError::UnmetPathRequirement { .. }
+			| ParseError::Unserializable { .. }
+			| ParseError::NullValue { .. } => None,
+		}
+	}
+
+	/// key the error is about. `None` for errors about no key or about
+	/// several. a [`ParseError::MigrationConflict`] gives the key being
+	/// renamed
+	pub fn key(&self,) -> Option<&str,> {
+		match self {
+			ParseError::ConflictingTypes { key, .. }
+			| ParseError::InvalidValue { key, .. }
+			| ParseError::UnknownKey { key, .. }
+			| ParseError::LocalizedNumber { key, .. }
+			| ParseError::OutOfRange { key, .. }
+			| ParseError::InvalidEnumValue { key, .. }
+			| ParseError::InvalidDeclaration { key, .. }
+			| ParseError::UnknownType { key, .. }
+			| ParseError::ResolverFailed { key, .. }
+			| ParseError::DuplicateKey { key, .. }
+			| ParseError::MergeConflict { key, }
+			| ParseError::UnmetPathRequirement { key, .. }
+			| ParseError::Unserializable { key, .. }
+			| ParseError::NullValue { key, }
+			| ParseError::ArityMismatch { key, .. }
+			| ParseError::NotRepeatable { key, .. }
+			| ParseError::MigrationConflict { old_key: key, .. } => Some(key,),
+			ParseError::InFile { error, .. } => error.key(),
+			ParseError::Io(_,)
+			| ParseError::MissingDelimiter { .. }
+			| ParseError::EmptyKey { .. }
+			| ParseError::EmptyValue { .. }
+			| ParseError::InvalidKeySegment { .. }
+			| ParseError::UnterminatedString { .. }
+			| ParseError::InvalidSectionHeader { .. }
+			| ParseError::TrailingCharacters { .. }
+			| ParseError::Unresolved { .. }
+			| ParseError::MissingKey { .. }
+			| ParseError::InvalidMigration { .. }
+			| ParseError::CircularInclude { .. }
+			| ParseError::MountConflict { .. } => None,
+		}
+	}
+
+	/// category of the error. an error in an included file has the kind of
+	/// the error found there
+	pub fn kind(&self,) -> ErrorKind {
+		match self {
+			ParseError::Io(_,) => ErrorKind::Io,
+			ParseError::MissingDelimiter { .. }
+			| ParseError::EmptyKey { .. }
+			| ParseError::EmptyValue { .. }
+			| ParseError::InvalidKeySegment { .. }
+			| ParseError::UnterminatedString { .. }
+			| ParseError::InvalidSectionHeader { .. }
+			| ParseError::TrailingCharacters { .. }
+			| ParseError::InvalidMigration { .. }
+			| ParseError::CircularInclude { .. } => ErrorKind::Syntax,
+			ParseError::InvalidValue { .. }
+			| ParseError::LocalizedNumber { .. }
+			| ParseError::OutOfRange { .. }
+			| ParseError::InvalidEnumValue { .. }
+			| ParseError::UnmetPathRequirement { .. }
+			| ParseError::Unserializable { .. }
+			| ParseError::NullValue { .. }
+			| ParseError::ArityMismatch { .. } => ErrorKind::Type,
+			ParseError::UnknownKey { .. }
+			| ParseError::InvalidDeclaration { .. }
+			| ParseError::UnknownType { .. }
+			| ParseError::NotRepeatable { .. } => ErrorKind::Schema,
+			ParseError::ConflictingTypes { .. }
+			| ParseError::MigrationConflict { .. }
+			| ParseError::DuplicateKey { .. }
+			| ParseError::MergeConflict { .. }
+			| ParseError::MountConflict { .. } => ErrorKind::Conflict,
+			ParseError::MissingKey { .. } => ErrorKind::Missing,
+			ParseError::ResolverFailed { .. }
+			| ParseError::Unresolved { .. } => ErrorKind::Resolve,
+			ParseError::InFile { error, .. } => error.kind(),
+		}
+	}
+
+	/// short code which stays the same across versions. a new variant takes
+	/// the next free number. an error in an included file has the code of
+	/// the error found there
+	pub fn code(&self,) -> &'static str {
+		match self {
+			ParseError::Io(_,) => "E001",
+			ParseError::MissingDelimiter { .. } => "E002",
+			ParseError::EmptyKey { .. } => "E003",
+			ParseError::EmptyValue { .. } => "E004",
+			ParseError::InvalidKeySegment { .. } => "E005",
+			ParseError::ConflictingTypes { .. } => "E006",
+			ParseError::InvalidValue { .. } => "E007",
+			ParseError::UnknownKey { .. } => "E008",
+			ParseError::LocalizedNumber { .. } => "E009",
+			ParseError::OutOfRange { .. } => "E010",
+			ParseError::InvalidEnumValue { .. } => "E011",
+			ParseError::InvalidDeclaration { .. } => "E012",
+			ParseError::UnknownType { .. } => "E013",
+			ParseError::UnterminatedString { .. } => "E014",
+			ParseError::InvalidSectionHeader { .. } => "E015",
+			ParseError::TrailingCharacters { .. } => "E016",
+			ParseError::ResolverFailed { .. } => "E017",
+			ParseError::Unresolved { .. } => "E018",
+			ParseError::MissingKey { .. } => "E019",
+			ParseError::MigrationConflict { .. } => "E020",
+			ParseError::InvalidMigration { .. } => "E021",
+			ParseError::DuplicateKey { .. } => "E022",
+			ParseError::CircularInclude { .. } => "E023",
+			ParseError::MergeConflict { .. } => "E024",
+			ParseError::MountConflict { .. } => "E025",
+			ParseError::UnmetPathRequirement { .. } => "E026",
+			ParseError::Unserializable { .. } => "E027",
+			ParseError::NullValue { .. } => "E028",
+			ParseError::ArityMismatch { .. } => "E029",
+			ParseError::NotRepeatable { .. } => "E030",
+			ParseError::InFile { error, .. } => error.code(),
+		}
+	}
+
+	/// 1-based column, in chars, the error points at on [`Self::line`].
+	/// `None` for errors which only know their line
+	pub fn column(&self,) -> Option<usize,> {
+		match self {
+			ParseError::MissingDelimiter { column, .. }
+			| ParseError::EmptyKey { column, .. }
+			| ParseError::EmptyValue { column, .. }
+			| ParseError::InvalidKeySegment { column, .. }
+			| ParseError::ConflictingTypes { column, .. }
+			| ParseError::InvalidValue { column, .. }
+			| ParseError::TrailingCharacters { column, .. } => Some(*column,),
+			_ => None,
+		}
+	}
+
+	/// the plain headline followed by the line of `source` the error points
+	/// at, with the offending text underlined, like
+	///
+	/// ```text
+	/// invalid value 'x' while expecting Integer for 'port' on line 2, column 8
+	///   |
+	/// 2 | port = x
+	///   |        ^
+	/// ```
+	///
+	/// `source` is the text which was parsed. tabs before the underline are
+	/// kept so it lines up however they are displayed. the headline is
+	/// returned alone when the line is not in `source`
+	pub fn render_with_source(&self, source: &str,) -> String {
+		let mut rendered = self.to_string();
+		let Some(line_no,) = self.line() else {
+			return rendered;
+		};
+		let Some(line,) =
+			line_no.checked_sub(1,).and_then(|idx| source.lines().nth(idx,),)
+		else {
+			return rendered;
+		};
+
+		let line = line.trim_end();
+		let len = line.chars().count();
+		let start = match self.column() {
+			Some(column,) => column.clamp(1, len + 1,) - 1,
+			None => line.chars().take_while(|ch| ch.is_whitespace(),).count(),
+		};
+		let rest: String = line.chars().skip(start,).collect();
+		let marked = self.marked_len(&rest,).clamp(1, (len - start).max(1,),);
+
+		let gutter = " ".repeat(line_no.to_string().len(),);
+		let indent: String = line
+			.chars()
+			.take(start,)
+			.map(|ch| if ch == '\t' { '\t' } else { ' ' },)
+			.collect();
+		rendered.push_str(&format!(
+			"\n{gutter} |\n{line_no} | {line}\n{gutter} | {indent}{}",
+			"^".repeat(marked,)
+		),);
+		rendered
+	}
+
+	/// chars to underline in `rest`, the line from where the error points
+	fn marked_len(&self, rest: &str,) -> usize {
+		let to_end = || {
+			let content = &rest[..crate::parser::core::inline_comment_start(
+				rest,
+				&crate::parser::core::COMMENT_CHARS,
+			)
+			.unwrap_or(rest.len(),)];
+			content.trim_end().chars().count()
+		};
+		match self {
+			ParseError::EmptyKey { .. } | ParseError::EmptyValue { .. } => 1,
+			ParseError::InvalidKeySegment { segment, .. } => {
+				segment.chars().count()
+			},
+			ParseError::ConflictingTypes { key, .. } => key.chars().count(),
+			ParseError::TrailingCharacters { text, .. } => text.chars().count(),
+			_ => to_end(),
+		}
+	}
+
+	/// lines following the headline in the verbose report
+	fn details(&self,) -> Vec<String,> {
+		match self {
+			ParseError::Io(err,) => vec![format!("= note: {:?}", err.kind())],
+			ParseError::MissingDelimiter { .. } => vec![
+				"= help: write a delimiter between key and value".to_string(),
+			],
+			ParseError::EmptyKey { .. } => {
+				vec!["= help: write a key before the delimiter".to_string()]
+			},
+			ParseError::EmptyValue { .. } => vec![
+				"= help: write a value after the delimiter or remove the line"
+					.to_string(),
+			],
+			ParseError::InvalidKeySegment { segment, .. }
+				if segment.contains('=',) || segment.contains("->",) =>
+			{
+				vec!["= help: keys cannot contain '=' or '->'".to_string()]
+			},
+			ParseError::InvalidKeySegment { segment, .. }
+				if segment.is_empty() =>
+			{
+				vec![
+					"= help: separate key segments with a single '.'"
+						.to_string(),
+				]
+			},
+			ParseError::InvalidKeySegment { .. } => vec![
+				"= help: write key segments with letters, digits, '_' and \
+				 '-', or parse with a wider `SegmentCharset`"
+					.to_string(),
+			],
+			ParseError::ConflictingTypes { key, origin: Some(_,), .. } => {
+				vec![format!("= note: each file declares '{key}' differently")]
+			},
+			ParseError::ConflictingTypes { key, line, column, .. } => vec![
+				format!("--> line {line}, column {column}"),
+				format!(
+					"= note: '{key}' is used both as a value and as a section"
+				),
+			],
+			ParseError::InvalidValue { value, ty, line, column, .. } => vec![
+				format!("--> line {line}, column {column}"),
+				format!("= note: '{value}' cannot be read as {ty}"),
+			],
+			ParseError::LocalizedNumber { ty, line, .. } => vec![
+				format!("--> line {line}"),
+				format!(
+					"= note: {ty} values are written without grouping, with \
+					 '.' before any decimals"
+				),
+			],
+			ParseError::OutOfRange { line, .. } => vec![
+				format!("--> line {line}"),
+				"= note: both bounds are included".to_string(),
+			],
+			ParseError::UnknownKey { key, lines, .. } => lines
+				.iter()
+				.map(|line| format!("--> line {line}"),)
+				.chain([format!("= help: declare '{key}' in the schema"),],)
+				.collect(),
+			ParseError::InvalidDeclaration { declaration, line, .. }
+				if declaration.contains("->",) =>
+			{
+				vec![
+					format!("--> line {line}"),
+					"= help: declare a single type after '->'".to_string(),
+				]
+			},
+			ParseError::InvalidEnumValue { line, .. } => vec![
+				format!("--> line {line}"),
+				"= help: write one of the literals exactly, case included"
+					.to_string(),
+			],
+			ParseError::InvalidDeclaration { declaration, line, .. }
+				if declaration.ends_with("(repeatable)",) =>
+			{
+				vec![
+					format!("--> line {line}"),
+					"= help: only lists repeat, declare the key as '[Type] \
+					 (repeatable)'"
+						.to_string(),
+				]
+			},
+			ParseError::InvalidDeclaration { declaration, line, .. }
+				if declaration.starts_with("Enum(",) =>
+			{
+				vec![
+					format!("--> line {line}"),
+					"= help: list distinct, non-empty literals, as in \
+					 'Enum(debug, info)'"
+						.to_string(),
+				]
+			},
+			ParseError::InvalidDeclaration { declaration, line, .. }
+				if declaration.starts_with('[',)
+					&& declaration.contains(',',) =>
+			{
+				vec![
+					format!("--> line {line}"),
+					"= help: a list holds one type, as in '[Integer]'. \
+					 declare a tuple as 'Integer, Bool'"
+						.to_string(),
+				]
+			},
+			ParseError::InvalidDeclaration { declaration, line, .. }
+				if declaration.contains('[',) =>
+			{
+				vec![
+					format!("--> line {line}"),
+					"= help: bound a number type as in 'Integer[1..65535]', \
+					 with the minimum at most the maximum. either may be left \
+					 out"
+					.to_string(),
+				]
+			},
+			ParseError::InvalidDeclaration { declaration, line, .. }
+				if declaration.starts_with('(',) =>
+			{
+				vec![
+					format!("--> line {line}"),
+					"= help: label every element of a tuple as 'label: Type', \
+					 each label once"
+						.to_string(),
+				]
+			},
+			ParseError::InvalidDeclaration { line, .. } => vec![
+				format!("--> line {line}"),
+				"= help: declare a type as 'Integer', a list as '[Integer]' \
+				 or a tuple as 'Integer, Bool'"
+					.to_string(),
+			],
+			ParseError::UnknownType { line, .. } => vec![
+				format!("--> line {line}"),
+				"= help: read schemas of newer versions with \
+				 `parse_str_forward_compat`"
+					.to_string(),
+			],
+			ParseError::UnterminatedString { line, } => vec![
+				format!("--> line {line}"),
+				"= help: close the value with '\"', escaping quotes inside it \
+				 as '\\\"'"
+					.to_string(),
+			],
+			ParseError::InvalidSectionHeader { line, } => vec![
+				format!("--> line {line}"),
+				"= help: name the section between brackets, as in \
+				 '[server.tls]'"
+					.to_string(),
+			],
+			ParseError::TrailingCharacters { line, column, .. } => vec![
+				format!("--> line {line}, column {column}"),
+				"= help: start an inline comment with '#' or ';'".to_string(),
+			],
+			ParseError::ResolverFailed { resolver, .. } => vec![format!(
+				"= help: check the argument passed to '{resolver}'"
+			)],
+			ParseError::Unresolved { .. } => vec![
+				"= help: register a resolver for every placeholder".to_string(),
+			],
+			ParseError::MissingKey { .. } => vec![
+				"= help: set every key the schema declares, or parse with \
+				 `conf::parse_str` to allow leaving keys out"
+					.to_string(),
+			],
+			ParseError::MigrationConflict {
+				old_key,
+				old_line,
+				new_key,
+				new_line,
+			} => vec![
+				format!("--> line {old_line}: '{old_key}'"),
+				format!("--> line {new_line}: '{new_key}'"),
+				format!("= help: keep only '{new_key}'"),
+			],
+			ParseError::InvalidMigration { line, .. } => vec![
+				format!("--> line {line}"),
+				"= help: write 'rename OLD -> NEW', 'move OLD -> NEW' or \
+				 'drop KEY'"
+					.to_string(),
+			],
+			ParseError::DuplicateKey { first_line, second_line, .. } => vec![
+				format!("--> line {first_line}: first declaration"),
+				format!("--> line {second_line}: declared again"),
+			],
+			ParseError::CircularInclude { line, .. } => vec![
+				format!("--> line {line}"),
+				"= help: include every file from one place only".to_string(),
+			],
+			ParseError::InFile { path, error, } => {
+				std::iter::once(format!("--> {}", path.display()),)
+					.chain(error.details(),)
+					.collect()
+			},
+			ParseError::MergeConflict { .. } => vec![
+				"= help: merge with `MergePolicy::OverrideWithOther` or \
+				 `KeepExisting` to pick one of the values"
+					.to_string(),
+			],
+			ParseError::MountConflict { .. } => vec![
+				"= help: mount plugins under prefixes which do not nest"
+					.to_string(),
+			],
+			ParseError::UnmetPathRequirement { .. } => vec![
+				"= help: create the path, or point the key at one which \
+				 qualifies"
+					.to_string(),
+			],
+			ParseError::Unserializable { .. } => vec![
+				"= note: a conf map holds sections, plain values and \
+				 sequences of plain values"
+					.to_string(),
+			],
+			ParseError::NullValue { .. } => vec![
+				"= help: leave the key out instead of setting it to null"
+					.to_string(),
+			],
+			ParseError::ArityMismatch { expected, line, .. } => vec![
+				format!("--> line {line}"),
+				format!("= help: write {expected} values separated by ','"),
+			],
+			ParseError::NotRepeatable { key, line, } => vec![
+				format!("--> line {line}"),
+				format!(
+					"= help: assign '{key}' with '=', or declare it as \
+					 '[Type] (repeatable)'"
+				),
+			],
+		}
+	}
+}
+
+fn single_line(text: &str,) -> String {
+	text.replace('\r', "\\r",).replace('\n', "\\n",)
+}
+
+/// errors are equal when they are the same variant with equal fields. io
+/// errors, which carry no equality of their own, are equal when their
+/// [`std::io::ErrorKind`] and message are
+impl PartialEq for ParseError {
+	fn eq(&self, other: &Self,) -> bool {
+		match (self, other,) {
+			(ParseError::Io(left,), ParseError::Io(right,),) => {
+				left.kind() == right.kind()
+					&& left.to_string() == right.to_string()
+			},
+			(
+				ParseError::MissingDelimiter {
+					line: l_line,
+					column: l_column,
+					expected: l_expected,
+				},
+				ParseError::MissingDelimiter {
+					line: r_line,
+					column: r_column,
+					expected: r_expected,
+				},
+			) => {
+				l_line == r_line
+					&& l_column == r_column
+					&& l_expected == r_expected
+			},
+			(
+				ParseError::EmptyKey { line: l_line, column: l_column, },
+				ParseError::EmptyKey { line: r_line, column: r_column, },
+			) => l_line == r_line && l_column == r_column,
+			(
+				ParseError::EmptyValue { line: l_line, column: l_column, },
+				ParseError::EmptyValue { line: r_line, column: r_column, },
+			) => l_line == r_line && l_column == r_column,
+			(
+				ParseError::InvalidKeySegment {
+					segment: l_segment,
+					line: l_line,
+					column: l_column,
+				},
+				ParseError::InvalidKeySegment {
+					segment: r_segment,
+					line: r_line,
+					column: r_column,
+				},
+			) => {
+				l_segment == r_segment
+					&& l_line == r_line
+					&& l_column == r_column
+			},
+			(
+				ParseError::ConflictingTypes {
+					key: l_key,
+					line: l_line,
+					column: l_column,
+					origin: l_origin,
+				},
+				ParseError::ConflictingTypes {
+					key: r_key,
+					line: r_line,
+					column: r_column,
+					origin: r_origin,
+				},
+			) => {
+				l_key == r_key
+					&& l_line == r_line
+					&& l_column == r_column
+					&& l_origin == r_origin
+			},
+			(
+				ParseError::InvalidValue {
+					key: l_key,
+					value: l_value,
+					ty: l_ty,
+					line: l_line,
+					column: l_column,
+					element: l_element,
+					position: l_position,
+				},
+				ParseError::InvalidValue {
+					key: r_key,
+					value: r_value,
+					ty: r_ty,
+					line: r_line,
+					column: r_column,
+					element: r_element,
+					position: r_position,
+				},
+			) => {
+				l_key == r_key
+					&& l_value == r_value
+					&& l_ty == r_ty && l_line == r_line
+					&& l_column == r_column
+					&& l_element == r_element
+					&& l_position == r_position
+			},
+			(
+				ParseError::UnknownKey {
+					key: l_key,
+					lines: l_lines,
+					suggestion: l_suggestion,
+				},
+				ParseError::UnknownKey {
+					key: r_key,
+					lines: r_lines,
+					suggestion: r_suggestion,
+				},
+			) => {
+				l_key == r_key
+					&& l_lines == r_lines
+					&& l_suggestion == r_suggestion
+			},
+			(
+				ParseError::LocalizedNumber {
+					key: l_key,
+					value: l_value,
+					ty: l_ty,
+					line: l_line,
+					separator: l_separator,
+					suggestion: l_suggestion,
+				},
+				ParseError::LocalizedNumber {
+					key: r_key,
+					value: r_value,
+					ty: r_ty,
+					line: r_line,
+					separator: r_separator,
+					suggestion: r_suggestion,
+				},
+			) => {
+				l_key == r_key
+					&& l_value == r_value
+					&& l_ty == r_ty && l_line == r_line
+					&& l_separator == r_separator
+					&& l_suggestion == r_suggestion
+			},
+			(
+				ParseError::OutOfRange {
+					key: l_key,
+					value: l_value,
+					ty: l_ty,
+					min: l_min,
+					max: l_max,
+					line: l_line,
+				},
+				ParseError::OutOfRange {
+					key: r_key,
+					value: r_value,
+					ty: r_ty,
+					min: r_min,
+					max: r_max,
+					line: r_line,
+				},
+			) => {
+				l_key == r_key
+					&& l_value == r_value
+					&& l_ty == r_ty && l_min == r_min
+					&& l_max == r_max
+					&& l_line == r_line
+			},
+			(
+				ParseError::InvalidEnumValue {
+					key: l_key,
+					value: l_value,
+					allowed: l_allowed,
+					line: l_line,
+				},
+				ParseError::InvalidEnumValue {
+					key: r_key,
+					value: r_value,
+					allowed: r_allowed,
+					line: r_line,
+				},
+			) => {
+				l_key == r_key
+					&& l_value == r_value
+					&& l_allowed == r_allowed
+					&& l_line == r_line
+			},
+			(
+				ParseError::InvalidDeclaration {
+					key: l_key,
+					declaration: l_declaration,
+					line: l_line,
+				},
+				ParseError::InvalidDeclaration {
+					key: r_key,
+					declaration: r_declaration,
+					line: r_line,
+				},
+			) => {
+				l_key == r_key
+					&& l_declaration == r_declaration
+					&& l_line == r_line
+			},
+			(
+				ParseError::UnknownType {
+					key: l_key,
+					name: l_name,
+					line: l_line,
+				},
+				ParseError::UnknownType {
+					key: r_key,
+					name: r_name,
+					line: r_line,
+				},
+			) => l_key == r_key && l_name == r_name && l_line == r_line,
+			(
+				ParseError::UnterminatedString { line: l_line, },
+				ParseError::UnterminatedString { line: r_line, },
+			) => l_line == r_line,
+			(
+				ParseError::InvalidSectionHeader { line: l_line, },
+				ParseError::InvalidSectionHeader { line: r_line, },
+			) => l_line == r_line,
+			(
+				ParseError::TrailingCharacters {
+					line: l_line,
+					column: l_column,
+					text: l_text,
+				},
+				ParseError::TrailingCharacters {
+					line: r_line,
+					column: r_column,
+					text: r_text,
+				},
+			) => l_line == r_line && l_column == r_column && l_text == r_text,
+			(
+				ParseError::ResolverFailed {
+					key: l_key,
+					resolver: l_resolver,
+					reason: l_reason,
+				},
+				ParseError::ResolverFailed {
+					key: r_key,
+					resolver: r_resolver,
+					reason: r_reason,
+				},
+			) => {
+				l_key == r_key
+					&& l_resolver == r_resolver
+					&& l_reason == r_reason
+			},
+			(
+				ParseError::Unresolved { keys: l_keys, },
+				ParseError::Unresolved { keys: r_keys, },
+			) => l_keys == r_keys,
+			(
+				ParseError::MissingKey { keys: l_keys, },
+				ParseError::MissingKey { keys: r_keys, },
+			) => l_keys == r_keys,
+			(
+				ParseError::MigrationConflict {
+					old_key: l_old_key,
+					old_line: l_old_line,
+					new_key: l_new_key,
+					new_line: l_new_line,
+				},
+				ParseError::MigrationConflict {
+					old_key: r_old_key,
+					old_line: r_old_line,
+					new_key: r_new_key,
+					new_line: r_new_line,
+				},
+			) => {
+				l_old_key == r_old_key
+					&& l_old_line == r_old_line
+					&& l_new_key == r_new_key
+					&& l_new_line == r_new_line
+			},
+			(
+				ParseError::InvalidMigration { rule: l_rule, line: l_line, },
+				ParseError::InvalidMigration { rule: r_rule, line: r_line, },
+			) => l_rule == r_rule && l_line == r_line,
+			(
+				ParseError::DuplicateKey {
+					key: l_key,
+					first_line: l_first_line,
+					second_line: l_second_line,
+				},
+				ParseError::DuplicateKey {
+					key: r_key,
+					first_line: r_first_line,
+					second_line: r_second_line,
+				},
+			) => {
+				l_key == r_key
+					&& l_first_line == r_first_line
+					&& l_second_line == r_second_line
+			},
+			(
+				ParseError::CircularInclude { path: l_path, line: l_line, },
+				ParseError::CircularInclude { path: r_path, line: r_line, },
+			) => l_path == r_path && l_line == r_line,
+			(
+				ParseError::InFile { path: l_path, error: l_error, },
+				ParseError::InFile { path: r_path, error: r_error, },
+			) => l_path == r_path && l_error == r_error,
+			(
+				ParseError::MergeConflict { key: l_key, },
+				ParseError::MergeConflict { key: r_key, },
+			) => l_key == r_key,
+			(
+				ParseError::MountConflict {
+					plugin: l_plugin,
+					prefix: l_prefix,
+					other: l_other,
+					other_prefix: l_other_prefix,
+				},
+				ParseError::MountConflict {
+					plugin: r_plugin,
+					prefix: r_prefix,
+					other: r_other,
+					other_prefix: r_other_prefix,
+				},
+			) => {
+				l_plugin == r_plugin
+					&& l_prefix == r_prefix
+					&& l_other == r_other
+					&& l_other_prefix == r_other_prefix
+			},
+			(
+				ParseError::UnmetPathRequirement {
+					key: l_key,
+					path: l_path,
+					requirement: l_requirement,
+				},
+				ParseError::UnmetPathRequirement {
+					key: r_key,
+					path: r_path,
+					requirement: r_requirement,
+				},
+			) => {
+				l_key == r_key
+					&& l_path == r_path
+					&& l_requirement == r_requirement
+			},
+			(
+				ParseError::Unserializable { key: l_key, reason: l_reason, },
+				ParseError::Unserializable { key: r_key, reason: r_reason, },
+			) => l_key == r_key && l_reason == r_reason,
+			(
+				ParseError::NullValue { key: l_key, },
+				ParseError::NullValue { key: r_key, },
+			) => l_key == r_key,
+			(
+				ParseError::ArityMismatch {
+					key: l_key,
+					expected: l_expected,
+					found: l_found,
+					line: l_line,
+				},
+				ParseError::ArityMismatch {
+					key: r_key,
+					expected: r_expected,
+					found: r_found,
+					line: r_line,
+				},
+			) => {
+				l_key == r_key
+					&& l_expected == r_expected
+					&& l_found == r_found
+					&& l_line == r_line
+			},
+			(
+				ParseError::NotRepeatable { key: l_key, line: l_line, },
+				ParseError::NotRepeatable { key: r_key, line: r_line, },
+			) => l_key == r_key && l_line == r_line,
+			_ => false,
+		}
+	}
+}
+
+/// `is_*` predicates, one per variant, for checking what an error is
+/// without matching on it. an error in an included file is only
+/// [`ParseError::is_in_file`]
+macro_rules! variant_predicates {
+	($($predicate:ident => $variant:ident,)*) => {
+		impl ParseError {
+			$(
+				#[doc = concat!(
+					"whether this is a [`ParseError::",
+					stringify!($variant),
+					"`]"
+				)]
+				pub fn $predicate(&self,) -> bool {
+					matches!(self, ParseError::$variant { .. })
+				}
+			)*
+		}
+	};
+}
+
+variant_predicates! {
+	is_io => Io,
+	is_missing_delimiter => MissingDelimiter,
+	is_empty_key => EmptyKey,
+	is_empty_value => EmptyValue,
+	is_invalid_key_segment => InvalidKeySegment,
+	is_conflicting_types => ConflictingTypes,
+	is_invalid_value => InvalidValue,
+	is_unknown_key => UnknownKey,
+	is_localized_number => LocalizedNumber,
+	is_out_of_range => OutOfRange,
+	is_invalid_enum_value => InvalidEnumValue,
+	is_invalid_declaration => InvalidDeclaration,
+	is_unknown_type => UnknownType,
+	is_unterminated_string => UnterminatedString,
+	is_invalid_section_header => InvalidSectionHeader,
+	is_trailing_characters => TrailingCharacters,
+	is_resolver_failed => ResolverFailed,
+	is_unresolved => Unresolved,
+	is_missing_key => MissingKey,
+	is_migration_conflict => MigrationConflict,
+	is_invalid_migration => InvalidMigration,
+	is_duplicate_key => DuplicateKey,
+	is_circular_include => CircularInclude,
+	is_in_file => InFile,
+	is_merge_conflict => MergeConflict,
+	is_mount_conflict => MountConflict,
+	is_unmet_path_requirement => UnmetPathRequirement,
+	is_unserializable => Unserializable,
+	is_null_value => NullValue,
+	is_arity_mismatch => ArityMismatch,
+	is_not_repeatable => NotRepeatable,
 }
 
 impl std::error::Error for ParseError {
 	fn source(&self,) -> Option<&(dyn std::error::Error + 'static),> {
 		match self {
 			ParseError::Io(err,) => Some(err,),
+			ParseError::InFile { error, .. } => Some(error.as_ref(),),
 			_ => None,
 		}
 	}
@@ -82,49 +1427,111 @@ impl From<std::io::Error,> for ParseError {
 	}
 }
 
-impl From<strum::ParseError,> for ParseError {
-	fn from(_: strum::ParseError,) -> Self {
-		Self::InvalidValue {
-			key:   "".to_string(),
-			value: "".to_string(),
-			ty:    SingleValueDiscriminants::Bool,
-			line:  0,
+pub type PRslt<T,> = Result<T, ParseError,>;
+
+/// non fatal finding reported next to a successful parse
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub enum Warning {
+	/// schema key declared again with the same type
+	RedundantDeclaration {
+		key:         String,
+		first_line:  usize,
+		second_line: usize,
+	},
+	/// conf key rewritten by a migration. `to` is `None` for a dropped key
+	MigratedKey { from: String, to: Option<String,>, line: usize, },
+	/// schema declaration not understood by this version, which accepts any
+	/// value for the key instead
+	UnrecognizedDeclaration {
+		key:         String,
+		declaration: String,
+		line:        usize,
+	},
+}
+
+impl std::fmt::Display for Warning {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			Warning::RedundantDeclaration { key, first_line, second_line, } => {
+				write!(
+					f,
+					"'{key}' on line {second_line} redeclares the same type \
+					 as line {first_line}"
+				)
+			},
+			Warning::MigratedKey { from, to: Some(to,), line, } => {
+				write!(f, "'{from}' on line {line} is deprecated, use '{to}'")
+			},
+			Warning::MigratedKey { from, to: None, line, } => {
+				write!(f, "'{from}' on line {line} is obsolete and was ignored")
+			},
+			Warning::UnrecognizedDeclaration { key, declaration, line, } => {
+				write!(
+					f,
+					"'{declaration}' for '{key}' on line {line} is not \
+					 understood, any value is accepted"
+				)
+			},
 		}
 	}
 }
 
-pub type PRslt<T,> = Result<T, ParseError,>;
-
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use std::io;
-	use std::str::FromStr;
 
 	#[test]
 	fn display_formats_missing_delimiter() {
-		let msg = ParseError::MissingDelimiter { line: 12, }.to_string();
-		assert_eq!(msg, "missing delimiter on line 12");
+		let msg = ParseError::MissingDelimiter {
+			line:     12,
+			column:   3,
+			expected: Vec::new(),
+		}
+		.to_string();
+		assert_eq!(msg, "[E002] missing delimiter on line 12, column 3");
 	}
 
 	#[test]
 	fn display_formats_invalid_value_payload() {
 		let err = ParseError::InvalidValue {
-			key:   "flag".to_string(),
-			value: "yes".to_string(),
-			ty:    SingleValueDiscriminants::Bool,
-			line:  7,
+			key:      "flag".to_string(),
+			value:    "yes".to_string(),
+			ty:       SingleValueDiscriminants::Bool,
+			line:     7,
+			column:   8,
+			element:  None,
+			position: None,
 		};
 		let msg = err.to_string();
 		assert_eq!(
 			msg,
-			"invalid value 'yes' while expecting Bool for 'flag' on line 7",
+			"[E007] invalid value 'yes' while expecting Bool for 'flag' on \
+			 line 7, column 8",
+		);
+	}
+
+	#[test]
+	fn display_names_labeled_element_of_invalid_value() {
+		let err = ParseError::InvalidValue {
+			key:      "limits".to_string(),
+			value:    "x".to_string(),
+			ty:       SingleValueDiscriminants::Integer,
+			line:     4,
+			column:   10,
+			element:  Some("max".to_string(),),
+			position: None,
+		};
+		assert_eq!(
+			err.to_string(),
+			"[E007] invalid value 'x' while expecting Integer for element \
+			 'max' of 'limits' on line 4, column 10",
 		);
 	}
 
 	#[test]
 	fn io_error_conversion_wraps_source() {
-		let io_err = io::Error::new(io::ErrorKind::Other, "boom",);
+		let io_err = io::Error::other("boom",);
 		let parse_err: ParseError = io_err.into();
 		match parse_err {
 			ParseError::Io(inner,) => {
@@ -135,26 +1542,56 @@ mod tests {
 	}
 
 	#[test]
-	fn strum_error_conversion_defaults_to_invalid_value() {
-		let parse_err =
-			SingleValueDiscriminants::from_str("unsupported",).unwrap_err();
-		let converted: ParseError = parse_err.into();
-		match converted {
-			ParseError::InvalidValue { key, value, ty, line, } => {
-				assert!(key.is_empty());
-				assert!(value.is_empty());
-				assert_eq!(ty, SingleValueDiscriminants::Bool);
-				assert_eq!(line, 0);
-			},
-			other => panic!("unexpected error: {other:?}"),
-		}
+	fn display_names_both_duplicate_lines() {
+		let err = ParseError::DuplicateKey {
+			key:         "server.port".to_string(),
+			first_line:  2,
+			second_line: 40,
+		};
+		assert_eq!(
+			err.to_string(),
+			"[E022] duplicate key 'server.port' on lines 2 and 40"
+		);
+	}
+
+	#[test]
+	fn plain_display_escapes_line_breaks() {
+		let err = ParseError::InvalidValue {
+			key:      "motd".to_string(),
+			value:    "hello\r\nworld".to_string(),
+			ty:       SingleValueDiscriminants::Integer,
+			line:     0,
+			column:   0,
+			element:  None,
+			position: None,
+		};
+		assert_eq!(
+			err.to_string(),
+			"[E007] invalid value 'hello\\r\\nworld' while expecting Integer \
+			 for 'motd' on line 0, column 0"
+		);
+	}
+
+	#[test]
+	fn alternate_display_appends_details() {
+		let err = ParseError::DuplicateKey {
+			key:         "server.port".to_string(),
+			first_line:  2,
+			second_line: 40,
+		};
+		assert_eq!(
+			format!("{err:#}"),
+			"[E022] duplicate key 'server.port' on lines 2 and 40\n  --> line \
+			 2: first declaration\n  --> line 40: declared again"
+		);
 	}
 
 	#[test]
 	fn display_lists_unknown_key_lines() {
 		let err = ParseError::UnknownKey {
-			key:   "db.port".to_string(),
-			lines: vec![5, 9],
+			key:        "db.port".to_string(),
+			lines:      vec![5, 9],
+			suggestion: None,
 		};
 		let msg = err.to_string();
 		assert!(msg.contains("db.port"));
@@ -3,3 +3,6 @@
 pub mod error;
 pub mod parser;
 pub mod show;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+mod trace;
@@ -1,3 +1,25 @@
 pub mod conf;
 pub(crate) mod core;
+pub mod deferred;
+pub mod diff;
+pub mod duration;
+pub mod edit;
+pub mod env;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod merge;
+pub mod migrate;
+pub mod mount;
+pub mod raw;
+pub mod retain;
 pub mod schema;
+pub mod secret;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod source;
+pub mod stats;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod view;
+#[cfg(feature = "watch")]
+pub mod watch;
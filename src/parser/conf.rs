@@ -1,70 +1,606 @@
 use crate::error::PRslt;
 use crate::error::ParseError;
+use crate::error::Separator;
+use crate::error::Warning;
+pub use crate::parser::core::DuplicateKeyPolicy;
+pub use crate::parser::core::KeyCase;
+pub use crate::parser::core::LINE_ERROR_BUDGET;
+use crate::parser::core::MirLeaf;
+use crate::parser::core::MirObserver;
+pub use crate::parser::core::ParseOptions;
+pub use crate::parser::core::SegmentCharset;
 use crate::parser::core::StructuredInput;
 use crate::parser::core::TreeValue;
 use crate::parser::core::Valuable;
+use crate::parser::core::split_quoted;
+use crate::parser::deferred::parse_placeholder;
+use crate::parser::diff::render_value;
+use crate::parser::duration::parse_duration;
+pub use crate::parser::edit::DocLine;
+pub use crate::parser::edit::Document;
+pub use crate::parser::edit::parse_document;
+pub use crate::parser::env::apply_env_overrides;
+#[cfg(feature = "serde")]
+pub use crate::parser::json::from_json;
+use crate::parser::merge::MergePolicy;
+use crate::parser::migrate::Migrations;
+use crate::parser::schema::AsSchema;
+use crate::parser::schema::SchemaLeaf;
 use crate::parser::schema::SchemaMap;
 use crate::parser::schema::SchemaValue;
+#[cfg(any(feature = "serde", feature = "toml"))]
+use crate::parser::schema::for_each_leaf;
+use crate::parser::secret::Secret;
+use crate::parser::secret::redact_for;
+#[cfg(feature = "serde")]
+pub use crate::parser::serde::from_map;
+#[cfg(feature = "serde")]
+pub use crate::parser::serde::to_map;
+use crate::parser::source::FileSources;
+use crate::parser::source::Origin;
+use crate::parser::source::SourceMap;
+use crate::parser::stats::ParseStats;
+#[cfg(feature = "toml")]
+pub use crate::parser::toml::from_toml;
+use crate::parser::view::ConfView;
+#[cfg(feature = "watch")]
+pub use crate::parser::watch::WatchHandle;
+#[cfg(feature = "watch")]
+pub use crate::parser::watch::WatchOptions;
+#[cfg(feature = "watch")]
+pub use crate::parser::watch::watch_file;
+#[cfg(feature = "watch")]
+pub use crate::parser::watch::watch_file_with;
+use crate::show::DEBUG_SUMMARY_LEAVES;
+use crate::trace;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::btree_map;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::io::BufRead;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::num::IntErrorKind;
+use std::num::ParseIntError;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::Instant;
+use strum_macros::EnumIter;
 use strum_macros::EnumString;
 
 pub type ConfValue = TreeValue<Value<SingleValue,>,>;
 
-#[derive(Debug, Default,)]
-pub struct ConfMap(BTreeMap<String, ConfValue,>,);
+/// parsed conf file. like every other public type of the crate it is
+/// `Send + Sync`, so it can be shared between threads behind an `Arc`.
+#[derive(Default, Clone,)]
+pub struct ConfMap(
+	BTreeMap<String, ConfValue,>,
+	/// element labels of labeled tuples, by dotted key
+	BTreeMap<String, Vec<String,>,>,
+	SourceMap,
+	/// dotted keys of lists declared repeatable
+	BTreeSet<String,>,
+	/// dotted keys of leaves in the order they were first assigned, see
+	/// [`ParseOptions::preserve_order`]. empty for key order
+	Vec<String,>,
+);
+
+/// maps are equal when their entries, labels and repeatable keys are, so
+/// where values were assigned and in which order does not matter
+impl PartialEq for ConfMap {
+	fn eq(&self, other: &Self,) -> bool {
+		self.0 == other.0 && self.1 == other.1 && self.3 == other.3
+	}
+}
+
+impl Eq for ConfMap {}
+
+/// maps of more than [`DEBUG_SUMMARY_LEAVES`] leaves are only summarized
+impl Debug for ConfMap {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		let leaves = self.iter_leaves().count();
+		if leaves > DEBUG_SUMMARY_LEAVES {
+			return f
+				.debug_struct("ConfMap",)
+				.field("leaves", &leaves,)
+				.field("top_level_keys", &self.0.len(),)
+				.finish_non_exhaustive();
+		}
+		f.debug_tuple("ConfMap",)
+			.field(&self.0,)
+			.field(&self.1,)
+			.field(&self.2,)
+			.field(&self.3,)
+			.field(&self.4,)
+			.finish()
+	}
+}
 
 impl ConfMap {
 	pub fn new() -> Self {
-		Self(BTreeMap::new(),)
+		Self(
+			BTreeMap::new(),
+			BTreeMap::new(),
+			SourceMap::new(),
+			BTreeSet::new(),
+			Vec::new(),
+		)
 	}
 
 	pub fn into_inner(self,) -> BTreeMap<String, ConfValue,> {
 		self.0
 	}
 
+	/// map of `entries` typed against `schema`, keeping the labels and
+	/// repeatability each set leaf is declared with, as [`parse_str`] does
+	#[cfg(any(feature = "serde", feature = "toml"))]
+	pub(crate) fn from_typed(
+		entries: BTreeMap<String, ConfValue,>,
+		schema: &SchemaMap,
+	) -> Self {
+		let mut conf = Self::from(&entries,);
+		for (key, value,) in schema.iter() {
+			for_each_leaf(key, value, &mut |full_key, leaf| {
+				if conf.get(full_key,).is_none() {
+					return;
+				}
+				if let Some(labels,) = &leaf.labels {
+					conf.1.insert(full_key.to_string(), labels.clone(),);
+				}
+				if leaf.repeatable {
+					conf.3.insert(full_key.to_string(),);
+				}
+			},);
+		}
+		conf
+	}
+
 	pub fn get(&self, key: &str,) -> Option<&ConfValue,> {
-		if let Some(value,) = self.0.get(key,) {
-			return Some(value,);
+		ConfView::get(self, key,)
+	}
+
+	/// [`Self::get`] of `key` lowercased, for maps parsed with
+	/// [`KeyCase::Lowercase`]
+	pub fn get_ci(&self, key: &str,) -> Option<&ConfValue,> {
+		self.get(&key.to_lowercase(),)
+	}
+
+	/// where each leaf was assigned. only [`parse_file`] records anything by
+	/// itself
+	pub fn sources(&self,) -> &SourceMap {
+		&self.2
+	}
+
+	pub fn sources_mut(&mut self,) -> &mut SourceMap {
+		&mut self.2
+	}
+
+	/// element labels of labeled tuples, by dotted key
+	pub(crate) fn labels(&self,) -> &BTreeMap<String, Vec<String,>,> {
+		&self.1
+	}
+
+	/// whether the list at the dotted `key` is declared repeatable, so it is
+	/// written as one assignment per element
+	pub fn is_repeatable(&self, key: &str,) -> bool {
+		self.3.contains(key,)
+	}
+
+	pub(crate) fn repeatable_keys(&self,) -> &BTreeSet<String,> {
+		&self.3
+	}
+
+	pub(crate) fn repeatable_keys_mut(&mut self,) -> &mut BTreeSet<String,> {
+		&mut self.3
+	}
+
+	pub(crate) fn labels_mut(
+		&mut self,
+	) -> &mut BTreeMap<String, Vec<String,>,> {
+		&mut self.1
+	}
+
+	/// dotted keys of leaves in the order they were first assigned, for maps
+	/// parsed with [`ParseOptions::preserve_order`]. empty otherwise. leaves
+	/// added later are not in it and are written after these, in key order
+	pub fn key_order(&self,) -> &[String] {
+		&self.4
+	}
+
+	/// string at the dotted `key`. `None` when the key is absent or holds
+	/// anything else, see [`ConfValue::as_str`]
+	pub fn get_str(&self, key: &str,) -> Option<&str,> {
+		self.get(key,)?.as_str()
+	}
+
+	/// same as [`Self::get_str`] for a bool
+	pub fn get_bool(&self, key: &str,) -> Option<bool,> {
+		self.get(key,)?.as_bool()
+	}
+
+	/// same as [`Self::get_str`] for an integer
+	pub fn get_int(&self, key: &str,) -> Option<i32,> {
+		self.get(key,)?.as_int()
+	}
+
+	/// same as [`Self::get_str`] for an unsigned integer
+	pub fn get_uint(&self, key: &str,) -> Option<u64,> {
+		self.get(key,)?.as_uint()
+	}
+
+	/// same as [`Self::get_str`] for a 64-bit integer
+	pub fn get_long(&self, key: &str,) -> Option<i64,> {
+		self.get(key,)?.as_long()
+	}
+
+	/// same as [`Self::get_str`] for a float
+	pub fn get_float(&self, key: &str,) -> Option<f64,> {
+		self.get(key,)?.as_float()
+	}
+
+	/// same as [`Self::get_str`] for a duration
+	pub fn get_duration(&self, key: &str,) -> Option<Duration,> {
+		self.get(key,)?.as_duration()
+	}
+
+	/// same as [`Self::get_str`] for a collection of integers
+	pub fn get_ints(&self, key: &str,) -> Option<Vec<i32,>,> {
+		self.get(key,)?.as_ints()
+	}
+
+	/// element of the labeled tuple at `key` named `field` by the schema
+	pub fn get_field(&self, key: &str, field: &str,) -> Option<&SingleValue,> {
+		let idx = self.1.get(key,)?.iter().position(|label| label == field,)?;
+		match self.get(key,)? {
+			ConfValue::Scalar(Value::Collection(items,),) => items.get(idx,),
+			_ => None,
+		}
+	}
+
+	/// sets the dotted `key` to `value`, creating the sections on the way.
+	/// returns the value it replaced. fails with
+	/// [`ParseError::ConflictingTypes`] when a section on the way is a value,
+	/// or when `key` holds a section and `value` is not one or vice versa.
+	/// the value is not checked against any schema
+	pub fn insert_dotted(
+		&mut self,
+		key: &str,
+		value: ConfValue,
+	) -> PRslt<Option<ConfValue,>,> {
+		let segments = crate::parser::core::parse_key(
+			key,
+			0,
+			0,
+			SegmentCharset::Permissive,
+		)?;
+		match self.entry_of(&segments,)? {
+			btree_map::Entry::Occupied(entry,)
+				if matches!(
+					(entry.get(), &value,),
+					(TreeValue::Map(_,), TreeValue::Scalar(_,),)
+						| (TreeValue::Scalar(_,), TreeValue::Map(_,),)
+				) =>
+			{
+				Err(Self::conflict_at(&segments, segments.len() - 1,),)
+			},
+			btree_map::Entry::Occupied(mut entry,) => {
+				Ok(Some(entry.insert(value,),),)
+			},
+			btree_map::Entry::Vacant(entry,) => {
+				entry.insert(value,);
+				Ok(None,)
+			},
+		}
+	}
+
+	/// entry of the dotted `key` in its section, creating the sections on
+	/// the way. as every segment gets a section of its own, no key holding
+	/// dots is ever made this way. fails with
+	/// [`ParseError::ConflictingTypes`] when a section on the way is a value
+	pub fn entry_dotted(
+		&mut self,
+		key: &str,
+	) -> PRslt<btree_map::Entry<'_, String, ConfValue,>,> {
+		let segments = crate::parser::core::parse_key(
+			key,
+			0,
+			0,
+			SegmentCharset::Permissive,
+		)?;
+		self.entry_of(&segments,)
+	}
+
+	fn entry_of(
+		&mut self,
+		segments: &[String],
+	) -> PRslt<btree_map::Entry<'_, String, ConfValue,>,> {
+		let (last, sections,) =
+			segments.split_last().expect("keys have at least one segment",);
+		let mut current = &mut self.0;
+		for (depth, segment,) in sections.iter().enumerate() {
+			current = match current
+				.entry(segment.clone(),)
+				.or_insert_with(|| TreeValue::Map(BTreeMap::new(),),)
+			{
+				TreeValue::Map(children,) => children,
+				TreeValue::Scalar(_,) => {
+					return Err(Self::conflict_at(segments, depth,),);
+				},
+			};
 		}
+		Ok(current.entry(last.clone(),),)
+	}
 
-		let mut segments = key.split('.',);
-		let first = segments.next()?;
-		let mut current = self.0.get(first,)?;
+	/// conflict at the first `depth + 1` of `segments`
+	fn conflict_at(segments: &[String], depth: usize,) -> ParseError {
+		ParseError::ConflictingTypes {
+			key:    segments[..=depth].join(".",),
+			line:   0,
+			column: 0,
+			origin: None,
+		}
+	}
+
+	/// removes the value or section at the dotted `key`, with the labels,
+	/// sources, repeatability and order of its leaves, and every section left
+	/// empty by that
+	pub fn remove_dotted(&mut self, key: &str,) -> Option<ConfValue,> {
+		fn remove_at(
+			map: &mut BTreeMap<String, ConfValue,>,
+			segments: &[&str],
+		) -> Option<ConfValue,> {
+			let [first, rest @ ..,] = segments else { return None };
+			if rest.is_empty() {
+				return map.remove(*first,);
+			}
 
-		for segment in segments {
-			current = match current {
-				ConfValue::Map(children,) => children.get(segment,)?,
-				_ => return None,
+			let TreeValue::Map(children,) = map.get_mut(*first,)? else {
+				return None;
 			};
+			let removed = remove_at(children, rest,)?;
+			if children.is_empty() {
+				map.remove(*first,);
+			}
+			Some(removed,)
 		}
 
-		Some(current,)
+		let segments: Vec<&str,> = key.split('.',).collect();
+		let removed = remove_at(&mut self.0, &segments,)?;
+
+		let dotted = format!("{key}.");
+		let gone = |leaf: &str| leaf == key || leaf.starts_with(&dotted,);
+		self.1.retain(|leaf, _| !gone(leaf,),);
+		self.2.retain(|leaf| !gone(leaf,),);
+		self.3.retain(|leaf| !gone(leaf,),);
+		self.4.retain(|leaf| !gone(leaf,),);
+		Some(removed,)
 	}
 }
 
+/// typed reads of a single value. each returns `None` for a section and for
+/// a value of any other type, secrets included
+impl ConfValue {
+	pub fn as_str(&self,) -> Option<&str,> {
+		match self {
+			TreeValue::Scalar(Value::Single(SingleValue::String(s,),),) => {
+				Some(s.as_str(),)
+			},
+			_ => None,
+		}
+	}
+
+	pub fn as_bool(&self,) -> Option<bool,> {
+		match self {
+			TreeValue::Scalar(Value::Single(SingleValue::Bool(flag,),),) => {
+				Some(*flag,)
+			},
+			_ => None,
+		}
+	}
+
+	pub fn as_int(&self,) -> Option<i32,> {
+		match self {
+			TreeValue::Scalar(Value::Single(SingleValue::Integer(num,),),) => {
+				Some(*num,)
+			},
+			_ => None,
+		}
+	}
+
+	pub fn as_uint(&self,) -> Option<u64,> {
+		match self {
+			TreeValue::Scalar(Value::Single(SingleValue::UInteger(num,),),) => {
+				Some(*num,)
+			},
+			_ => None,
+		}
+	}
+
+	pub fn as_long(&self,) -> Option<i64,> {
+		match self {
+			TreeValue::Scalar(Value::Single(SingleValue::Long(num,),),) => {
+				Some(*num,)
+			},
+			_ => None,
+		}
+	}
+
+	pub fn as_float(&self,) -> Option<f64,> {
+		match self {
+			TreeValue::Scalar(Value::Single(SingleValue::Float(num,),),) => {
+				Some(*num,)
+			},
+			_ => None,
+		}
+	}
+
+	pub fn as_duration(&self,) -> Option<Duration,> {
+		match self {
+			TreeValue::Scalar(Value::Single(SingleValue::Duration(
+				duration,
+			),),) => Some(*duration,),
+			_ => None,
+		}
+	}
+
+	/// `None` as well when any element is not an integer
+	pub fn as_ints(&self,) -> Option<Vec<i32,>,> {
+		match self {
+			TreeValue::Scalar(Value::Collection(items,),) => items
+				.iter()
+				.map(|item| match item {
+					SingleValue::Integer(num,) => Some(*num,),
+					_ => None,
+				},)
+				.collect(),
+			_ => None,
+		}
+	}
+}
+
+/// value of another type than asked for by a conversion. a value does not
+/// know its key or line, so both are empty: a section is a
+/// [`ParseError::ConflictingTypes`] and anything else a
+/// [`ParseError::InvalidValue`] holding the value as written
+fn shape_error(value: &ConfValue, ty: SingleValueDiscriminants,) -> ParseError {
+	match value {
+		TreeValue::Scalar(value,) => mismatch(value, ty,),
+		TreeValue::Map(_,) => ParseError::ConflictingTypes {
+			key:    String::new(),
+			line:   0,
+			column: 0,
+			origin: None,
+		},
+	}
+}
+
+fn mismatch(
+	value: &Value<SingleValue,>,
+	ty: SingleValueDiscriminants,
+) -> ParseError {
+	ParseError::InvalidValue {
+		key: String::new(),
+		value: render_value(value,),
+		ty,
+		line: 0,
+		column: 0,
+		element: None,
+		position: None,
+	}
+}
+
+fn single_of(
+	value: &ConfValue,
+	ty: SingleValueDiscriminants,
+) -> PRslt<&SingleValue,> {
+	match value {
+		TreeValue::Scalar(Value::Single(single,),) => Ok(single,),
+		other => Err(shape_error(other, ty,),),
+	}
+}
+
+fn collection_of(
+	value: &ConfValue,
+	ty: SingleValueDiscriminants,
+) -> PRslt<&[SingleValue],> {
+	match value {
+		TreeValue::Scalar(Value::Collection(items,),) => Ok(items,),
+		other => Err(shape_error(other, ty,),),
+	}
+}
+
+fn into_single(
+	value: ConfValue,
+	ty: SingleValueDiscriminants,
+) -> PRslt<SingleValue,> {
+	match value {
+		TreeValue::Scalar(Value::Single(single,),) => Ok(single,),
+		other => Err(shape_error(&other, ty,),),
+	}
+}
+
+fn into_collection(
+	value: ConfValue,
+	ty: SingleValueDiscriminants,
+) -> PRslt<Vec<SingleValue,>,> {
+	match value {
+		TreeValue::Scalar(Value::Collection(items,),) => Ok(items,),
+		other => Err(shape_error(&other, ty,),),
+	}
+}
+
+/// `TryFrom<ConfValue>` and `TryFrom<&ConfValue>` for `$target` and
+/// `Vec<$target>`, through the `SingleValue::$convert` of each element. a
+/// collection with a single element of another type fails as a whole
+macro_rules! conf_value_conversions {
+	($($target:ty => $ty:ident, $convert:ident;)*) => {$(
+		impl TryFrom<ConfValue,> for $target {
+			type Error = ParseError;
+
+			fn try_from(value: ConfValue,) -> PRslt<Self,> {
+				into_single(value, SingleValueDiscriminants::$ty,)?.$convert()
+			}
+		}
+
+		impl TryFrom<&ConfValue,> for $target {
+			type Error = ParseError;
+
+			fn try_from(value: &ConfValue,) -> PRslt<Self,> {
+				single_of(value, SingleValueDiscriminants::$ty,)?
+					.clone()
+					.$convert()
+			}
+		}
+
+		impl TryFrom<ConfValue,> for Vec<$target,> {
+			type Error = ParseError;
+
+			fn try_from(value: ConfValue,) -> PRslt<Self,> {
+				into_collection(value, SingleValueDiscriminants::$ty,)?
+					.into_iter()
+					.map(SingleValue::$convert,)
+					.collect()
+			}
+		}
+
+		impl TryFrom<&ConfValue,> for Vec<$target,> {
+			type Error = ParseError;
+
+			fn try_from(value: &ConfValue,) -> PRslt<Self,> {
+				collection_of(value, SingleValueDiscriminants::$ty,)?
+					.iter()
+					.cloned()
+					.map(SingleValue::$convert,)
+					.collect()
+			}
+		}
+	)*};
+}
+
+conf_value_conversions! {
+	String => String, try_into_string;
+	bool => Bool, try_into_bool;
+	i32 => Integer, try_into_int;
+}
+
+/// an owned copy of a section, without labels, sources or repeatability.
+/// [`ConfMapRef`](crate::parser::view::ConfMapRef) reads a section without
+/// copying it
 impl From<&BTreeMap<String, ConfValue,>,> for ConfMap {
 	fn from(inner: &BTreeMap<String, ConfValue,>,) -> Self {
-		let inner = inner
-			.iter()
-			.map(|(key, value,)| {
-				// let value = match value {
-				// 	TreeValue::Scalar(v,) => match v {
-				// 		Value::Single(v,) => {
-				// 			TreeValue::Scalar(Value::Single(v.clone(),),)
-				// 		},
-				// 		Value::Collection(items,) => {
-				// 			TreeValue::Scalar(Value::Collection(items.clone(),),)
-				// 		},
-				// 	},
-				// 	TreeValue::Map(btree_map,) => todo!(),
-				// };
-				(key.clone(), value.clone(),)
-			},)
-			.collect();
-		Self(inner,)
+		Self(
+			inner.clone(),
+			BTreeMap::new(),
+			SourceMap::new(),
+			BTreeSet::new(),
+			Vec::new(),
+		)
 	}
 }
 
@@ -82,48 +618,957 @@ impl DerefMut for ConfMap {
 	}
 }
 
-#[derive(Debug, strum_macros::EnumDiscriminants, Clone,)]
+#[derive(Debug, strum_macros::EnumDiscriminants, Clone, PartialEq, Eq,)]
 pub enum Value<T: Valuable,> {
 	Single(T,),
 	Collection(Vec<T,>,),
 }
 
-#[derive(strum_macros::EnumDiscriminants, Debug, Clone, PartialEq, Eq,)]
-#[strum_discriminants(derive(EnumString))]
+#[derive(strum_macros::EnumDiscriminants, Debug, Clone, PartialEq,)]
+#[strum_discriminants(derive(EnumString, EnumIter))]
 pub enum SingleValue {
 	String(String,),
 	Bool(bool,),
 	Integer(i32,),
+	/// declared as `UInt`, for counts beyond `i32` like byte sizes
+	#[strum_discriminants(strum(serialize = "UInt"))]
+	UInteger(u64,),
+	/// 64-bit signed integer, like epoch timestamps
+	Long(i64,),
+	/// always finite
+	Float(f64,),
+	/// redacted wherever it is rendered, see [`Secret`]
+	Secret(Secret,),
+	/// written as `1h30m`, `250ms` or a plain number of seconds, see
+	/// [`parse_duration`]
+	Duration(Duration,),
+	/// IPv4 or IPv6 address, declared as `Ip`
+	#[strum_discriminants(strum(serialize = "Ip"))]
+	IpAddr(IpAddr,),
+	/// address with a port, as in `127.0.0.1:80` or `[::1]:8080`
+	SocketAddr(SocketAddr,),
+	/// kept as written, never empty. see [`validate_paths`] to check it
+	/// against the file system
+	Path(PathBuf,),
+	/// `@resolver(arg)` placeholder of a resolvable key, replaced by
+	/// [`ConfMap::resolve`]. not a type a schema can declare
+	#[strum_discriminants(strum(disabled))]
+	Deferred {
+		resolver: String,
+		arg:      String,
+	},
+}
+
+/// floats are finite, as every way the crate builds one rejects NaN, so
+/// every value equals itself
+impl Eq for SingleValue {}
+
+/// conversions failing with a keyless [`ParseError::InvalidValue`] when the
+/// value is of another type
+impl SingleValue {
+	pub fn try_into_string(self,) -> PRslt<String,> {
+		match self {
+			SingleValue::String(s,) => Ok(s,),
+			other => Err(other.mismatch(SingleValueDiscriminants::String,),),
+		}
+	}
+
+	pub fn try_into_bool(self,) -> PRslt<bool,> {
+		match self {
+			SingleValue::Bool(flag,) => Ok(flag,),
+			other => Err(other.mismatch(SingleValueDiscriminants::Bool,),),
+		}
+	}
+
+	pub fn try_into_int(self,) -> PRslt<i32,> {
+		match self {
+			SingleValue::Integer(num,) => Ok(num,),
+			other => Err(other.mismatch(SingleValueDiscriminants::Integer,),),
+		}
+	}
+
+	fn mismatch(self, ty: SingleValueDiscriminants,) -> ParseError {
+		mismatch(&Value::Single(self,), ty,)
+	}
+}
+
+impl From<&str,> for SingleValue {
+	fn from(value: &str,) -> Self {
+		Self::String(value.to_string(),)
+	}
+}
+
+impl From<String,> for SingleValue {
+	fn from(value: String,) -> Self {
+		Self::String(value,)
+	}
+}
+
+impl From<bool,> for SingleValue {
+	fn from(value: bool,) -> Self {
+		Self::Bool(value,)
+	}
+}
+
+impl From<i32,> for SingleValue {
+	fn from(value: i32,) -> Self {
+		Self::Integer(value,)
+	}
+}
+
+/// floats are finite, so NaN and the infinities fail with a keyless
+/// [`ParseError::InvalidValue`]
+impl TryFrom<f64,> for SingleValue {
+	type Error = ParseError;
+
+	fn try_from(value: f64,) -> PRslt<Self,> {
+		if value.is_finite() {
+			Ok(Self::Float(value,),)
+		} else {
+			Err(ParseError::InvalidValue {
+				key:      String::new(),
+				value:    value.to_string(),
+				ty:       SingleValueDiscriminants::Float,
+				line:     0,
+				column:   0,
+				element:  None,
+				position: None,
+			},)
+		}
+	}
+}
+
+impl Valuable for SingleValue {
+	const APPENDS: bool = true;
+
+	fn seps() -> &'static [&'static str] {
+		&["=",]
+	}
+}
+
+pub fn parse_file<P: AsRef<Path,>,>(
+	path: P,
+	schema_path: P,
+) -> PRslt<ConfMap,> {
+	parse_file_with(path, schema_path, &ParseOptions::default(),)
+}
+
+/// same as [`parse_file`], parsing the conf file as `options` ask, see
+/// [`parse_str_with`]. the schema file is read as [`parse_file`] does
+pub fn parse_file_with<P: AsRef<Path,>,>(
+	path: P,
+	schema_path: P,
+	options: &ParseOptions,
+) -> PRslt<ConfMap,> {
+	let schema = crate::parser::schema::parse_file(schema_path,)?;
+	parse_file_against(path, &schema, options,)
+}
+
+/// parses every file of `paths` against the one schema and merges them in
+/// order, as by [`ConfMap::merge`] with `policy`. the sources of each value
+/// name the file it was read from
+pub fn parse_files_merged<P: AsRef<Path,>,>(
+	paths: &[PathBuf],
+	schema_path: P,
+	policy: MergePolicy,
+) -> PRslt<ConfMap,> {
+	let schema = crate::parser::schema::parse_file(schema_path,)?;
+	let mut merged = ConfMap::new();
+	for path in paths {
+		merged.merge(
+			parse_file_against(path, &schema, &ParseOptions::default(),)?,
+			policy,
+		)?;
+	}
+	Ok(merged,)
+}
+
+fn parse_file_against<P: AsRef<Path,>,>(
+	path: P,
+	schema: &SchemaMap,
+	options: &ParseOptions,
+) -> PRslt<ConfMap,> {
+	let mut sources = SourceMap::new();
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::file_to_mir_observed::<_, SingleValue, _,>(
+		&path,
+		&mut (
+			&mut FileSources {
+				sources:  &mut sources,
+				path:     path.as_ref().to_path_buf(),
+				included: Vec::new(),
+			},
+			&mut assignments,
+		),
+		&mir_options(options,),
+	)?;
+	let mut conf = build_conf_with(mir, schema, &assignments, options,)
+		.map_err(|err| in_included_file(err, &sources, path.as_ref(),),)?;
+	conf.2 = sources;
+	Ok(conf,)
+}
+
+/// typing errors only know the line of the value. when it was read from a
+/// file included by `path`, the error is wrapped in [`ParseError::InFile`]
+/// naming that file
+fn in_included_file(
+	err: ParseError,
+	sources: &SourceMap,
+	path: &Path,
+) -> ParseError {
+	let key = match &err {
+		ParseError::InvalidValue { key, .. }
+		| ParseError::LocalizedNumber { key, .. }
+		| ParseError::OutOfRange { key, .. }
+		| ParseError::InvalidEnumValue { key, .. }
+		| ParseError::ConflictingTypes { key, .. } => key,
+		_ => return err,
+	};
+	match sources.source_of(key,).map(|source| &source.origin,) {
+		Some(Origin::File(file,),) if file != path => {
+			ParseError::InFile { path: file.clone(), error: Box::new(err,), }
+		},
+		_ => err,
+	}
+}
+
+/// the schema lists the keys which are allowed. any of them may be left out,
+/// see [`parse_str_strict`] for requiring every one
+pub fn parse_str<S: AsSchema,>(input: &str, schema: S,) -> PRslt<ConfMap,> {
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::str_to_mir_observed::<SingleValue, _,>(
+		input,
+		&mut assignments,
+	)?;
+	build_conf(mir, schema.as_schema(), &assignments,)
+}
+
+/// same as [`parse_str`], reading lines from `reader` as they come instead of
+/// the whole text at once, so it also reads from pipes
+pub fn parse_reader<R: BufRead, S: AsSchema,>(
+	reader: R,
+	schema: S,
+) -> PRslt<ConfMap,> {
+	parse_reader_with(reader, schema, &ParseOptions::default(),)
+}
+
+/// same as [`parse_reader`], parsing as `options` ask, see [`parse_str_with`]
+pub fn parse_reader_with<R: BufRead, S: AsSchema,>(
+	reader: R,
+	schema: S,
+	options: &ParseOptions,
+) -> PRslt<ConfMap,> {
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::read_to_mir_observed::<_, SingleValue, _,>(
+		reader,
+		&mut assignments,
+		&mir_options(options,),
+	)?;
+	build_conf_with(mir, schema.as_schema(), &assignments, options,)
+}
+
+/// same as [`parse_str`], parsing as `options` ask. with
+/// [`KeyCase::Lowercase`] the keys of `schema` are matched ignoring case too,
+/// the map holds lowercase keys, see [`ConfMap::get_ci`], and errors name
+/// keys as they were written
+pub fn parse_str_with<S: AsSchema,>(
+	input: &str,
+	schema: S,
+	options: &ParseOptions,
+) -> PRslt<ConfMap,> {
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::str_to_mir_with::<SingleValue, _,>(
+		input,
+		&mut assignments,
+		&mir_options(options,),
+	)?;
+	build_conf_with(mir, schema.as_schema(), &assignments, options,)
+}
+
+/// options to build the mir with. lists declared repeatable are assigned
+/// more than once on purpose, which only the schema tells, so
+/// [`DuplicateKeyPolicy::Error`] is left to [`build_conf_with`]
+fn mir_options(options: &ParseOptions,) -> Cow<'_, ParseOptions,> {
+	match options.duplicate_keys {
+		DuplicateKeyPolicy::Error => Cow::Owned(
+			options.clone().duplicate_keys(DuplicateKeyPolicy::LastWins,),
+		),
+		_ => Cow::Borrowed(options,),
+	}
+}
+
+/// [`build_conf`] as `options` ask, see [`parse_str_with`]
+fn build_conf_with(
+	mir: StructuredInput,
+	schema: &SchemaMap,
+	assignments: &Assignments,
+	options: &ParseOptions,
+) -> PRslt<ConfMap,> {
+	build_conf_as(mir, schema, assignments, options, false,)
+		.map_err(|mut errors| errors.swap_remove(0,),)
+}
+
+/// [`build_conf_with`], going on after errors like [`build_conf_all`] when
+/// `all`
+fn build_conf_as(
+	mir: StructuredInput,
+	schema: &SchemaMap,
+	assignments: &Assignments,
+	options: &ParseOptions,
+	all: bool,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	let schema = match options.key_case {
+		KeyCase::Sensitive => Cow::Borrowed(schema,),
+		KeyCase::Lowercase => {
+			Cow::Owned(SchemaMap::from_inner(lowercase_keys(schema,),),)
+		},
+	};
+	let mut errors = Vec::new();
+	if options.duplicate_keys == DuplicateKeyPolicy::Error
+		&& let Err(err,) = reject_duplicates(&schema, assignments,)
+	{
+		if !all {
+			return Err(vec![err],);
+		}
+		errors.push(err,);
+	}
+	let order = if options.preserve_order {
+		assignment_order(&mir, assignments,)
+	} else {
+		Vec::new()
+	};
+	let typed = if all {
+		build_conf_all(mir, &schema, assignments,)
+	} else {
+		build_conf(mir, &schema, assignments,).map_err(|err| vec![err],)
+	};
+	let mut conf = match typed {
+		Ok(conf,) if errors.is_empty() => conf,
+		Ok(_,) => return Err(errors,),
+		Err(typing,) => {
+			errors.extend(typing,);
+			return Err(errors,);
+		},
+	};
+	conf.4 = order;
+	if options.expand_home
+		&& let Some(home,) = std::env::var_os("HOME",)
+	{
+		expand_home(&mut conf, Path::new(&home,),);
+	}
+	Ok(conf,)
+}
+
+/// [`ParseError::DuplicateKey`] for the earliest second assignment of a key
+/// not declared a repeatable list
+fn reject_duplicates(
+	schema: &SchemaMap,
+	assignments: &Assignments,
+) -> PRslt<(),> {
+	let duplicate = assignments
+		.0
+		.iter()
+		.filter(|(key, _,)| {
+			!matches!(
+				schema.get(key),
+				Some(SchemaValue::Scalar(leaf)) if leaf.repeatable
+			)
+		},)
+		.min_by_key(|(_, values,)| values[1].1,);
+	match duplicate {
+		Some((key, values,),) => Err(ParseError::DuplicateKey {
+			key:         assignments.as_written(key,),
+			first_line:  values[0].1,
+			second_line: values[1].1,
+		},),
+		None => Ok((),),
+	}
+}
+
+/// dotted keys of the leaves of `mir` by the line and column they were first
+/// assigned on
+fn assignment_order(
+	mir: &StructuredInput,
+	assignments: &Assignments,
+) -> Vec<String,> {
+	fn walk(
+		prefix: Option<&str,>,
+		mir: &StructuredInput,
+		assignments: &Assignments,
+		leaves: &mut Vec<(usize, usize, String,),>,
+	) {
+		for (key, value,) in mir {
+			let dotted = match prefix {
+				Some(base,) => format!("{base}.{key}"),
+				None => key.clone(),
+			};
+			match value {
+				TreeValue::Scalar((_, line, column,),) => {
+					let (line, column,) = match assignments.0.get(&dotted,) {
+						Some(values,) => (values[0].1, values[0].2,),
+						None => (*line, *column,),
+					};
+					leaves.push((line, column, dotted,),);
+				},
+				TreeValue::Map(children,) => {
+					walk(Some(&dotted,), children, assignments, leaves,)
+				},
+			}
+		}
+	}
+
+	let mut leaves = Vec::new();
+	walk(None, mir, assignments, &mut leaves,);
+	leaves.sort_unstable();
+	leaves.into_iter().map(|(_, _, dotted,)| dotted,).collect()
+}
+
+/// replaces the leading `~` of every `Path` value below `map` by `home`
+fn expand_home(map: &mut BTreeMap<String, ConfValue,>, home: &Path,) {
+	let expand = |single: &mut SingleValue| {
+		if let SingleValue::Path(path,) = single
+			&& let Ok(rest,) = path.strip_prefix("~",)
+		{
+			*path = home.join(rest,);
+		}
+	};
+	for value in map.values_mut() {
+		match value {
+			TreeValue::Map(children,) => expand_home(children, home,),
+			TreeValue::Scalar(Value::Single(single,),) => expand(single,),
+			TreeValue::Scalar(Value::Collection(items,),) => {
+				items.iter_mut().for_each(expand,)
+			},
+		}
+	}
+}
+
+/// copy of `schema` with every key lowercased. of keys differing only in
+/// case, the last one is kept
+fn lowercase_keys(
+	schema: &BTreeMap<String, SchemaValue,>,
+) -> BTreeMap<String, SchemaValue,> {
+	schema
+		.iter()
+		.map(|(key, value,)| {
+			let value = match value {
+				TreeValue::Map(children,) => {
+					TreeValue::Map(lowercase_keys(children,),)
+				},
+				leaf => leaf.clone(),
+			};
+			(key.to_lowercase(), value,)
+		},)
+		.collect()
+}
+
+/// same as [`parse_str`], but every malformed line is reported rather than
+/// the first one only, at most [`LINE_ERROR_BUDGET`] errors per line. typing
+/// against the schema still stops at its first error
+pub fn parse_str_collecting<S: AsSchema,>(
+	input: &str,
+	schema: S,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	parse_str_collecting_with(input, schema, &ParseOptions::default(),)
+}
+
+/// same as [`parse_str_collecting`], parsing as `options` ask, see
+/// [`parse_str_with`]
+pub fn parse_str_collecting_with<S: AsSchema,>(
+	input: &str,
+	schema: S,
+	options: &ParseOptions,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::str_to_mir_collecting::<SingleValue, _,>(
+		input,
+		&mut assignments,
+		&mir_options(options,),
+	)?;
+	build_conf_with(mir, schema.as_schema(), &assignments, options,)
+		.map_err(|err| vec![err],)
+}
+
+/// same as [`parse_str`], but parsing goes on after every error which only
+/// concerns one line or one key: malformed lines, unknown keys and values
+/// which do not fit the schema. every error is returned, ordered by line.
+/// unlike [`parse_str_collecting`], a malformed line does not keep the other
+/// lines from being typed
+pub fn parse_str_all_errors<S: AsSchema,>(
+	input: &str,
+	schema: S,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	parse_str_all_errors_with(input, schema, &ParseOptions::default(),)
+}
+
+/// same as [`parse_str_all_errors`], parsing as `options` ask, see
+/// [`parse_str_with`]
+pub fn parse_str_all_errors_with<S: AsSchema,>(
+	input: &str,
+	schema: S,
+	options: &ParseOptions,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	let mut assignments = Assignments::default();
+	let (mir, mut errors,) = crate::parser::core::str_to_mir_all_errors::<
+		SingleValue,
+		_,
+	>(
+		input, &mut assignments, &mir_options(options,),
+	);
+	match build_conf_as(mir, schema.as_schema(), &assignments, options, true,) {
+		Ok(conf,) if errors.is_empty() => return Ok(conf,),
+		Ok(_,) => {},
+		Err(typing,) => errors.extend(typing,),
+	}
+	Err(crate::parser::core::by_line(errors,),)
+}
+
+/// same as [`parse_str_all_errors`], reading the file at `path` and the files
+/// it includes. errors in an included file are wrapped in
+/// [`ParseError::InFile`] naming it
+pub fn parse_file_all_errors<P: AsRef<Path,>, S: AsSchema,>(
+	path: P,
+	schema: S,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	parse_file_all_errors_with(path, schema, &ParseOptions::default(),)
+}
+
+/// same as [`parse_file_all_errors`], parsing as `options` ask, see
+/// [`parse_str_with`]
+pub fn parse_file_all_errors_with<P: AsRef<Path,>, S: AsSchema,>(
+	path: P,
+	schema: S,
+	options: &ParseOptions,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	let mut sources = SourceMap::new();
+	let mut assignments = Assignments::default();
+	let (mir, mut errors,) =
+		crate::parser::core::file_to_mir_all_errors::<_, SingleValue, _,>(
+			&path,
+			&mut (
+				&mut FileSources {
+					sources:  &mut sources,
+					path:     path.as_ref().to_path_buf(),
+					included: Vec::new(),
+				},
+				&mut assignments,
+			),
+			&mir_options(options,),
+		);
+	match build_conf_as(mir, schema.as_schema(), &assignments, options, true,) {
+		Ok(mut conf,) if errors.is_empty() => {
+			conf.2 = sources;
+			return Ok(conf,);
+		},
+		Ok(_,) => {},
+		Err(typing,) => errors.extend(
+			typing
+				.into_iter()
+				.map(|err| in_included_file(err, &sources, path.as_ref(),),),
+		),
+	}
+	Err(crate::parser::core::by_line(errors,),)
+}
+
+/// same as [`parse_str`], but every key the schema declares must be set
+pub fn parse_str_strict<S: AsSchema,>(
+	input: &str,
+	schema: S,
+) -> PRslt<ConfMap,> {
+	let schema = schema.as_schema();
+	let conf = parse_str(input, schema,)?;
+	conf.require_keys(schema,)?;
+	Ok(conf,)
+}
+
+/// same as [`parse_str`] for lines taken out of a larger document. each line
+/// comes with its number in that document, which errors report instead of a
+/// count from 1
+pub fn parse_lines<'a, S: AsSchema,>(
+	lines: impl IntoIterator<Item = (usize, &'a str,),>,
+	schema: S,
+) -> PRslt<ConfMap,> {
+	parse_lines_with(lines, schema, &ParseOptions::default(),)
+}
+
+/// same as [`parse_lines`], parsing as `options` ask, see [`parse_str_with`]
+pub fn parse_lines_with<'a, S: AsSchema,>(
+	lines: impl IntoIterator<Item = (usize, &'a str,),>,
+	schema: S,
+	options: &ParseOptions,
+) -> PRslt<ConfMap,> {
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::lines_to_mir_observed::<SingleValue, _,>(
+		lines,
+		&mut assignments,
+		&mir_options(options,),
+	)?;
+	build_conf_with(mir, schema.as_schema(), &assignments, options,)
+}
+
+/// same as [`parse_str`], after rewriting outdated keys with `migrations`.
+/// every rewritten key is reported as a [`Warning`]
+pub fn parse_str_with_migrations<S: AsSchema,>(
+	input: &str,
+	schema: S,
+	migrations: &Migrations,
+) -> PRslt<(ConfMap, Vec<Warning,>,),> {
+	let mut assignments = Assignments::default();
+	let mut mir = crate::parser::core::str_to_mir_observed::<SingleValue, _,>(
+		input,
+		&mut assignments,
+	)?;
+	let warnings = migrations.apply(&mut mir,)?;
+	assignments.migrate(&warnings,);
+	Ok((build_conf(mir, schema.as_schema(), &assignments,)?, warnings,),)
+}
+
+/// same as [`parse_str`], additionally reporting [`ParseStats`] about the run
+pub fn parse_str_instrumented<S: AsSchema,>(
+	input: &str,
+	schema: S,
+) -> PRslt<(ConfMap, ParseStats,),> {
+	let mut stats = ParseStats::default();
+	let mut assignments = Assignments::default();
+
+	let started = Instant::now();
+	let mir = crate::parser::core::str_to_mir_observed::<SingleValue, _,>(
+		input,
+		&mut (&mut stats, &mut assignments,),
+	)?;
+	stats.mir_time = started.elapsed();
+
+	let started = Instant::now();
+	let conf = build_conf(mir, schema.as_schema(), &assignments,)?;
+	stats.typing_time = started.elapsed();
+
+	stats.count_collection_elements(&conf,);
+	Ok((conf, stats,),)
+}
+
+/// `conf` as a conf file, the text of [`ConfMap::canonicalize`]. parsing it
+/// against the schema `conf` was parsed with yields an equal map. text
+/// which would not read back as itself, string elements of collections
+/// included, is written as a quoted string, and other elements have their
+/// commas escaped as `\\,`
+pub fn to_string(conf: &ConfMap,) -> String {
+	conf.canonicalize()
+}
+
+/// writes [`to_string`] of `conf` to `path`, replacing the file. secrets are
+/// written in clear
+pub fn write_file<P: AsRef<Path,>,>(conf: &ConfMap, path: P,) -> PRslt<(),> {
+	std::fs::write(path, to_string(conf,),)?;
+	Ok((),)
+}
+
+/// checks `conf`, which may have been built or merged in memory, against
+/// `schema` without consuming it. reports every unknown key, every mistyped
+/// value and, in one [`ParseError::MissingKey`], every required key left
+/// out. keys are full dotted paths, and lines and columns are 0 as values
+/// carry none
+pub fn validate(
+	conf: &ConfMap,
+	schema: &SchemaMap,
+) -> Result<(), Vec<ParseError,>,> {
+	fn walk(
+		prefix: Option<&str,>,
+		conf: &BTreeMap<String, ConfValue,>,
+		schema: &BTreeMap<String, SchemaValue,>,
+		missing: &mut Vec<String,>,
+		errors: &mut Vec<ParseError,>,
+	) {
+		let dotted = |key: &str| match prefix {
+			Some(base,) => format!("{base}.{key}"),
+			None => key.to_string(),
+		};
+
+		for key in conf.keys() {
+			if !schema.contains_key(key,) {
+				errors.push(ParseError::UnknownKey {
+					key:        dotted(key,),
+					lines:      Vec::new(),
+					suggestion: closest_key(key, schema.keys(),)
+						.map(|known| dotted(known,),),
+				},);
+			}
+		}
+
+		for (key, schema_value,) in schema {
+			let dotted_key = dotted(key,);
+			match (schema_value, conf.get(key,),) {
+				(TreeValue::Scalar(leaf,), None,) => {
+					if !leaf.optional {
+						missing.push(dotted_key,);
+					}
+				},
+				(
+					TreeValue::Scalar(leaf,),
+					Some(TreeValue::Scalar(value,),),
+				) => {
+					if let Err(err,) = check_value(&dotted_key, leaf, value,) {
+						errors.push(err,);
+					}
+				},
+				(TreeValue::Map(children,), None,) => {
+					walk(
+						Some(&dotted_key,),
+						&BTreeMap::new(),
+						children,
+						missing,
+						errors,
+					);
+				},
+				(TreeValue::Map(children,), Some(TreeValue::Map(nested,),),) =>
+				{
+					walk(Some(&dotted_key,), nested, children, missing, errors,);
+				},
+				_ => errors.push(ParseError::ConflictingTypes {
+					key:    dotted_key,
+					line:   0,
+					column: 0,
+					origin: None,
+				},),
+			}
+		}
+	}
+
+	let mut missing = Vec::new();
+	let mut errors = Vec::new();
+	walk(None, conf, schema, &mut missing, &mut errors,);
+	if !missing.is_empty() {
+		errors.push(ParseError::MissingKey { keys: missing, },);
+	}
+	if errors.is_empty() { Ok((),) } else { Err(errors,) }
+}
+
+/// `value` against the declaration of `key`. a placeholder passes where
+/// the key is resolvable
+fn check_value(
+	key: &str,
+	leaf: &SchemaLeaf,
+	value: &Value<SingleValue,>,
+) -> PRslt<(),> {
+	let mismatch = |ty, value: &Value<SingleValue,>| ParseError::InvalidValue {
+		key: key.to_string(),
+		value: render_value(value,),
+		ty,
+		line: 0,
+		column: 0,
+		element: None,
+		position: None,
+	};
+	let element_mismatch = |ty, item: &SingleValue, idx, count| {
+		let err = mismatch(ty, &Value::Single(item.clone(),),);
+		at_element(err, idx, count, leaf.label(idx,),)
+	};
+	let fits = |ty: SingleValueDiscriminants, single: &SingleValue| {
+		SingleValueDiscriminants::from(single,) == ty
+	};
+
+	if leaf.unrecognized.is_some() {
+		return Ok((),);
+	}
+	match (&leaf.ty, value,) {
+		(_, Value::Single(SingleValue::Deferred { .. },),)
+			if leaf.resolvable =>
+		{
+			Ok((),)
+		},
+		(Value::Single(ty,), Value::Single(single,),) => {
+			if fits(*ty, single,) {
+				check_constraints(key, leaf, 0, single, 0,)
+			} else {
+				Err(mismatch(*ty, value,),)
+			}
+		},
+		(Value::Collection(kinds,), Value::Collection(items,),)
+			if leaf.list =>
+		{
+			match items.iter().position(|item| !fits(kinds[0], item,),) {
+				Some(idx,) => Err(element_mismatch(
+					kinds[0],
+					&items[idx],
+					idx,
+					items.len(),
+				),),
+				None => items.iter().try_for_each(|item| {
+					check_constraints(key, leaf, 0, item, 0,)
+				},),
+			}
+		},
+		(Value::Collection(kinds,), Value::Collection(items,),)
+			if kinds.len() == items.len() =>
+		{
+			match kinds
+				.iter()
+				.zip(items,)
+				.position(|(ty, item,)| !fits(*ty, item,),)
+			{
+				Some(idx,) => Err(element_mismatch(
+					kinds[idx],
+					&items[idx],
+					idx,
+					kinds.len(),
+				),),
+				None => items.iter().enumerate().try_for_each(|(idx, item,)| {
+					check_constraints(key, leaf, idx, item, 0,)
+				},),
+			}
+		},
+		(Value::Collection(kinds,), Value::Collection(items,),)
+			if !leaf.list =>
+		{
+			Err(ParseError::ArityMismatch {
+				key:      key.to_string(),
+				expected: kinds.len(),
+				found:    items.len(),
+				line:     0,
+			},)
+		},
+		(Value::Single(ty,), Value::Collection(_,),) => {
+			Err(mismatch(*ty, value,),)
+		},
+		(Value::Collection(kinds,), _,) => Err(mismatch(kinds[0], value,),),
+	}
+}
+
+/// what [`validate_paths`] asks of every `Path` value
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq,)]
+pub enum PathRequirement {
+	/// anything, every path passes
+	#[default]
+	None,
+	Exists,
+	IsFile,
+	IsDir,
 }
 
-impl Valuable for SingleValue {
-	fn sep() -> &'static str {
-		"="
+impl Display for PathRequirement {
+	/// completes "the path must ..."
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			Self::None => write!(f, "be any path"),
+			Self::Exists => write!(f, "exist"),
+			Self::IsFile => write!(f, "be a file"),
+			Self::IsDir => write!(f, "be a directory"),
+		}
 	}
 }
 
-pub fn parse_file<P: AsRef<Path,>,>(
-	path: P,
-	schema_path: P,
-) -> PRslt<ConfMap,> {
-	let mir = crate::parser::core::file_to_mir::<_, SingleValue,>(path,)?;
-	let schema = crate::parser::schema::parse_file(schema_path,)?;
-	mir.into_conf(&schema,)
+/// checks every `Path` value of `conf`, collection elements included,
+/// against the file system. each path failing `requirement` is reported as
+/// [`ParseError::UnmetPathRequirement`] under its dotted key. relative paths
+/// are taken from the current directory
+pub fn validate_paths(
+	conf: &ConfMap,
+	requirement: PathRequirement,
+) -> Result<(), Vec<ParseError,>,> {
+	fn walk(
+		prefix: Option<&str,>,
+		map: &BTreeMap<String, ConfValue,>,
+		requirement: PathRequirement,
+		errors: &mut Vec<ParseError,>,
+	) {
+		for (key, value,) in map {
+			let dotted_key = match prefix {
+				Some(base,) => format!("{base}.{key}"),
+				None => key.clone(),
+			};
+			let items = match value {
+				TreeValue::Map(children,) => {
+					walk(Some(&dotted_key,), children, requirement, errors,);
+					continue;
+				},
+				TreeValue::Scalar(Value::Single(single,),) => {
+					std::slice::from_ref(single,)
+				},
+				TreeValue::Scalar(Value::Collection(items,),) => items,
+			};
+			for item in items {
+				let SingleValue::Path(path,) = item else {
+					continue;
+				};
+				let met = match requirement {
+					PathRequirement::None => true,
+					PathRequirement::Exists => path.exists(),
+					PathRequirement::IsFile => path.is_file(),
+					PathRequirement::IsDir => path.is_dir(),
+				};
+				if !met {
+					errors.push(ParseError::UnmetPathRequirement {
+						key: dotted_key.clone(),
+						path: path.clone(),
+						requirement,
+					},);
+				}
+			}
+		}
+	}
+
+	let mut errors = Vec::new();
+	walk(None, conf, requirement, &mut errors,);
+	if errors.is_empty() { Ok((),) } else { Err(errors,) }
 }
 
-pub fn parse_str(input: &str, schema: SchemaMap,) -> PRslt<ConfMap,> {
-	let mir = crate::parser::core::str_to_mir::<SingleValue,>(input,)?;
-	mir.into_conf(&schema,)
+impl ConfMap {
+	/// fails with [`ParseError::MissingKey`] listing every leaf of `schema`
+	/// which has no value and is not optional
+	pub fn require_keys(&self, schema: &SchemaMap,) -> PRslt<(),> {
+		fn collect_missing(
+			prefix: Option<&str,>,
+			schema: &BTreeMap<String, SchemaValue,>,
+			conf: Option<&BTreeMap<String, ConfValue,>,>,
+			missing: &mut Vec<String,>,
+		) {
+			for (key, schema_value,) in schema {
+				let dotted_key = match prefix {
+					Some(base,) => format!("{base}.{key}"),
+					None => key.clone(),
+				};
+				let conf_value = conf.and_then(|conf| conf.get(key,),);
+
+				match schema_value {
+					TreeValue::Scalar(leaf,) => {
+						if !leaf.optional
+							&& !matches!(
+								conf_value,
+								Some(TreeValue::Scalar(_,))
+							) {
+							missing.push(dotted_key,);
+						}
+					},
+					TreeValue::Map(children,) => {
+						let nested = match conf_value {
+							Some(TreeValue::Map(nested,),) => Some(nested,),
+							_ => None,
+						};
+						collect_missing(
+							Some(&dotted_key,),
+							children,
+							nested,
+							missing,
+						);
+					},
+				}
+			}
+		}
+
+		let mut missing = Vec::new();
+		collect_missing(None, schema, Some(self,), &mut missing,);
+		if missing.is_empty() {
+			Ok((),)
+		} else {
+			Err(ParseError::MissingKey { keys: missing, },)
+		}
+	}
 }
 
 pub trait BuildConf {
 	fn into_conf(self, schema: &SchemaMap,) -> PRslt<ConfMap,>;
 }
 
-fn format_unknown_key_path(
-	root: &str,
-	value: &TreeValue<(String, usize,),>,
-) -> String {
+fn format_unknown_key_path(root: &str, value: &TreeValue<MirLeaf,>,) -> String {
 	let mut path = root.to_string();
 	let mut current = value;
 
@@ -146,6 +1591,7 @@ fn format_unknown_key_path(
 trait SchemaLookup {
 	fn lookup(&self, key: &str,) -> Option<&SchemaValue,>;
 	fn is_empty(&self,) -> bool;
+	fn known_keys(&self,) -> impl Iterator<Item = &String,>;
 }
 
 impl SchemaLookup for SchemaMap {
@@ -156,6 +1602,10 @@ impl SchemaLookup for SchemaMap {
 	fn is_empty(&self,) -> bool {
 		self.is_empty()
 	}
+
+	fn known_keys(&self,) -> impl Iterator<Item = &String,> {
+		self.keys()
+	}
 }
 
 impl SchemaLookup for BTreeMap<String, SchemaValue,> {
@@ -166,13 +1616,197 @@ impl SchemaLookup for BTreeMap<String, SchemaValue,> {
 	fn is_empty(&self,) -> bool {
 		self.is_empty()
 	}
+
+	fn known_keys(&self,) -> impl Iterator<Item = &String,> {
+		self.keys()
+	}
+}
+
+/// the known key closest to `key`, if it is close enough to be a typo of
+/// it: one edit for keys of 3 chars, two from 4 chars on. ties go to the
+/// first in order
+fn closest_key<'a,>(
+	key: &str,
+	known: impl Iterator<Item = &'a String,>,
+) -> Option<&'a String,> {
+	let limit = match key.chars().count() {
+		0..=2 => return None,
+		3 => 1,
+		_ => 2,
+	};
+	known
+		.map(|candidate| (edit_distance(key, candidate,), candidate,),)
+		.filter(|(distance, _,)| *distance <= limit,)
+		.min_by_key(|(distance, _,)| *distance,)
+		.map(|(_, candidate,)| candidate,)
+}
+
+/// optimal string alignment distance: insertions, deletions, substitutions
+/// and swaps of two adjacent chars each count as one edit
+fn edit_distance(a: &str, b: &str,) -> usize {
+	let a: Vec<char,> = a.chars().collect();
+	let b: Vec<char,> = b.chars().collect();
+	let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+	for (i, row,) in rows.iter_mut().enumerate() {
+		row[0] = i;
+	}
+	for (j, cell,) in rows[0].iter_mut().enumerate() {
+		*cell = j;
+	}
+	for i in 1..=a.len() {
+		for j in 1..=b.len() {
+			let cost = usize::from(a[i - 1] != b[j - 1],);
+			let mut distance = (rows[i - 1][j] + 1)
+				.min(rows[i][j - 1] + 1,)
+				.min(rows[i - 1][j - 1] + cost,);
+			if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+				distance = distance.min(rows[i - 2][j - 2] + 1,);
+			}
+			rows[i][j] = distance;
+		}
+	}
+	rows[a.len()][b.len()]
+}
+
+/// every value of each key assigned more than once, oldest first, by dotted
+/// key. the mir only keeps the last one, but a list declared repeatable is
+/// built from all of them
+#[derive(Debug, Default, Clone,)]
+pub(crate) struct Assignments(
+	BTreeMap<String, Vec<MirLeaf,>,>,
+	/// segments of the first key written in another case than the dotted
+	/// key it was folded into
+	BTreeMap<String, Vec<String,>,>,
+	/// line of the first `+=` assignment of each dotted key
+	BTreeMap<String, usize,>,
+);
+
+impl Assignments {
+	/// dotted `key`, a full key or a section, as it was written. `key` is
+	/// kept when it was written as it is
+	fn as_written(&self, key: &str,) -> String {
+		let depth = key.split('.',).count();
+		self.1
+			.range(key.to_string()..,)
+			.take_while(|(folded, _,)| folded.starts_with(key,),)
+			.find(|(folded, _,)| {
+				folded.len() == key.len()
+					|| folded[key.len()..].starts_with('.',)
+			},)
+			.map_or_else(
+				|| key.to_string(),
+				|(_, written,)| written[..depth].join(".",),
+			)
+	}
+
+	/// the values of each key moved to where the `warnings` of applied
+	/// migrations moved it, and those of dropped keys discarded
+	fn migrate(&mut self, warnings: &[Warning],) {
+		/// entries of `from` and the keys below it moved below `to`, or
+		/// dropped without one
+		fn move_keys<T: Default,>(
+			map: &mut BTreeMap<String, T,>,
+			from: &str,
+			to: Option<&str,>,
+		) {
+			let moved: Vec<String,> = map
+				.keys()
+				.filter(|key| {
+					key.strip_prefix(from,).is_some_and(|rest| {
+						rest.is_empty() || rest.starts_with('.',)
+					},)
+				},)
+				.cloned()
+				.collect();
+			for key in moved {
+				let entry = map.remove(&key,).unwrap_or_default();
+				if let Some(to,) = to {
+					map.insert(format!("{to}{}", &key[from.len()..]), entry,);
+				}
+			}
+		}
+
+		for warning in warnings {
+			let Warning::MigratedKey { from, to, .. } = warning else {
+				continue;
+			};
+			move_keys(&mut self.0, from, to.as_deref(),);
+			move_keys(&mut self.2, from, to.as_deref(),);
+		}
+	}
+
+	/// `err` naming its key as written instead of as folded
+	fn written_key(&self, mut err: ParseError,) -> ParseError {
+		if let ParseError::UnknownKey { key, .. }
+		| ParseError::ConflictingTypes { key, .. }
+		| ParseError::InvalidValue { key, .. }
+		| ParseError::LocalizedNumber { key, .. }
+		| ParseError::OutOfRange { key, .. }
+		| ParseError::InvalidEnumValue { key, .. }
+		| ParseError::NotRepeatable { key, .. } = &mut err
+		{
+			*key = self.as_written(key,);
+		}
+		err
+	}
+}
+
+impl MirObserver for Assignments {
+	fn entry_appended(&mut self, segments: &[String], line: usize,) {
+		self.2.entry(segments.join(".",),).or_insert(line,);
+	}
+
+	fn key_folded(&mut self, segments: &[String], written: &[String],) {
+		self.1.entry(segments.join(".",),).or_insert_with(|| written.to_vec(),);
+	}
+
+	fn entry_overridden(
+		&mut self,
+		segments: &[String],
+		previous: (&str, usize, usize,),
+		current: (&str, usize, usize,),
+	) {
+		let values = self.0.entry(segments.join(".",),).or_default();
+		if values.is_empty() {
+			values.push((previous.0.to_string(), previous.1, previous.2,),);
+		}
+		values.push((current.0.to_string(), current.1, current.2,),);
+	}
+}
+
+/// a value and a section for the same key, at the first assignment of
+/// `mir_value`
+fn section_conflict(key: &str, mir_value: &TreeValue<MirLeaf,>,) -> ParseError {
+	let (line, column,) = first_position(mir_value,);
+	ParseError::ConflictingTypes {
+		key: key.to_string(),
+		line,
+		column,
+		origin: None,
+	}
+}
+
+/// line and column of the earliest assignment below `mir_value`
+fn first_position(mir_value: &TreeValue<MirLeaf,>,) -> (usize, usize,) {
+	match mir_value {
+		TreeValue::Scalar((_, line, column,),) => (*line, *column,),
+		TreeValue::Map(children,) => {
+			children.values().map(first_position,).min().unwrap_or_default()
+		},
+	}
 }
 
+/// labels and repeatable keys are collected into `meta` on the way. a key
+/// with an error is left out and the error pushed onto `errors`, so every
+/// key is checked
 fn build_conf_map<L: SchemaLookup + ?Sized,>(
 	input: StructuredInput,
 	schema: &L,
 	prefix: Option<&str,>,
-) -> PRslt<BTreeMap<String, ConfValue,>,> {
+	assignments: &Assignments,
+	meta: &mut ConfMap,
+	errors: &mut Vec<ParseError,>,
+) -> BTreeMap<String, ConfValue,> {
 	let mut conf_map = BTreeMap::new();
 
 	for (key, mir_value,) in input.into_iter() {
@@ -182,50 +1816,120 @@ fn build_conf_map<L: SchemaLookup + ?Sized,>(
 		};
 
 		let Some(schema_value,) = schema.lookup(&key,) else {
-			if prefix.is_none() && !schema.is_empty() {
-				return Err(ParseError::UnknownKey {
-					key,
-					lines: mir_value.get_lines_of_key(),
-				},);
-			}
-
-			let unknown_key = format_unknown_key_path(&dotted_key, &mir_value,);
-			return Err(ParseError::UnknownKey {
-				key:   unknown_key,
+			let suggestion = closest_key(&key, schema.known_keys(),).map(
+				|known| match prefix {
+					Some(base,) => format!("{base}.{known}"),
+					None => known.clone(),
+				},
+			);
+			let key = if prefix.is_none() && !schema.is_empty() {
+				key
+			} else {
+				format_unknown_key_path(&dotted_key, &mir_value,)
+			};
+			errors.push(ParseError::UnknownKey {
+				key,
 				lines: mir_value.get_lines_of_key(),
+				suggestion,
 			},);
+			continue;
 		};
 
 		let conf_value = match schema_value {
-			TreeValue::Scalar(schema_value,) => {
-				inject_payload(&dotted_key, schema_value, mir_value,)?
+			TreeValue::Scalar(leaf,) => {
+				if let Some(leaf_labels,) = &leaf.labels {
+					meta.1.insert(dotted_key.clone(), leaf_labels.clone(),);
+				}
+				if leaf.repeatable {
+					meta.3.insert(dotted_key.clone(),);
+				} else if let Some(line,) = assignments.2.get(&dotted_key,) {
+					errors.push(ParseError::NotRepeatable {
+						key:  dotted_key,
+						line: *line,
+					},);
+					continue;
+				}
+				let payload = match assignments.0.get(&dotted_key,) {
+					Some(values,) if leaf.repeatable => {
+						repeated_payload(&dotted_key, leaf, values,)
+					},
+					_ => inject_payload(&dotted_key, leaf, mir_value,),
+				};
+				match payload {
+					Ok(payload,) => payload,
+					Err(err,) => {
+						errors.push(err,);
+						continue;
+					},
+				}
 			},
 			TreeValue::Map(schema_map,) => {
-				let TreeValue::Map(nested_input,) = mir_value else { todo!() };
-				let nested = build_conf_map(
+				let nested_input = match mir_value {
+					TreeValue::Map(nested_input,) => nested_input,
+					value => {
+						errors.push(section_conflict(&dotted_key, &value,),);
+						continue;
+					},
+				};
+				TreeValue::Map(build_conf_map(
 					nested_input,
 					schema_map,
 					Some(&dotted_key,),
-				)?;
-				TreeValue::Map(nested,)
+					assignments,
+					meta,
+					errors,
+				),)
 			},
 		};
 
 		conf_map.insert(key, conf_value,);
 	}
 
-	Ok(conf_map,)
+	conf_map
 }
 
+/// the mir alone only knows the last assignment of each key, so a list
+/// declared repeatable only holds the elements of that one
 impl BuildConf for StructuredInput {
 	fn into_conf(self, schema: &SchemaMap,) -> PRslt<ConfMap,> {
-		let conf_map = build_conf_map(self, schema, None,)?;
-		Ok(ConfMap::from(&conf_map,),)
+		build_conf(self, schema, &Assignments::default(),)
+	}
+}
+
+/// fails with the first error [`build_conf_all`] finds
+pub(crate) fn build_conf(
+	mir: StructuredInput,
+	schema: &SchemaMap,
+	assignments: &Assignments,
+) -> PRslt<ConfMap,> {
+	build_conf_all(mir, schema, assignments,)
+		.map_err(|mut errors| errors.swap_remove(0,),)
+}
+
+/// fails only with a non-empty list, in the order the keys are visited
+fn build_conf_all(
+	mir: StructuredInput,
+	schema: &SchemaMap,
+	assignments: &Assignments,
+) -> Result<ConfMap, Vec<ParseError,>,> {
+	let mut conf = ConfMap::new();
+	let mut errors = Vec::new();
+	conf.0 =
+		build_conf_map(mir, schema, None, assignments, &mut conf, &mut errors,);
+	if errors.is_empty() {
+		Ok(conf,)
+	} else if assignments.1.is_empty() {
+		Err(errors,)
+	} else {
+		Err(errors
+			.into_iter()
+			.map(|err| assignments.written_key(err,),)
+			.collect(),)
 	}
 }
 
 impl SingleValueDiscriminants {
-	fn into_payload(
+	pub(crate) fn into_payload(
 		self,
 		key: &str,
 		value: &str,
@@ -233,67 +1937,644 @@ impl SingleValueDiscriminants {
 	) -> PRslt<SingleValue,> {
 		Ok(match self {
 			Self::String => SingleValue::String(value.to_string(),),
-			Self::Bool => SingleValue::Bool(value == "true",),
+			Self::Bool => {
+				SingleValue::Bool(parse_str_as_bool(key, value, line,)?,)
+			},
 			Self::Integer => {
-				SingleValue::Integer(parse_str_as_i32(key, value, line,)?,)
+				SingleValue::Integer(parse_str_as_int(key, value, self, line,)?,)
+			},
+			Self::UInteger => SingleValue::UInteger(parse_str_as_int(
+				key, value, self, line,
+			)?,),
+			Self::Long => {
+				SingleValue::Long(parse_str_as_int(key, value, self, line,)?,)
+			},
+			Self::Float => {
+				SingleValue::Float(parse_str_as_f64(key, value, line,)?,)
+			},
+			Self::Secret => SingleValue::Secret(Secret::new(value,),),
+			Self::Path if value.is_empty() => {
+				return Err(ParseError::InvalidValue {
+					key: key.to_string(),
+					value: String::new(),
+					ty: self,
+					line,
+					column: 0,
+					element: None,
+					position: None,
+				},);
+			},
+			Self::Path => SingleValue::Path(PathBuf::from(value,),),
+			Self::Duration => {
+				SingleValue::Duration(parse_str_as_duration(key, value, line,)?,)
+			},
+			Self::IpAddr => {
+				SingleValue::IpAddr(parse_str_as(key, value, self, line,)?,)
+			},
+			Self::SocketAddr => {
+				SingleValue::SocketAddr(parse_str_as(key, value, self, line,)?,)
+			},
+			// rejected by `FromStr`, so only a schema built in code has it,
+			// and no value is of that type
+			Self::Deferred => {
+				return Err(ParseError::UnknownType {
+					key: key.to_string(),
+					name: self.to_string(),
+					line,
+				},);
 			},
 		},)
 	}
 }
 
-fn parse_str_as_i32(key: &str, value: &str, line: usize,) -> PRslt<i32,> {
-	value.parse::<i32>().map_err(|_| ParseError::InvalidValue {
+/// only the exact literals `true` and `false`, the way they are rendered.
+/// `yes`, `1` or `True` are rejected rather than read as either
+fn parse_str_as_bool(key: &str, value: &str, line: usize,) -> PRslt<bool,> {
+	match value {
+		"true" => Ok(true,),
+		"false" => Ok(false,),
+		_ => Err(ParseError::InvalidValue {
+			key: key.to_string(),
+			value: value.to_string(),
+			ty: SingleValueDiscriminants::Bool,
+			line,
+			column: 0,
+			element: None,
+			position: None,
+		},),
+	}
+}
+
+/// `value` through its `FromStr`, which for addresses accepts exactly the
+/// text their `Display` writes
+fn parse_str_as<T: FromStr,>(
+	key: &str,
+	value: &str,
+	ty: SingleValueDiscriminants,
+	line: usize,
+) -> PRslt<T,> {
+	value.parse().map_err(|_| ParseError::InvalidValue {
+		key: key.to_string(),
+		value: value.to_string(),
+		ty,
+		line,
+		column: 0,
+		element: None,
+		position: None,
+	},)
+}
+
+fn parse_str_as_duration(
+	key: &str,
+	value: &str,
+	line: usize,
+) -> PRslt<Duration,> {
+	parse_duration(value,).ok_or_else(|| ParseError::InvalidValue {
 		key: key.to_string(),
 		value: value.to_string(),
-		ty: SingleValueDiscriminants::Integer,
+		ty: SingleValueDiscriminants::Duration,
 		line,
+		column: 0,
+		element: None,
+		position: None,
+	},)
+}
+
+/// an integer which does not fit `ty`, a negative one for `UInt` included,
+/// is [`ParseError::OutOfRange`] rather than an invalid value
+fn parse_str_as_int<T: FromStr<Err = ParseIntError,>,>(
+	key: &str,
+	value: &str,
+	ty: SingleValueDiscriminants,
+	line: usize,
+) -> PRslt<T,> {
+	value.parse::<T>().map_err(|err| {
+		let out_of_range = matches!(
+			err.kind(),
+			IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+		) || value.parse::<i128>().is_ok();
+		if out_of_range {
+			let (min, max,) = integer_bounds(ty,);
+			ParseError::OutOfRange {
+				key: key.to_string(),
+				value: value.to_string(),
+				ty,
+				min: Some(min,),
+				max: Some(max,),
+				line,
+			}
+		} else {
+			invalid_number(key, value, ty, line,)
+		}
+	},)
+}
+
+/// smallest and largest value of an integer type
+fn integer_bounds(ty: SingleValueDiscriminants,) -> (String, String,) {
+	match ty {
+		SingleValueDiscriminants::UInteger => {
+			(u64::MIN.to_string(), u64::MAX.to_string(),)
+		},
+		SingleValueDiscriminants::Long => {
+			(i64::MIN.to_string(), i64::MAX.to_string(),)
+		},
+		_ => (i32::MIN.to_string(), i32::MAX.to_string(),),
+	}
+}
+
+/// anything `f64::from_str` accepts, scientific notation and a leading `+`
+/// included, except `NaN` and the infinities, which no renderer could write
+/// back as a number
+fn parse_str_as_f64(key: &str, value: &str, line: usize,) -> PRslt<f64,> {
+	value.parse::<f64>().ok().filter(|num| num.is_finite(),).ok_or_else(|| {
+		invalid_number(key, value, SingleValueDiscriminants::Float, line,)
+	},)
+}
+
+/// [`ParseError::LocalizedNumber`] when `value` looks like a localized
+/// number, [`ParseError::InvalidValue`] otherwise
+fn invalid_number(
+	key: &str,
+	value: &str,
+	ty: SingleValueDiscriminants,
+	line: usize,
+) -> ParseError {
+	match separator_misuse(value, ty,) {
+		Some((separator, suggestion,),) => ParseError::LocalizedNumber {
+			key: key.to_string(),
+			value: value.to_string(),
+			ty,
+			line,
+			separator,
+			suggestion,
+		},
+		None => ParseError::InvalidValue {
+			key: key.to_string(),
+			value: value.to_string(),
+			ty,
+			line,
+			column: 0,
+			element: None,
+			position: None,
+		},
+	}
+}
+
+/// spots digits split into groups of three by `.` or `,`, and a `,` before
+/// the decimals of a float, returning the separator and the number as it
+/// should have been written
+fn separator_misuse(
+	value: &str,
+	ty: SingleValueDiscriminants,
+) -> Option<(Separator, String,),> {
+	let (sign, digits,) = match value.strip_prefix(['+', '-',],) {
+		Some(digits,) => (&value[..1], digits,),
+		None => ("", value,),
+	};
+	let separators: Vec<char,> =
+		digits.chars().filter(|ch| matches!(ch, '.' | ','),).collect();
+	let groups: Vec<&str,> = digits.split(['.', ',',],).collect();
+	if groups.iter().any(|group| {
+		group.is_empty() || !group.bytes().all(|b| b.is_ascii_digit(),)
 	},)
+	{
+		return None;
+	}
+
+	let (last_separator, leading_separators,) = separators.split_last()?;
+	let (last_group, leading_groups,) = groups.split_last()?;
+	let grouped = |groups: &[&str]| {
+		groups[0].len() <= 3
+			&& groups[1..].iter().all(|group| group.len() == 3,)
+	};
+	let uniform = |separators: &[char], separator: char| {
+		separators.iter().all(|ch| *ch == separator,)
+	};
+	// the integral part of a float is either not grouped at all or grouped
+	// with the other separator
+	let integral_grouped_by = |separator: char| {
+		uniform(leading_separators, separator,)
+			&& (leading_separators.is_empty() || grouped(leading_groups,))
+	};
+
+	match ty {
+		SingleValueDiscriminants::Integer
+		| SingleValueDiscriminants::UInteger
+		| SingleValueDiscriminants::Long
+			if uniform(&separators, *last_separator,) && grouped(&groups,) =>
+		{
+			Some((Separator::Thousands, format!("{sign}{}", groups.concat()),),)
+		},
+		SingleValueDiscriminants::Float
+			if *last_separator == ',' && integral_grouped_by('.',) =>
+		{
+			Some((
+				Separator::DecimalComma,
+				format!("{sign}{}.{last_group}", leading_groups.concat()),
+			),)
+		},
+		SingleValueDiscriminants::Float
+			if *last_separator == '.' && integral_grouped_by(',',) =>
+		{
+			Some((
+				Separator::Thousands,
+				format!("{sign}{}.{last_group}", leading_groups.concat()),
+			),)
+		},
+		SingleValueDiscriminants::Float
+			if uniform(&separators, *last_separator,) && grouped(&groups,) =>
+		{
+			Some((Separator::Thousands, format!("{sign}{}", groups.concat()),),)
+		},
+		_ => None,
+	}
 }
 
-fn inject_payload(
+pub(crate) fn inject_payload(
 	key: &str,
-	schema_value: &Value<SingleValueDiscriminants,>,
-	mir_value: TreeValue<(String, usize,),>,
+	leaf: &SchemaLeaf,
+	mir_value: TreeValue<MirLeaf,>,
 ) -> PRslt<ConfValue,> {
-	let TreeValue::Scalar((value, line,),) = mir_value else { todo!() };
-	Ok(match schema_value {
-		Value::Single(single,) => TreeValue::Scalar(Value::Single(
-			single.into_payload(key, &value, line,)?,
-		),),
-		Value::Collection(items,) => TreeValue::Scalar(Value::Collection(
-			items
-				.iter()
-				.map(|single| single.into_payload(key, &value, line,),)
-				.try_collect()?,
-		),),
+	let (value, line, column,) = match mir_value {
+		TreeValue::Scalar(scalar,) => scalar,
+		section => return Err(section_conflict(key, &section,),),
+	};
+	// empty values are written `""` or allowed by the parse options. text
+	// and collections take them as they are
+	let rejects_empty = matches!(
+		leaf.ty,
+		Value::Single(
+			SingleValueDiscriminants::Integer
+				| SingleValueDiscriminants::UInteger
+				| SingleValueDiscriminants::Long
+				| SingleValueDiscriminants::Float
+				| SingleValueDiscriminants::Bool
+				| SingleValueDiscriminants::Path,
+		)
+	);
+	if value.trim().is_empty() && rejects_empty && leaf.unrecognized.is_none() {
+		return Err(ParseError::EmptyValue { line, column, },);
+	}
+	let conf_value = typed_payload(key, leaf, value, line,)
+		.map_err(|err| redact_for(leaf, at_column(err, column,),),)?;
+
+	trace::event!(debug, key, line, kind = ?leaf.ty, "entry accepted");
+	Ok(conf_value,)
+}
+
+/// the typing functions only know the line of a value. an invalid value
+/// points at the column it starts at, its elements included. trailing
+/// characters are found at a column counted from the start of the value
+fn at_column(mut err: ParseError, value_column: usize,) -> ParseError {
+	match &mut err {
+		ParseError::InvalidValue { column, .. } => *column = value_column,
+		ParseError::TrailingCharacters { column, .. } => {
+			*column += value_column
+		},
+		_ => {},
+	}
+	err
+}
+
+/// elements of every assignment of a repeatable list, in order of
+/// appearance
+fn repeated_payload(
+	key: &str,
+	leaf: &SchemaLeaf,
+	values: &[MirLeaf],
+) -> PRslt<ConfValue,> {
+	let mut elements = Vec::new();
+	for value in values {
+		match inject_payload(key, leaf, TreeValue::Scalar(value.clone(),),)? {
+			TreeValue::Scalar(Value::Collection(items,),) => {
+				elements.extend(items,)
+			},
+			other => return Ok(other,),
+		}
+	}
+	Ok(TreeValue::Scalar(Value::Collection(elements,),),)
+}
+
+fn typed_payload(
+	key: &str,
+	leaf: &SchemaLeaf,
+	value: String,
+	line: usize,
+) -> PRslt<ConfValue,> {
+	if leaf.unrecognized.is_some() {
+		trace::event!(
+			warn,
+			key,
+			line,
+			declaration = ?leaf.unrecognized,
+			"value accepted without checking its type"
+		);
+	}
+	let value = match &leaf.ty {
+		Value::Single(_,) => unquote_single(value, line,)?,
+		Value::Collection(_,) => value,
+	};
+	let placeholder =
+		leaf.resolvable.then(|| parse_placeholder(&value,),).flatten();
+	let conf_value = match (&leaf.ty, placeholder,) {
+		(_, Some((resolver, arg,),),) => {
+			TreeValue::Scalar(Value::Single(SingleValue::Deferred {
+				resolver,
+				arg,
+			},),)
+		},
+		(Value::Single(single,), None,) => {
+			let payload = single.into_payload(key, &value, line,)?;
+			check_constraints(key, leaf, 0, &payload, line,)?;
+			TreeValue::Scalar(Value::Single(payload,),)
+		},
+		(Value::Collection(items,), None,) if leaf.list => {
+			let elements = split_list(&value, line,)?;
+			let count = elements.len();
+			TreeValue::Scalar(Value::Collection(
+				elements
+					.into_iter()
+					.enumerate()
+					.map(|(idx, (element, quoted,),)| {
+						let invalid = |err| at_element(err, idx, count, None,);
+						if element.is_empty() && !quoted {
+							return Err(invalid(ParseError::InvalidValue {
+								key: key.to_string(),
+								value: String::new(),
+								ty: items[0],
+								line,
+								column: 0,
+								element: None,
+								position: None,
+							},),);
+						}
+						items[0]
+							.into_payload(key, &element, line,)
+							.and_then(|payload| {
+								check_constraints(
+									key, leaf, 0, &payload, line,
+								)?;
+								Ok(payload,)
+							},)
+							.map_err(invalid,)
+					},)
+					.try_collect()?,
+			),)
+		},
+		(Value::Collection(items,), None,) => {
+			let mut elements = split_elements(&value, line,)?;
+			// one trailing comma too many, as after a lone `""`, is ignored
+			if elements.len() == items.len() + 1
+				&& elements
+					.last()
+					.is_some_and(|(last, quoted,)| last.is_empty() && !quoted,)
+			{
+				elements.pop();
+			}
+			if elements.len() != items.len() {
+				return Err(ParseError::ArityMismatch {
+					key: key.to_string(),
+					expected: items.len(),
+					found: elements.len(),
+					line,
+				},);
+			}
+
+			TreeValue::Scalar(Value::Collection(
+				items
+					.iter()
+					.zip(elements,)
+					.enumerate()
+					.map(|(idx, (single, (element, _,),),)| {
+						single
+							.into_payload(key, &element, line,)
+							.and_then(|payload| {
+								check_constraints(
+									key, leaf, idx, &payload, line,
+								)?;
+								Ok(payload,)
+							},)
+							.map_err(|err| {
+								at_element(
+									err,
+									idx,
+									items.len(),
+									leaf.label(idx,),
+								)
+							},)
+					},)
+					.try_collect()?,
+			),)
+		},
+	};
+	Ok(conf_value,)
+}
+
+/// an invalid element of a collection names its position, and its label in
+/// a labeled tuple
+fn at_element(
+	mut err: ParseError,
+	idx: usize,
+	count: usize,
+	label: Option<&str,>,
+) -> ParseError {
+	if let ParseError::InvalidValue { element, position, .. } = &mut err {
+		*element = label.map(str::to_string,);
+		*position = Some((idx + 1, count,),);
+	}
+	err
+}
+
+/// a key declared `Enum(...)` takes only its literals, compared
+/// case-sensitively, and one declared with a range only numbers within it.
+/// `value` is the `idx`th element of a collection, 0 for a single value
+pub(crate) fn check_constraints(
+	key: &str,
+	leaf: &SchemaLeaf,
+	idx: usize,
+	value: &SingleValue,
+	line: usize,
+) -> PRslt<(),> {
+	if let Some(range,) = leaf.range_of(idx,)
+		&& !range.contains(value,)
+	{
+		let bound = |bound: &Option<SingleValue,>| {
+			bound.clone().map(|bound| render_value(&Value::Single(bound,),),)
+		};
+		return Err(ParseError::OutOfRange {
+			key: key.to_string(),
+			value: render_value(&Value::Single(value.clone(),),),
+			ty: SingleValueDiscriminants::from(value,),
+			min: bound(&range.min,),
+			max: bound(&range.max,),
+			line,
+		},);
+	}
+	match (&leaf.allowed, value,) {
+		(Some(allowed,), SingleValue::String(text,),)
+			if !allowed.contains(text,) =>
+		{
+			Err(ParseError::InvalidEnumValue {
+				key: key.to_string(),
+				value: text.clone(),
+				allowed: allowed.clone(),
+				line,
+			},)
+		},
+		_ => Ok((),),
+	}
+}
+
+/// a scalar value opening with a double quote is that one quoted string,
+/// decoded. a `,` after the closing quote separates collection elements, so
+/// here it is as out of place as any other trailing text
+fn unquote_single(value: String, line: usize,) -> PRslt<String,> {
+	let Some((text, end,),) = split_quoted(&value,) else {
+		return Ok(value,);
+	};
+	match trailing_text(&value, end, value.len(), line,) {
+		Some(err,) => Err(err,),
+		None => Ok(text,),
+	}
+}
+
+/// error for anything but whitespace in `value[start..end]`, which follows
+/// a closing quote
+fn trailing_text(
+	value: &str,
+	start: usize,
+	end: usize,
+	line: usize,
+) -> Option<ParseError,> {
+	let tail = &value[start..end];
+	let text = tail.trim();
+	(!text.is_empty()).then(|| {
+		let offset = start + tail.len() - tail.trim_start().len();
+		ParseError::TrailingCharacters {
+			line,
+			column: value[..offset].chars().count(),
+			text: text.to_string(),
+		}
 	},)
 }
 
+/// elements of a list, split like [`split_elements`]. a trailing comma is
+/// ignored, and an empty list is written as `""`, so a list of one empty
+/// string is written as `"",`
+fn split_list(
+	value: &str,
+	line: usize,
+) -> PRslt<Vec<(Cow<'_, str,>, bool,),>,> {
+	if value.is_empty() {
+		return Ok(Vec::new(),);
+	}
+	let mut elements = split_elements(value, line,)?;
+	if elements.len() > 1
+		&& elements
+			.last()
+			.is_some_and(|(last, quoted,)| last.is_empty() && !quoted,)
+	{
+		elements.pop();
+	}
+	Ok(elements,)
+}
+
+/// values of a collection are written positionally, one per element,
+/// separated by commas. elements are trimmed, and `\,` is a comma inside
+/// one. an element may be a double-quoted string, decoded like a quoted
+/// value and holding commas as written. each element comes with whether it
+/// was quoted, as a quoted `""` is an empty string rather than a missing
+/// element
+fn split_elements(
+	value: &str,
+	line: usize,
+) -> PRslt<Vec<(Cow<'_, str,>, bool,),>,> {
+	fn unquoted(text: &str,) -> Cow<'_, str,> {
+		let text = text.trim();
+		if text.contains("\\,",) {
+			Cow::Owned(text.replace("\\,", ",",),)
+		} else {
+			Cow::Borrowed(text,)
+		}
+	}
+
+	let mut elements = Vec::new();
+	let mut start = 0;
+	loop {
+		let rest = &value[start..];
+		let quoted = split_quoted(rest,);
+		if quoted.is_none() && rest.trim_start().starts_with('"',) {
+			return Err(ParseError::UnterminatedString { line, },);
+		}
+		let end = quoted.as_ref().map_or(0, |(_, end,)| *end,);
+		let after = &rest[end..];
+		let mut escaped = false;
+		let comma = after.char_indices().find_map(|(idx, ch,)| {
+			let separates = ch == ',' && !escaped;
+			escaped = ch == '\\';
+			separates.then_some(idx,)
+		},);
+		let stop = start + end + comma.unwrap_or(after.len(),);
+		match quoted {
+			Some((text, _,),) => {
+				if let Some(err,) =
+					trailing_text(value, start + end, stop, line,)
+				{
+					return Err(err,);
+				}
+				elements.push((Cow::Owned(text,), true,),);
+			},
+			None => elements.push((unquoted(&value[start..stop],), false,),),
+		}
+		match comma {
+			Some(_,) => start = stop + 1,
+			None => return Ok(elements,),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use crate::parser::schema::SchemaValue;
 
-	fn mir_scalar(value: &str, line: usize,) -> TreeValue<(String, usize,),> {
-		TreeValue::Scalar((value.to_string(), line,),)
+	fn mir_scalar(value: &str, line: usize,) -> TreeValue<MirLeaf,> {
+		TreeValue::Scalar((value.to_string(), line, 1,),)
 	}
 
 	fn schema_scalar(kind: SingleValueDiscriminants,) -> SchemaValue {
-		TreeValue::Scalar(Value::Single(kind,),)
+		TreeValue::Scalar(Value::Single(kind,).into(),)
 	}
 
 	#[test]
 	fn parse_str_as_i32_parses_valid_integer() -> PRslt<(),> {
-		assert_eq!(parse_str_as_i32("port", "42", 6)?, 42);
+		assert_eq!(
+			parse_str_as_int::<i32,>(
+				"port",
+				"42",
+				SingleValueDiscriminants::Integer,
+				6,
+			)?,
+			42
+		);
 		Ok((),)
 	}
 
 	#[test]
 	fn parse_str_as_i32_reports_invalid_value() -> PRslt<(),> {
-		let err = parse_str_as_i32("port", "not-a-number", 3,).unwrap_err();
+		let err = parse_str_as_int::<i32,>(
+			"port",
+			"not-a-number",
+			SingleValueDiscriminants::Integer,
+			3,
+		)
+		.unwrap_err();
 		match err {
-			ParseError::InvalidValue { key, value, ty, line, } => {
+			ParseError::InvalidValue {
+				key, value, ty, line, element, ..
+			} => {
 				assert_eq!(key, "port");
+				assert_eq!(element, None);
 				assert_eq!(value, "not-a-number");
 				assert_eq!(ty, SingleValueDiscriminants::Integer);
 				assert_eq!(line, 3);
@@ -318,7 +2599,8 @@ mod tests {
 
 	#[test]
 	fn inject_payload_handles_single_value() -> PRslt<(),> {
-		let schema_value = Value::Single(SingleValueDiscriminants::String,);
+		let schema_value =
+			Value::Single(SingleValueDiscriminants::String,).into();
 		let conf_value = inject_payload(
 			"endpoint",
 			&schema_value,
@@ -339,24 +2621,205 @@ mod tests {
 		let schema_value = Value::Collection(vec![
 			SingleValueDiscriminants::Integer,
 			SingleValueDiscriminants::Integer,
-		],);
-		let conf_value =
-			inject_payload("ports", &schema_value, mir_scalar("8080", 9,),)?;
+		],)
+		.into();
+		let conf_value = inject_payload(
+			"ports",
+			&schema_value,
+			mir_scalar("8080, 8443", 9,),
+		)?;
 		match conf_value {
-			TreeValue::Scalar(Value::Collection(items,),) => {
-				assert_eq!(items.len(), 2);
-				assert!(
-					items
-						.iter()
-						.all(|item| matches!(item, SingleValue::Integer(8080)))
-				);
-			},
+			TreeValue::Scalar(Value::Collection(items,),) => assert_eq!(
+				items,
+				vec![SingleValue::Integer(8080), SingleValue::Integer(8443)]
+			),
+			other => panic!("unexpected conf value: {other:?}"),
+		}
+
+		let err =
+			inject_payload("ports", &schema_value, mir_scalar("8080", 9,),)
+				.unwrap_err();
+		assert!(err.is_arity_mismatch(), "{err:?}");
+
+		Ok((),)
+	}
+
+	#[test]
+	fn inject_payload_splits_collection_positionally() -> PRslt<(),> {
+		let schema_value = Value::Collection(vec![
+			SingleValueDiscriminants::String,
+			SingleValueDiscriminants::Integer,
+		],)
+		.into();
+		let conf_value = inject_payload(
+			"endpoint",
+			&schema_value,
+			mir_scalar("localhost , 8080", 2,),
+		)?;
+
+		match conf_value {
+			TreeValue::Scalar(Value::Collection(items,),) => assert_eq!(
+				items,
+				vec![
+					SingleValue::String("localhost".to_string()),
+					SingleValue::Integer(8080)
+				]
+			),
 			other => panic!("unexpected conf value: {other:?}"),
 		}
 
 		Ok((),)
 	}
 
+	#[test]
+	fn separator_misuse_is_spotted_by_pattern() {
+		use SingleValueDiscriminants::Float;
+		use SingleValueDiscriminants::Integer;
+
+		for (value, ty, expected,) in [
+			("1.000", Integer, Some((Separator::Thousands, "1000",),),),
+			(
+				"-12,345,678",
+				Integer,
+				Some((Separator::Thousands, "-12345678",),),
+			),
+			("3,14", Float, Some((Separator::DecimalComma, "3.14",),),),
+			("+1.234,5", Float, Some((Separator::DecimalComma, "+1234.5",),),),
+			("1,234.5", Float, Some((Separator::Thousands, "1234.5",),),),
+			("1.000.000", Float, Some((Separator::Thousands, "1000000",),),),
+			// not grouped in threes, or mixed separators
+			("3,14", Integer, None,),
+			("1.0000", Integer, None,),
+			("1.000,000", Integer, None,),
+			("1,23.4", Float, None,),
+			("12,34,567.8", Float, None,),
+			// not a number at all
+			("1.2.3a", Float, None,),
+			("192.168.000.001", SingleValueDiscriminants::Bool, None,),
+			(".5", Float, None,),
+		] {
+			assert_eq!(
+				separator_misuse(value, ty,),
+				expected.map(|(separator, suggestion,)| {
+					(separator, suggestion.to_string(),)
+				},),
+				"{value} as {ty}"
+			);
+		}
+	}
+
+	#[test]
+	fn floats_accept_exponents_and_reject_non_finite_values() -> PRslt<(),> {
+		let schema = crate::parser::schema::parse_str("rate -> Float\n",)?;
+		for (text, expected,) in
+			[("2.5", 2.5,), ("1e-3", 0.001,), ("+4", 4.0,), ("-0.25", -0.25,),]
+		{
+			let conf = parse_str(&format!("rate = {text}\n"), &schema,)?;
+			assert_eq!(crate::testing::get_float(&conf, "rate"), Ok(expected));
+		}
+
+		for text in ["NaN", "inf", "-infinity", "2.5.1", "1e400",] {
+			let err =
+				parse_str(&format!("rate = {text}\n"), &schema,).unwrap_err();
+			assert!(
+				matches!(
+					err,
+					ParseError::InvalidValue {
+						ty: SingleValueDiscriminants::Float,
+						line: 1,
+						..
+					}
+				),
+				"{text}: {err}"
+			);
+		}
+		Ok((),)
+	}
+
+	#[test]
+	fn floats_round_trip_through_conf_format() {
+		let schema =
+			crate::schema_map! { "rate" => Float, "ratios" => [Float, Float] };
+		let conf = crate::conf_map! { "rate" => 0.01, "ratios" => [1e-3, 2.0] };
+		crate::testing::assert_round_trip(&conf, &schema,)
+			.unwrap_or_else(|report| panic!("{report}"),);
+	}
+
+	#[test]
+	fn migrations_rewrite_keys_before_typing() -> PRslt<(),> {
+		let schema = crate::parser::schema::parse_str(
+			"server.port -> Integer\nlog.file -> String\n",
+		)?;
+		let migrations = Migrations::parse_str(
+			"rename net.port -> server.port\nmove logging -> log\n",
+		)?;
+
+		let (conf, warnings,) = parse_str_with_migrations(
+			"net.port = 8080\nlogging.file = a.log\n",
+			&schema,
+			&migrations,
+		)?;
+		crate::assert_conf_eq!(
+			conf,
+			crate::conf_map! { "server.port" => 8080, "log.file" => "a.log" }
+		);
+		assert_eq!(
+			warnings.iter().map(ToString::to_string,).collect::<Vec<_,>>(),
+			vec![
+				"'net.port' on line 1 is deprecated, use 'server.port'",
+				"'logging' on line 2 is deprecated, use 'log'",
+			]
+		);
+		Ok((),)
+	}
+
+	#[test]
+	fn migrated_repeatable_lists_keep_every_assignment() -> PRslt<(),> {
+		let schema = crate::parser::schema::parse_str(
+			"net.allow -> [String] (repeatable)\n",
+		)?;
+		let migrations =
+			Migrations::parse_str("rename firewall.allow -> net.allow\n",)?;
+
+		let (conf, _,) = parse_str_with_migrations(
+			"firewall.allow = a, b\nfirewall.allow = c\n",
+			&schema,
+			&migrations,
+		)?;
+		crate::assert_conf_eq!(
+			conf,
+			crate::conf_map! { "net.allow" => ["a", "b", "c"] }
+		);
+		assert!(conf.is_repeatable("net.allow"));
+		Ok((),)
+	}
+
+	#[test]
+	fn labeled_tuple_elements_are_named_in_errors_and_lookups() -> PRslt<(),> {
+		let schema = crate::parser::schema::parse_str(
+			"limits -> (min: Integer, max: Integer)",
+		)?;
+		let conf = parse_str_instrumented("limits = 1, 10", &schema,)?.0;
+		assert_eq!(
+			conf.get_field("limits", "min"),
+			Some(&SingleValue::Integer(1))
+		);
+		assert_eq!(
+			conf.get_field("limits", "max"),
+			Some(&SingleValue::Integer(10))
+		);
+		assert_eq!(conf.get_field("limits", "mid"), None);
+
+		let err = parse_str("limits = 1, x", schema,).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"[E007] invalid value 'x' while expecting Integer for element \
+			 'max' of 'limits' on line 1, column 10"
+		);
+
+		Ok((),)
+	}
+
 	#[test]
 	fn structured_input_into_conf_converts_known_keys() -> PRslt<(),> {
 		let mut mir = StructuredInput::new();
@@ -401,7 +2864,7 @@ mod tests {
 		let schema = SchemaMap::new();
 		let err = mir.into_conf(&schema,).unwrap_err();
 		match err {
-			ParseError::UnknownKey { key, lines, } => {
+			ParseError::UnknownKey { key, lines, .. } => {
 				assert_eq!(key, "unexpected");
 				assert_eq!(lines, vec![3]);
 			},
@@ -441,12 +2904,32 @@ mod tests {
 		Ok((),)
 	}
 
+	#[test]
+	fn parse_str_instrumented_reports_stats() -> PRslt<(),> {
+		let schema = crate::parser::schema::parse_str(
+			"server.port -> Integer\nlimits -> Integer, Integer",
+		)?;
+		let input =
+			"# ports\n\nserver.port = 80\nserver.port = 8080\nlimits = 3, 5\n";
+
+		let (conf, stats,) = parse_str_instrumented(input, &schema,)?;
+		assert!(conf.get("server.port",).is_some());
+		assert_eq!(stats.lines_scanned, 5);
+		assert_eq!(stats.lines_skipped, 2);
+		assert_eq!(stats.keys_parsed, 3);
+		assert_eq!(stats.max_depth, 2);
+		assert_eq!(stats.overrides, 1);
+		assert_eq!(stats.collection_elements, 2);
+
+		Ok((),)
+	}
+
 	#[test]
 	fn parse_str_propagates_unknown_key_error() -> PRslt<(),> {
 		let schema = SchemaMap::new();
 		let err = parse_str("feature.enabled = true", schema,).unwrap_err();
 		match err {
-			ParseError::UnknownKey { key, lines, } => {
+			ParseError::UnknownKey { key, lines, .. } => {
 				assert_eq!(key, "feature.enabled");
 				assert_eq!(lines, vec![1]);
 			},
@@ -455,4 +2938,54 @@ mod tests {
 
 		Ok((),)
 	}
+
+	#[test]
+	fn parse_lines_reports_the_supplied_line_numbers() {
+		let schema = crate::parser::schema::parse_str(
+			"server.port -> Integer\nserver.host -> String",
+		)
+		.unwrap();
+		let lines =
+			[(10, "server.host = localhost",), (42, "server.port = x",),];
+
+		let err = parse_lines(lines, &schema,).unwrap_err();
+		assert!(matches!(err, ParseError::InvalidValue { line: 42, .. }));
+
+		let err = parse_lines(
+			[(7, "server.port = 80",), (3, "server.port",),],
+			&schema,
+		)
+		.unwrap_err();
+		assert!(matches!(err, ParseError::MissingDelimiter { line: 3, .. }));
+
+		let conf = parse_lines([(5, "server.port = 80",),], &schema,).unwrap();
+		assert_eq!(*conf, *parse_str("server.port = 80", &schema,).unwrap());
+	}
+
+	#[test]
+	fn typed_accessors_follow_dotted_keys() {
+		let mut conf = crate::conf_map! {
+			"server.host" => "localhost",
+			"server.port" => 80,
+			"debug" => true,
+			"ratio" => 0.5,
+		};
+		conf.insert(
+			"ports".to_string(),
+			TreeValue::Scalar(Value::Collection(vec![
+				SingleValue::Integer(80,),
+				SingleValue::Integer(443,),
+			],),),
+		);
+
+		assert_eq!(conf.get_str("server.host"), Some("localhost"));
+		assert_eq!(conf.get_int("server.port"), Some(80));
+		assert_eq!(conf.get_bool("debug"), Some(true));
+		assert_eq!(conf.get_float("ratio"), Some(0.5));
+		assert_eq!(conf.get_ints("ports"), Some(vec![80, 443]));
+
+		assert_eq!(conf.get_int("server.host"), None);
+		assert_eq!(conf.get_str("server"), None);
+		assert_eq!(conf.get_bool("missing"), None);
+	}
 }
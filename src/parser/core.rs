@@ -1,11 +1,15 @@
 use crate::error::PRslt;
 use crate::error::ParseError;
+use crate::trace;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::collections::btree_map::Entry;
 use std::fmt::Debug;
 use std::fs::File;
-use std::io::Read;
+use std::io::BufRead;
+use std::io::BufReader;
 use std::path::Path;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq, Eq,)]
 pub enum TreeValue<T,> {
@@ -13,168 +17,1174 @@ pub enum TreeValue<T,> {
 	Map(BTreeMap<String, TreeValue<T,>,>,),
 }
 
-impl TreeValue<(String, usize,),> {
+impl<T,> TreeValue<T,> {
+	/// value at the dotted `key` of `entries`. sections are followed first,
+	/// so `a.b` is `b` of section `a` even when there also is a top-level key
+	/// `a.b` itself containing dots. such a key is only found when no path
+	/// through sections leads to `key`, and the shortest one is tried first,
+	/// with the rest of `key` followed below it
+	pub(crate) fn get_dotted<'a,>(
+		entries: &'a BTreeMap<String, TreeValue<T,>,>,
+		key: &str,
+	) -> Option<&'a TreeValue<T,>,> {
+		key.match_indices('.',)
+			.map(|(idx, _,)| idx,)
+			.chain([key.len(),],)
+			.find_map(|split| {
+				let mut current = entries.get(&key[..split],)?;
+				let Some(rest,) = key.get(split + 1..,) else {
+					return Some(current,);
+				};
+				for segment in rest.split('.',) {
+					current = match current {
+						TreeValue::Map(children,) => children.get(segment,)?,
+						TreeValue::Scalar(_,) => return None,
+					};
+				}
+				Some(current,)
+			},)
+	}
+}
+
+impl TreeValue<MirLeaf,> {
 	pub fn get_lines_of_key(&self,) -> Vec<usize,> {
 		match self {
-			Self::Scalar((_, l,),) => vec![*l],
-			Self::Map(btree_map,) => btree_map
-				.iter()
-				.flat_map(|(_, v,)| v.get_lines_of_key(),)
-				.collect(),
+			Self::Scalar((_, l, _,),) => vec![*l],
+			Self::Map(btree_map,) => {
+				btree_map.values().flat_map(|v| v.get_lines_of_key(),).collect()
+			},
 		}
 	}
 }
 
 pub trait Valuable {
-	fn sep() -> &'static str;
+	/// delimiters between key and value, unless the parse options name
+	/// others
+	fn seps() -> &'static [&'static str];
+
+	/// whether a key followed by `+`, as in `key += value`, appends to it
+	const APPENDS: bool = false;
 
+	/// [`split_assignment`] at the delimiters of the type
 	fn extract_key_value(s: &str, line_no: usize,) -> PRslt<(&str, &str,),> {
-		let sep = Self::sep();
-		match s.find(sep,) {
-			Some(eq_index,) => {
-				let key_part = &s[..eq_index];
-				let value_part = &s[eq_index + sep.len()..];
-				Ok((
-					key_part.trim(),
-					//  NOTE: this code is actually valid. see
-					// `confirm_range_exp_valid_bound` test function
-					value_part,
-				),)
+		split_assignment(s, line_no, Self::seps(),)
+	}
+}
+
+/// splits at the first delimiter only, the one starting earliest in `s` and
+/// the longest of those starting there. everything after it is the value,
+/// further delimiters included, so a key can never contain one. `s` is a
+/// whole line, leading whitespace included, so errors can tell the column
+pub(crate) fn split_assignment<'a, D: AsRef<str,>,>(
+	s: &'a str,
+	line_no: usize,
+	delimiters: &[D],
+) -> PRslt<(&'a str, &'a str,),> {
+	let first = delimiters
+		.iter()
+		.map(AsRef::as_ref,)
+		.filter_map(|sep| Some((s.find(sep,)?, sep.len(),),),)
+		.min_by_key(|&(idx, len,)| (idx, std::cmp::Reverse(len,),),);
+	match first {
+		Some((idx, len,),) => {
+			let key_part = &s[..idx];
+			let value_part = &s[idx + len..];
+			Ok((
+				key_part.trim(),
+				//  NOTE: this code is actually valid. see
+				// `confirm_range_exp_valid_bound` test function
+				value_part,
+			),)
+		},
+		None => Err(ParseError::MissingDelimiter {
+			line:     line_no,
+			column:   column_at(s, s.len() - s.trim_start().len(),),
+			expected: delimiters
+				.iter()
+				.map(|sep| sep.as_ref().to_string(),)
+				.collect(),
+		},),
+	}
+}
+
+/// hooks invoked while the mir is built. every method is a no-op by default,
+/// so `()` can be passed on the plain parse path without any cost
+pub(crate) trait MirObserver {
+	fn line_scanned(&mut self,) {}
+	fn line_skipped(&mut self,) {}
+	fn entry_parsed(&mut self, _segments: &[String], _line: usize,) {}
+	/// `segments` were assigned with `+=` on `line`, which only adds to a
+	/// list declared repeatable
+	fn entry_appended(&mut self, _segments: &[String], _line: usize,) {}
+	/// `segments` of the mir were `written` in another case, see
+	/// [`KeyCase::Lowercase`]
+	fn key_folded(&mut self, _segments: &[String], _written: &[String],) {}
+	/// `previous` and `current` are the raw value, line and column of the
+	/// earlier and the later assignment of a key assigned again. the mir keeps
+	/// the one the [`DuplicateKeyPolicy`] picks
+	fn entry_overridden(
+		&mut self,
+		_segments: &[String],
+		_previous: (&str, usize, usize,),
+		_current: (&str, usize, usize,),
+	) {
+	}
+	/// the lines which follow, up to the matching
+	/// [`Self::include_left`], are read from the included `path`
+	fn include_entered(&mut self, _path: &Path,) {}
+	fn include_left(&mut self,) {}
+}
+
+impl MirObserver for () {}
+
+/// both observers see every hook, `.0` first
+impl<A: MirObserver, B: MirObserver,> MirObserver for (&mut A, &mut B,) {
+	fn line_scanned(&mut self,) {
+		self.0.line_scanned();
+		self.1.line_scanned();
+	}
+
+	fn line_skipped(&mut self,) {
+		self.0.line_skipped();
+		self.1.line_skipped();
+	}
+
+	fn entry_parsed(&mut self, segments: &[String], line: usize,) {
+		self.0.entry_parsed(segments, line,);
+		self.1.entry_parsed(segments, line,);
+	}
+
+	fn entry_appended(&mut self, segments: &[String], line: usize,) {
+		self.0.entry_appended(segments, line,);
+		self.1.entry_appended(segments, line,);
+	}
+
+	fn key_folded(&mut self, segments: &[String], written: &[String],) {
+		self.0.key_folded(segments, written,);
+		self.1.key_folded(segments, written,);
+	}
+
+	fn entry_overridden(
+		&mut self,
+		segments: &[String],
+		previous: (&str, usize, usize,),
+		current: (&str, usize, usize,),
+	) {
+		self.0.entry_overridden(segments, previous, current,);
+		self.1.entry_overridden(segments, previous, current,);
+	}
+
+	fn include_entered(&mut self, path: &Path,) {
+		self.0.include_entered(path,);
+		self.1.include_entered(path,);
+	}
+
+	fn include_left(&mut self,) {
+		self.0.include_left();
+		self.1.include_left();
+	}
+}
+
+/// what the mir keeps of a key assigned more than once
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq,)]
+pub enum DuplicateKeyPolicy {
+	#[default]
+	LastWins,
+	FirstWins,
+	/// fails with [`ParseError::DuplicateKey`]
+	Error,
+}
+
+/// how keys are told apart
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq,)]
+pub enum KeyCase {
+	#[default]
+	Sensitive,
+	/// keys are lowercased as they are read, section headers included, so
+	/// keys differing only in case are the same key
+	Lowercase,
+}
+
+impl KeyCase {
+	fn apply(self, segment: &str,) -> String {
+		match self {
+			Self::Sensitive => segment.to_string(),
+			Self::Lowercase => segment.to_lowercase(),
+		}
+	}
+}
+
+/// characters a key segment may be made of. segments never contain `.`,
+/// and never a delimiter whatever the charset
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq,)]
+pub enum SegmentCharset {
+	/// ascii letters and digits, `_` and `-`
+	#[default]
+	AsciiIdent,
+	/// letters and digits of any script, `_` and `-`
+	UnicodeIdent,
+	/// anything, spaces and control characters included
+	Permissive,
+}
+
+impl SegmentCharset {
+	fn allows(self, segment: &str,) -> bool {
+		let ident = |c: char, letter_or_digit: fn(&char,) -> bool| {
+			letter_or_digit(&c,) || c == '_' || c == '-'
+		};
+		match self {
+			Self::AsciiIdent => {
+				segment.chars().all(|c| ident(c, char::is_ascii_alphanumeric,),)
 			},
-			None => Err(ParseError::MissingDelimiter { line: line_no, },),
+			Self::UnicodeIdent => {
+				segment.chars().all(|c| ident(c, |c| c.is_alphanumeric(),),)
+			},
+			Self::Permissive => true,
+		}
+	}
+}
+
+/// comment markers of the default [`ParseOptions`]
+pub(crate) const COMMENT_CHARS: [char; 2] = ['#', ';',];
+
+/// knobs of the `parse_str_with` functions of conf and schema. the default
+/// parses like `parse_str`
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub struct ParseOptions {
+	/// [`DuplicateKeyPolicy::Error`] lets lists declared repeatable be
+	/// assigned more than once when parsing against a schema
+	pub duplicate_keys:     DuplicateKeyPolicy,
+	/// chars starting a comment, at the start of a line or after a value
+	pub comment_chars:      Vec<char,>,
+	/// `key =` assigns an empty string to keys declared `String` or
+	/// `Secret` instead of failing with [`ParseError::EmptyValue`]. keys of
+	/// other types still fail
+	pub allow_empty_values: bool,
+	/// whitespace around unquoted values is dropped
+	pub trim_values:        bool,
+	pub key_case:           KeyCase,
+	/// a `Path` value of `~` or starting with `~/` has the `~` replaced by
+	/// `$HOME`. left as written when `HOME` is not set
+	pub expand_home:        bool,
+	/// the conf map remembers the order keys were first assigned in and is
+	/// written in it, instead of in key order
+	pub preserve_order:     bool,
+	/// delimiters between key and value, replacing `=` of conf files or `->`
+	/// of schemas. the earliest in a line wins, so `["=", ":"]` reads both
+	/// `a = 1` and `url: http://x`
+	pub delimiters:         Option<Vec<String,>,>,
+	/// what key segments and section headers may be made of
+	pub segment_charset:    SegmentCharset,
+}
+
+impl Default for ParseOptions {
+	fn default() -> Self {
+		Self {
+			duplicate_keys:     DuplicateKeyPolicy::default(),
+			comment_chars:      COMMENT_CHARS.to_vec(),
+			allow_empty_values: false,
+			trim_values:        true,
+			key_case:           KeyCase::default(),
+			expand_home:        false,
+			preserve_order:     false,
+			delimiters:         None,
+			segment_charset:    SegmentCharset::default(),
+		}
+	}
+}
+
+impl ParseOptions {
+	pub fn duplicate_keys(self, duplicate_keys: DuplicateKeyPolicy,) -> Self {
+		Self { duplicate_keys, ..self }
+	}
+
+	pub fn comment_chars(
+		self,
+		comment_chars: impl IntoIterator<Item = char,>,
+	) -> Self {
+		Self { comment_chars: comment_chars.into_iter().collect(), ..self }
+	}
+
+	pub fn allow_empty_values(self, allow_empty_values: bool,) -> Self {
+		Self { allow_empty_values, ..self }
+	}
+
+	pub fn trim_values(self, trim_values: bool,) -> Self {
+		Self { trim_values, ..self }
+	}
+
+	pub fn key_case(self, key_case: KeyCase,) -> Self {
+		Self { key_case, ..self }
+	}
+
+	pub fn expand_home(self, expand_home: bool,) -> Self {
+		Self { expand_home, ..self }
+	}
+
+	pub fn preserve_order(self, preserve_order: bool,) -> Self {
+		Self { preserve_order, ..self }
+	}
+
+	pub fn delimiters(
+		self,
+		delimiters: impl IntoIterator<Item = impl Into<String,>,>,
+	) -> Self {
+		let delimiters = delimiters.into_iter().map(Into::into,).collect();
+		Self { delimiters: Some(delimiters,), ..self }
+	}
+
+	pub fn segment_charset(self, segment_charset: SegmentCharset,) -> Self {
+		Self { segment_charset, ..self }
+	}
+
+	/// [`split_assignment`] at the delimiters of these options, or of `V`
+	/// when they name none
+	pub(crate) fn split_assignment<'a, V: Valuable,>(
+		&self,
+		line: &'a str,
+		line_no: usize,
+	) -> PRslt<(&'a str, &'a str,),> {
+		match &self.delimiters {
+			Some(delimiters,) => split_assignment(line, line_no, delimiters,),
+			None => V::extract_key_value(line, line_no,),
 		}
 	}
 }
 
+/// how lines are read into the mir
+#[derive(Debug, Clone, Copy,)]
+pub(crate) struct LineRules<'o,> {
+	/// go on after a line with errors, to report every one
+	pub(crate) collect: bool,
+	pub(crate) options: &'o ParseOptions,
+}
+
+impl<'o,> LineRules<'o,> {
+	pub(crate) fn new(options: &'o ParseOptions,) -> Self {
+		Self { collect: false, options, }
+	}
+}
+
+/// raw value of an assignment, with its line and the 1-based column, in
+/// chars, it starts at
+pub type MirLeaf = (String, usize, usize,);
+
 /// mir
-pub type StructuredInput = BTreeMap<String, TreeValue<(String, usize,),>,>;
+pub type StructuredInput = BTreeMap<String, TreeValue<MirLeaf,>,>;
+
+/// 1-based column of the char at byte `offset` of `line`
+pub(crate) fn column_at(line: &str, offset: usize,) -> usize {
+	line[..offset].chars().count() + 1
+}
 
-pub(crate) fn file_to_mir<P: AsRef<Path,>, V: Valuable,>(
+pub(crate) fn file_to_mir_observed<
+	P: AsRef<Path,>,
+	V: Valuable,
+	O: MirObserver,
+>(
 	path: P,
-	// line_parser: impl Fn(&str,) -> Result<(&str, &str,),>,
+	observer: &mut O,
+	options: &ParseOptions,
+) -> PRslt<StructuredInput,> {
+	let (root, errors,) =
+		read_file::<_, V, _,>(path, observer, LineRules::new(options,),)?;
+	match errors.into_iter().next() {
+		Some(err,) => Err(err,),
+		None => Ok(root,),
+	}
+}
+
+/// same as [`file_to_mir_observed`], going on after every malformed line of
+/// the file and the files it includes, like [`str_to_mir_all_errors`]. a
+/// file which cannot be read is the one error
+pub(crate) fn file_to_mir_all_errors<
+	P: AsRef<Path,>,
+	V: Valuable,
+	O: MirObserver,
+>(
+	path: P,
+	observer: &mut O,
+	options: &ParseOptions,
+) -> (StructuredInput, Vec<ParseError,>,) {
+	let rules = LineRules { collect: true, ..LineRules::new(options,) };
+	read_file::<_, V, _,>(path, observer, rules,)
+		.unwrap_or_else(|err| (StructuredInput::new(), vec![err],),)
+}
+
+/// mir of the file at `path`, `@include` lines followed, and the errors of
+/// its lines. an I/O error fails the whole read
+fn read_file<P: AsRef<Path,>, V: Valuable, O: MirObserver,>(
+	path: P,
+	observer: &mut O,
+	rules: LineRules,
+) -> PRslt<(StructuredInput, Vec<ParseError,>,),> {
+	let _span = trace::span!("file", path = %path.as_ref().display());
+	let file = BufReader::new(File::open(&path,)?,);
+
+	let mut root = StructuredInput::new();
+	let mut errors = Vec::new();
+	let mut io_error = None;
+	let mut open_files = vec![canonical(path.as_ref(),)];
+	read_lines::<V, _,>(
+		&mut root,
+		numbered_reader(file, &mut io_error,),
+		observer,
+		rules,
+		Some(&mut open_files,),
+		&mut errors,
+	);
+	match io_error {
+		Some(err,) => Err(err.into(),),
+		None => Ok((root, errors,),),
+	}
+}
+
+/// same as [`str_to_mir`], reading lines from `reader` as they come instead
+/// of the whole text at once. an I/O error fails the whole read, whatever
+/// was read before it. the entry points all observe, so only tests read
+/// without
+#[cfg(test)]
+pub(crate) fn read_to_mir<R: BufRead, V: Valuable,>(
+	reader: R,
+) -> PRslt<StructuredInput,> {
+	read_to_mir_observed::<_, V, _,>(reader, &mut (), &ParseOptions::default(),)
+}
+
+pub(crate) fn read_to_mir_observed<R: BufRead, V: Valuable, O: MirObserver,>(
+	reader: R,
+	observer: &mut O,
+	options: &ParseOptions,
 ) -> PRslt<StructuredInput,> {
-	let mut file = File::open(path,)?;
-	let mut contents = String::new();
-	file.read_to_string(&mut contents,)?;
-	str_to_mir::<V,>(&contents,)
+	let mut io_error = None;
+	let mir = lines_to_mir_observed::<V, _,>(
+		numbered_reader(reader, &mut io_error,),
+		observer,
+		options,
+	);
+	match io_error {
+		Some(err,) => Err(err.into(),),
+		None => mir,
+	}
+}
+
+/// `@include path` line, reading the file at `path`, relative to the
+/// directory of the including file, in its place. only followed when parsing
+/// files
+const INCLUDE: &str = "@include";
+
+/// target of an include directive. `line` is trimmed
+fn include_target(line: &str,) -> Option<&str,> {
+	let target = line.strip_prefix(INCLUDE,)?;
+	target.starts_with(char::is_whitespace,).then(|| target.trim(),)
+}
+
+/// `path` made absolute, so the same file is recognized however it was
+/// reached. a file which does not exist is kept as it is and fails to open
+fn canonical(path: &Path,) -> PathBuf {
+	path.canonicalize().unwrap_or_else(|_| path.to_path_buf(),)
+}
+
+/// whether `input` has an include directive
+pub(crate) fn has_include(input: &str,) -> bool {
+	input.lines().any(|line| include_target(line.trim(),).is_some(),)
+}
+
+fn numbered(input: &str,) -> impl Iterator<Item = (usize, &str,),> {
+	input.lines().enumerate().map(|(idx, line,)| (idx + 1, line,),)
+}
+
+/// lines of `reader` numbered like [`numbered`] does. they end at the first
+/// I/O error, which is left in `io_error`
+fn numbered_reader<R: BufRead,>(
+	reader: R,
+	io_error: &mut Option<std::io::Error,>,
+) -> impl Iterator<Item = (usize, String,),> {
+	reader
+		.lines()
+		.map_while(|line| line.map_err(|err| *io_error = Some(err,),).ok(),)
+		.enumerate()
+		.map(|(idx, line,)| (idx + 1, line,),)
 }
 
+/// joins every line ending with a backslash, inline comment stripped, with
+/// the line after it, separated by a single space. the joined line keeps the
+/// number of its first line. a blank line ends the continuation, as does the
+/// end of the input. `\\` at the end of a line is a literal backslash and does
+/// not continue it, nor does a backslash inside a quoted value
+fn logical_lines<'a, V: Valuable,>(
+	lines: impl IntoIterator<Item = (usize, impl Into<Cow<'a, str,>,>,),>,
+	options: &ParseOptions,
+) -> impl Iterator<Item = (usize, Cow<'a, str,>,),> {
+	let mut lines = lines.into_iter();
+	std::iter::from_fn(move || {
+		let (line_no, first,) = lines.next()?;
+		let first = first.into();
+		let Some((mut joined, mut continued,),) =
+			trailing_backslash::<V,>(&first, options,)
+		else {
+			return Some((line_no, first,),);
+		};
+		while continued && let Some((_, next,),) = lines.next() {
+			let next = next.into();
+			let next = next.trim();
+			if next.is_empty() {
+				break;
+			}
+			let part = match trailing_backslash::<V,>(next, options,) {
+				Some((part, continues,),) => {
+					continued = continues;
+					Cow::Owned(part,)
+				},
+				None => {
+					continued = false;
+					Cow::Borrowed(next,)
+				},
+			};
+			joined.push(' ',);
+			joined.push_str(&part,);
+		}
+		Some((line_no, Cow::Owned(joined,),),)
+	},)
+}
+
+/// `line` without the backslash it ends with, inline comment stripped, and
+/// whether that backslash continues it. `None` for comments, quoted values
+/// and lines not ending with a backslash
+pub(crate) fn trailing_backslash<V: Valuable,>(
+	line: &str,
+	options: &ParseOptions,
+) -> Option<(String, bool,),> {
+	let comment_chars = &options.comment_chars;
+	if line.trim_start().starts_with(comment_chars.as_slice(),) {
+		return None;
+	}
+	if let Ok((_, value,),) = options.split_assignment::<V>(line, 0,)
+		&& value.trim_start().starts_with('"',)
+	{
+		return None;
+	}
+	let code = strip_inline_comment(line, comment_chars,);
+	let code = code.trim_end().strip_suffix('\\',)?;
+	match code.strip_suffix('\\',) {
+		Some(_,) => Some((code.to_string(), false,),),
+		None => Some((code.trim_end().to_string(), true,),),
+	}
+}
+
+/// reads the file included by the directive on `line_no` into `root`.
+/// `open_files` holds the files being read, outermost first, and an include
+/// of any of them is circular. errors from inside the included file are
+/// wrapped in [`ParseError::InFile`]
+fn include_file<V: Valuable, O: MirObserver,>(
+	root: &mut StructuredInput,
+	target: &str,
+	line_no: usize,
+	observer: &mut O,
+	rules: LineRules,
+	open_files: &mut Vec<PathBuf,>,
+	errors: &mut Vec<ParseError,>,
+) {
+	let including = open_files.last().expect("the including file is open",);
+	let path = canonical(
+		&including.parent().unwrap_or(Path::new("",),).join(target,),
+	);
+	if open_files.contains(&path,) {
+		errors.push(ParseError::CircularInclude { path, line: line_no, },);
+		return;
+	}
+
+	let in_file = |path: &Path, error| ParseError::InFile {
+		path:  path.to_path_buf(),
+		error: Box::new(error,),
+	};
+	let contents = match std::fs::read_to_string(&path,) {
+		Ok(contents,) => contents,
+		Err(err,) => {
+			errors.push(in_file(&path, ParseError::Io(err,),),);
+			return;
+		},
+	};
+
+	let _span = trace::span!("include", path = %path.display(), line = line_no);
+	observer.include_entered(&path,);
+	open_files.push(path.clone(),);
+	let mut included = Vec::new();
+	read_lines::<V, _,>(
+		root,
+		numbered(&contents,),
+		observer,
+		rules,
+		Some(open_files,),
+		&mut included,
+	);
+	open_files.pop();
+	observer.include_left();
+	errors.extend(included.into_iter().map(|err| in_file(&path, err,),),);
+}
+
+/// the entry points all observe, so only tests read without
+#[cfg(test)]
 pub(crate) fn str_to_mir<V: Valuable,>(
 	input: &str,
 ) -> PRslt<StructuredInput,> {
+	str_to_mir_observed::<V, _,>(input, &mut (),)
+}
+
+pub(crate) fn str_to_mir_observed<V: Valuable, O: MirObserver,>(
+	input: &str,
+	observer: &mut O,
+) -> PRslt<StructuredInput,> {
+	str_to_mir_with::<V, _,>(input, observer, &ParseOptions::default(),)
+}
+
+/// same as [`str_to_mir_observed`], reading lines as `options` ask
+pub(crate) fn str_to_mir_with<V: Valuable, O: MirObserver,>(
+	input: &str,
+	observer: &mut O,
+	options: &ParseOptions,
+) -> PRslt<StructuredInput,> {
+	lines_to_mir_observed::<V, _,>(numbered(input,), observer, options,)
+}
+
+/// every line comes with the number errors and observers report for it
+pub(crate) fn lines_to_mir_observed<'a, V: Valuable, O: MirObserver,>(
+	lines: impl IntoIterator<Item = (usize, impl Into<Cow<'a, str,>,>,),>,
+	observer: &mut O,
+	options: &ParseOptions,
+) -> PRslt<StructuredInput,> {
+	build_mir::<V, _,>(lines, observer, LineRules::new(options,),)
+		.map_err(|mut errors| errors.swap_remove(0,),)
+}
+
+/// same as [`str_to_mir`], but every malformed line is reported instead of
+/// the first one only
+pub(crate) fn str_to_mir_collecting<V: Valuable, O: MirObserver,>(
+	input: &str,
+	observer: &mut O,
+	options: &ParseOptions,
+) -> Result<StructuredInput, Vec<ParseError,>,> {
+	let rules = LineRules { collect: true, ..LineRules::new(options,) };
+	build_mir::<V, _,>(numbered(input,), observer, rules,)
+}
+
+/// same as [`str_to_mir_collecting`], also returning the mir of every line
+/// without errors
+pub(crate) fn str_to_mir_all_errors<V: Valuable, O: MirObserver,>(
+	input: &str,
+	observer: &mut O,
+	options: &ParseOptions,
+) -> (StructuredInput, Vec<ParseError,>,) {
+	let rules = LineRules { collect: true, ..LineRules::new(options,) };
 	let mut root = StructuredInput::new();
+	let mut errors = Vec::new();
+	read_lines::<V, _,>(
+		&mut root,
+		numbered(input,),
+		observer,
+		rules,
+		None,
+		&mut errors,
+	);
+	(root, errors,)
+}
+
+/// `errors` ordered by [`ParseError::line`], those without a line last. the
+/// errors of one line keep their order
+pub(crate) fn by_line(mut errors: Vec<ParseError,>,) -> Vec<ParseError,> {
+	errors.sort_by_key(|err| err.line().unwrap_or(usize::MAX,),);
+	errors
+}
+
+/// most errors reported for a single line when errors are collected, so a
+/// degenerate line cannot flood the list
+pub const LINE_ERROR_BUDGET: usize = 4;
 
-	for (idx, raw_line,) in input.lines().enumerate() {
-		let line_no = idx + 1;
+/// stops at the first line with errors unless `rules` collect them. fails
+/// only with a non-empty list
+fn build_mir<'a, V: Valuable, O: MirObserver,>(
+	lines: impl IntoIterator<Item = (usize, impl Into<Cow<'a, str,>,>,),>,
+	observer: &mut O,
+	rules: LineRules,
+) -> Result<StructuredInput, Vec<ParseError,>,> {
+	let mut root = StructuredInput::new();
+	let mut errors = Vec::new();
+	read_lines::<V, _,>(&mut root, lines, observer, rules, None, &mut errors,);
+	if errors.is_empty() { Ok(root,) } else { Err(errors,) }
+}
+
+/// assignments of `lines` go into `root`, errors into `errors`. include
+/// directives are only followed when `open_files` is given, see
+/// [`include_file`]
+fn read_lines<'a, V: Valuable, O: MirObserver,>(
+	root: &mut StructuredInput,
+	lines: impl IntoIterator<Item = (usize, impl Into<Cow<'a, str,>,>,),>,
+	observer: &mut O,
+	rules: LineRules,
+	mut open_files: Option<&mut Vec<PathBuf,>,>,
+	errors: &mut Vec<ParseError,>,
+) {
+	// segments of the last section header, prefixed to every key below it
+	let mut section = Vec::new();
+	let options = rules.options;
+	for (line_no, raw_line,) in logical_lines::<V,>(lines, options,) {
+		let raw_line = &*raw_line;
 		let trimmed = raw_line.trim();
+		observer.line_scanned();
 
 		if trimmed.is_empty() {
+			observer.line_skipped();
 			continue;
 		}
 
 		// we can assume that this `unwrap` do not panic, because it is ensured
 		// `trimmed` is not empty
 		let first_char = trimmed.chars().next().unwrap();
-		if first_char == '#' || first_char == ';' {
+		if options.comment_chars.contains(&first_char,) {
+			observer.line_skipped();
 			continue;
 		}
 
-		let (key_part, value_part,) = V::extract_key_value(trimmed, line_no,)?;
+		if let Some(open_files,) = open_files.as_deref_mut()
+			&& let Some(target,) = include_target(trimmed,)
+		{
+			include_file::<V, _,>(
+				root, target, line_no, observer, rules, open_files, errors,
+			);
+			if !rules.collect && !errors.is_empty() {
+				break;
+			}
+			continue;
+		}
+
+		if first_char == '[' {
+			match section_header(trimmed, line_no, options,) {
+				Ok(header,) => section = header,
+				Err(err,) => {
+					errors.push(err,);
+					if !rules.collect {
+						break;
+					}
+				},
+			}
+			continue;
+		}
+
+		let parsed = parse_assignment::<V,>(raw_line, line_no, options,)
+			.and_then(|(relative, key_column, leaf, appends,)| {
+				let written: Vec<String,> =
+					section.iter().cloned().chain(relative,).collect();
+				let segments: Vec<String,> = written
+					.iter()
+					.map(|segment| options.key_case.apply(segment,),)
+					.collect();
+				if segments != written {
+					observer.key_folded(&segments, &written,);
+				}
+				insert_value(
+					root,
+					&segments,
+					leaf.clone(),
+					key_column,
+					options.duplicate_keys,
+				)
+				.map(|previous| (segments, leaf, previous, appends,),)
+				.map_err(|err| vec![err],)
+			},);
+		let (segments, (value, _, column,), previous, appends,) = match parsed {
+			Ok(parsed,) => parsed,
+			Err(line_errors,) => {
+				errors
+					.extend(line_errors.into_iter().take(LINE_ERROR_BUDGET,),);
+				if rules.collect {
+					continue;
+				}
+				break;
+			},
+		};
+
+		observer.entry_parsed(&segments, line_no,);
+		if appends {
+			observer.entry_appended(&segments, line_no,);
+		}
+		if let Some((previous_value, previous_line, previous_column,),) =
+			previous
+		{
+			trace::event!(
+				info,
+				key = %segments.join(".",),
+				previous_line,
+				line = line_no,
+				"value overridden"
+			);
+			observer.entry_overridden(
+				&segments,
+				(&previous_value, previous_line, previous_column,),
+				(&value, line_no, column,),
+			);
+		}
+	}
+}
+
+/// segments of a `[dotted.section]` line. `line` is trimmed and starts with
+/// `[`
+pub(crate) fn section_header(
+	line: &str,
+	line_no: usize,
+	options: &ParseOptions,
+) -> PRslt<Vec<String,>,> {
+	let invalid = || ParseError::InvalidSectionHeader { line: line_no, };
+	let line = strip_inline_comment(line, &options.comment_chars,);
+	let inner = line
+		.trim_end()
+		.strip_prefix('[',)
+		.and_then(|rest| rest.strip_suffix(']',),)
+		.ok_or_else(invalid,)?;
+	if inner.trim().is_empty() {
+		return Err(invalid(),);
+	}
+	parse_key(inner, line_no, 0, options.segment_charset,)
+		.map_err(|_| invalid(),)
+}
+
+/// segments, column of the key and value of a non-blank, non-comment line,
+/// and whether it appends as in `key += value`. the key and the value are
+/// checked independently, so a line wrong in both reports both, key errors
+/// first
+fn parse_assignment<V: Valuable,>(
+	raw_line: &str,
+	line_no: usize,
+	options: &ParseOptions,
+) -> Result<(Vec<String,>, usize, MirLeaf, bool,), Vec<ParseError,>,> {
+	let line = if options.trim_values { raw_line.trim_end() } else { raw_line };
+	let (key_part, value_part,) = options
+		.split_assignment::<V>(line, line_no,)
+		.map_err(|err| vec![err],)?;
+	let (key_part, appends,) = match key_part.strip_suffix('+',) {
+		Some(key_part,) if V::APPENDS => (key_part, true,),
+		_ => (key_part, false,),
+	};
 
-		let segments = parse_key(key_part, line_no,)?;
+	let key_column = column_at(line, line.len() - line.trim_start().len(),);
+	let segments =
+		check_key(key_part, line_no, key_column, options.segment_charset,);
+	let value_offset = line.len() - value_part.len();
+	let value_column = column_at(line, value_offset,);
+	let value = reject_trailing_characters(
+		value_part,
+		line_no,
+		value_column,
+		&options.comment_chars,
+	)
+	.and_then(|()| parse_value(value_part, line_no, value_column, options,),);
+	let leading = if options.trim_values {
+		value_part.len() - value_part.trim_start().len()
+	} else {
+		0
+	};
+	let value_column = column_at(line, value_offset + leading,);
 
-		let value = parse_value(value_part, line_no,)?;
-		insert_value(&mut root, &segments, value, line_no,)?;
+	match (segments, value,) {
+		(Ok(segments,), Ok(value,),) => Ok((
+			segments,
+			key_column,
+			(value, line_no, value_column,),
+			appends,
+		),),
+		(segments, value,) => Err(segments
+			.err()
+			.unwrap_or_default()
+			.into_iter()
+			.chain(value.err(),)
+			.collect(),),
 	}
+}
+
+/// separators of conf and schema lines. a key containing one of them could
+/// be declared in one kind of file but never assigned in the other
+pub(crate) const KEY_SEPARATORS: [&str; 2] = ["=", "->",];
 
-	Ok(root,)
+/// `column` is where `key_part` starts
+pub(crate) fn parse_key(
+	key_part: &str,
+	line_no: usize,
+	column: usize,
+	charset: SegmentCharset,
+) -> PRslt<Vec<String,>,> {
+	check_key(key_part, line_no, column, charset,)
+		.map_err(|mut errors| errors.swap_remove(0,),)
 }
 
-fn parse_key(key_part: &str, line_no: usize,) -> PRslt<Vec<String,>,> {
-	if key_part.trim().is_empty() {
-		return Err(ParseError::EmptyKey { line: line_no, },);
+/// every segment containing a separator or a character outside `charset` is
+/// reported, followed by one error for the first empty segment, if any.
+/// segments are trimmed first, so whitespace around dots is fine. `column`
+/// is where `key_part` starts
+fn check_key(
+	key_part: &str,
+	line_no: usize,
+	column: usize,
+	charset: SegmentCharset,
+) -> Result<Vec<String,>, Vec<ParseError,>,> {
+	let leading = key_part.len() - key_part.trim_start().len();
+	let key = key_part.trim();
+	let column = column + key_part[..leading].chars().count();
+	if key.is_empty() {
+		return Err(vec![ParseError::EmptyKey { line: line_no, column, }],);
 	}
 
-	let segments: Vec<String,> = key_part
-		.trim()
+	// each segment with the column it starts at, not counting whitespace
+	let mut offset = 0;
+	let segments: Vec<(&str, usize,),> = key
 		.split('.',)
-		.map(|segment| segment.trim(),)
-		.map(|segment| segment.to_string(),)
+		.map(|segment| {
+			let leading = segment.len() - segment.trim_start().len();
+			let start = column + key[..offset + leading].chars().count();
+			offset += segment.len() + 1;
+			(segment.trim(), start,)
+		},)
 		.collect();
 
-	if segments.iter().any(|segment| segment.is_empty(),) {
-		let bad = segments
-			.into_iter()
-			.find(|segment| segment.is_empty(),)
-			.unwrap_or_default();
-		return Err(ParseError::InvalidKeySegment {
-			segment: bad,
+	let mut errors: Vec<ParseError,> = segments
+		.iter()
+		.filter(|(segment, _,)| {
+			KEY_SEPARATORS.iter().any(|sep| segment.contains(sep,),)
+				|| !charset.allows(segment,)
+		},)
+		.map(|(bad, column,)| ParseError::InvalidKeySegment {
+			segment: bad.to_string(),
 			line:    line_no,
+			column:  *column,
+		},)
+		.collect();
+
+	if let Some((_, column,),) =
+		segments.iter().find(|(segment, _,)| segment.is_empty(),)
+	{
+		errors.push(ParseError::InvalidKeySegment {
+			segment: String::new(),
+			line:    line_no,
+			column:  *column,
 		},);
 	}
 
-	Ok(segments,)
+	if errors.is_empty() {
+		Ok(segments
+			.into_iter()
+			.map(|(segment, _,)| segment.to_string(),)
+			.collect(),)
+	} else {
+		Err(errors,)
+	}
 }
 
-fn parse_value(value_part: &str, line_no: usize,) -> PRslt<String,> {
-	let without_comment = strip_inline_comment(value_part,);
-	let trimmed = without_comment.trim();
+/// a value which is a single double-quoted string is taken verbatim, escapes
+/// decoded and inline comment markers inside the quotes kept. anything else,
+/// collections of quoted elements included, is read as written, only trimmed
+/// at both ends unless `options` keep the whitespace there. whitespace inside
+/// is kept, and so are comment markers inside quoted elements. a quoted
+/// string which itself opens with a quote, after any whitespace, is kept
+/// quoted too, so the typing stage, which decodes quoted values, reads it
+/// back as that one string. `column` is where `value_part` starts
+pub(crate) fn parse_value(
+	value_part: &str,
+	line_no: usize,
+	column: usize,
+	options: &ParseOptions,
+) -> PRslt<String,> {
+	let comment_chars = &options.comment_chars;
+	if let Some(quoted,) = value_part.trim_start().strip_prefix('"',) {
+		let Some(close,) = closing_quote(quoted,) else {
+			return Err(ParseError::UnterminatedString { line: line_no, },);
+		};
+		let unescaped = unescape(&quoted[..close],);
+		if strip_inline_comment(&quoted[close + 1..], comment_chars,)
+			.trim()
+			.is_empty()
+			&& !unescaped.trim_start().starts_with('"',)
+		{
+			return Ok(unescaped,);
+		}
+	}
 
-	if trimmed.is_empty() {
-		return Err(ParseError::EmptyValue { line: line_no, },);
+	let without_comment =
+		&value_part[..value_comment_start(value_part, comment_chars,)
+			.unwrap_or(value_part.len(),)];
+	if without_comment.trim().is_empty() && !options.allow_empty_values {
+		return Err(ParseError::EmptyValue { line: line_no, column, },);
 	}
 
-	let mut normalized = String::with_capacity(trimmed.len(),);
-	let mut last_was_space = false;
+	if options.trim_values {
+		Ok(without_comment.trim().to_string(),)
+	} else {
+		Ok(without_comment.to_string(),)
+	}
+}
 
-	for ch in trimmed.chars() {
-		if ch.is_whitespace() {
-			if !normalized.is_empty() && !last_was_space {
-				normalized.push(' ',);
-				last_was_space = true;
-			}
-		} else {
-			normalized.push(ch,);
-			last_was_space = false;
+/// byte offset of the inline comment marker in a value, if any. markers
+/// inside the quotes of a quoted element do not count
+fn value_comment_start(value: &str, comment_chars: &[char],) -> Option<usize,> {
+	let mut start = 0;
+	let mut element_start = true;
+	loop {
+		let rest = &value[start..];
+		if element_start && let Some((_, end,),) = split_quoted(rest,) {
+			start += end;
+		}
+		let rest = &value[start..];
+		let idx = rest.find(|ch| ch == ',' || comment_chars.contains(&ch,),)?;
+		if !rest[idx..].starts_with(',',) {
+			return Some(start + idx,);
+		}
+		// `\,` is a comma inside an unquoted element
+		element_start = !rest[..idx].ends_with('\\',);
+		start += idx + 1;
+	}
+}
+
+/// the decoded string of a value opening with a double quote, leading
+/// whitespace aside, and the byte offset right after its closing quote.
+/// `None` unless the value opens with a quote which is closed
+pub(crate) fn split_quoted(value: &str,) -> Option<(String, usize,),> {
+	let trimmed = value.trim_start();
+	let quoted = trimmed.strip_prefix('"',)?;
+	let close = closing_quote(quoted,)?;
+	let open = value.len() - trimmed.len();
+	Some((unescape(&quoted[..close],), open + close + 2,),)
+}
+
+/// fails when anything but whitespace, an inline comment or a `,` separating
+/// collection elements follows the closing quote of a quoted value.
+/// `value_column` is the 1-based column `value_part` starts at
+fn reject_trailing_characters(
+	value_part: &str,
+	line_no: usize,
+	value_column: usize,
+	comment_chars: &[char],
+) -> PRslt<(),> {
+	let unquoted = value_part.trim_start();
+	let Some(quoted,) = unquoted.strip_prefix('"',) else {
+		return Ok((),);
+	};
+	let Some(close,) = closing_quote(quoted,) else {
+		return Ok((),);
+	};
+
+	let after_quote = &quoted[close + 1..];
+	let tail = &after_quote
+		[..inline_comment_start(after_quote, comment_chars,)
+			.unwrap_or(after_quote.len(),)];
+	let text = tail.trim();
+	if text.is_empty() || text.starts_with(',',) {
+		return Ok((),);
+	}
+
+	let tail_offset = value_part.len() - after_quote.len()
+		+ (tail.len() - tail.trim_start().len());
+	Err(ParseError::TrailingCharacters {
+		line:   line_no,
+		column: value_column + value_part[..tail_offset].chars().count(),
+		text:   text.to_string(),
+	},)
+}
+
+/// byte offset of the quote closing `quoted`, which starts right after the
+/// opening one. quotes escaped with `\` do not count
+fn closing_quote(quoted: &str,) -> Option<usize,> {
+	let mut escaped = false;
+	for (idx, ch,) in quoted.char_indices() {
+		match ch {
+			_ if escaped => escaped = false,
+			'\\' => escaped = true,
+			'"' => return Some(idx,),
+			_ => {},
 		}
 	}
+	None
+}
 
-	Ok(normalized,)
+/// decodes `\"`, `\\`, `\n` and `\t`. other escapes are kept as written
+fn unescape(quoted: &str,) -> String {
+	let mut unescaped = String::with_capacity(quoted.len(),);
+	let mut chars = quoted.chars();
+	while let Some(ch,) = chars.next() {
+		if ch != '\\' {
+			unescaped.push(ch,);
+			continue;
+		}
+		match chars.next() {
+			Some('n',) => unescaped.push('\n',),
+			Some('t',) => unescaped.push('\t',),
+			Some(ch @ ('"' | '\\'),) => unescaped.push(ch,),
+			Some(other,) => {
+				unescaped.push('\\',);
+				unescaped.push(other,);
+			},
+			None => unescaped.push('\\',),
+		}
+	}
+	unescaped
 }
 
-fn strip_inline_comment(input: &str,) -> String {
-	match input.find(['#', ';',],) {
+pub(crate) fn strip_inline_comment(
+	input: &str,
+	comment_chars: &[char],
+) -> String {
+	match inline_comment_start(input, comment_chars,) {
 		Some(cmt_index,) => input[..cmt_index].to_string(),
 		None => input.to_string(),
 	}
 }
 
+/// byte offset of the inline comment marker in `input`, if any
+pub(crate) fn inline_comment_start(
+	input: &str,
+	comment_chars: &[char],
+) -> Option<usize,> {
+	input.find(comment_chars,)
+}
+
+/// returns the earlier assignment of the key, if any. which of the two is
+/// kept is up to `duplicate_keys`. conflicts point at `key_column`
 fn insert_value(
 	root: &mut StructuredInput,
 	segments: &[String],
-	value: String,
-	line_no: usize,
-) -> PRslt<(),> {
+	leaf: MirLeaf,
+	key_column: usize,
+	duplicate_keys: DuplicateKeyPolicy,
+) -> PRslt<Option<MirLeaf,>,> {
+	let line_no = leaf.1;
 	let mut current = root;
 	for (idx, segment,) in segments.iter().enumerate() {
 		let is_last = idx == segments.len() - 1;
 		if is_last {
 			match current.entry(segment.clone(),) {
 				Entry::Vacant(entry,) => {
-					entry.insert(TreeValue::Scalar((
-						value.to_string(),
-						line_no,
-					),),);
+					entry.insert(TreeValue::Scalar(leaf,),);
+					return Ok(None,);
 				},
 				Entry::Occupied(mut entry,) => match entry.get_mut() {
 					TreeValue::Scalar(existing,) => {
-						existing.0 = value.to_string();
-						existing.1 = line_no;
+						return match duplicate_keys {
+							DuplicateKeyPolicy::LastWins => {
+								Ok(Some(std::mem::replace(existing, leaf,),),)
+							},
+							DuplicateKeyPolicy::FirstWins => {
+								Ok(Some(existing.clone(),),)
+							},
+							DuplicateKeyPolicy::Error => {
+								Err(ParseError::DuplicateKey {
+									key:         segments.join(".",),
+									first_line:  existing.1,
+									second_line: line_no,
+								},)
+							},
+						};
 					},
 					TreeValue::Map(_,) => {
 						return Err(ParseError::ConflictingTypes {
-							key:  segments[..=idx].join(".",),
-							line: line_no,
+							key:    segments[..=idx].join(".",),
+							line:   line_no,
+							column: key_column,
+							origin: None,
 						},);
 					},
 				},
@@ -193,8 +1203,10 @@ fn insert_value(
 				//  (like a.b.c.d = xxx with a.b.c = yyy)
 				Some(TreeValue::Scalar(_,),) => {
 					return Err(ParseError::ConflictingTypes {
-						key:  segments[..=idx].join(".",),
-						line: line_no,
+						key:    segments[..=idx].join(".",),
+						line:   line_no,
+						column: key_column,
+						origin: None,
 					},);
 				},
 				None => unreachable!(),
@@ -202,13 +1214,63 @@ fn insert_value(
 		}
 	}
 
-	Ok((),)
+	Ok(None,)
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use crate::parser::conf::SingleValue;
+	use crate::parser::conf::SingleValueDiscriminants;
+
+	#[test]
+	fn include_directives_need_a_target() {
+		assert_eq!(
+			include_target("@include conf.d/a.conf"),
+			Some("conf.d/a.conf")
+		);
+		assert_eq!(include_target("@include\t a.conf "), Some("a.conf"));
+		assert_eq!(include_target("@includes = 1"), None);
+		assert_eq!(include_target("@include"), None);
+		assert!(str_to_mir::<SingleValue,>("@include a.conf\n").is_err());
+	}
+
+	#[test]
+	fn reading_lines_as_they_come_matches_reading_the_whole_text() {
+		for input in [
+			include_str!("../../tests/examples/sysctl_sample.conf"),
+			include_str!("../../tests/examples/sysctl_nested_assignment.conf"),
+			"a = 1\r\nb = long \\\r\n  line\r\nc = last",
+			"",
+		] {
+			let streamed =
+				read_to_mir::<_, SingleValue,>(std::io::Cursor::new(input,),);
+			let whole = str_to_mir::<SingleValue,>(input,);
+			assert_eq!(format!("{streamed:?}"), format!("{whole:?}"));
+		}
+	}
+
+	#[test]
+	fn io_error_mid_stream_discards_what_was_read() {
+		struct FailingAfter<'a,>(&'a [u8],);
+		impl std::io::Read for FailingAfter<'_,> {
+			fn read(&mut self, buf: &mut [u8],) -> std::io::Result<usize,> {
+				if self.0.is_empty() {
+					return Err(std::io::Error::other("pipe closed",),);
+				}
+				let len = buf.len().min(self.0.len(),);
+				buf[..len].copy_from_slice(&self.0[..len],);
+				self.0 = &self.0[len..];
+				Ok(len,)
+			}
+		}
+
+		let reader = BufReader::new(FailingAfter(b"a = 1\nb = 2",),);
+		let err = read_to_mir::<_, SingleValue,>(reader,).unwrap_err();
+		assert!(
+			matches!(err, ParseError::Io(ref err) if err.to_string() == "pipe closed")
+		);
+	}
 
 	#[test]
 	fn extract_key_value_uses_type_separator() {
@@ -218,23 +1280,88 @@ mod tests {
 		assert_eq!(value, " beta");
 	}
 
+	#[test]
+	fn extract_key_value_splits_at_first_separator_only() {
+		for (line, key, value,) in [
+			("query = a=b&c=d", "query", " a=b&c=d",),
+			(
+				"jvm.opts = -Dfoo=bar -Dbaz=qux",
+				"jvm.opts",
+				" -Dfoo=bar -Dbaz=qux",
+			),
+			("a=b.c = d", "a", "b.c = d",),
+			("arrow = x -> y", "arrow", " x -> y",),
+		] {
+			assert_eq!(
+				SingleValue::extract_key_value(line, 1,).unwrap(),
+				(key, value,)
+			);
+		}
+
+		for (line, key, value,) in [
+			("desc -> String -> more", "desc", " String -> more",),
+			("a=b.c -> String", "a=b.c", " String",),
+		] {
+			assert_eq!(
+				SingleValueDiscriminants::extract_key_value(line, 1,).unwrap(),
+				(key, value,)
+			);
+		}
+	}
+
+	#[test]
+	fn split_assignment_takes_the_earliest_and_longest_delimiter() {
+		for (line, key, value,) in [
+			("a => b = c", "a", " b = c",),
+			("a = b => c", "a", " b => c",),
+			("a: b = c", "a", " b = c",),
+		] {
+			assert_eq!(
+				split_assignment(line, 1, &["=", "=>", ":",],).unwrap(),
+				(key, value,)
+			);
+		}
+	}
+
+	#[test]
+	fn parse_key_rejects_separators() {
+		for (key, segment,) in [("a=b.c", "a=b",), ("net.a->b", "a->b",),] {
+			match parse_key(key, 2, 1, SegmentCharset::default(),).unwrap_err()
+			{
+				ParseError::InvalidKeySegment {
+					segment: bad, line: 2,
+				..
+				} => {
+					assert_eq!(bad, segment)
+				},
+				other => panic!("unexpected error: {other:?}"),
+			}
+		}
+	}
+
 	#[test]
 	fn extract_key_value_missing_separator_surfaces_error() {
 		let err =
 			SingleValue::extract_key_value("no_delimiter", 4,).unwrap_err();
 		match err {
-			ParseError::MissingDelimiter { line, } => assert_eq!(line, 4),
+			ParseError::MissingDelimiter { line, column, expected, } => {
+				assert_eq!(
+					(line, column, expected),
+					(4, 1, vec!["=".to_string()])
+				)
+			},
 			other => panic!("unexpected error: {other:?}"),
 		}
 	}
 
 	#[test]
 	fn parse_key_rejects_empty_segments() {
-		let err = parse_key("foo..bar", 8,).unwrap_err();
+		let err = parse_key("foo..bar", 8, 1, SegmentCharset::default(),)
+			.unwrap_err();
 		match err {
-			ParseError::InvalidKeySegment { segment, line, } => {
+			ParseError::InvalidKeySegment { segment, line, column, } => {
 				assert_eq!(segment, "");
-				assert_eq!(line, 8);
+				assert_eq!((line, column), (8, 5));
 			},
 			other => panic!("unexpected error: {other:?}"),
 		}
@@ -242,25 +1369,140 @@ mod tests {
 
 	#[test]
 	fn parse_key_happy_path() {
-		let key_segments = parse_key(" network . ipv4 . port", 1,).unwrap();
+		let key_segments = parse_key(
+			" network . ipv4 . port",
+			1,
+			1,
+			SegmentCharset::default(),
+		)
+		.unwrap();
 		assert_eq!(key_segments, vec!["network", "ipv4", "port"]);
 	}
 
 	#[test]
 	fn parse_value_trims_and_ignores_inline_comment() {
-		let value = parse_value(" on 	 value ; comment ", 5,).unwrap();
-		assert_eq!(value, "on value");
+		let value = parse_value(
+			" on 	 value ; comment ",
+			5,
+			1,
+			&ParseOptions::default(),
+		)
+		.unwrap();
+		assert_eq!(value, "on 	 value");
+	}
+
+	#[test]
+	fn strip_inline_comment_keeps_the_colons_of_addresses() {
+		let comment_chars = ParseOptions::default().comment_chars;
+		assert_eq!(
+			strip_inline_comment("fe80::1 ; comment", &comment_chars,),
+			"fe80::1 "
+		);
+		assert_eq!(
+			strip_inline_comment("[::1]:8080 # comment", &comment_chars,),
+			"[::1]:8080 "
+		);
 	}
 
 	#[test]
 	fn parse_value_rejects_empty_payload() {
-		let err = parse_value("   # fully commented", 2,).unwrap_err();
+		let err = parse_value(
+			"   # fully commented",
+			2,
+			6,
+			&ParseOptions::default(),
+		)
+		.unwrap_err();
 		match err {
-			ParseError::EmptyValue { line, } => assert_eq!(line, 2),
+			ParseError::EmptyValue { line, column, } => {
+				assert_eq!((line, column), (2, 6))
+			},
 			other => panic!("unexpected error: {other:?}"),
 		}
 	}
 
+	#[test]
+	fn quoted_value_followed_by_garbage_is_rejected() {
+		let err = str_to_mir::<SingleValue,>("name = \"prod\" extra text",)
+			.unwrap_err();
+		match err {
+			ParseError::TrailingCharacters { line, column, text, } => {
+				assert_eq!(line, 1);
+				assert_eq!(column, 15);
+				assert_eq!(text, "extra text");
+			},
+			other => panic!("unexpected error: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn quoted_value_may_be_followed_by_comment_or_element() {
+		let mir = str_to_mir::<SingleValue,>(
+			"name = \"prod\"   # env\nports = \"80\", \"443\"\ntags = \
+			 \"a#b\", \"c\" # note\nmotd = \"\\\"hi\\\"\"\npad = \" \\\"x\\\" \
+			 y\"",
+		)
+		.unwrap();
+		assert_eq!(
+			mir.get("name",),
+			Some(&TreeValue::Scalar(("prod".to_string(), 1, 8)))
+		);
+		assert_eq!(
+			mir.get("ports",),
+			Some(&TreeValue::Scalar(("\"80\", \"443\"".to_string(), 2, 9)))
+		);
+		assert_eq!(
+			mir.get("tags",),
+			Some(&TreeValue::Scalar(("\"a#b\", \"c\"".to_string(), 3, 8)))
+		);
+		assert_eq!(
+			mir.get("motd",),
+			Some(&TreeValue::Scalar(("\"\\\"hi\\\"\"".to_string(), 4, 8)))
+		);
+		assert_eq!(
+			mir.get("pad",),
+			Some(&TreeValue::Scalar(("\" \\\"x\\\" y\"".to_string(), 5, 7)))
+		);
+	}
+
+	#[test]
+	fn quoted_value_keeps_comment_markers_and_decodes_escapes() {
+		let mir = str_to_mir::<SingleValue,>(
+			"password = \"abc#123 ; still part of value\" # comment\nmotd = \
+			 \"say \\\"hi\\\"\\n\\tC:\\\\  \"\nempty = \"\"",
+		)
+		.unwrap();
+		assert_eq!(
+			mir.get("password",),
+			Some(&TreeValue::Scalar((
+				"abc#123 ; still part of value".to_string(),
+				1,
+				12
+			)))
+		);
+		assert_eq!(
+			mir.get("motd",),
+			Some(&TreeValue::Scalar((
+				"say \"hi\"\n\tC:\\  ".to_string(),
+				2,
+				8
+			)))
+		);
+		assert_eq!(
+			mir.get("empty",),
+			Some(&TreeValue::Scalar((String::new(), 3, 9)))
+		);
+	}
+
+	#[test]
+	fn unterminated_quoted_value_is_rejected() {
+		let err = str_to_mir::<SingleValue,>(
+			"name = prod\nmotd = \"open # never closed\\\"",
+		)
+		.unwrap_err();
+		assert!(matches!(err, ParseError::UnterminatedString { line: 2, }));
+	}
+
 	#[test]
 	fn str_to_mir_ignores_comments_and_blank_lines() {
 		let input = "# heading\n\n endpoint = localhost \n log.file = \
@@ -268,7 +1510,10 @@ mod tests {
 		let mir = str_to_mir::<SingleValue,>(input,).unwrap();
 
 		let endpoint = mir.get("endpoint",).unwrap();
-		assert_eq!(endpoint, &TreeValue::Scalar(("localhost".to_string(), 3)));
+		assert_eq!(
+			endpoint,
+			&TreeValue::Scalar(("localhost".to_string(), 3, 13))
+		);
 
 		let nested = mir.get("log",).unwrap();
 		match nested {
@@ -276,7 +1521,7 @@ mod tests {
 				let value = children.get("file",).unwrap();
 				assert_eq!(
 					value,
-					&TreeValue::Scalar(("/tmp/out.log".to_string(), 4))
+					&TreeValue::Scalar(("/tmp/out.log".to_string(), 4, 13))
 				);
 			},
 			other => panic!("expected map, got {other:?}"),
@@ -288,9 +1533,9 @@ mod tests {
 		let input = "foo = one\nfoo.bar = two";
 		let err = str_to_mir::<SingleValue,>(input,).unwrap_err();
 		match err {
-			ParseError::ConflictingTypes { key, line, } => {
+			ParseError::ConflictingTypes { key, line, column, .. } => {
 				assert_eq!(key, "foo");
-				assert_eq!(line, 2);
+				assert_eq!((line, column), (2, 1));
 			},
 			other => panic!("unexpected error: {other:?}"),
 		}
@@ -301,13 +1546,13 @@ mod tests {
 		let tree = TreeValue::Map(BTreeMap::from([
 			(
 				"first".to_string(),
-				TreeValue::Scalar(("value".to_string(), 7,),),
+				TreeValue::Scalar(("value".to_string(), 7, 9,),),
 			),
 			(
 				"nested".to_string(),
 				TreeValue::Map(BTreeMap::from([(
 					"inner".to_string(),
-					TreeValue::Scalar(("another".to_string(), 11,),),
+					TreeValue::Scalar(("another".to_string(), 11, 9,),),
 				),],),),
 			),
 		],),);
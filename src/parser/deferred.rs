@@ -0,0 +1,246 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::Value;
+use crate::parser::conf::inject_payload;
+use crate::parser::core::TreeValue;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+/// resolvers by the name used in `@name(arg)` placeholders. they must be
+/// `Send + Sync`, so a set of resolvers can be shared between threads
+pub type Resolvers = HashMap<
+	String,
+	Box<dyn Fn(&str,) -> Result<String, String,> + Send + Sync,>,
+>;
+
+impl ConfMap {
+	/// replaces every `@resolver(arg)` placeholder with the value returned by
+	/// the named resolver, typed against the declaration in `schema`.
+	///
+	/// either every placeholder is replaced or, on error, none is. a
+	/// placeholder naming a resolver missing from `resolvers` is reported as
+	/// [`ParseError::Unresolved`] together with all others
+	pub fn resolve(
+		&mut self,
+		resolvers: &Resolvers,
+		schema: &SchemaMap,
+	) -> PRslt<(),> {
+		let mut placeholders = Vec::new();
+		collect_placeholders(self, &mut Vec::new(), &mut placeholders,);
+
+		let mut resolved = Vec::new();
+		let mut unresolved = Vec::new();
+		for (segments, resolver, arg,) in placeholders {
+			let key = segments.join(".",);
+			let Some(resolve,) = resolvers.get(&resolver,) else {
+				unresolved.push(key,);
+				continue;
+			};
+
+			let text = resolve(&arg,).map_err(|reason| {
+				ParseError::ResolverFailed {
+					key: key.clone(),
+					resolver: resolver.clone(),
+					reason,
+				}
+			},)?;
+			let Some(TreeValue::Scalar(leaf,),) = schema.get(&key,) else {
+				return Err(ParseError::UnknownKey {
+					key,
+					lines: vec![],
+					suggestion: None,
+				},);
+			};
+			// a resolved value is final, even when it looks like a placeholder
+			let leaf = SchemaLeaf { resolvable: false, ..leaf.clone() };
+			let value = inject_payload(
+				&key,
+				&leaf,
+				TreeValue::Scalar((text, 0, 0,),),
+			)?;
+			resolved.push((segments, value,),);
+		}
+
+		if !unresolved.is_empty() {
+			return Err(ParseError::Unresolved { keys: unresolved, },);
+		}
+		for (segments, value,) in resolved {
+			replace(self, &segments, value,);
+		}
+		Ok((),)
+	}
+}
+
+/// splits `@name(arg)` into name and argument
+pub(crate) fn parse_placeholder(value: &str,) -> Option<(String, String,),> {
+	let (resolver, arg,) =
+		value.strip_prefix('@',)?.strip_suffix(')',)?.split_once('(',)?;
+	let valid_name = !resolver.is_empty()
+		&& resolver
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-',);
+	valid_name.then(|| (resolver.to_string(), arg.to_string(),),)
+}
+
+/// text a placeholder is written as
+pub(crate) fn placeholder(resolver: &str, arg: &str,) -> String {
+	format!("@{resolver}({arg})")
+}
+
+fn collect_placeholders(
+	map: &BTreeMap<String, ConfValue,>,
+	path: &mut Vec<String,>,
+	placeholders: &mut Vec<(Vec<String,>, String, String,),>,
+) {
+	for (key, value,) in map {
+		path.push(key.clone(),);
+		match value {
+			ConfValue::Scalar(Value::Single(SingleValue::Deferred {
+				resolver,
+				arg,
+			},),) => {
+				placeholders
+					.push((path.clone(), resolver.clone(), arg.clone(),),)
+			},
+			ConfValue::Scalar(_,) => {},
+			ConfValue::Map(children,) => {
+				collect_placeholders(children, path, placeholders,)
+			},
+		}
+		path.pop();
+	}
+}
+
+fn replace(
+	map: &mut BTreeMap<String, ConfValue,>,
+	segments: &[String],
+	value: ConfValue,
+) {
+	let Some((last, parents,),) = segments.split_last() else {
+		return;
+	};
+
+	let mut current = map;
+	for segment in parents {
+		let Some(ConfValue::Map(children,),) = current.get_mut(segment,) else {
+			return;
+		};
+		current = children;
+	}
+	current.insert(last.clone(), value,);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::schema;
+	use crate::testing::get_int;
+	use crate::testing::get_str;
+
+	const SCHEMA: &str =
+		"db.password -> @String\ndb.port -> @Integer\nname -> String\n";
+
+	fn resolvers() -> Resolvers {
+		let mut resolvers: Resolvers = HashMap::new();
+		resolvers.insert(
+			"secret".to_string(),
+			Box::new(|arg| match arg {
+				"prod/db/password" => Ok("hunter2".to_string(),),
+				other => Err(format!("no secret at {other}"),),
+			},),
+		);
+		resolvers.insert(
+			"env".to_string(),
+			Box::new(|_| Ok("not-a-port".to_string(),),),
+		);
+		resolvers
+	}
+
+	#[test]
+	fn placeholders_parse_only_for_resolvable_keys() {
+		let conf = conf::parse_str(
+			"db.password = @secret(prod/db/password)\nname = @secret(x)\n",
+			schema::parse_str(SCHEMA,).unwrap(),
+		)
+		.unwrap();
+
+		assert_eq!(
+			conf.get("db.password"),
+			Some(&ConfValue::Scalar(Value::Single(SingleValue::Deferred {
+				resolver: "secret".to_string(),
+				arg:      "prod/db/password".to_string(),
+			}))),
+		);
+		assert_eq!(get_str(&conf, "name"), Ok("@secret(x)"));
+	}
+
+	#[test]
+	fn resolve_replaces_placeholders_with_typed_values() {
+		let schema = schema::parse_str(SCHEMA,).unwrap();
+		let mut conf = conf::parse_str(
+			"db.password = @secret(prod/db/password)\ndb.port = 5432\n",
+			&schema,
+		)
+		.unwrap();
+
+		conf.resolve(&resolvers(), &schema,).unwrap();
+		assert_eq!(get_str(&conf, "db.password"), Ok("hunter2"));
+		assert_eq!(get_int(&conf, "db.port"), Ok(5432));
+	}
+
+	#[test]
+	fn failed_resolution_leaves_every_placeholder_in_place() {
+		let schema = schema::parse_str(SCHEMA,).unwrap();
+		let mut conf = conf::parse_str(
+			"db.password = @secret(prod/db/password)\ndb.port = @env(PORT)\n",
+			&schema,
+		)
+		.unwrap();
+
+		let err = conf.resolve(&resolvers(), &schema,).unwrap_err();
+		assert!(
+			matches!(err, ParseError::InvalidValue { ref key, .. } if key == "db.port")
+		);
+		assert!(matches!(
+			conf.get("db.password"),
+			Some(ConfValue::Scalar(Value::Single(
+				SingleValue::Deferred { .. }
+			)))
+		));
+	}
+
+	#[test]
+	fn unknown_resolvers_are_listed() {
+		let schema = schema::parse_str(SCHEMA,).unwrap();
+		let mut conf = conf::parse_str(
+			"db.password = @vault(a)\ndb.port = @consul(b)\n",
+			&schema,
+		)
+		.unwrap();
+
+		let err = conf.resolve(&resolvers(), &schema,).unwrap_err();
+		match err {
+			ParseError::Unresolved { keys, } => {
+				assert_eq!(keys, vec!["db.password", "db.port"])
+			},
+			other => panic!("unexpected error: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn placeholder_syntax() {
+		assert_eq!(
+			parse_placeholder("@secret(prod/db)"),
+			Some(("secret".to_string(), "prod/db".to_string()))
+		);
+		assert_eq!(parse_placeholder("@(x)"), None);
+		assert_eq!(parse_placeholder("secret(x)"), None);
+		assert_eq!(parse_placeholder("@secret(x"), None);
+	}
+}
@@ -0,0 +1,155 @@
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::Value;
+use crate::parser::deferred::placeholder;
+use crate::parser::duration::render_duration;
+use crate::parser::secret::REDACTED;
+use crate::parser::view::ConfView;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+/// difference of a single leaf between two conf maps, keyed by dotted path
+#[derive(Debug, Clone, PartialEq,)]
+pub enum ConfDiff {
+	Added { key: String, value: Value<SingleValue,>, },
+	Removed { key: String, value: Value<SingleValue,>, },
+	Changed { key: String, old: Value<SingleValue,>, new: Value<SingleValue,>, },
+}
+
+impl ConfDiff {
+	pub fn key(&self,) -> &str {
+		match self {
+			Self::Added { key, .. }
+			| Self::Removed { key, .. }
+			| Self::Changed { key, .. } => key,
+		}
+	}
+}
+
+impl ConfMap {
+	/// leaves which differ between `self` and `other`, in key order. a leaf
+	/// counts as changed when its typed value differs, including the number
+	/// of elements of a collection
+	pub fn diff(&self, other: &ConfMap,) -> Vec<ConfDiff,> {
+		let before: BTreeMap<_, _,> = self.iter_leaves().collect();
+		let after: BTreeMap<_, _,> = other.iter_leaves().collect();
+
+		let mut diffs = Vec::new();
+		for (key, old,) in &before {
+			match after.get(key,) {
+				None => diffs.push(ConfDiff::Removed {
+					key:   key.clone(),
+					value: (*old).clone(),
+				},),
+				Some(new,) if new != old => diffs.push(ConfDiff::Changed {
+					key: key.clone(),
+					old: (*old).clone(),
+					new: (*new).clone(),
+				},),
+				Some(_,) => {},
+			}
+		}
+		for (key, new,) in &after {
+			if !before.contains_key(key,) {
+				diffs.push(ConfDiff::Added {
+					key:   key.clone(),
+					value: (*new).clone(),
+				},);
+			}
+		}
+
+		diffs.sort_by(|l, r| l.key().cmp(r.key(),),);
+		diffs
+	}
+}
+
+/// value as it is written in a conf file
+pub fn render_value(value: &Value<SingleValue,>,) -> String {
+	fn render_single(value: &SingleValue,) -> String {
+		match value {
+			SingleValue::String(s,) => s.clone(),
+			SingleValue::Bool(flag,) => flag.to_string(),
+			SingleValue::Integer(num,) => num.to_string(),
+			SingleValue::UInteger(num,) => num.to_string(),
+			SingleValue::Long(num,) => num.to_string(),
+			SingleValue::Float(num,) => num.to_string(),
+			SingleValue::Secret(_,) => REDACTED.to_string(),
+			SingleValue::Duration(duration,) => render_duration(*duration,),
+			SingleValue::IpAddr(addr,) => addr.to_string(),
+			SingleValue::SocketAddr(addr,) => addr.to_string(),
+			SingleValue::Path(path,) => path.to_string_lossy().into_owned(),
+			SingleValue::Deferred { resolver, arg, } => {
+				placeholder(resolver, arg,)
+			},
+		}
+	}
+
+	match value {
+		Value::Single(single,) => render_single(single,),
+		Value::Collection(items,) => {
+			items.iter().map(render_single,).collect::<Vec<_,>>().join(",",)
+		},
+	}
+}
+
+/// one line per diff: `+ key = value`, `- key = value` or
+/// `~ key = old -> new`
+impl Display for ConfDiff {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			Self::Added { key, value, } => {
+				write!(f, "+ {key} = {}", render_value(value))
+			},
+			Self::Removed { key, value, } => {
+				write!(f, "- {key} = {}", render_value(value))
+			},
+			Self::Changed { key, old, new, } => write!(
+				f,
+				"~ {key} = {} -> {}",
+				render_value(old),
+				render_value(new)
+			),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::schema;
+
+	const SCHEMA: &str = "server.port -> Integer\nserver.host -> \
+	                      String\nports -> Integer, Integer\ndebug -> Bool\n";
+
+	fn parse(input: &str,) -> ConfMap {
+		conf::parse_str(input, schema::parse_str(SCHEMA,).unwrap(),).unwrap()
+	}
+
+	#[test]
+	fn identical_maps_have_no_diff() {
+		let conf = parse("server.port = 80\ndebug = true\n",);
+		assert!(
+			conf.diff(&parse("debug = true\nserver.port = 80\n",)).is_empty()
+		);
+	}
+
+	#[test]
+	fn diff_reports_added_removed_and_changed_leaves() {
+		let before =
+			parse("server.port = 80\nserver.host = a\nports = 1, 2\n",);
+		let after = parse("server.port = 81\ndebug = false\nports = 1, 3\n",);
+
+		let rendered: Vec<String,> =
+			before.diff(&after,).iter().map(ToString::to_string,).collect();
+		assert_eq!(
+			rendered,
+			vec![
+				"+ debug = false",
+				"~ ports = 1,2 -> 1,3",
+				"- server.host = a",
+				"~ server.port = 80 -> 81",
+			]
+		);
+	}
+}
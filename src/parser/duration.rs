@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+/// units a duration is written in, from the largest, with their length in
+/// nanoseconds
+const UNITS: [(&str, u128,); 6] = [
+	("h", 3_600_000_000_000,),
+	("m", 60_000_000_000,),
+	("s", 1_000_000_000,),
+	("ms", 1_000_000,),
+	("us", 1_000,),
+	("ns", 1,),
+];
+
+/// `text` as a duration: a plain number of seconds, or numbers each followed
+/// by a unit of [`UNITS`] as in `1h30m`, the units from the largest down and
+/// none twice. `None` for anything else, a sign or a fraction included
+pub fn parse_duration(text: &str,) -> Option<Duration,> {
+	if text.bytes().all(|b| b.is_ascii_digit(),) {
+		return text.parse().ok().map(Duration::from_secs,);
+	}
+
+	let mut rest = text;
+	let mut nanos: u128 = 0;
+	let mut smallest = 0;
+	while !rest.is_empty() {
+		let digits =
+			rest.find(|c: char| !c.is_ascii_digit(),).unwrap_or(rest.len(),);
+		let (count, tail,) = rest.split_at(digits,);
+		let unit_len =
+			tail.find(|c: char| c.is_ascii_digit(),).unwrap_or(tail.len(),);
+		let (unit, tail,) = tail.split_at(unit_len,);
+
+		let idx = smallest
+			+ UNITS[smallest..].iter().position(|(name, _,)| *name == unit,)?;
+		let count: u128 = count.parse().ok()?;
+		nanos = nanos.checked_add(count.checked_mul(UNITS[idx].1,)?,)?;
+		smallest = idx + 1;
+		rest = tail;
+	}
+
+	let secs = u64::try_from(nanos / 1_000_000_000,).ok()?;
+	Some(Duration::new(secs, (nanos % 1_000_000_000) as u32,),)
+}
+
+/// `duration` in the units of [`parse_duration`], largest first and those
+/// counting 0 left out, as in `1h30m`. zero is `0s`
+pub fn render_duration(duration: Duration,) -> String {
+	let mut nanos = duration.as_nanos();
+	let mut text = String::new();
+	for (name, len,) in UNITS {
+		if nanos >= len {
+			text.push_str(&format!("{}{name}", nanos / len),);
+			nanos %= len;
+		}
+	}
+	if text.is_empty() {
+		text.push_str("0s",);
+	}
+	text
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_units_and_plain_seconds() {
+		for (text, expected,) in [
+			("30s", Duration::from_secs(30,),),
+			("5m", Duration::from_secs(300,),),
+			("1h30m", Duration::from_secs(5_400,),),
+			("250ms", Duration::from_millis(250,),),
+			("2s500ms", Duration::from_millis(2_500,),),
+			("90", Duration::from_secs(90,),),
+			("0", Duration::ZERO,),
+		] {
+			assert_eq!(parse_duration(text), Some(expected), "{text}");
+		}
+	}
+
+	#[test]
+	fn rejects_malformed_durations() {
+		for text in [
+			"",
+			"-5s",
+			"+5s",
+			"1.5s",
+			"s",
+			"5x",
+			"30s5m",
+			"5m5m",
+			"1h 30m",
+			"5 s",
+			"99999999999999999999999999999999999999999h",
+		] {
+			assert_eq!(parse_duration(text), None, "{text}");
+		}
+	}
+
+	#[test]
+	fn rendered_durations_parse_back() {
+		for duration in [
+			Duration::ZERO,
+			Duration::from_secs(90,),
+			Duration::from_secs(5_400,),
+			Duration::from_millis(250,),
+			Duration::new(3_661, 1_001_001,),
+		] {
+			let text = render_duration(duration,);
+			assert_eq!(parse_duration(&text), Some(duration), "{text}");
+		}
+		assert_eq!(render_duration(Duration::from_secs(90,)), "1m30s");
+		assert_eq!(render_duration(Duration::ZERO), "0s");
+	}
+}
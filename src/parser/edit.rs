@@ -0,0 +1,641 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::conf::inject_payload;
+use crate::parser::core::COMMENT_CHARS;
+use crate::parser::core::ParseOptions;
+use crate::parser::core::SegmentCharset;
+use crate::parser::core::TreeValue;
+use crate::parser::core::Valuable;
+use crate::parser::core::inline_comment_start;
+use crate::parser::core::parse_key;
+use crate::parser::core::parse_value;
+use crate::parser::core::section_header;
+use crate::parser::core::split_quoted;
+use crate::parser::core::trailing_backslash;
+use crate::parser::schema::AsSchema;
+use crate::parser::schema::SchemaMap;
+use std::fmt::Display;
+use std::ops::Range;
+
+/// an assignment line of the original text
+struct Assignment {
+	/// full key, the section the line is under included
+	segments: Vec<String,>,
+	/// how many of `segments` come from the section header
+	section:  usize,
+	/// byte range of the value, excluding surrounding whitespace and the
+	/// inline comment
+	value:    Range<usize,>,
+	/// byte offset of the start of the line
+	start:    usize,
+	/// byte offset just past the line terminator
+	line_end: usize,
+}
+
+/// conf text kept line by line, so that editing a key leaves every line it
+/// does not touch as it was, comments and blank lines included.
+///
+/// built by [`parse_document`]. unlike
+/// [`ConfMap`](crate::parser::conf::ConfMap) nothing of the text is dropped:
+/// [`Document::to_string`] yields the input back until it is edited
+#[derive(Debug, Clone, PartialEq,)]
+pub struct Document {
+	text:   String,
+	schema: SchemaMap,
+	lines:  Vec<DocLine,>,
+}
+
+/// what one line of a [`Document`] holds. a continued assignment is a single
+/// entry spanning all of its lines
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub enum DocLine {
+	Blank,
+	Comment,
+	Section(Vec<String,>,),
+	Entry {
+		/// full key, the section the line is under included
+		key:     String,
+		/// the inline comment after the value, comment char included
+		comment: Option<String,>,
+	},
+}
+
+/// parses `input` against `schema` into a [`Document`]. the text has to parse
+/// as [`conf::parse_str`](crate::parser::conf::parse_str) would accept it
+pub fn parse_document<S: AsSchema,>(
+	input: &str,
+	schema: S,
+) -> PRslt<Document,> {
+	let schema = schema.as_schema();
+	crate::parser::conf::parse_str(input, schema,)?;
+	Ok(Document::new(input.to_string(), schema.clone(),),)
+}
+
+impl Document {
+	fn new(text: String, schema: SchemaMap,) -> Self {
+		let lines = classify_lines(&text,);
+		Self { text, schema, lines, }
+	}
+
+	/// the lines of the document, in order
+	pub fn lines(&self,) -> &[DocLine] {
+		&self.lines
+	}
+
+	/// sets `key` to `value` as [`set_value`] does, rewriting only the line
+	/// assigning `key` or adding a single line for it
+	pub fn set(&mut self, key: &str, value: &str,) -> PRslt<(),> {
+		let text = set_value(&self.text, key, value, &self.schema,)?;
+		*self = Self::new(text, std::mem::take(&mut self.schema,),);
+		Ok((),)
+	}
+
+	/// removes every line assigning `key`, continuation lines included.
+	/// returns whether there was any
+	pub fn remove(&mut self, key: &str,) -> PRslt<bool,> {
+		let segments = parse_key(key, 0, 0, SegmentCharset::Permissive,)?;
+		let (assignments, _,) = scan_assignments(&self.text,);
+		let mut removed = false;
+		for assignment in assignments
+			.iter()
+			.rev()
+			.filter(|assignment| assignment.segments == segments,)
+		{
+			self.text.replace_range(assignment.start..assignment.line_end, "",);
+			removed = true;
+		}
+		if removed {
+			self.lines = classify_lines(&self.text,);
+		}
+		Ok(removed,)
+	}
+}
+
+impl Display for Document {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		f.write_str(&self.text,)
+	}
+}
+
+/// one [`DocLine`] per line of `text`, continuation lines folded into the
+/// entry they continue
+fn classify_lines(text: &str,) -> Vec<DocLine,> {
+	let (assignments, _,) = scan_assignments(text,);
+	let mut assignments = assignments.into_iter().peekable();
+	let mut lines = Vec::new();
+	let mut line_start = 0;
+	let mut skip_until = 0;
+
+	for piece in text.split_inclusive('\n',) {
+		let offset = line_start;
+		line_start += piece.len();
+		if offset < skip_until {
+			continue;
+		}
+		if let Some(assignment,) =
+			assignments.next_if(|assignment| assignment.start == offset,)
+		{
+			skip_until = assignment.line_end;
+			let last = text[offset..assignment.line_end]
+				.trim_end_matches(['\n', '\r',],)
+				.rsplit('\n',)
+				.next()
+				.unwrap_or_default();
+			let comment = inline_comment_start(last, &COMMENT_CHARS,)
+				.map(|at| last[at..].trim_end().to_string(),);
+			lines.push(DocLine::Entry {
+				key: assignment.segments.join(".",),
+				comment,
+			},);
+			continue;
+		}
+
+		let trimmed = piece.trim();
+		lines.push(if trimmed.is_empty() {
+			DocLine::Blank
+		} else if trimmed.starts_with('[',) {
+			let header = section_header(trimmed, 0, &ParseOptions::default(),);
+			header.map_or(DocLine::Comment, DocLine::Section,)
+		} else {
+			DocLine::Comment
+		},);
+	}
+
+	lines
+}
+
+/// sets `key` to `new_value` in `original_text`, leaving every other byte
+/// untouched.
+///
+/// the last line assigning `key` is rewritten, matching the last-wins
+/// semantics of the parser. when no line assigns `key`, a new line is
+/// inserted after the last assignment sharing the longest key prefix with
+/// it, or appended to the end of the text. under a section header the new
+/// line is written relative to the section, and a key outside of every
+/// section of the text goes before the first header. `new_value` is type
+/// checked against `schema` before anything is written
+pub fn set_value(
+	original_text: &str,
+	key: &str,
+	new_value: &str,
+	schema: &SchemaMap,
+) -> PRslt<String,> {
+	let segments = parse_key(key, 0, 0, SegmentCharset::Permissive,)?;
+	let key = segments.join(".",);
+	let value = checked_value(&key, new_value, schema,)?;
+	let (assignments, first_header,) = scan_assignments(original_text,);
+
+	let mut edited = String::with_capacity(original_text.len() + value.len(),);
+	if let Some(target,) = assignments
+		.iter()
+		.rev()
+		.find(|assignment| assignment.segments == segments,)
+	{
+		edited.push_str(&original_text[..target.value.start],);
+		// `key =` followed by nothing: keep a space after the delimiter
+		if target.value.is_empty() && !edited.ends_with(char::is_whitespace,) {
+			edited.push(' ',);
+		}
+		edited.push_str(&value,);
+		edited.push_str(&original_text[target.value.end..],);
+		return Ok(edited,);
+	}
+
+	let newline = if original_text.contains("\r\n",) { "\r\n" } else { "\n" };
+	let neighbour = assignments
+		.iter()
+		.filter(|assignment| {
+			let section = &assignment.segments[..assignment.section];
+			segments.len() > section.len()
+				&& segments.starts_with(section,)
+				&& shared_prefix(&assignment.segments, &segments,) > 0
+		},)
+		.max_by_key(|assignment| {
+			shared_prefix(&assignment.segments, &segments,)
+		},);
+	let (insert_at, written_key,) = match neighbour {
+		Some(assignment,) => {
+			(assignment.line_end, segments[assignment.section..].join(".",),)
+		},
+		None => (first_header.unwrap_or(original_text.len(),), key,),
+	};
+
+	edited.push_str(&original_text[..insert_at],);
+	if !edited.is_empty() && !edited.ends_with('\n',) {
+		edited.push_str(newline,);
+	}
+	edited.push_str(&format!("{written_key} = {value}{newline}"),);
+	edited.push_str(&original_text[insert_at..],);
+	Ok(edited,)
+}
+
+fn checked_value(
+	key: &str,
+	new_value: &str,
+	schema: &SchemaMap,
+) -> PRslt<String,> {
+	let Some(schema_value,) = schema.get(key,) else {
+		return Err(ParseError::UnknownKey {
+			key:        key.to_string(),
+			lines:      vec![],
+			suggestion: None,
+		},);
+	};
+	let TreeValue::Scalar(leaf,) = schema_value else {
+		return Err(ParseError::ConflictingTypes {
+			key:    key.to_string(),
+			line:   0,
+			column: 0,
+			origin: None,
+		},);
+	};
+
+	let written = new_value.trim();
+	let value = parse_value(written, 0, 0, &ParseOptions::default(),)?;
+	// anything the parser would drop or rewrite when reading the file back.
+	// a quoted string reads back decoded, and is type checked that way
+	let reads_back = match split_quoted(written,) {
+		Some((_, end,),) if end == written.len() => true,
+		_ => value == written,
+	};
+	if !reads_back {
+		let ty = match &leaf.ty {
+			Value::Single(kind,) => *kind,
+			Value::Collection(kinds,) => kinds
+				.first()
+				.copied()
+				.unwrap_or(SingleValueDiscriminants::String,),
+		};
+		return Err(ParseError::InvalidValue {
+			key: key.to_string(),
+			value: new_value.to_string(),
+			ty,
+			line: 0,
+			column: 0,
+			element: None,
+			position: None,
+		},);
+	}
+
+	inject_payload(key, leaf, TreeValue::Scalar((value, 0, 0,),),)?;
+	Ok(written.to_string(),)
+}
+
+/// assignments of `text` and the byte offset of its first section header.
+/// the value of a continued assignment spans its continuation lines
+fn scan_assignments(text: &str,) -> (Vec<Assignment,>, Option<usize,>,) {
+	let mut assignments: Vec<Assignment,> = Vec::new();
+	let mut section = Vec::new();
+	let mut first_header = None;
+	let mut line_start = 0;
+	let options = ParseOptions::default();
+	// whether the previous line continues on this one, and whether that line
+	// was an assignment
+	let mut continued = false;
+	let mut continues_value = false;
+
+	for (idx, piece,) in text.split_inclusive('\n',).enumerate() {
+		let line_end = line_start + piece.len();
+		let line = piece.trim_end_matches(['\n', '\r',],);
+		let trimmed = line.trim();
+		let offset = line_start;
+		line_start = line_end;
+
+		let continues = trailing_backslash::<SingleValue,>(line, &options,)
+			.is_some_and(|(_, c,)| c,);
+		if std::mem::replace(&mut continued, continues,) {
+			if trimmed.is_empty() {
+				continued = false;
+			} else if continues_value
+				&& let Some(assignment,) = assignments.last_mut()
+			{
+				let code = &line[..inline_comment_start(line, &COMMENT_CHARS,)
+					.unwrap_or(line.len(),)];
+				if !code.trim().is_empty() {
+					assignment.value.end = offset + code.trim_end().len();
+				}
+				assignment.line_end = line_end;
+			}
+			continue;
+		}
+		continues_value = false;
+
+		if trimmed.is_empty() || trimmed.starts_with(['#', ';',],) {
+			continue;
+		}
+		if trimmed.starts_with('[',) {
+			if let Ok(header,) = section_header(trimmed, idx + 1, &options,) {
+				section = header;
+				first_header.get_or_insert(offset,);
+			}
+			continue;
+		}
+		let Ok((key_part, _,),) =
+			SingleValue::extract_key_value(trimmed, idx + 1,)
+		else {
+			continue;
+		};
+		let Ok(relative,) =
+			parse_key(key_part, idx + 1, 1, options.segment_charset,)
+		else {
+			continue;
+		};
+		let segments = section.iter().cloned().chain(relative,).collect();
+
+		// the first delimiter of the raw line is the one of the trimmed line
+		// as leading whitespace cannot contain it
+		let Ok((_, rest,),) = SingleValue::extract_key_value(line, idx + 1,)
+		else {
+			continue;
+		};
+		let value_start = line.len() - rest.len();
+		let rest = &rest[..inline_comment_start(rest, &COMMENT_CHARS,)
+			.unwrap_or(rest.len(),)];
+		let start = value_start + (rest.len() - rest.trim_start().len());
+		let end = start + rest.trim().len();
+
+		assignments.push(Assignment {
+			segments,
+			section: section.len(),
+			value: offset + start..offset + end,
+			start: offset,
+			line_end,
+		},);
+		continues_value = true;
+	}
+
+	(assignments, first_header,)
+}
+
+fn shared_prefix(left: &[String], right: &[String],) -> usize {
+	left.iter().zip(right,).take_while(|(l, r,)| l == r,).count()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::schema;
+
+	/// asserts that `after` differs from `before` only by `removed` having
+	/// been replaced with `inserted`
+	fn assert_only_changed(
+		before: &str,
+		after: &str,
+		removed: &str,
+		inserted: &str,
+	) {
+		let prefix = before
+			.bytes()
+			.zip(after.bytes(),)
+			.take_while(|(l, r,)| l == r,)
+			.count();
+		let suffix = before[prefix..]
+			.bytes()
+			.rev()
+			.zip(after[prefix..].bytes().rev(),)
+			.take_while(|(l, r,)| l == r,)
+			.count();
+
+		assert_eq!(&before[prefix..before.len() - suffix], removed);
+		assert_eq!(&after[prefix..after.len() - suffix], inserted);
+	}
+
+	fn sample_schema() -> SchemaMap {
+		schema::parse_str(
+			"server.port -> Integer\nserver.host -> String\nlog.file -> \
+			 String\ndebug -> Bool",
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn rewrites_value_keeping_spacing_and_comment() {
+		let before = "# server\nserver.port   =   80   # http\ndebug=true\n";
+		let after = set_value(before, "server.port", "8080", &sample_schema(),)
+			.unwrap();
+
+		assert_eq!(
+			after,
+			"# server\nserver.port   =   8080   # http\ndebug=true\n"
+		);
+		assert_only_changed(before, &after, "", "80",);
+	}
+
+	#[test]
+	fn rewrites_only_the_last_assignment() {
+		let before = "server.port = 80\nserver.port = 81 ; later\n";
+		let after =
+			set_value(before, "server.port", "90", &sample_schema(),).unwrap();
+
+		assert_eq!(after, "server.port = 80\nserver.port = 90 ; later\n");
+	}
+
+	#[test]
+	fn appends_new_key_after_its_section() {
+		let before = "server.host = localhost\ndebug = true\n";
+		let after = set_value(before, "server.port", "8080", &sample_schema(),)
+			.unwrap();
+
+		assert_eq!(
+			after,
+			"server.host = localhost\nserver.port = 8080\ndebug = true\n"
+		);
+		assert_only_changed(before, &after, "", "server.port = 8080\n",);
+	}
+
+	#[test]
+	fn writes_keys_relative_to_their_section_header() {
+		let before = "[server]\nhost = localhost\n\n[log]\nfile = a.log\n";
+		let after = set_value(before, "server.port", "8080", &sample_schema(),)
+			.unwrap();
+		assert_only_changed(before, &after, "", "port = 8080\n",);
+		let after =
+			set_value(&after, "server.port", "90", &sample_schema(),).unwrap();
+		assert!(after.starts_with("[server]\nhost = localhost\nport = 90\n"));
+
+		let after =
+			set_value(before, "debug", "true", &sample_schema(),).unwrap();
+		assert_eq!(after, format!("debug = true\n{before}"));
+	}
+
+	#[test]
+	fn rewrites_every_line_of_a_continued_value() {
+		let before = "log.file = /var/\\\n\t/log/a.log # old\ndebug = true\n";
+		let after =
+			set_value(before, "log.file", "/tmp/b.log", &sample_schema(),)
+				.unwrap();
+
+		assert_eq!(after, "log.file = /tmp/b.log # old\ndebug = true\n");
+	}
+
+	#[test]
+	fn fills_in_missing_value() {
+		let before = "debug =\n";
+		let after =
+			set_value(before, "debug", "false", &sample_schema(),).unwrap();
+
+		assert_eq!(after, "debug = false\n");
+	}
+
+	#[test]
+	fn appends_unrelated_key_to_end_of_text() {
+		let before = "debug = true";
+		let after =
+			set_value(before, "log.file", "/tmp/out.log", &sample_schema(),)
+				.unwrap();
+
+		assert_eq!(after, "debug = true\nlog.file = /tmp/out.log\n");
+	}
+
+	#[test]
+	fn rejects_value_of_wrong_type() {
+		let err = set_value(
+			"server.port = 80\n",
+			"server.port",
+			"http",
+			&sample_schema(),
+		)
+		.unwrap_err();
+
+		match err {
+			ParseError::InvalidValue { key, value, ty, .. } => {
+				assert_eq!(key, "server.port");
+				assert_eq!(value, "http");
+				assert_eq!(ty, SingleValueDiscriminants::Integer);
+			},
+			other => panic!("unexpected error: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn rejects_value_which_would_not_read_back() {
+		let err = set_value("", "log.file", "/tmp/a#b", &sample_schema(),)
+			.unwrap_err();
+
+		assert!(matches!(err, ParseError::InvalidValue { .. }));
+	}
+
+	#[test]
+	fn quoted_value_keeps_its_quotes_and_comment_markers() {
+		let after = set_value(
+			"server.host = a\n",
+			"server.host",
+			"\"hello # world\"",
+			&sample_schema(),
+		)
+		.unwrap();
+
+		assert_eq!(after, "server.host = \"hello # world\"\n");
+		let conf =
+			crate::parser::conf::parse_str(&after, sample_schema(),).unwrap();
+		assert_eq!(conf.get_str("server.host"), Some("hello # world"));
+
+		let err = set_value(
+			"",
+			"server.host",
+			"\"hello\" # world",
+			&sample_schema(),
+		)
+		.unwrap_err();
+		assert!(matches!(err, ParseError::InvalidValue { .. }));
+	}
+
+	fn sysctl_sample() -> (&'static str, SchemaMap,) {
+		let schema = schema::parse_str(include_str!(
+			"../../tests/examples/sysctl_sample.schema"
+		),)
+		.unwrap();
+		(include_str!("../../tests/examples/sysctl_sample.conf"), schema,)
+	}
+
+	#[test]
+	fn document_round_trips_untouched_text() {
+		let (text, schema,) = sysctl_sample();
+		let document = parse_document(text, &schema,).unwrap();
+
+		assert_eq!(document.to_string(), text);
+		assert_eq!(document.lines().len(), text.lines().count());
+	}
+
+	#[test]
+	fn document_edit_leaves_every_other_byte() {
+		let (text, schema,) = sysctl_sample();
+		let mut document = parse_document(text, &schema,).unwrap();
+		document.set("vm.swappiness", "60",).unwrap();
+
+		assert_only_changed(text, &document.to_string(), "1", "6",);
+		let reparsed =
+			crate::parser::conf::parse_str(&document.to_string(), &schema,)
+				.unwrap();
+		assert_eq!(reparsed.get_int("vm.swappiness"), Some(60));
+	}
+
+	#[test]
+	fn document_tells_comments_blanks_and_entries_apart() {
+		let text = "# top\n\n[server]\nport = 80 ; http\nhost = \\\n\tlocal\n";
+		let document = parse_document(text, sample_schema(),).unwrap();
+
+		assert_eq!(
+			document.lines(),
+			[
+				DocLine::Comment,
+				DocLine::Blank,
+				DocLine::Section(vec!["server".to_string()]),
+				DocLine::Entry {
+					key:     "server.port".to_string(),
+					comment: Some("; http".to_string()),
+				},
+				DocLine::Entry {
+					key:     "server.host".to_string(),
+					comment: None,
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn document_appends_new_keys_to_their_section() {
+		let text = "[server]\nhost = a\n\n# logging\n[log]\nfile = a.log\n";
+		let mut document = parse_document(text, sample_schema(),).unwrap();
+		document.set("server.port", "80",).unwrap();
+
+		assert_only_changed(text, &document.to_string(), "", "port = 80\n",);
+		assert_eq!(
+			document.lines()[2],
+			DocLine::Entry {
+				key:     "server.port".to_string(),
+				comment: None,
+			}
+		);
+	}
+
+	#[test]
+	fn document_removes_every_assignment_of_a_key() {
+		let (text, schema,) = sysctl_sample();
+		let mut document = parse_document(text, &schema,).unwrap();
+
+		assert!(document.remove("service.mode").unwrap());
+		assert!(!document.remove("service.mode").unwrap());
+		let edited = document.to_string();
+		assert!(!edited.contains("service.mode"));
+		assert!(edited.contains("after value\n\n# override"));
+		assert_eq!(edited.lines().count(), text.lines().count() - 2);
+	}
+
+	#[test]
+	fn rejects_key_missing_from_schema() {
+		let err =
+			set_value("", "server.tls", "on", &sample_schema(),).unwrap_err();
+
+		match err {
+			ParseError::UnknownKey { key, lines, .. } => {
+				assert_eq!(key, "server.tls");
+				assert!(lines.is_empty());
+			},
+			other => panic!("unexpected error: {other:?}"),
+		}
+	}
+}
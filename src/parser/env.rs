@@ -0,0 +1,246 @@
+use crate::error::ParseError;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::inject_payload;
+use crate::parser::core::TreeValue;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaValue;
+use crate::parser::source::Origin;
+use std::collections::BTreeMap;
+
+/// overrides entries of `conf` with the environment variables named after
+/// `prefix` and a key of `schema`, as `MYAPP_SERVER_PORT` sets `server.port`
+/// under the prefix `MYAPP`. see [`apply_overrides`]
+pub fn apply_env_overrides(
+	conf: &mut ConfMap,
+	schema: &SchemaMap,
+	prefix: &str,
+) -> (Vec<String,>, Vec<ParseError,>,) {
+	let vars = std::env::vars_os().filter_map(|(name, value,)| {
+		Some((name.into_string().ok()?, value.into_string().ok()?,),)
+	},);
+	apply_overrides(conf, schema, prefix, vars,)
+}
+
+/// sets the key each of `vars` names to its value, typed against `schema`.
+///
+/// a name is `prefix`, `_` and the key in upper case with `_` between its
+/// segments. a segment holding `_` itself is told apart by trying the
+/// longest segment which fits first. names of no key are ignored. each key
+/// set records the variable as its [`Origin::Env`] and keeps the labels and
+/// repeatability its declaration gives. returns the dotted keys set, in the
+/// order of the sorted names, and an error for each value which does not
+/// type
+pub fn apply_overrides<I, N, V,>(
+	conf: &mut ConfMap,
+	schema: &SchemaMap,
+	prefix: &str,
+	vars: I,
+) -> (Vec<String,>, Vec<ParseError,>,)
+where
+	I: IntoIterator<Item = (N, V,),>,
+	N: AsRef<str,>,
+	V: AsRef<str,>,
+{
+	let prefix = prefix.strip_suffix('_',).unwrap_or(prefix,);
+	let mut vars: Vec<_,> = vars
+		.into_iter()
+		.filter_map(|(name, value,)| {
+			let rest =
+				name.as_ref().strip_prefix(prefix,)?.strip_prefix('_',)?;
+			let (segments, leaf,) = find_key(schema, rest,)?;
+			Some((name, segments.join(".",), leaf, value,),)
+		},)
+		.collect();
+	vars.sort_by(|left, right| left.0.as_ref().cmp(right.0.as_ref(),),);
+
+	let mut applied = Vec::new();
+	let mut errors = Vec::new();
+	for (name, key, leaf, value,) in vars {
+		let mir = TreeValue::Scalar((value.as_ref().to_string(), 0, 0,),);
+		match inject_payload(&key, leaf, mir,)
+			.and_then(|value| conf.insert_dotted(&key, value,),)
+		{
+			Ok(_,) => {
+				if let Some(labels,) = &leaf.labels {
+					conf.labels_mut().insert(key.clone(), labels.clone(),);
+				}
+				if leaf.repeatable {
+					conf.repeatable_keys_mut().insert(key.clone(),);
+				}
+				conf.sources_mut().record(
+					&key,
+					Origin::Env(name.as_ref().to_string(),),
+					None,
+				);
+				applied.push(key,)
+			},
+			Err(err,) => errors.push(err,),
+		}
+	}
+	(applied, errors,)
+}
+
+/// segments of the leaf of `section` whose variable name is `name`
+fn find_key<'s,>(
+	section: &'s BTreeMap<String, SchemaValue,>,
+	name: &str,
+) -> Option<(Vec<&'s str,>, &'s SchemaLeaf,),> {
+	let mut candidates: Vec<_,> = section
+		.iter()
+		.filter_map(|(segment, value,)| {
+			let rest = name.strip_prefix(&var_name(segment,),)?;
+			Some((segment.as_str(), value, rest,),)
+		},)
+		.collect();
+	candidates.sort_by_key(|(segment, ..,)| std::cmp::Reverse(segment.len(),),);
+
+	candidates.into_iter().find_map(|(segment, value, rest,)| {
+		let (mut segments, leaf,) = match value {
+			TreeValue::Scalar(leaf,) if rest.is_empty() => (vec![], leaf,),
+			TreeValue::Map(children,) => {
+				find_key(children, rest.strip_prefix('_',)?,)?
+			},
+			TreeValue::Scalar(_,) => return None,
+		};
+		segments.insert(0, segment,);
+		Some((segments, leaf,),)
+	},)
+}
+
+/// `segment` as it is written in a variable name: upper case, with `_` for
+/// anything but ASCII letters and digits
+pub(crate) fn var_name(segment: &str,) -> String {
+	segment
+		.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }
+		},)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::conf::SingleValueDiscriminants;
+	use crate::parser::schema;
+
+	const SCHEMA: &str = "server.port -> Integer\nserver.max_conns -> \
+	                      Integer\nserver.max.conns -> String\nlog.level -> \
+	                      String\ntags -> [String]\n";
+
+	fn sample() -> (ConfMap, SchemaMap,) {
+		let schema = schema::parse_str(SCHEMA,).unwrap();
+		let conf = conf::parse_str("server.port = 80\n", &schema,).unwrap();
+		(conf, schema,)
+	}
+
+	#[test]
+	fn variables_override_and_add_keys() {
+		let (mut conf, schema,) = sample();
+		let (applied, errors,) = apply_overrides(
+			&mut conf,
+			&schema,
+			"APP",
+			[
+				("APP_SERVER_PORT", "8080",),
+				("APP_LOG_LEVEL", "debug",),
+				("APP_TAGS", "a, b",),
+				("APP_UNKNOWN", "1",),
+				("OTHER_SERVER_PORT", "1",),
+			],
+		);
+
+		assert!(errors.is_empty(), "{errors:?}");
+		assert_eq!(applied, ["log.level", "server.port", "tags"]);
+		assert_eq!(conf.get_int("server.port"), Some(8080));
+		assert_eq!(conf.get_str("log.level"), Some("debug"));
+		assert_eq!(
+			conf.get("tags"),
+			Some(&TreeValue::Scalar(crate::parser::conf::Value::Collection(
+				vec!["a".into(), "b".into()],
+			),),)
+		);
+	}
+
+	#[test]
+	fn overrides_record_their_variable_and_keep_labels() {
+		let schema = schema::parse_str(
+			"limits -> (min: Integer, max: Integer)\nallow -> [String] \
+			 (repeatable)\n",
+		)
+		.unwrap();
+		let mut conf = conf::parse_str("allow = a\n", &schema,).unwrap();
+		let (applied, errors,) = apply_overrides(
+			&mut conf,
+			&schema,
+			"APP",
+			[("APP_LIMITS", "1, 10",), ("APP_ALLOW", "b, c",),],
+		);
+
+		assert!(errors.is_empty(), "{errors:?}");
+		assert_eq!(applied, ["allow", "limits"]);
+		assert_eq!(
+			conf.get_field("limits", "max"),
+			Some(&crate::parser::conf::SingleValue::Integer(10))
+		);
+		assert!(conf.is_repeatable("allow"));
+		assert_eq!(
+			conf.history_of("limits")
+				.iter()
+				.map(|source| &source.origin)
+				.collect::<Vec<_,>>(),
+			[&Origin::Env("APP_LIMITS".to_string())]
+		);
+		assert_eq!(
+			conf.sources().source_of("allow").map(|source| &source.origin),
+			Some(&Origin::Env("APP_ALLOW".to_string()))
+		);
+	}
+
+	#[test]
+	fn segments_holding_underscores_match_longest_first() {
+		let (mut conf, schema,) = sample();
+		let (applied, errors,) = apply_overrides(
+			&mut conf,
+			&schema,
+			"APP_",
+			[("APP_SERVER_MAX_CONNS", "64",),],
+		);
+
+		assert!(errors.is_empty(), "{errors:?}");
+		assert_eq!(applied, ["server.max_conns"]);
+		assert_eq!(conf.get_int("server.max_conns"), Some(64));
+		assert!(conf.get("server.max.conns").is_none());
+	}
+
+	#[test]
+	fn values_of_the_wrong_type_are_reported() {
+		let (mut conf, schema,) = sample();
+		// a prefix no other test or process uses, as the environment is
+		// shared between test threads
+		// SAFETY: no other thread reads or writes this variable
+		unsafe {
+			std::env::set_var("DOT_CONF_ENV_TEST_SERVER_PORT", "eighty",);
+			std::env::set_var("DOT_CONF_ENV_TEST_LOG_LEVEL", "warn",);
+		}
+		let (applied, errors,) =
+			apply_env_overrides(&mut conf, &schema, "DOT_CONF_ENV_TEST",);
+
+		assert_eq!(applied, ["log.level"]);
+		assert_eq!(
+			errors,
+			[ParseError::InvalidValue {
+				key:      "server.port".to_string(),
+				value:    "eighty".to_string(),
+				ty:       SingleValueDiscriminants::Integer,
+				line:     0,
+				column:   0,
+				element:  None,
+				position: None,
+			}]
+		);
+		assert_eq!(conf.get_int("server.port"), Some(80));
+	}
+}
@@ -0,0 +1,455 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::conf::check_constraints;
+use crate::parser::core::TreeValue;
+use crate::parser::deferred::placeholder;
+use crate::parser::duration::render_duration;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaValue;
+use crate::parser::secret::REDACTED;
+use crate::parser::secret::redact_for;
+use std::collections::BTreeMap;
+
+impl From<&SingleValue,> for serde_json::Value {
+	fn from(value: &SingleValue,) -> Self {
+		match value {
+			SingleValue::String(s,) => Self::String(s.clone(),),
+			SingleValue::Bool(flag,) => Self::Bool(*flag,),
+			SingleValue::Integer(num,) => Self::from(*num,),
+			SingleValue::UInteger(num,) => Self::from(*num,),
+			SingleValue::Long(num,) => Self::from(*num,),
+			// `Null` only for non-finite floats, which are never parsed
+			SingleValue::Float(num,) => Self::from(*num,),
+			SingleValue::Secret(_,) => Self::String(REDACTED.to_string(),),
+			SingleValue::Duration(duration,) => {
+				Self::String(render_duration(*duration,),)
+			},
+			SingleValue::IpAddr(addr,) => Self::String(addr.to_string(),),
+			SingleValue::SocketAddr(addr,) => Self::String(addr.to_string(),),
+			SingleValue::Path(path,) => {
+				Self::String(path.to_string_lossy().into_owned(),)
+			},
+			SingleValue::Deferred { resolver, arg, } => {
+				Self::String(placeholder(resolver, arg,),)
+			},
+		}
+	}
+}
+
+impl From<&ConfValue,> for serde_json::Value {
+	fn from(value: &ConfValue,) -> Self {
+		match value {
+			ConfValue::Scalar(Value::Single(single,),) => single.into(),
+			ConfValue::Scalar(Value::Collection(items,),) => {
+				Self::Array(items.iter().map(Self::from,).collect(),)
+			},
+			ConfValue::Map(children,) => map_to_json(children,),
+		}
+	}
+}
+
+fn map_to_json(map: &BTreeMap<String, ConfValue,>,) -> serde_json::Value {
+	serde_json::Value::Object(
+		map.iter().map(|(k, v,)| (k.clone(), v.into(),),).collect(),
+	)
+}
+
+impl ConfMap {
+	/// typed json object of every entry, nested by key segment
+	pub fn to_json_value(&self,) -> serde_json::Value {
+		map_to_json(self,)
+	}
+}
+
+/// builds a conf map from a json object typed against `schema`, the way
+/// [`ConfValue::try_from_json`] reads a section. a json number is no value
+/// for a `String` key, and `null` is a [`ParseError::NullValue`] wherever it
+/// stands
+pub fn from_json(
+	value: &serde_json::Value,
+	schema: &SchemaMap,
+) -> PRslt<ConfMap,> {
+	map_from_json("", value, schema,)
+		.map(|entries| ConfMap::from_typed(entries, schema,),)
+}
+
+impl ConfValue {
+	/// reads `value` back as the conf value described by `expected`.
+	///
+	/// objects must only contain keys known to `expected`, arrays must have
+	/// exactly one element per collection element
+	pub fn try_from_json(
+		value: &serde_json::Value,
+		expected: &SchemaValue,
+	) -> PRslt<ConfValue,> {
+		from_json_at("", value, expected,)
+	}
+}
+
+fn from_json_at(
+	key: &str,
+	value: &serde_json::Value,
+	expected: &SchemaValue,
+) -> PRslt<ConfValue,> {
+	match expected {
+		TreeValue::Scalar(leaf,) => {
+			let leaf_value = leaf_from_json(key, value, leaf,)
+				.map_err(|err| redact_for(leaf, err,),)?;
+			Ok(TreeValue::Scalar(leaf_value,),)
+		},
+		TreeValue::Map(schema_map,) => {
+			map_from_json(key, value, schema_map,).map(TreeValue::Map,)
+		},
+	}
+}
+
+/// entries of the section at `key`
+fn map_from_json(
+	key: &str,
+	value: &serde_json::Value,
+	schema_map: &BTreeMap<String, SchemaValue,>,
+) -> PRslt<BTreeMap<String, ConfValue,>,> {
+	let object = match value {
+		serde_json::Value::Object(object,) => object,
+		serde_json::Value::Null => {
+			return Err(ParseError::NullValue { key: key.to_string(), },);
+		},
+		_ => {
+			return Err(ParseError::ConflictingTypes {
+				key:    key.to_string(),
+				line:   0,
+				column: 0,
+				origin: None,
+			},);
+		},
+	};
+
+	let mut conf_map = BTreeMap::new();
+	for (child_key, child_value,) in object {
+		let dotted_key = if key.is_empty() {
+			child_key.clone()
+		} else {
+			format!("{key}.{child_key}")
+		};
+		let Some(child_schema,) = schema_map.get(child_key,) else {
+			return Err(ParseError::UnknownKey {
+				key:        dotted_key,
+				lines:      vec![],
+				suggestion: None,
+			},);
+		};
+
+		conf_map.insert(
+			child_key.clone(),
+			from_json_at(&dotted_key, child_value, child_schema,)?,
+		);
+	}
+	Ok(conf_map,)
+}
+
+fn leaf_from_json(
+	key: &str,
+	value: &serde_json::Value,
+	leaf: &SchemaLeaf,
+) -> PRslt<Value<SingleValue,>,> {
+	match &leaf.ty {
+		Value::Single(kind,) => {
+			let single = single_from_json(key, value, *kind, None,)?;
+			check_constraints(key, leaf, 0, &single, 0,)?;
+			Ok(Value::Single(single,),)
+		},
+		Value::Collection(kinds,) => {
+			let serde_json::Value::Array(items,) = value else {
+				return Err(invalid_value(key, value, kinds[0], None,),);
+			};
+			if !leaf.list && items.len() != kinds.len() {
+				return Err(invalid_value(key, value, kinds[0], None,),);
+			}
+
+			Ok(Value::Collection(
+				items
+					.iter()
+					.enumerate()
+					.map(|(idx, item,)| -> PRslt<SingleValue,> {
+						let kind = leaf.element_kind(idx,).unwrap_or(kinds[0],);
+						let single = single_from_json(
+							key,
+							item,
+							kind,
+							leaf.label(idx,),
+						)?;
+						check_constraints(key, leaf, idx, &single, 0,)?;
+						Ok(single,)
+					},)
+					.try_collect()?,
+			),)
+		},
+	}
+}
+
+fn single_from_json(
+	key: &str,
+	value: &serde_json::Value,
+	kind: SingleValueDiscriminants,
+	element: Option<&str,>,
+) -> PRslt<SingleValue,> {
+	let single = match (kind, value,) {
+		(_, serde_json::Value::Null,) => {
+			return Err(ParseError::NullValue { key: key.to_string(), },);
+		},
+		(SingleValueDiscriminants::String, serde_json::Value::String(s,),) => {
+			Some(SingleValue::String(s.clone(),),)
+		},
+		// types written as text in conf files are read from the same text
+		(
+			SingleValueDiscriminants::Secret
+			| SingleValueDiscriminants::Duration
+			| SingleValueDiscriminants::IpAddr
+			| SingleValueDiscriminants::SocketAddr
+			| SingleValueDiscriminants::Path,
+			serde_json::Value::String(s,),
+		) => kind.into_payload(key, s, 0,).ok(),
+		(SingleValueDiscriminants::Bool, serde_json::Value::Bool(flag,),) => {
+			Some(SingleValue::Bool(*flag,),)
+		},
+		(
+			SingleValueDiscriminants::Integer,
+			serde_json::Value::Number(num,),
+		) => num
+			.as_i64()
+			.and_then(|num| i32::try_from(num,).ok(),)
+			.map(SingleValue::Integer,),
+		(
+			SingleValueDiscriminants::UInteger,
+			serde_json::Value::Number(num,),
+		) => num.as_u64().map(SingleValue::UInteger,),
+		(SingleValueDiscriminants::Long, serde_json::Value::Number(num,),) => {
+			num.as_i64().map(SingleValue::Long,)
+		},
+		(SingleValueDiscriminants::Float, serde_json::Value::Number(num,),) => {
+			num.as_f64().map(SingleValue::Float,)
+		},
+		_ => None,
+	};
+
+	single.ok_or_else(|| invalid_value(key, value, kind, element,),)
+}
+
+fn invalid_value(
+	key: &str,
+	value: &serde_json::Value,
+	ty: SingleValueDiscriminants,
+	element: Option<&str,>,
+) -> ParseError {
+	ParseError::InvalidValue {
+		key: key.to_string(),
+		value: value.to_string(),
+		ty,
+		line: 0,
+		column: 0,
+		element: element.map(str::to_string,),
+		position: None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::schema;
+	use serde_json::json;
+
+	const SCHEMA: &str = "name -> String\ndebug -> Bool\nnet.port -> \
+	                      Integer\nnet.range -> (lo: Integer, hi: Integer)\n";
+
+	fn sample() -> (ConfMap, schema::SchemaMap,) {
+		let conf = conf::parse_str(
+			"name = app\ndebug = false\nnet.port = 8080\nnet.range = 1, 9\n",
+			schema::parse_str(SCHEMA,).unwrap(),
+		)
+		.unwrap();
+		(conf, schema::parse_str(SCHEMA,).unwrap(),)
+	}
+
+	#[test]
+	fn conf_map_becomes_typed_json() {
+		let (conf, _,) = sample();
+		assert_eq!(
+			conf.to_json_value(),
+			json!({
+				"debug": false,
+				"name": "app",
+				"net": { "port": 8080, "range": [1, 9] },
+			})
+		);
+	}
+
+	#[test]
+	fn json_round_trip_preserves_types() {
+		let (conf, schema,) = sample();
+		let expected = TreeValue::Map(schema.into_inner(),);
+		let original = TreeValue::Map(conf.into_inner(),);
+
+		let json = serde_json::Value::from(&original,);
+		let restored = ConfValue::try_from_json(&json, &expected,).unwrap();
+		assert_eq!(restored, original);
+	}
+
+	#[test]
+	fn mistyped_json_is_rejected() {
+		let schema = schema::parse_str(SCHEMA,).unwrap();
+		let expected = TreeValue::Map(schema.into_inner(),);
+
+		let err = ConfValue::try_from_json(
+			&json!({ "net": { "port": "8080" } }),
+			&expected,
+		)
+		.unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::InvalidValue { ref key, ty: SingleValueDiscriminants::Integer, .. }
+				if key == "net.port"
+		));
+
+		let err = ConfValue::try_from_json(
+			&json!({ "net": { "range": [1, true] } }),
+			&expected,
+		)
+		.unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::InvalidValue { element: Some(ref label), .. } if label == "hi"
+		));
+
+		let err =
+			ConfValue::try_from_json(&json!({ "colour": "red" }), &expected,)
+				.unwrap_err();
+		assert!(matches!(err, ParseError::UnknownKey { .. }));
+	}
+
+	#[test]
+	fn json_fixture_builds_the_map_its_conf_parses_into() {
+		let schema = schema::parse_str(include_str!(
+			"../../tests/examples/sysctl_sample.schema"
+		),)
+		.unwrap();
+		let json: serde_json::Value = serde_json::from_str(include_str!(
+			"../../tests/examples/sysctl_sample.json"
+		),)
+		.unwrap();
+		let parsed = conf::parse_str(
+			include_str!("../../tests/examples/sysctl_sample.conf"),
+			&schema,
+		)
+		.unwrap();
+
+		let built = from_json(&json, &schema,).unwrap();
+		assert_eq!(*built, *parsed);
+	}
+
+	#[test]
+	fn from_json_keeps_labels_and_repeatable_keys() {
+		let schema = schema::parse_str(
+			"limits -> (min: Integer, max: Integer)\nallow -> [String] \
+			 (repeatable)\n",
+		)
+		.unwrap();
+		let conf =
+			conf::parse_str("limits = 1, 10\nallow = a\nallow = b\n", &schema,)
+				.unwrap();
+
+		let built = from_json(&conf.to_json_value(), &schema,).unwrap();
+		assert_eq!(built, conf);
+		assert_eq!(
+			built.get_field("limits", "max"),
+			Some(&SingleValue::Integer(10))
+		);
+		assert!(built.is_repeatable("allow"));
+	}
+
+	#[test]
+	fn from_json_rejects_nulls_numbers_for_text_and_unknown_keys() {
+		let (_, schema,) = sample();
+
+		assert_eq!(
+			from_json(&json!({ "net": { "port": null } }), &schema,),
+			Err(ParseError::NullValue { key: "net.port".to_string(), })
+		);
+		assert_eq!(
+			from_json(&json!({ "net": null }), &schema,),
+			Err(ParseError::NullValue { key: "net".to_string(), })
+		);
+		assert_eq!(
+			from_json(&json!({ "net": { "range": [1, null] } }), &schema,),
+			Err(ParseError::NullValue { key: "net.range".to_string(), })
+		);
+		assert_eq!(
+			from_json(&json!({ "name": 7 }), &schema,),
+			Err(ParseError::InvalidValue {
+				key:      "name".to_string(),
+				value:    "7".to_string(),
+				ty:       SingleValueDiscriminants::String,
+				line:     0,
+				column:   0,
+				element:  None,
+				position: None,
+			})
+		);
+		assert_eq!(
+			from_json(&json!({ "nmae": "app" }), &schema,),
+			Err(ParseError::UnknownKey {
+				key:        "nmae".to_string(),
+				lines:      vec![],
+				suggestion: None,
+			})
+		);
+	}
+
+	#[test]
+	fn from_json_reads_text_types_and_checks_constraints() {
+		let schema = schema::parse_str(
+			"timeout -> Duration
+listen -> SocketAddr
+port -> Integer[1..65535]
+level -> Enum(debug, info)
+",
+		)
+		.unwrap();
+		let conf = from_json(
+			&json!({ "timeout": "1m30s", "listen": "127.0.0.1:80", "port": 80 }),
+			&schema,
+		)
+		.unwrap();
+		assert_eq!(
+			conf.get_duration("timeout"),
+			Some(std::time::Duration::from_secs(90))
+		);
+		assert!(conf.get("listen").is_some());
+
+		let err = from_json(&json!({ "port": 0 }), &schema,).unwrap_err();
+		assert!(err.is_out_of_range(), "{err:?}");
+		let err =
+			from_json(&json!({ "level": "trace" }), &schema,).unwrap_err();
+		assert!(err.is_invalid_enum_value(), "{err:?}");
+	}
+
+	#[test]
+	fn secrets_are_redacted_both_ways() {
+		let schema = schema::parse_str("token -> Secret\n",).unwrap();
+		let conf = conf::parse_str("token = hunter2\n", &schema,).unwrap();
+		assert_eq!(conf.to_json_value(), json!({ "token": REDACTED }));
+
+		let err = ConfValue::try_from_json(
+			&json!({ "token": 1234 }),
+			&TreeValue::Map(schema.into_inner(),),
+		)
+		.unwrap_err();
+		assert!(!err.to_string().contains("1234"), "{err}");
+	}
+}
@@ -0,0 +1,250 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::core::TreeValue;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaValue;
+use crate::parser::view::ConfMapRef;
+use crate::parser::view::ConfView;
+use std::collections::BTreeMap;
+
+type Entries = BTreeMap<String, ConfValue,>;
+
+/// what [`ConfMap::merge`] does with a value both maps set
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq,)]
+pub enum MergePolicy {
+	/// the merged map wins, like a later file overriding an earlier one
+	#[default]
+	OverrideWithOther,
+	KeepExisting,
+	/// fails with [`ParseError::MergeConflict`]
+	ErrorOnConflict,
+}
+
+impl ConfMap {
+	/// adds every value of `other`, recursing into sections both maps have.
+	/// a value set by both is resolved by `policy`, and a key which is a
+	/// value in one map and a section in the other fails with
+	/// [`ParseError::ConflictingTypes`]. `self` is left as it was on failure.
+	///
+	/// labels and repeatability follow the values taken from `other`, whose
+	/// sources are appended to the history of their keys
+	pub fn merge(
+		&mut self,
+		mut other: ConfMap,
+		policy: MergePolicy,
+	) -> PRslt<(),> {
+		check(self, &other, policy, None,)?;
+
+		let mut taken = Vec::new();
+		apply(self, std::mem::take(&mut *other,), policy, None, &mut taken,);
+
+		for key in taken {
+			match other.labels().get(&key,) {
+				Some(labels,) => {
+					self.labels_mut().insert(key.clone(), labels.clone(),);
+				},
+				None => {
+					self.labels_mut().remove(&key,);
+				},
+			}
+			if other.is_repeatable(&key,) {
+				self.repeatable_keys_mut().insert(key.clone(),);
+			} else {
+				self.repeatable_keys_mut().remove(&key,);
+			}
+			for source in other.history_of(&key,) {
+				self.sources_mut().record(
+					&key,
+					source.origin.clone(),
+					source.line,
+				);
+			}
+		}
+		Ok((),)
+	}
+}
+
+impl SchemaMap {
+	/// adds every declaration of `other`, recursing into sections both
+	/// schemas have. a key both declare alike is kept, while one declared
+	/// differently, or as a leaf in one and a section in the other, fails
+	/// with [`ParseError::ConflictingTypes`]. `self` is left as it was on
+	/// failure
+	pub fn merge(&mut self, other: SchemaMap,) -> PRslt<(),> {
+		check_schema(self, &other, None,)?;
+		apply_schema(self, other.into_inner(),);
+		Ok((),)
+	}
+}
+
+fn dotted(prefix: Option<&str,>, key: &str,) -> String {
+	match prefix {
+		Some(prefix,) => format!("{prefix}.{key}"),
+		None => key.to_string(),
+	}
+}
+
+fn check(
+	existing: &Entries,
+	other: &Entries,
+	policy: MergePolicy,
+	prefix: Option<&str,>,
+) -> PRslt<(),> {
+	for (key, value,) in other {
+		let Some(current,) = existing.get(key,) else { continue };
+		let dotted_key = dotted(prefix, key,);
+		match (current, value,) {
+			(TreeValue::Map(current,), TreeValue::Map(value,),) => {
+				check(current, value, policy, Some(&dotted_key,),)?
+			},
+			(TreeValue::Scalar(_,), TreeValue::Scalar(_,),) => {
+				if policy == MergePolicy::ErrorOnConflict {
+					return Err(ParseError::MergeConflict { key: dotted_key, },);
+				}
+			},
+			_ => {
+				return Err(ParseError::ConflictingTypes {
+					key:    dotted_key,
+					line:   0,
+					column: 0,
+					origin: None,
+				},);
+			},
+		}
+	}
+	Ok((),)
+}
+
+fn check_schema(
+	existing: &BTreeMap<String, SchemaValue,>,
+	other: &BTreeMap<String, SchemaValue,>,
+	prefix: Option<&str,>,
+) -> PRslt<(),> {
+	for (key, value,) in other {
+		let Some(current,) = existing.get(key,) else { continue };
+		let dotted_key = dotted(prefix, key,);
+		match (current, value,) {
+			(TreeValue::Map(current,), TreeValue::Map(value,),) => {
+				check_schema(current, value, Some(&dotted_key,),)?
+			},
+			(TreeValue::Scalar(current,), TreeValue::Scalar(value,),)
+				if current == value => {},
+			_ => {
+				return Err(ParseError::ConflictingTypes {
+					key:    dotted_key,
+					line:   0,
+					column: 0,
+					origin: None,
+				},);
+			},
+		}
+	}
+	Ok((),)
+}
+
+/// `check_schema` passed, or the schemas share nothing but sections
+pub(crate) fn apply_schema(
+	existing: &mut BTreeMap<String, SchemaValue,>,
+	other: BTreeMap<String, SchemaValue,>,
+) {
+	for (key, value,) in other {
+		match (existing.get_mut(&key,), value,) {
+			(Some(TreeValue::Map(current,),), TreeValue::Map(value,),) => {
+				apply_schema(current, value,)
+			},
+			(_, value,) => {
+				existing.insert(key, value,);
+			},
+		}
+	}
+}
+
+/// `check` passed, so values only meet values and sections only sections.
+/// pushes the dotted key of every leaf taken from `other` onto `taken`
+fn apply(
+	existing: &mut Entries,
+	other: Entries,
+	policy: MergePolicy,
+	prefix: Option<&str,>,
+	taken: &mut Vec<String,>,
+) {
+	for (key, value,) in other {
+		let dotted_key = dotted(prefix, &key,);
+		match (existing.get_mut(&key,), value,) {
+			(Some(TreeValue::Map(current,),), TreeValue::Map(value,),) => {
+				apply(current, value, policy, Some(&dotted_key,), taken,)
+			},
+			(Some(_,), _,) if policy == MergePolicy::KeepExisting => {},
+			(_, TreeValue::Map(children,),) => {
+				taken.extend(
+					ConfMapRef::from(&children,)
+						.iter_leaves()
+						.map(|(leaf, _,)| format!("{dotted_key}.{leaf}"),),
+				);
+				existing.insert(key, TreeValue::Map(children,),);
+			},
+			(_, value,) => {
+				taken.push(dotted_key,);
+				existing.insert(key, value,);
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::schema;
+
+	const SCHEMA: &str = "server.port -> Integer\nserver.host -> \
+	                      String\nserver.tls.cert -> String\nlimits -> (lo: \
+	                      Integer, hi: Integer)\n";
+
+	fn parse(input: &str,) -> ConfMap {
+		conf::parse_str(input, schema::parse_str(SCHEMA,).unwrap(),).unwrap()
+	}
+
+	#[test]
+	fn policies_resolve_values_set_by_both() {
+		let base = parse("server.port = 80\nserver.host = a\n",);
+		let other = parse("server.port = 8080\nserver.tls.cert = c.pem\n",);
+
+		let mut merged = base.clone();
+		merged.merge(other.clone(), MergePolicy::OverrideWithOther,).unwrap();
+		assert_eq!(
+			conf::to_string(&merged,),
+			"server.host = a\nserver.port = 8080\nserver.tls.cert = c.pem\n"
+		);
+
+		let mut merged = base.clone();
+		merged.merge(other.clone(), MergePolicy::KeepExisting,).unwrap();
+		assert_eq!(
+			conf::to_string(&merged,),
+			"server.host = a\nserver.port = 80\nserver.tls.cert = c.pem\n"
+		);
+
+		let mut merged = base.clone();
+		let err =
+			merged.merge(other, MergePolicy::ErrorOnConflict,).unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::MergeConflict { ref key } if key == "server.port"
+		));
+		assert_eq!(merged, base);
+	}
+
+	#[test]
+	fn labels_follow_the_values_taken() {
+		let mut merged = parse("server.port = 80\n",);
+		merged
+			.merge(parse("limits = 1, 5\n",), MergePolicy::default(),)
+			.unwrap();
+		assert_eq!(
+			merged.get_field("limits", "hi"),
+			Some(&crate::parser::conf::SingleValue::Integer(5))
+		);
+	}
+}
@@ -0,0 +1,405 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::error::Warning;
+use crate::parser::core::COMMENT_CHARS;
+use crate::parser::core::MirLeaf;
+use crate::parser::core::SegmentCharset;
+use crate::parser::core::StructuredInput;
+use crate::parser::core::TreeValue;
+use crate::parser::core::parse_key;
+use crate::parser::core::strip_inline_comment;
+use crate::trace;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::path::Path;
+
+type MirValue = TreeValue<MirLeaf,>;
+
+/// one rule of [`Migrations`]. keys are dotted
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub enum Migration {
+	/// moves the value at `from` to `to`
+	Rename { from: String, to: String, },
+	/// moves every key below `from` below `to`
+	MoveSubtree { from: String, to: String, },
+	/// discards the value or section at `key`
+	Drop { key: String, },
+}
+
+/// rules rewriting the keys of an outdated conf file before it is typed
+/// against the schema, applied in order. the text format has one rule per
+/// line:
+///
+/// ```text
+/// rename net.port -> server.port
+/// move logging -> log
+/// drop obsolete.flag
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq,)]
+pub struct Migrations(Vec<Migration,>,);
+
+impl Migrations {
+	pub fn new() -> Self {
+		Self(Vec::new(),)
+	}
+
+	pub fn rename(mut self, from: &str, to: &str,) -> Self {
+		self.0.push(Migration::Rename {
+			from: from.to_string(),
+			to:   to.to_string(),
+		},);
+		self
+	}
+
+	pub fn move_subtree(mut self, from: &str, to: &str,) -> Self {
+		self.0.push(Migration::MoveSubtree {
+			from: from.to_string(),
+			to:   to.to_string(),
+		},);
+		self
+	}
+
+	pub fn drop(mut self, key: &str,) -> Self {
+		self.0.push(Migration::Drop { key: key.to_string(), },);
+		self
+	}
+
+	pub fn rules(&self,) -> &[Migration] {
+		&self.0
+	}
+
+	pub fn parse_str(input: &str,) -> PRslt<Self,> {
+		let mut migrations = Self::new();
+		for (idx, raw_line,) in input.lines().enumerate() {
+			let line = idx + 1;
+			let rule = strip_inline_comment(raw_line, &COMMENT_CHARS,);
+			let rule = rule.trim();
+			if rule.is_empty() {
+				continue;
+			}
+
+			let invalid = || ParseError::InvalidMigration {
+				rule: rule.to_string(),
+				line,
+			};
+			let key = |text: &str| {
+				let text = text.trim();
+				if text.contains(char::is_whitespace,) {
+					return Err(invalid(),);
+				}
+				parse_key(text, line, 0, SegmentCharset::default(),)
+					.map(|segments| segments.join(".",),)
+					.map_err(|_| invalid(),)
+			};
+			let pair = |args: &str| {
+				let (from, to,) = args.split_once("->",).ok_or_else(invalid,)?;
+				PRslt::Ok((key(from,)?, key(to,)?,),)
+			};
+
+			let (verb, args,) =
+				rule.split_once(char::is_whitespace,).ok_or_else(invalid,)?;
+			migrations.0.push(match verb {
+				"rename" => {
+					let (from, to,) = pair(args,)?;
+					Migration::Rename { from, to, }
+				},
+				"move" => {
+					let (from, to,) = pair(args,)?;
+					Migration::MoveSubtree { from, to, }
+				},
+				"drop" => Migration::Drop { key: key(args,)?, },
+				_ => return Err(invalid(),),
+			},);
+		}
+		Ok(migrations,)
+	}
+
+	pub fn parse_file<P: AsRef<Path,>,>(path: P,) -> PRslt<Self,> {
+		Self::parse_str(&std::fs::read_to_string(path,)?,)
+	}
+
+	/// rewrites `mir` rule by rule. a rule whose key is absent is skipped,
+	/// every applied one is reported as a warning
+	pub(crate) fn apply(
+		&self,
+		mir: &mut StructuredInput,
+	) -> PRslt<Vec<Warning,>,> {
+		let mut warnings = Vec::new();
+		for rule in &self.0 {
+			let (from, to, node,) = match rule {
+				Migration::Rename { from, to, } => {
+					let node = detach(mir, &segments(from,), &|node| {
+						matches!(node, TreeValue::Scalar(_,))
+					},);
+					(from, Some(to,), node,)
+				},
+				Migration::MoveSubtree { from, to, } => {
+					let node = detach(mir, &segments(from,), &|node| {
+						matches!(node, TreeValue::Map(_,))
+					},);
+					(from, Some(to,), node,)
+				},
+				Migration::Drop { key, } => {
+					(key, None, detach(mir, &segments(key,), &|_| true,),)
+				},
+			};
+			let Some(node,) = node else {
+				continue;
+			};
+
+			let line = first_line(&node,);
+			if let Some(to,) = to {
+				attach(mir, &segments(to,), node, from, to,)?;
+			}
+			trace::event!(warn, from = %from, to = ?to, line, "key migrated");
+			warnings.push(Warning::MigratedKey {
+				from: from.clone(),
+				to: to.cloned(),
+				line,
+			},);
+		}
+		Ok(warnings,)
+	}
+}
+
+fn segments(key: &str,) -> Vec<String,> {
+	key.split('.',).map(str::to_string,).collect()
+}
+
+fn first_line(node: &MirValue,) -> usize {
+	node.get_lines_of_key().into_iter().min().unwrap_or_default()
+}
+
+/// removes the node at `segments` if `wanted` accepts it, together with the
+/// sections left empty by its removal
+fn detach(
+	map: &mut StructuredInput,
+	segments: &[String],
+	wanted: &impl Fn(&MirValue,) -> bool,
+) -> Option<MirValue,> {
+	let (first, rest,) = segments.split_first()?;
+	if rest.is_empty() {
+		return if wanted(map.get(first,)?,) {
+			map.remove(first,)
+		} else {
+			None
+		};
+	}
+
+	let TreeValue::Map(children,) = map.get_mut(first,)? else {
+		return None;
+	};
+	let node = detach(children, rest, wanted,);
+	if children.is_empty() {
+		map.remove(first,);
+	}
+	node
+}
+
+/// inserts `node` at `segments`, merging sections. `old_key` and `new_key`
+/// name the node before and after the move, for conflicts
+fn attach(
+	map: &mut StructuredInput,
+	segments: &[String],
+	node: MirValue,
+	old_key: &str,
+	new_key: &str,
+) -> PRslt<(),> {
+	let Some((first, rest,),) = segments.split_first() else {
+		return Ok((),);
+	};
+	if rest.is_empty() {
+		return match map.entry(first.clone(),) {
+			Entry::Vacant(entry,) => {
+				entry.insert(node,);
+				Ok((),)
+			},
+			Entry::Occupied(mut entry,) => {
+				merge(entry.get_mut(), node, old_key, new_key,)
+			},
+		};
+	}
+
+	match map
+		.entry(first.clone(),)
+		.or_insert_with(|| TreeValue::Map(BTreeMap::new(),),)
+	{
+		TreeValue::Map(children,) => {
+			attach(children, rest, node, old_key, new_key,)
+		},
+		existing => Err(conflict(old_key, &node, new_key, existing,),),
+	}
+}
+
+fn merge(
+	existing: &mut MirValue,
+	node: MirValue,
+	old_key: &str,
+	new_key: &str,
+) -> PRslt<(),> {
+	match (existing, node,) {
+		(TreeValue::Map(existing,), TreeValue::Map(incoming,),) => {
+			for (key, child,) in incoming {
+				match existing.entry(key.clone(),) {
+					Entry::Vacant(entry,) => {
+						entry.insert(child,);
+					},
+					Entry::Occupied(mut entry,) => merge(
+						entry.get_mut(),
+						child,
+						&format!("{old_key}.{key}"),
+						&format!("{new_key}.{key}"),
+					)?,
+				}
+			}
+			Ok((),)
+		},
+		(existing, node,) => Err(conflict(old_key, &node, new_key, existing,),),
+	}
+}
+
+fn conflict(
+	old_key: &str,
+	node: &MirValue,
+	new_key: &str,
+	existing: &MirValue,
+) -> ParseError {
+	ParseError::MigrationConflict {
+		old_key:  old_key.to_string(),
+		old_line: first_line(node,),
+		new_key:  new_key.to_string(),
+		new_line: first_line(existing,),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf::SingleValue;
+	use crate::parser::core::str_to_mir;
+
+	fn migrate(
+		input: &str,
+		migrations: &Migrations,
+	) -> PRslt<(StructuredInput, Vec<Warning,>,),> {
+		let mut mir = str_to_mir::<SingleValue,>(input,)?;
+		let warnings = migrations.apply(&mut mir,)?;
+		Ok((mir, warnings,),)
+	}
+
+	#[test]
+	fn rules_apply_in_order_and_report_each_key() -> PRslt<(),> {
+		let migrations = Migrations::new()
+			.rename("net.port", "server.port",)
+			.move_subtree("logging", "log",)
+			.drop("obsolete.flag",)
+			.rename("server.port", "server.listen",);
+		let (mir, warnings,) = migrate(
+			"net.port = 80\nlogging.file = a.log\nlogging.level = \
+			 info\nobsolete.flag = true\n",
+			&migrations,
+		)?;
+
+		assert_eq!(mir.keys().collect::<Vec<_,>>(), vec!["log", "server"]);
+		assert_eq!(
+			mir["server"],
+			TreeValue::Map(BTreeMap::from([(
+				"listen".to_string(),
+				TreeValue::Scalar(("80".to_string(), 1, 12,),),
+			),],),)
+		);
+		// the lines of the original file are kept
+		assert_eq!(mir["log"].get_lines_of_key(), vec![2, 3]);
+		assert_eq!(
+			warnings,
+			vec![
+				Warning::MigratedKey {
+					from: "net.port".to_string(),
+					to:   Some("server.port".to_string()),
+					line: 1,
+				},
+				Warning::MigratedKey {
+					from: "logging".to_string(),
+					to:   Some("log".to_string()),
+					line: 2,
+				},
+				Warning::MigratedKey {
+					from: "obsolete.flag".to_string(),
+					to:   None,
+					line: 4,
+				},
+				Warning::MigratedKey {
+					from: "server.port".to_string(),
+					to:   Some("server.listen".to_string()),
+					line: 1,
+				},
+			]
+		);
+		Ok((),)
+	}
+
+	#[test]
+	fn setting_old_and_new_key_is_a_conflict() {
+		let err = migrate(
+			"server.port = 81\nnet.port = 80\n",
+			&Migrations::new().rename("net.port", "server.port",),
+		)
+		.unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::MigrationConflict { old_line: 2, new_line: 1, .. }
+		));
+
+		let err = migrate(
+			"log.file = b.log\nlogging.file = a.log\nlogging.level = info\n",
+			&Migrations::new().move_subtree("logging", "log",),
+		)
+		.unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"[E020] 'logging.file' on line 2 and its replacement 'log.file' \
+			 on line 1 are both set"
+		);
+	}
+
+	#[test]
+	fn absent_keys_are_skipped() -> PRslt<(),> {
+		let (mir, warnings,) = migrate(
+			"net = 80\n",
+			&Migrations::new()
+				.rename("net.port", "server.port",)
+				.move_subtree("net", "server",),
+		)?;
+		assert!(warnings.is_empty());
+		assert_eq!(mir, str_to_mir::<SingleValue,>("net = 80\n",)?);
+		Ok((),)
+	}
+
+	#[test]
+	fn text_format_lists_one_rule_per_line() -> PRslt<(),> {
+		let migrations = Migrations::parse_str(
+			"# 2.0\nrename net.port -> server.port\n\nmove logging -> log ; \
+			 since 2.1\ndrop obsolete.flag\n",
+		)?;
+		assert_eq!(
+			migrations,
+			Migrations::new()
+				.rename("net.port", "server.port",)
+				.move_subtree("logging", "log",)
+				.drop("obsolete.flag",)
+		);
+
+		for rule in
+			["rename a", "copy a -> b", "drop a b", "move a -> ", "drop",]
+		{
+			assert!(
+				matches!(
+					Migrations::parse_str(rule,),
+					Err(ParseError::InvalidMigration { line: 1, .. })
+				),
+				"{rule}"
+			);
+		}
+		Ok((),)
+	}
+}
@@ -0,0 +1,175 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::ConfMap;
+use crate::parser::core::TreeValue;
+use crate::parser::merge::apply_schema;
+use crate::parser::schema::SchemaMap;
+use std::collections::BTreeMap;
+
+impl SchemaMap {
+	/// the same schema with every key under the dotted `prefix`. an empty
+	/// prefix leaves it as it is
+	pub fn with_prefix(self, prefix: &str,) -> SchemaMap {
+		let mut inner = self.into_inner();
+		for segment in prefix.rsplit('.',).filter(|s| !s.is_empty(),) {
+			inner =
+				BTreeMap::from([
+					(segment.to_string(), TreeValue::Map(inner,),),
+				],);
+		}
+		SchemaMap::from_inner(inner,)
+	}
+}
+
+/// schemas of several plugins, each mounted under its own prefix, combined
+/// into the schema of the file they share
+#[derive(Debug, Default,)]
+pub struct SchemaMounts {
+	schema: SchemaMap,
+	/// plugin name and prefix of every mount, in mount order
+	mounts: Vec<(String, String,),>,
+}
+
+impl SchemaMounts {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// adds `schema` of `plugin` under `prefix`. fails with
+	/// [`ParseError::MountConflict`] when `prefix` equals, contains or is
+	/// contained in the prefix of a plugin mounted before
+	pub fn mount(
+		&mut self,
+		plugin: &str,
+		prefix: &str,
+		schema: SchemaMap,
+	) -> PRslt<(),> {
+		if let Some((other, other_prefix,),) =
+			self.mounts.iter().find(|(_, mounted,)| {
+				nests(mounted, prefix,) || nests(prefix, mounted,)
+			},)
+		{
+			return Err(ParseError::MountConflict {
+				plugin:       plugin.to_string(),
+				prefix:       prefix.to_string(),
+				other:        other.clone(),
+				other_prefix: other_prefix.clone(),
+			},);
+		}
+
+		// prefixes of mounts never nest, so two schemas only ever share
+		// sections
+		apply_schema(
+			&mut self.schema,
+			schema.with_prefix(prefix,).into_inner(),
+		);
+		self.mounts.push((plugin.to_string(), prefix.to_string(),),);
+		Ok((),)
+	}
+
+	/// combined schema of every mounted plugin
+	pub fn into_schema(self,) -> SchemaMap {
+		self.schema
+	}
+}
+
+/// whether the dotted `inner` is `outer` or lies below it. the empty prefix
+/// contains every other one
+fn nests(outer: &str, inner: &str,) -> bool {
+	outer.is_empty()
+		|| inner == outer
+		|| inner
+			.strip_prefix(outer,)
+			.is_some_and(|rest| rest.starts_with('.',),)
+}
+
+impl ConfMap {
+	/// the section at the dotted `prefix` as a map of its own, with keys
+	/// relative to it. labels, sources and repeatability go with their
+	/// leaves. `None` when there is no such section
+	pub fn strip_prefix(&self, prefix: &str,) -> Option<ConfMap,> {
+		let (entries, dotted,) = if prefix.is_empty() {
+			(&**self, String::new(),)
+		} else {
+			let TreeValue::Map(entries,) = self.get(prefix,)? else {
+				return None;
+			};
+			(entries, format!("{prefix}."),)
+		};
+		let relative =
+			|key: &str| key.strip_prefix(&dotted,).map(str::to_string,);
+
+		let mut map = ConfMap::from(entries,);
+		*map.labels_mut() = self
+			.labels()
+			.iter()
+			.filter_map(|(key, labels,)| {
+				Some((relative(key,)?, labels.clone(),),)
+			},)
+			.collect();
+		*map.sources_mut() = self.sources().strip_prefix(&dotted,);
+		*map.repeatable_keys_mut() = self
+			.repeatable_keys()
+			.iter()
+			.filter_map(|key| relative(key,),)
+			.collect();
+		Some(map,)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::schema;
+
+	#[test]
+	fn with_prefix_nests_every_key() {
+		let schema =
+			schema::parse_str("port -> Integer\nlog.level -> String\n",)
+				.unwrap()
+				.with_prefix("plugins.web",);
+
+		assert!(matches!(
+			schema.get("plugins.web.port"),
+			Some(TreeValue::Scalar(_))
+		));
+		assert!(schema.get("plugins.web.log.level").is_some());
+		assert!(schema.get("port").is_none());
+		assert!(
+			schema::parse_str("port -> Integer\n",)
+				.unwrap()
+				.with_prefix("",)
+				.get("port")
+				.is_some()
+		);
+	}
+
+	#[test]
+	fn nested_and_equal_prefixes_conflict() {
+		assert!(nests("a", "a.b"));
+		assert!(nests("a.b", "a.b"));
+		assert!(nests("", "a"));
+		assert!(!nests("a", "ab"));
+		assert!(!nests("a.b", "a"));
+	}
+
+	#[test]
+	fn strip_prefix_keeps_repeatability_relative() {
+		let schema = schema::parse_str(
+			"web.allow -> [String] (repeatable)\nweb.port -> Integer\n",
+		)
+		.unwrap();
+		let conf = conf::parse_str(
+			"web.allow = a\nweb.allow = b\nweb.port = 80\n",
+			&schema,
+		)
+		.unwrap();
+
+		let web = conf.strip_prefix("web",).unwrap();
+		assert!(web.is_repeatable("allow"));
+		assert_eq!(web.get_int("port"), Some(80));
+		assert!(conf.strip_prefix("web.port").is_none());
+		assert!(conf.strip_prefix("db").is_none());
+	}
+}
@@ -0,0 +1,86 @@
+use crate::error::PRslt;
+use crate::parser::conf::Assignments;
+use crate::parser::conf::BuildConf;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::build_conf;
+pub use crate::parser::core::MirLeaf;
+use crate::parser::core::ParseOptions;
+use crate::parser::core::StructuredInput;
+pub use crate::parser::core::TreeValue;
+use crate::parser::schema::SchemaMap;
+use std::path::Path;
+
+/// a conf read but not yet checked against a schema, so it can be inspected
+/// first or typed against several schemas. leaves are untyped [`MirLeaf`]s,
+/// the raw text of the value with the line and the column it starts at
+#[derive(Debug, Clone,)]
+pub struct RawConf {
+	mir:         StructuredInput,
+	/// every assignment of keys assigned more than once, for lists declared
+	/// repeatable
+	assignments: Assignments,
+}
+
+/// reads `input` like [`conf::parse_str`](crate::parser::conf::parse_str)
+/// without typing it
+pub fn parse_raw_str(input: &str,) -> PRslt<RawConf,> {
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::str_to_mir_observed::<SingleValue, _,>(
+		input,
+		&mut assignments,
+	)?;
+	Ok(RawConf { mir, assignments, },)
+}
+
+/// reads the file at `path`, following its include directives, without
+/// typing it. unlike [`conf::parse_file`](crate::parser::conf::parse_file)
+/// no sources are recorded
+pub fn parse_raw_file<P: AsRef<Path,>,>(path: P,) -> PRslt<RawConf,> {
+	let mut assignments = Assignments::default();
+	let mir = crate::parser::core::file_to_mir_observed::<_, SingleValue, _,>(
+		path,
+		&mut assignments,
+		&ParseOptions::default(),
+	)?;
+	Ok(RawConf { mir, assignments, },)
+}
+
+impl RawConf {
+	/// leaf or section at the dotted `key`, resolved like
+	/// [`ConfView::get`](crate::parser::view::ConfView::get)
+	pub fn get(&self, key: &str,) -> Option<&TreeValue<MirLeaf,>,> {
+		TreeValue::get_dotted(&self.mir, key,)
+	}
+
+	/// raw text of the leaf at the dotted `key`
+	pub fn value_of(&self, key: &str,) -> Option<&str,> {
+		match self.get(key,)? {
+			TreeValue::Scalar((value, _, _,),) => Some(value,),
+			TreeValue::Map(_,) => None,
+		}
+	}
+
+	/// lines assigning the leaf at the dotted `key` or the leaves below it,
+	/// ascending. empty when nothing is assigned there
+	pub fn lines_of(&self, key: &str,) -> Vec<usize,> {
+		let mut lines = self
+			.get(key,)
+			.map(TreeValue::get_lines_of_key,)
+			.unwrap_or_default();
+		lines.sort_unstable();
+		lines
+	}
+
+	pub fn into_inner(self,) -> StructuredInput {
+		self.mir
+	}
+}
+
+/// typed as by [`conf::parse_str`](crate::parser::conf::parse_str), lists
+/// declared repeatable included
+impl BuildConf for RawConf {
+	fn into_conf(self, schema: &SchemaMap,) -> PRslt<ConfMap,> {
+		build_conf(self.mir, schema, &self.assignments,)
+	}
+}
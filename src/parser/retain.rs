@@ -0,0 +1,182 @@
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::core::TreeValue;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaValue;
+use crate::parser::view::ConfMapRef;
+use crate::parser::view::ConfView;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+type Entries = BTreeMap<String, ConfValue,>;
+
+impl ConfMap {
+	/// removes every leaf `schema` does not declare, and the sections left
+	/// empty. returns the dotted keys of the removed leaves, in key order
+	pub fn retain_schema(&mut self, schema: &SchemaMap,) -> Vec<String,> {
+		let (known, unknown,) = split(std::mem::take(&mut **self,), schema,);
+		**self = known;
+
+		let removed: Vec<String,> = ConfMapRef::from(&unknown,)
+			.iter_leaves()
+			.map(|(key, _,)| key,)
+			.collect();
+		let removed_set: BTreeSet<&str,> =
+			removed.iter().map(String::as_str,).collect();
+		self.labels_mut()
+			.retain(|key, _| !removed_set.contains(key.as_str(),),);
+		self.sources_mut().retain(|key| !removed_set.contains(key,),);
+		self.repeatable_keys_mut()
+			.retain(|key| !removed_set.contains(key.as_str(),),);
+		removed
+	}
+
+	/// the leaves `schema` declares and the ones it does not, as two maps.
+	/// labels, sources and repeatability go with their leaves
+	pub fn partition_by_schema(
+		&self,
+		schema: &SchemaMap,
+	) -> (ConfMap, ConfMap,) {
+		let (known, unknown,) = split((**self).clone(), schema,);
+		(self.with_entries(known,), self.with_entries(unknown,),)
+	}
+
+	/// copy of `self` holding `entries`, with the labels and sources of their
+	/// leaves
+	fn with_entries(&self, entries: Entries,) -> ConfMap {
+		let keys: BTreeSet<String,> = ConfMapRef::from(&entries,)
+			.iter_leaves()
+			.map(|(key, _,)| key,)
+			.collect();
+
+		let mut map = ConfMap::new();
+		*map = entries;
+		*map.labels_mut() = self
+			.labels()
+			.iter()
+			.filter(|(key, _,)| keys.contains(*key,),)
+			.map(|(key, labels,)| (key.clone(), labels.clone(),),)
+			.collect();
+		*map.sources_mut() = self.sources().clone();
+		map.sources_mut().retain(|key| keys.contains(key,),);
+		*map.repeatable_keys_mut() =
+			self.repeatable_keys().intersection(&keys,).cloned().collect();
+		map
+	}
+}
+
+/// leaves whose key is declared as a leaf by `schema`, and the rest. neither
+/// side keeps an empty section
+fn split(
+	entries: Entries,
+	schema: &BTreeMap<String, SchemaValue,>,
+) -> (Entries, Entries,) {
+	let mut known = BTreeMap::new();
+	let mut unknown = BTreeMap::new();
+	for (key, value,) in entries {
+		match (value, schema.get(&key,),) {
+			(value @ TreeValue::Scalar(_,), Some(TreeValue::Scalar(_,),),) => {
+				known.insert(key, value,);
+			},
+			(TreeValue::Map(children,), Some(TreeValue::Map(schema,),),) => {
+				let (known_children, unknown_children,) =
+					split(children, schema,);
+				if !known_children.is_empty() {
+					known.insert(key.clone(), TreeValue::Map(known_children,),);
+				}
+				if !unknown_children.is_empty() {
+					unknown.insert(key, TreeValue::Map(unknown_children,),);
+				}
+			},
+			(TreeValue::Map(children,), _,) if children.is_empty() => {},
+			(value, _,) => {
+				unknown.insert(key, value,);
+			},
+		}
+	}
+	(known, unknown,)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sample() -> ConfMap {
+		crate::conf_map! {
+			"server.port" => 80,
+			"server.host" => "localhost",
+			"server.legacy.mode" => "compat",
+			"server.legacy.retries" => 3,
+			"limits" => [1, 2],
+			"obsolete" => true,
+		}
+	}
+
+	fn schema() -> SchemaMap {
+		crate::schema_map! {
+			"server.port" => Integer,
+			"server.host" => String,
+			"limits" => [Integer, Integer],
+		}
+	}
+
+	#[test]
+	fn unknown_leaves_are_removed_with_their_empty_sections() {
+		let mut conf = sample();
+		let removed = conf.retain_schema(&schema(),);
+
+		assert_eq!(
+			removed,
+			vec!["obsolete", "server.legacy.mode", "server.legacy.retries"]
+		);
+		assert_eq!(
+			conf.iter_leaves().map(|(key, _,)| key,).collect::<Vec<_,>>(),
+			vec!["limits", "server.host", "server.port"]
+		);
+		assert!(conf.get("server.legacy").is_none());
+		assert!(conf.retain_schema(&schema()).is_empty());
+	}
+
+	#[test]
+	fn leaves_and_sections_must_match_the_schema_shape() {
+		let mut conf = crate::conf_map! {
+			"server.port.number" => 80,
+			"limits" => 1,
+		};
+		let schema = crate::schema_map! {
+			"server.port" => Integer,
+			"limits.lo" => Integer,
+		};
+		assert_eq!(
+			conf.retain_schema(&schema,),
+			vec!["limits", "server.port.number"]
+		);
+		assert!(conf.is_empty());
+	}
+
+	#[test]
+	fn partition_keeps_the_original_and_splits_sources() {
+		let mut conf = sample();
+		conf.sources_mut().record(
+			"server.port",
+			crate::parser::source::Origin::Override,
+			None,
+		);
+		conf.sources_mut().record(
+			"server.legacy.mode",
+			crate::parser::source::Origin::Override,
+			None,
+		);
+
+		let (known, unknown,) = conf.partition_by_schema(&schema(),);
+		assert_eq!(conf.iter_leaves().count(), 6);
+		assert_eq!(
+			unknown.iter_leaves().map(|(key, _,)| key,).collect::<Vec<_,>>(),
+			vec!["obsolete", "server.legacy.mode", "server.legacy.retries"]
+		);
+		assert_eq!(known.iter_leaves().count(), 3);
+		assert!(known.source_of("server.port").is_some());
+		assert!(known.source_of("server.legacy.mode").is_none());
+		assert!(unknown.source_of("server.legacy.mode").is_some());
+	}
+}
@@ -1,18 +1,38 @@
 use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::error::Warning;
+use crate::parser::conf::SingleValue;
 use crate::parser::conf::SingleValueDiscriminants;
 use crate::parser::conf::Value;
 use crate::parser::conf::ValueDiscriminants;
+use crate::parser::core::MirObserver;
+use crate::parser::core::ParseOptions;
 use crate::parser::core::StructuredInput;
 use crate::parser::core::TreeValue;
 use crate::parser::core::Valuable;
+use crate::parser::diff::render_value;
+use crate::trace;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::io::BufRead;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
+use strum::IntoEnumIterator;
 
-#[derive(Debug, Default,)]
+mod cache;
+mod compat;
+
+pub use compat::CompatReport;
+pub use compat::Compatibility;
+pub use compat::SchemaChange;
+pub use compat::compat_check;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq,)]
 pub struct SchemaMap(BTreeMap<String, SchemaValue,>,);
 
 impl SchemaMap {
@@ -32,23 +52,103 @@ impl SchemaMap {
 		self.0.is_empty()
 	}
 
+	/// declaration or section at the dotted `key`, resolved like
+	/// [`ConfView::get`](crate::parser::view::ConfView::get)
 	pub fn get(&self, key: &str,) -> Option<&SchemaValue,> {
-		if let Some(value,) = self.0.get(key,) {
-			return Some(value,);
-		}
+		TreeValue::get_dotted(&self.0, key,)
+	}
 
-		let mut segments = key.split('.',);
-		let first = segments.next()?;
-		let mut current = self.0.get(first,)?;
+	/// type declared for the leaf at the dotted `key`. `None` for sections
+	/// and keys not declared
+	pub fn type_of(
+		&self,
+		key: &str,
+	) -> Option<&Value<SingleValueDiscriminants,>,> {
+		match self.get(key,)? {
+			SchemaValue::Scalar(leaf,) => Some(&leaf.ty,),
+			SchemaValue::Map(_,) => None,
+		}
+	}
 
-		for segment in segments {
-			current = match current {
-				SchemaValue::Map(children,) => children.get(segment,)?,
-				_ => return None,
-			};
+	/// dotted keys of every leaf, in key order
+	pub fn keys_flat(&self,) -> Vec<String,> {
+		let mut keys = Vec::new();
+		for (key, value,) in &self.0 {
+			for_each_leaf(key, value, &mut |path, _| {
+				keys.push(path.to_string(),)
+			},);
 		}
+		keys
+	}
+
+	/// whether the dotted `key` is declared, as a leaf or as a section
+	pub fn contains_path(&self, key: &str,) -> bool {
+		self.get(key,).is_some()
+	}
+}
 
-		Some(current,)
+/// calls `visit` with the dotted key of every leaf below `value`, at `path`
+pub(crate) fn for_each_leaf(
+	path: &str,
+	value: &SchemaValue,
+	visit: &mut impl FnMut(&str, &SchemaLeaf,),
+) {
+	match value {
+		TreeValue::Scalar(leaf,) => visit(path, leaf,),
+		TreeValue::Map(children,) => {
+			for (key, child,) in children {
+				for_each_leaf(&format!("{path}.{key}"), child, visit,);
+			}
+		},
+	}
+}
+
+impl SchemaMap {
+	/// moves the schema behind an [`Arc`], for sharing between threads
+	pub fn into_shared(self,) -> SchemaRef {
+		SchemaRef(Arc::new(self,),)
+	}
+
+	/// writes [`to_conf_template`] of the schema to `path`, replacing the
+	/// file
+	pub fn write_template<P: AsRef<Path,>,>(&self, path: P,) -> PRslt<(),> {
+		std::fs::write(path, to_conf_template(self,),)?;
+		Ok((),)
+	}
+}
+
+/// shared handle to a [`SchemaMap`]. cloning it only bumps a refcount
+#[derive(Debug, Clone,)]
+pub struct SchemaRef(Arc<SchemaMap,>,);
+
+impl Deref for SchemaRef {
+	type Target = SchemaMap;
+
+	fn deref(&self,) -> &Self::Target {
+		&self.0
+	}
+}
+
+/// anything a conf can be parsed against
+pub trait AsSchema {
+	fn as_schema(&self,) -> &SchemaMap;
+}
+
+impl AsSchema for SchemaMap {
+	fn as_schema(&self,) -> &SchemaMap {
+		self
+	}
+}
+
+impl AsSchema for SchemaRef {
+	fn as_schema(&self,) -> &SchemaMap {
+		self
+	}
+}
+
+impl<S: AsSchema + ?Sized,> AsSchema for &S {
+	fn as_schema(&self,) -> &SchemaMap {
+		(**self).as_schema()
 	}
 }
 
@@ -78,7 +178,142 @@ impl DerefMut for SchemaMap {
 	}
 }
 
-pub type SchemaValue = TreeValue<Value<SingleValueDiscriminants,>,>;
+pub type SchemaValue = TreeValue<SchemaLeaf,>;
+
+/// declaration of a leaf key
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub struct SchemaLeaf {
+	pub ty:             Value<SingleValueDiscriminants,>,
+	/// element names of a labeled tuple like `(min: Integer, max: Integer)`
+	pub labels:         Option<Vec<String,>,>,
+	/// declared as `@Type`: the value may be a `@resolver(arg)` placeholder
+	pub resolvable:     bool,
+	/// declared with a trailing `?`: strict parsing lets the key be left out
+	pub optional:       bool,
+	/// declared as `[Type]`: `ty` is a collection of that single type, which
+	/// holds any number of elements
+	pub list:           bool,
+	/// declared as `[Type] (repeatable)`: every assignment of the key adds
+	/// its elements to the list instead of replacing it. `key += value` is
+	/// only allowed for such keys
+	pub repeatable:     bool,
+	/// declaration as written, when it was not understood by
+	/// [`parse_str_forward_compat`]. `ty` is then `String`, so any value is
+	/// accepted
+	pub unrecognized:   Option<String,>,
+	/// literals of a declaration like `Enum(debug, info)`. `ty` is then
+	/// `String`, and a value has to equal one of them
+	pub allowed:        Option<Vec<String,>,>,
+	/// bounds of a declaration like `Integer[1..65535]` or `[Float[0..1]]`,
+	/// which every element of a list has to lie within
+	pub range:          Option<ValueRange,>,
+	/// bounds of each element of a tuple like `Integer[1..10], Integer`, when
+	/// any element has them
+	pub element_ranges: Option<Vec<Option<ValueRange,>,>,>,
+}
+
+/// inclusive bounds of the declared type, either of which may be left out
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub struct ValueRange {
+	pub min: Option<SingleValue,>,
+	pub max: Option<SingleValue,>,
+}
+
+impl ValueRange {
+	/// whether `value` lies within the bounds. a value of another type than
+	/// the bounds never does
+	pub fn contains(&self, value: &SingleValue,) -> bool {
+		let within = |bound: &Option<SingleValue,>, beyond: Ordering| {
+			bound.as_ref().is_none_or(|bound| {
+				compare_numbers(value, bound,)
+					.is_some_and(|ordering| ordering != beyond,)
+			},)
+		};
+		within(&self.min, Ordering::Less,)
+			&& within(&self.max, Ordering::Greater,)
+	}
+}
+
+/// `[min..max]`, as declared
+impl Display for ValueRange {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		let bound = |bound: &Option<SingleValue,>| {
+			bound
+				.clone()
+				.map(|bound| render_value(&Value::Single(bound,),),)
+				.unwrap_or_default()
+		};
+		write!(f, "[{}..{}]", bound(&self.min), bound(&self.max))
+	}
+}
+
+/// `None` unless both are numbers of the same type
+pub(crate) fn compare_numbers(
+	lhs: &SingleValue,
+	rhs: &SingleValue,
+) -> Option<Ordering,> {
+	match (lhs, rhs,) {
+		(SingleValue::Integer(lhs,), SingleValue::Integer(rhs,),) => {
+			Some(lhs.cmp(rhs,),)
+		},
+		(SingleValue::UInteger(lhs,), SingleValue::UInteger(rhs,),) => {
+			Some(lhs.cmp(rhs,),)
+		},
+		(SingleValue::Long(lhs,), SingleValue::Long(rhs,),) => {
+			Some(lhs.cmp(rhs,),)
+		},
+		(SingleValue::Float(lhs,), SingleValue::Float(rhs,),) => {
+			lhs.partial_cmp(rhs,)
+		},
+		_ => None,
+	}
+}
+
+impl SchemaLeaf {
+	/// name of the `idx`th element, if the leaf is a labeled tuple
+	pub fn label(&self, idx: usize,) -> Option<&str,> {
+		self.labels.as_ref()?.get(idx,).map(String::as_str,)
+	}
+
+	/// bounds of the `idx`th element of a tuple, otherwise of the value or
+	/// of every element of a list
+	pub fn range_of(&self, idx: usize,) -> Option<&ValueRange,> {
+		match &self.element_ranges {
+			Some(ranges,) => ranges.get(idx,)?.as_ref(),
+			None => self.range.as_ref(),
+		}
+	}
+
+	/// type of the `idx`th element of a collection. `None` for a single
+	/// value, or past the end of a tuple
+	pub fn element_kind(
+		&self,
+		idx: usize,
+	) -> Option<SingleValueDiscriminants,> {
+		match &self.ty {
+			Value::Single(_,) => None,
+			Value::Collection(kinds,) if self.list => kinds.first().copied(),
+			Value::Collection(kinds,) => kinds.get(idx,).copied(),
+		}
+	}
+}
+
+impl From<Value<SingleValueDiscriminants,>,> for SchemaLeaf {
+	fn from(ty: Value<SingleValueDiscriminants,>,) -> Self {
+		Self {
+			ty,
+			labels: None,
+			resolvable: false,
+			optional: false,
+			list: false,
+			repeatable: false,
+			unrecognized: None,
+			allowed: None,
+			range: None,
+			element_ranges: None,
+		}
+	}
+}
 
 impl Display for ValueDiscriminants {
 	/// required by `ParseError`
@@ -91,8 +326,15 @@ impl Display for ValueDiscriminants {
 }
 
 impl Valuable for SingleValueDiscriminants {
-	fn sep() -> &'static str {
-		"->"
+	fn seps() -> &'static [&'static str] {
+		&["->",]
+	}
+}
+
+impl SingleValueDiscriminants {
+	/// every type a schema can declare, in declaration order
+	pub(crate) fn declarable() -> impl Iterator<Item = Self,> {
+		Self::iter().filter(|kind| *kind != Self::Deferred,)
 	}
 }
 
@@ -103,74 +345,844 @@ impl Display for SingleValueDiscriminants {
 			Self::String => write!(f, "String"),
 			Self::Bool => write!(f, "Bool"),
 			Self::Integer => write!(f, "Integer"),
+			Self::UInteger => write!(f, "UInt"),
+			Self::Long => write!(f, "Long"),
+			Self::Float => write!(f, "Float"),
+			Self::Secret => write!(f, "Secret"),
+			Self::Duration => write!(f, "Duration"),
+			Self::IpAddr => write!(f, "Ip"),
+			Self::SocketAddr => write!(f, "SocketAddr"),
+			Self::Path => write!(f, "Path"),
+			Self::Deferred => write!(f, "Deferred"),
 		}
 	}
 }
 
 pub fn parse_file<P: AsRef<Path,>,>(path: P,) -> PRslt<SchemaMap,> {
-	let mir = crate::parser::core::file_to_mir::<_, SingleValueDiscriminants,>(
-		path,
-	)?;
+	parse_file_with_warnings(path,).map(|(schema, _,)| schema,)
+}
+
+/// same as [`parse_file`], reading lines as `options` ask
+pub fn parse_file_with<P: AsRef<Path,>,>(
+	path: P,
+	options: &ParseOptions,
+) -> PRslt<SchemaMap,> {
+	let mut declarations = Redeclarations::default();
+	let mir = crate::parser::core::file_to_mir_observed::<
+		_,
+		SingleValueDiscriminants,
+		_,
+	>(path, &mut declarations, options,)?;
+	declarations.into_warnings()?;
 	mir.into_schema()
 }
 
+/// schemas of every file in `paths`, combined by [`SchemaMap::merge`] in
+/// order. an error reading a file is wrapped in [`ParseError::InFile`]
+/// naming it, and a conflict between two files names both
+pub fn parse_files<P: AsRef<Path,>,>(paths: &[P],) -> PRslt<SchemaMap,> {
+	let mut merged = SchemaMap::new();
+	let mut parsed: Vec<(&Path, SchemaMap,),> = Vec::new();
+	for path in paths.iter().map(AsRef::as_ref,) {
+		let schema = parse_file(path,).map_err(|err| ParseError::InFile {
+			path:  path.to_path_buf(),
+			error: Box::new(err,),
+		},)?;
+		if let Err(mut err,) = merged.merge(schema.clone(),) {
+			if let ParseError::ConflictingTypes { key, origin, .. } = &mut err
+				&& let Some((first, _,),) = parsed
+					.iter()
+					.find(|(_, earlier,)| earlier.contains_path(key,),)
+			{
+				*origin = Some((first.to_path_buf(), path.to_path_buf(),),);
+			}
+			return Err(err,);
+		}
+		parsed.push((path, schema,),);
+	}
+	Ok(merged,)
+}
+
+/// same as [`parse_file`], reusing the schema cached in `cache_dir` while
+/// the source text is unchanged. a missing, stale or corrupt cache is
+/// rebuilt, failing to write it is ignored
+pub fn parse_file_cached<P: AsRef<Path,>, D: AsRef<Path,>,>(
+	path: P,
+	cache_dir: D,
+) -> PRslt<SchemaMap,> {
+	let source = std::fs::read(path.as_ref(),)?;
+	let source_hash = cache::fnv1a(&source,).to_le_bytes();
+	let cache_path = cache_path(path.as_ref(), cache_dir.as_ref(),);
+
+	if let Ok(cached,) = std::fs::read(&cache_path,)
+		&& let Some(bytes,) = cached.strip_prefix(&source_hash,)
+		&& let Ok(schema,) = SchemaMap::from_cache_bytes(bytes,)
+	{
+		return Ok(schema,);
+	}
+
+	let source = String::from_utf8(source,).map_err(|err| {
+		std::io::Error::new(std::io::ErrorKind::InvalidData, err,)
+	},)?;
+	// the cache is keyed by this file only, so it would miss changes to the
+	// files it includes
+	if crate::parser::core::has_include(&source,) {
+		return parse_file(path,);
+	}
+	let schema = parse_str(&source,)?;
+
+	let mut cached = source_hash.to_vec();
+	cached.extend(schema.to_cache_bytes(),);
+	let _ = std::fs::create_dir_all(cache_dir,)
+		.and_then(|_| std::fs::write(&cache_path, cached,),);
+	Ok(schema,)
+}
+
+/// one cache file per schema path, named after the file for readability
+fn cache_path(path: &Path, cache_dir: &Path,) -> PathBuf {
+	let name = path.file_name().unwrap_or_default().to_string_lossy();
+	let path_hash = cache::fnv1a(path.as_os_str().as_encoded_bytes(),);
+	cache_dir.join(format!("{name}.{path_hash:016x}.cache"),)
+}
+
 pub fn parse_str(input: &str,) -> PRslt<SchemaMap,> {
-	let mir =
-		crate::parser::core::str_to_mir::<SingleValueDiscriminants,>(input,)?;
+	parse_str_with_warnings(input,).map(|(schema, _,)| schema,)
+}
+
+/// schema counterpart of
+/// [`conf::parse_reader`](crate::parser::conf::parse_reader)
+pub fn parse_reader<R: BufRead,>(reader: R,) -> PRslt<SchemaMap,> {
+	parse_reader_with(reader, &ParseOptions::default(),)
+}
+
+/// same as [`parse_reader`], reading lines as `options` ask
+pub fn parse_reader_with<R: BufRead,>(
+	reader: R,
+	options: &ParseOptions,
+) -> PRslt<SchemaMap,> {
+	let mut declarations = Redeclarations::default();
+	let mir = crate::parser::core::read_to_mir_observed::<
+		_,
+		SingleValueDiscriminants,
+		_,
+	>(reader, &mut declarations, options,)?;
+	declarations.into_warnings()?;
 	mir.into_schema()
 }
 
+/// same as [`parse_str`], reading lines as `options` ask. see
+/// [`conf::parse_str_with`](crate::parser::conf::parse_str_with)
+pub fn parse_str_with(
+	input: &str,
+	options: &ParseOptions,
+) -> PRslt<SchemaMap,> {
+	let mut declarations = Redeclarations::default();
+	let mir = crate::parser::core::str_to_mir_with::<
+		SingleValueDiscriminants,
+		_,
+	>(input, &mut declarations, options,)?;
+	declarations.into_warnings()?;
+	mir.into_schema()
+}
+
+/// schema counterpart of
+/// [`conf::parse_lines`](crate::parser::conf::parse_lines)
+pub fn parse_lines<'a,>(
+	lines: impl IntoIterator<Item = (usize, &'a str,),>,
+) -> PRslt<SchemaMap,> {
+	parse_lines_with(lines, &ParseOptions::default(),)
+}
+
+/// same as [`parse_lines`], reading lines as `options` ask
+pub fn parse_lines_with<'a,>(
+	lines: impl IntoIterator<Item = (usize, &'a str,),>,
+	options: &ParseOptions,
+) -> PRslt<SchemaMap,> {
+	let mut declarations = Redeclarations::default();
+	let mir = crate::parser::core::lines_to_mir_observed::<
+		SingleValueDiscriminants,
+		_,
+	>(lines, &mut declarations, options,)?;
+	declarations.into_warnings()?;
+	mir.into_schema()
+}
+
+pub fn parse_file_with_warnings<P: AsRef<Path,>,>(
+	path: P,
+) -> PRslt<(SchemaMap, Vec<Warning,>,),> {
+	let mut declarations = Redeclarations::default();
+	let mir = crate::parser::core::file_to_mir_observed::<
+		_,
+		SingleValueDiscriminants,
+		_,
+	>(path, &mut declarations, &ParseOptions::default(),)?;
+	let warnings = declarations.into_warnings()?;
+	Ok((mir.into_schema()?, warnings,),)
+}
+
+pub fn parse_str_with_warnings(
+	input: &str,
+) -> PRslt<(SchemaMap, Vec<Warning,>,),> {
+	let mut declarations = Redeclarations::default();
+	let mir = crate::parser::core::str_to_mir_observed::<
+		SingleValueDiscriminants,
+		_,
+	>(input, &mut declarations,)?;
+	let warnings = declarations.into_warnings()?;
+	Ok((mir.into_schema()?, warnings,),)
+}
+
+/// same as [`parse_str_with_warnings`] for schemas written for a newer
+/// version. a declaration naming an unknown type or using unknown syntax
+/// does not fail, but becomes an [`SchemaLeaf::unrecognized`] leaf accepting
+/// any value and is reported as [`Warning::UnrecognizedDeclaration`]
+pub fn parse_str_forward_compat(
+	input: &str,
+) -> PRslt<(SchemaMap, Vec<Warning,>,),> {
+	let mut declarations = Redeclarations::default();
+	let mir = crate::parser::core::str_to_mir_observed::<
+		SingleValueDiscriminants,
+		_,
+	>(input, &mut declarations,)?;
+	let mut warnings = declarations.into_warnings()?;
+	let mut errors = Vec::new();
+	let schema =
+		build_schema_map(mir, None, Some(&mut warnings,), &mut errors,);
+	match errors.into_iter().next() {
+		Some(err,) => Err(err,),
+		None => Ok((SchemaMap::from_inner(schema,), warnings,),),
+	}
+}
+
+/// schema counterpart of
+/// [`conf::parse_str_all_errors`](crate::parser::conf::parse_str_all_errors).
+/// redeclarations with a different type are reported along with malformed
+/// lines and declarations
+pub fn parse_str_all_errors(
+	input: &str,
+) -> Result<SchemaMap, Vec<ParseError,>,> {
+	parse_str_all_errors_with(input, &ParseOptions::default(),)
+}
+
+/// same as [`parse_str_all_errors`], reading lines as `options` ask
+pub fn parse_str_all_errors_with(
+	input: &str,
+	options: &ParseOptions,
+) -> Result<SchemaMap, Vec<ParseError,>,> {
+	let mut declarations = Redeclarations::default();
+	let (mir, mut errors,) = crate::parser::core::str_to_mir_all_errors::<
+		SingleValueDiscriminants,
+		_,
+	>(input, &mut declarations, options,);
+	errors.extend(
+		declarations.into_reports().into_iter().filter_map(Result::err,),
+	);
+	let schema = build_schema_map(mir, None, None, &mut errors,);
+	if errors.is_empty() {
+		Ok(SchemaMap::from_inner(schema,),)
+	} else {
+		Err(crate::parser::core::by_line(errors,),)
+	}
+}
+
+/// keys declared more than once. unlike conf files, a schema has no use for
+/// overriding a declaration, so a redeclaration with a different type is an
+/// error and one with the same type a warning
+#[derive(Default,)]
+struct Redeclarations(Vec<(String, RawDeclaration, RawDeclaration,),>,);
+
+/// raw type and line of one declaration
+type RawDeclaration = (String, usize,);
+
+impl MirObserver for Redeclarations {
+	fn entry_overridden(
+		&mut self,
+		segments: &[String],
+		previous: (&str, usize, usize,),
+		current: (&str, usize, usize,),
+	) {
+		self.0.push((
+			segments.join(".",),
+			(previous.0.to_string(), previous.1,),
+			(current.0.to_string(), current.1,),
+		),);
+	}
+}
+
+impl Redeclarations {
+	fn into_warnings(self,) -> PRslt<Vec<Warning,>,> {
+		self.into_reports().into_iter().try_collect()
+	}
+
+	/// a warning or an error for each redeclaration
+	fn into_reports(self,) -> Vec<PRslt<Warning,>,> {
+		self.0
+			.into_iter()
+			.map(|(key, (previous, first_line,), (current, second_line,),)| {
+				let same_type = match (
+					parse_schema_value(&key, &previous, first_line,),
+					parse_schema_value(&key, &current, second_line,),
+				) {
+					(Ok(previous,), Ok(current,),) => previous == current,
+					_ => false,
+				};
+
+				if same_type {
+					trace::event!(
+						warn,
+						key = %key,
+						first_line,
+						second_line,
+						"redundant declaration"
+					);
+					Ok(Warning::RedundantDeclaration {
+						key,
+						first_line,
+						second_line,
+					},)
+				} else {
+					Err(ParseError::DuplicateKey {
+						key,
+						first_line,
+						second_line,
+					},)
+				}
+			},)
+			.collect()
+	}
+}
+
 pub trait BuildSchema {
 	fn into_schema(self,) -> PRslt<SchemaMap,>;
 }
 
 impl BuildSchema for StructuredInput {
 	fn into_schema(self,) -> PRslt<SchemaMap,> {
-		let mut schema_map = BTreeMap::new();
+		let mut errors = Vec::new();
+		let schema = build_schema_map(self, None, None, &mut errors,);
+		match errors.into_iter().next() {
+			Some(err,) => Err(err,),
+			None => Ok(SchemaMap::from_inner(schema,),),
+		}
+	}
+}
 
-		for (key, mir_value,) in self.into_iter() {
-			let schema = match mir_value {
-				TreeValue::Scalar((s, _,),) => parse_schema_value(&s,)?,
-				TreeValue::Map(btree_map,) => {
-					TreeValue::Map(btree_map.into_schema()?.into_inner(),)
-				},
-			};
+/// with `unrecognized`, declarations which are not understood are kept and
+/// reported there rather than failing. other declarations with an error are
+/// left out and the error pushed onto `errors`
+fn build_schema_map(
+	input: StructuredInput,
+	prefix: Option<&str,>,
+	mut unrecognized: Option<&mut Vec<Warning,>,>,
+	errors: &mut Vec<ParseError,>,
+) -> BTreeMap<String, SchemaValue,> {
+	let mut schema_map = BTreeMap::new();
+
+	for (key, mir_value,) in input.into_iter() {
+		let dotted_key = match prefix {
+			Some(base,) => format!("{base}.{key}"),
+			None => key.clone(),
+		};
+
+		let schema = match mir_value {
+			TreeValue::Scalar((s, line, _,),) => {
+				match (
+					parse_schema_value(&dotted_key, &s, line,),
+					&mut unrecognized,
+				) {
+					(
+						Err(
+							ParseError::UnknownType { .. }
+							| ParseError::InvalidDeclaration { .. },
+						),
+						Some(warnings,),
+					) => {
+						trace::event!(
+							warn,
+							key = %dotted_key,
+							declaration = %s,
+							line,
+							"unrecognized declaration"
+						);
+						warnings.push(Warning::UnrecognizedDeclaration {
+							key: dotted_key,
+							declaration: s.clone(),
+							line,
+						},);
+						TreeValue::Scalar(SchemaLeaf {
+							optional: s.ends_with('?',),
+							unrecognized: Some(s,),
+							..SchemaLeaf::from(Value::Single(
+								SingleValueDiscriminants::String,
+							),)
+						},)
+					},
+					(Ok(parsed,), _,) => parsed,
+					(Err(err,), _,) => {
+						errors.push(err,);
+						continue;
+					},
+				}
+			},
+			TreeValue::Map(btree_map,) => TreeValue::Map(build_schema_map(
+				btree_map,
+				Some(&dotted_key,),
+				unrecognized.as_deref_mut(),
+				errors,
+			),),
+		};
 
-			schema_map.insert(key, schema,);
+		schema_map.insert(key, schema,);
+	}
+
+	schema_map
+}
+
+/// marks a list whose key may be assigned repeatedly
+const REPEATABLE: &str = "(repeatable)";
+
+fn parse_schema_value(
+	key: &str,
+	value: &str,
+	line: usize,
+) -> PRslt<SchemaValue,> {
+	let value = value.trim();
+	if SingleValueDiscriminants::seps().iter().any(|sep| value.contains(sep,),)
+	{
+		return Err(ParseError::InvalidDeclaration {
+			key: key.to_string(),
+			declaration: value.to_string(),
+			line,
+		},);
+	}
+	let (value, optional,) = match value.strip_suffix('?',) {
+		Some(value,) => (value.trim_end(), true,),
+		None => (value, false,),
+	};
+	let (value, resolvable,) = match value.strip_prefix('@',) {
+		Some(value,) => (value.trim_start(), true,),
+		None => (value, false,),
+	};
+
+	let (value, repeatable,) = match value.strip_suffix(REPEATABLE,) {
+		Some(value,) => (value.trim_end(), true,),
+		None => (value, false,),
+	};
+
+	let mut leaf = if let Some(literals,) =
+		value.strip_prefix("Enum(",).and_then(|value| value.strip_suffix(')',),)
+	{
+		parse_enum(key, value, literals, line,)?
+	} else if let Some(elements,) =
+		value.strip_prefix('(',).and_then(|value| value.strip_suffix(')',),)
+	{
+		parse_labeled_tuple(key, value, elements, line,)?
+	} else if let Some(kind,) =
+		value.strip_prefix('[',).and_then(|value| value.strip_suffix(']',),)
+	{
+		if kind.contains(',',) {
+			return Err(ParseError::InvalidDeclaration {
+				key: key.to_string(),
+				declaration: value.to_string(),
+				line,
+			},);
+		}
+		let (kind, range,) = type_with_range(key, value, kind.trim(), line,)?;
+		SchemaLeaf {
+			list: true,
+			range,
+			..SchemaLeaf::from(Value::Collection(vec![kind],),)
+		}
+	} else if value.contains(',',) {
+		let (kinds, ranges,): (Vec<_,>, Vec<_,>,) = value
+			.split(',',)
+			.map(|s| type_with_range(key, s.trim(), s.trim(), line,),)
+			.collect::<PRslt<Vec<_,>,>>()?
+			.into_iter()
+			.unzip();
+		SchemaLeaf {
+			element_ranges: element_ranges(ranges,),
+			..SchemaLeaf::from(Value::Collection(kinds,),)
 		}
+	} else {
+		let (kind, range,) = type_with_range(key, value, value, line,)?;
+		SchemaLeaf { range, ..SchemaLeaf::from(Value::Single(kind,),) }
+	};
+	if repeatable && !leaf.list {
+		return Err(ParseError::InvalidDeclaration {
+			key: key.to_string(),
+			declaration: format!("{value} {REPEATABLE}"),
+			line,
+		},);
+	}
+	leaf.resolvable = resolvable;
+	leaf.optional = optional;
+	leaf.repeatable = repeatable;
+	Ok(TreeValue::Scalar(leaf,),)
+}
+
+fn type_named(
+	key: &str,
+	name: &str,
+	line: usize,
+) -> PRslt<SingleValueDiscriminants,> {
+	SingleValueDiscriminants::from_str(name,).map_err(|_| {
+		ParseError::UnknownType {
+			key: key.to_string(),
+			name: name.to_string(),
+			line,
+		}
+	},)
+}
 
-		Ok(SchemaMap::from_inner(schema_map,),)
+/// `name` of a type, optionally followed by an inclusive range as in
+/// `Integer[1..65535]` or `Float[0..]`. only number types take a range, with
+/// at least one bound, and its minimum may not exceed its maximum
+fn type_with_range(
+	key: &str,
+	declaration: &str,
+	name: &str,
+	line: usize,
+) -> PRslt<(SingleValueDiscriminants, Option<ValueRange,>,),> {
+	let Some((name, range,),) =
+		name.strip_suffix(']',).and_then(|name| name.split_once('[',),)
+	else {
+		return Ok((type_named(key, name, line,)?, None,),);
+	};
+	let kind = type_named(key, name.trim_end(), line,)?;
+	let invalid = || ParseError::InvalidDeclaration {
+		key: key.to_string(),
+		declaration: declaration.to_string(),
+		line,
+	};
+
+	let numeric = matches!(
+		kind,
+		SingleValueDiscriminants::Integer
+			| SingleValueDiscriminants::UInteger
+			| SingleValueDiscriminants::Long
+			| SingleValueDiscriminants::Float
+	);
+	let Some((min, max,),) = range.split_once("..",).filter(|_| numeric,)
+	else {
+		return Err(invalid(),);
+	};
+	let bound = |text: &str| match text.trim() {
+		"" => Ok(None,),
+		text => kind
+			.into_payload(key, text, line,)
+			.map(Some,)
+			.map_err(|_| invalid(),),
+	};
+	let range = ValueRange { min: bound(min,)?, max: bound(max,)?, };
+	let reversed = match (&range.min, &range.max,) {
+		(Some(min,), Some(max,),) => {
+			compare_numbers(min, max,) == Some(Ordering::Greater,)
+		},
+		(None, None,) => true,
+		_ => false,
+	};
+	if reversed {
+		return Err(invalid(),);
 	}
+	Ok((kind, Some(range,),),)
 }
 
-fn parse_schema_value(value: &str,) -> PRslt<SchemaValue,> {
-	Ok(TreeValue::Scalar(
-		if value.contains(',',) {
-			Value::Collection(
-				value
-					.split(',',)
-					.map(|s| SingleValueDiscriminants::from_str(s.trim(),),)
-					.try_collect()?,
-			)
-		} else {
-			Value::Single(SingleValueDiscriminants::from_str(value,)?,)
+/// `elements` is the inside of `(min: Integer, max: Integer)`. every element
+/// has to be labeled, and labels have to be unique
+fn parse_labeled_tuple(
+	key: &str,
+	declaration: &str,
+	elements: &str,
+	line: usize,
+) -> PRslt<SchemaLeaf,> {
+	let invalid = || ParseError::InvalidDeclaration {
+		key: key.to_string(),
+		declaration: declaration.to_string(),
+		line,
+	};
+
+	let mut labels: Vec<String,> = Vec::new();
+	let mut kinds = Vec::new();
+	let mut ranges = Vec::new();
+	for element in elements.split(',',) {
+		let Some((label, kind,),) = element.split_once(':',) else {
+			return Err(invalid(),);
+		};
+
+		let label = label.trim();
+		if label.is_empty() || labels.iter().any(|known| known == label,) {
+			return Err(invalid(),);
+		}
+
+		labels.push(label.to_string(),);
+		let (kind, range,) =
+			type_with_range(key, kind.trim(), kind.trim(), line,)?;
+		kinds.push(kind,);
+		ranges.push(range,);
+	}
+
+	Ok(SchemaLeaf {
+		ty:             Value::Collection(kinds,),
+		labels:         Some(labels,),
+		resolvable:     false,
+		optional:       false,
+		list:           false,
+		repeatable:     false,
+		unrecognized:   None,
+		allowed:        None,
+		range:          None,
+		element_ranges: element_ranges(ranges,),
+	},)
+}
+
+/// `ranges` of the elements of a tuple, unless none of them has any
+fn element_ranges(
+	ranges: Vec<Option<ValueRange,>,>,
+) -> Option<Vec<Option<ValueRange,>,>,> {
+	ranges.iter().any(Option::is_some,).then_some(ranges,)
+}
+
+/// `literals` is the inside of `Enum(debug, info)`. literals are trimmed, and
+/// there has to be at least one, none of them empty or repeated
+fn parse_enum(
+	key: &str,
+	declaration: &str,
+	literals: &str,
+	line: usize,
+) -> PRslt<SchemaLeaf,> {
+	let mut allowed: Vec<String,> = Vec::new();
+	for literal in literals.split(',',) {
+		let literal = literal.trim();
+		if literal.is_empty() || allowed.iter().any(|known| known == literal,) {
+			return Err(ParseError::InvalidDeclaration {
+				key: key.to_string(),
+				declaration: declaration.to_string(),
+				line,
+			},);
+		}
+		allowed.push(literal.to_string(),);
+	}
+
+	Ok(SchemaLeaf {
+		allowed: Some(allowed,),
+		..SchemaLeaf::from(Value::Single(SingleValueDiscriminants::String,),)
+	},)
+}
+
+/// layout of the text produced by [`scaffold`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq,)]
+pub enum ScaffoldStyle {
+	/// one line per leaf key
+	#[default]
+	Compact,
+	/// additionally separates top level sections with a blank line
+	Grouped,
+	/// [`Self::Grouped`], with the declared type of each leaf in a comment
+	/// above it
+	Annotated,
+}
+
+/// emits a conf file listing every leaf of `schema` as a commented out
+/// assignment with a placeholder for its type (`# server.port = <Integer>`).
+/// the line of an optional leaf ends in `(optional)`
+pub fn scaffold(schema: &SchemaMap, style: ScaffoldStyle,) -> String {
+	let mut blocks: Vec<Vec<String,>,> = Vec::new();
+	for (key, value,) in schema.iter() {
+		if style != ScaffoldStyle::Compact || blocks.is_empty() {
+			blocks.push(Vec::new(),);
+		}
+		// we can assume that this `unwrap` do not panic, because a block has
+		// just been pushed when `blocks` was empty
+		let block = blocks.last_mut().unwrap();
+		for_each_leaf(key, value, &mut |full_key, leaf| {
+			if style == ScaffoldStyle::Annotated {
+				block.push(type_comment(leaf,),);
+			}
+			let optional = if leaf.optional { " (optional)" } else { "" };
+			block.push(format!(
+				"# {full_key} = {}{optional}",
+				placeholder(leaf,)
+			),);
+		},);
+	}
+
+	let mut output = blocks
+		.into_iter()
+		.map(|lines| lines.join("\n",),)
+		.collect::<Vec<_,>>()
+		.join("\n\n",);
+	if !output.is_empty() {
+		output.push('\n',);
+	}
+	output
+}
+
+/// writes [`scaffold`] of `schema` to `path`, replacing the file
+pub fn write_scaffold<P: AsRef<Path,>,>(
+	schema: &SchemaMap,
+	path: P,
+	style: ScaffoldStyle,
+) -> PRslt<(),> {
+	std::fs::write(path, scaffold(schema, style,),)?;
+	Ok((),)
+}
+
+/// conf file for users to fill in. every leaf of `schema` is assigned a
+/// placeholder for its type (`server.port = <Integer>`) below a comment with
+/// its declared type, in key order. the assignments of optional leaves are
+/// commented out
+pub fn to_conf_template(schema: &SchemaMap,) -> String {
+	let mut leaves = Vec::new();
+	for (key, value,) in schema.iter() {
+		for_each_leaf(key, value, &mut |full_key, leaf| {
+			let comment = if leaf.optional { "# " } else { "" };
+			leaves.push((
+				full_key.to_string(),
+				format!(
+					"{}\n{comment}{full_key} = {}\n",
+					type_comment(leaf,),
+					placeholder(leaf,)
+				),
+			),);
+		},);
+	}
+	leaves.sort_unstable();
+	leaves.into_iter().map(|(_, lines,)| lines,).collect()
+}
+
+/// `# type: ` comment with the declaration of `leaf`
+fn type_comment(leaf: &SchemaLeaf,) -> String {
+	let repeatable = if leaf.repeatable { " (repeatable)" } else { "" };
+	let optional = if leaf.optional { "?" } else { "" };
+	format!("# type: {}{repeatable}{optional}", declared(leaf,))
+}
+
+/// `schema` as schema text, a `dotted.key -> Type` line per leaf in key
+/// order. parsing it yields an equal map, except for a tuple of one unlabeled
+/// element, which has no declaration, a top-level key holding dots, which
+/// reads back nested, a declaration kept by [`parse_str_forward_compat`],
+/// which only that function reads back, and a `Deferred` type of a schema
+/// built in code, which no declaration yields
+pub fn to_string(schema: &SchemaMap,) -> String {
+	let mut text = String::new();
+	for (key, value,) in schema.iter() {
+		for_each_leaf(key, value, &mut |full_key, leaf| {
+			text.push_str(&format!("{full_key} -> {}\n", declaration(leaf,)),);
+		},);
+	}
+	text
+}
+
+/// writes [`to_string`] of `schema` to `path`, replacing the file
+pub fn write_file<P: AsRef<Path,>,>(
+	schema: &SchemaMap, path: P,
+) -> PRslt<(),> {
+	std::fs::write(path, to_string(schema,),)?;
+	Ok((),)
+}
+
+/// `leaf` as it is declared, labels and markers included
+fn declaration(leaf: &SchemaLeaf,) -> String {
+	if let Some(unrecognized,) = &leaf.unrecognized {
+		return unrecognized.clone();
+	}
+	let ty = match (&leaf.ty, leaf.labels.is_some(),) {
+		(Value::Collection(kinds,), true,) => {
+			let elements: Vec<String,> = kinds
+				.iter()
+				.enumerate()
+				.map(|(idx, kind,)| {
+					format!(
+						"{}: {kind}{}",
+						leaf.label(idx,).unwrap_or_default(),
+						range_text(leaf, idx,)
+					)
+				},)
+				.collect();
+			format!("({})", elements.join(", ",))
+		},
+		_ => declared(leaf,),
+	};
+	let resolvable = if leaf.resolvable { "@" } else { "" };
+	let repeatable = if leaf.repeatable { " (repeatable)" } else { "" };
+	let optional = if leaf.optional { "?" } else { "" };
+	format!("{resolvable}{ty}{repeatable}{optional}")
+}
+
+/// type as it is declared, labels left out
+pub(crate) fn declared(leaf: &SchemaLeaf,) -> String {
+	if let Some(allowed,) = &leaf.allowed {
+		return format!("Enum({})", allowed.join(", ",));
+	}
+	let range = range_text(leaf, 0,);
+	match &leaf.ty {
+		Value::Single(kind,) => format!("{kind}{range}"),
+		Value::Collection(kinds,) if leaf.list => {
+			format!("[{}{range}]", kinds[0])
 		},
-	),)
+		Value::Collection(kinds,) => kinds
+			.iter()
+			.enumerate()
+			.map(|(idx, kind,)| format!("{kind}{}", range_text(leaf, idx,)),)
+			.collect::<Vec<_,>>()
+			.join(", ",),
+	}
+}
+
+/// `[min..max]` of the `idx`th element of `leaf`, empty without bounds
+fn range_text(leaf: &SchemaLeaf, idx: usize,) -> String {
+	leaf.range_of(idx,).map(ToString::to_string,).unwrap_or_default()
+}
+
+fn placeholder(leaf: &SchemaLeaf,) -> String {
+	match &leaf.ty {
+		Value::Single(kind,) => format!("<{kind}>"),
+		Value::Collection(kinds,) if leaf.list => {
+			format!("<{}>, ...", kinds[0])
+		},
+		Value::Collection(kinds,) => kinds
+			.iter()
+			.enumerate()
+			.map(|(idx, kind,)| match leaf.label(idx,) {
+				Some(label,) => format!("<{label}: {kind}>"),
+				None => format!("<{kind}>"),
+			},)
+			.collect::<Vec<_,>>()
+			.join(", ",),
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::parser::core::MirLeaf;
+
+	fn scalar_line(value: &str, line: usize,) -> TreeValue<MirLeaf,> {
+		TreeValue::Scalar((value.to_string(), line, 1,),)
+	}
+
+	const _: () = {
+		const fn assert_send_sync<T: Send + Sync,>() {}
+		assert_send_sync::<SchemaRef,>();
+	};
+
+	#[test]
+	fn shared_schema_clones_share_one_map() {
+		let shared = parse_str("port -> Integer",).unwrap().into_shared();
+		let clone = shared.clone();
 
-	fn scalar_line(value: &str, line: usize,) -> TreeValue<(String, usize,),> {
-		TreeValue::Scalar((value.to_string(), line,),)
+		assert!(std::ptr::eq(shared.as_schema(), clone.as_schema()));
+		assert!(clone.get("port").is_some());
 	}
 
 	#[test]
 	fn parse_schema_value_accepts_single_discriminant() {
-		let schema = parse_schema_value("Bool",).unwrap();
+		let schema = parse_schema_value("flag", "Bool", 1,).unwrap();
 		match schema {
-			TreeValue::Scalar(Value::Single(kind,),) => {
+			TreeValue::Scalar(SchemaLeaf {
+				ty: Value::Single(kind,), ..
+			},) => {
 				assert_eq!(kind, SingleValueDiscriminants::Bool);
 			},
 			other => panic!("unexpected schema value: {other:?}"),
@@ -179,9 +1191,14 @@ mod tests {
 
 	#[test]
 	fn parse_schema_value_supports_collections() {
-		let schema = parse_schema_value("Integer, Integer",).unwrap();
+		let schema =
+			parse_schema_value("limits", "Integer, Integer", 1,).unwrap();
 		match schema {
-			TreeValue::Scalar(Value::Collection(kinds,),) => {
+			TreeValue::Scalar(SchemaLeaf {
+				ty: Value::Collection(kinds,),
+				labels: None,
+				..
+			},) => {
 				assert_eq!(kinds.len(), 2);
 				assert!(
 					kinds.iter().all(|k| matches!(
@@ -206,7 +1223,9 @@ mod tests {
 		let schema = mir.into_schema().unwrap();
 
 		match schema.get("flag",).unwrap() {
-			TreeValue::Scalar(Value::Single(kind,),) => {
+			TreeValue::Scalar(SchemaLeaf {
+				ty: Value::Single(kind,), ..
+			},) => {
 				assert_eq!(*kind, SingleValueDiscriminants::Bool);
 			},
 			other => panic!("unexpected flag schema: {other:?}"),
@@ -214,7 +1233,10 @@ mod tests {
 
 		match schema.get("server",).unwrap() {
 			TreeValue::Map(children,) => match children.get("port",).unwrap() {
-				TreeValue::Scalar(Value::Single(kind,),) => {
+				TreeValue::Scalar(SchemaLeaf {
+					ty: Value::Single(kind,),
+					..
+				},) => {
 					assert_eq!(*kind, SingleValueDiscriminants::Integer);
 				},
 				other => panic!("unexpected port schema: {other:?}"),
@@ -232,9 +1254,10 @@ mod tests {
 
 		assert!(matches!(
 			schema.get("flag"),
-			Some(TreeValue::Scalar(Value::Single(
-				SingleValueDiscriminants::Bool
-			)))
+			Some(TreeValue::Scalar(SchemaLeaf {
+				ty: Value::Single(SingleValueDiscriminants::Bool),
+				..
+			}))
 		));
 
 		let server = schema.get("server",).unwrap();
@@ -242,21 +1265,370 @@ mod tests {
 			TreeValue::Map(children,) => {
 				assert!(matches!(
 					children.get("port"),
-					Some(TreeValue::Scalar(Value::Single(
-						SingleValueDiscriminants::Integer
-					)))
+					Some(TreeValue::Scalar(SchemaLeaf {
+						ty: Value::Single(SingleValueDiscriminants::Integer),
+						..
+					}))
 				));
 				assert!(matches!(
 					children.get("host"),
-					Some(TreeValue::Scalar(Value::Single(
-						SingleValueDiscriminants::String
-					)))
+					Some(TreeValue::Scalar(SchemaLeaf {
+						ty: Value::Single(SingleValueDiscriminants::String),
+						..
+					}))
 				));
 			},
 			other => panic!("unexpected server schema: {other:?}"),
 		}
 	}
 
+	#[test]
+	fn scaffold_lists_every_leaf_as_placeholder() {
+		let schema = parse_str(
+			"server.port -> Integer\nserver.host -> String\nlimits -> \
+			 Integer, Integer\ndebug -> Bool",
+		)
+		.unwrap();
+
+		assert_eq!(
+			scaffold(&schema, ScaffoldStyle::Compact),
+			"# debug = <Bool>\n# limits = <Integer>, <Integer>\n# server.host \
+			 = <String>\n# server.port = <Integer>\n"
+		);
+		assert_eq!(
+			scaffold(&schema, ScaffoldStyle::Grouped),
+			"# debug = <Bool>\n\n# limits = <Integer>, <Integer>\n\n# \
+			 server.host = <String>\n# server.port = <Integer>\n"
+		);
+	}
+
+	#[test]
+	fn scaffold_marks_optional_keys() {
+		let schema = parse_str(
+			"server.port -> Integer
+server.host -> String?",
+		)
+		.unwrap();
+
+		assert_eq!(
+			scaffold(&schema, ScaffoldStyle::Compact),
+			"# server.host = <String> (optional)\n# server.port = <Integer>\n"
+		);
+		assert_eq!(
+			scaffold(&schema, ScaffoldStyle::Annotated),
+			"# type: String?\n# server.host = <String> (optional)\n# type: \
+			 Integer\n# server.port = <Integer>\n"
+		);
+	}
+
+	#[test]
+	fn scaffold_output_parses_against_its_schema() {
+		let schema =
+			parse_str("service.mode -> String\nservice.retries -> Integer",)
+				.unwrap();
+		let text = scaffold(&schema, ScaffoldStyle::Grouped,);
+
+		let conf = crate::parser::conf::parse_str(&text, schema,).unwrap();
+		assert!(conf.is_empty());
+	}
+
+	#[test]
+	fn annotated_scaffold_comments_declared_types() {
+		let schema = parse_str(
+			"server.tls.ciphers -> [String] (repeatable)\nserver.port -> \
+			 Integer\nlimits -> (min: Integer, max: Integer)\ndebug -> Bool",
+		)
+		.unwrap();
+		let text = scaffold(&schema, ScaffoldStyle::Annotated,);
+
+		assert_eq!(
+			text,
+			"# type: Bool\n# debug = <Bool>\n\n# type: Integer, Integer\n# \
+			 limits = <min: Integer>, <max: Integer>\n\n# type: Integer\n# \
+			 server.port = <Integer>\n# type: [String] (repeatable)\n# \
+			 server.tls.ciphers = <String>, ...\n"
+		);
+		assert!(
+			crate::parser::conf::parse_str(&text, schema).unwrap().is_empty()
+		);
+
+		let path = std::env::temp_dir()
+			.join(format!("dot_conf_scaffold_{}.conf", std::process::id()),);
+		write_scaffold(
+			&parse_str("debug -> Bool",).unwrap(),
+			&path,
+			ScaffoldStyle::Compact,
+		)
+		.unwrap();
+		assert_eq!(
+			std::fs::read_to_string(&path,).unwrap(),
+			"# debug = <Bool>\n"
+		);
+		std::fs::remove_file(&path,).unwrap();
+	}
+
+	#[test]
+	fn conf_template_assigns_placeholders_in_key_order() {
+		let schema = parse_str(
+			"server.tls.ciphers -> [String] (repeatable)\nserver.port -> \
+			 Integer[1..65535]\nserver.host -> String?\nlimits -> Integer, \
+			 Integer\nlog.level -> Enum(debug, info)\ndebug -> Bool",
+		)
+		.unwrap();
+
+		assert_eq!(
+			to_conf_template(&schema),
+			"# type: Bool\ndebug = <Bool>\n# type: Integer, Integer\nlimits = \
+			 <Integer>, <Integer>\n# type: Enum(debug, info)\nlog.level = \
+			 <String>\n# type: String?\n# server.host = <String>\n# type: \
+			 Integer[1..65535]\nserver.port = <Integer>\n# type: [String] \
+			 (repeatable)\nserver.tls.ciphers = <String>, ...\n"
+		);
+	}
+
+	#[test]
+	fn conf_template_comments_out_optional_keys() {
+		let schema =
+			parse_str("server.port -> Integer?\nserver.host -> String?",)
+				.unwrap();
+		let text = to_conf_template(&schema,);
+
+		assert_eq!(
+			text,
+			"# type: String?\n# server.host = <String>\n# type: Integer?\n# \
+			 server.port = <Integer>\n"
+		);
+		assert!(
+			crate::parser::conf::parse_str(&text, &schema).unwrap().is_empty()
+		);
+
+		let path = std::env::temp_dir()
+			.join(format!("dot_conf_template_{}.conf", std::process::id()),);
+		schema.write_template(&path,).unwrap();
+		assert_eq!(std::fs::read_to_string(&path,).unwrap(), text);
+		std::fs::remove_file(&path,).unwrap();
+	}
+
+	#[test]
+	fn parse_schema_value_supports_labeled_tuples() {
+		let schema =
+			parse_schema_value("limits", "(min: Integer, max : Integer)", 3,)
+				.unwrap();
+		match schema {
+			TreeValue::Scalar(leaf,) => {
+				assert_eq!(
+					leaf.ty,
+					Value::Collection(vec![
+						SingleValueDiscriminants::Integer,
+						SingleValueDiscriminants::Integer
+					])
+				);
+				assert_eq!(leaf.label(0), Some("min"));
+				assert_eq!(leaf.label(1), Some("max"));
+				assert_eq!(leaf.label(2), None);
+			},
+			other => panic!("unexpected schema value: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn separator_in_declaration_or_key_is_rejected() {
+		let err = parse_str("desc -> String -> more\n",).unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::InvalidDeclaration { ref key, ref declaration, line: 1 }
+				if key == "desc" && declaration == "String -> more"
+		));
+		assert!(
+			format!("{err:#}").contains("declare a single type after '->'")
+		);
+
+		let err = parse_str("a=b.c -> String\n",).unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::InvalidKeySegment { ref segment, line: 1, .. } if segment == "a=b"
+		));
+		assert!(format!("{err:#}").contains("keys cannot contain '=' or '->'"));
+	}
+
+	#[test]
+	fn parse_schema_value_rejects_mixed_or_repeated_labels() {
+		for declaration in [
+			"(min: Integer, Integer)",
+			"(min: Integer, min: Integer)",
+			"(: Bool)",
+		] {
+			let err = parse_schema_value("server.limits", declaration, 5,)
+				.unwrap_err();
+			match err {
+				ParseError::InvalidDeclaration {
+					key,
+					declaration: found,
+					line,
+				} => {
+					assert_eq!(key, "server.limits");
+					assert_eq!(found, declaration);
+					assert_eq!(line, 5);
+				},
+				other => panic!("unexpected error: {other:?}"),
+			}
+		}
+	}
+
+	#[test]
+	fn bad_declarations_get_help_of_their_own() {
+		for (declaration, help,) in [
+			("Integer (repeatable)", "only lists repeat",),
+			("[Integer, Bool]", "a list holds one type",),
+			("Integer[1-5]", "bound a number type",),
+			("Integer[1..10], Integer[10..1]", "bound a number type",),
+			("(lo: Integer[5..1], hi: Integer)", "bound a number type",),
+			("(min: Integer, Integer)", "label every element",),
+			("Enum(a, a)", "list distinct, non-empty literals",),
+		] {
+			let err = parse_schema_value("key", declaration, 1,).unwrap_err();
+			assert!(matches!(err, ParseError::InvalidDeclaration { .. }));
+			let shown = format!("{err:#}");
+			assert!(shown.contains(help), "{declaration}: {shown}");
+			if !help.starts_with("label",) {
+				assert!(!shown.contains("label every"), "{declaration}");
+			}
+		}
+	}
+
+	#[test]
+	fn scaffold_names_labeled_elements() {
+		let schema =
+			parse_str("limits -> (min: Integer, max: Integer)",).unwrap();
+
+		assert_eq!(
+			scaffold(&schema, ScaffoldStyle::Compact),
+			"# limits = <min: Integer>, <max: Integer>\n"
+		);
+	}
+
+	#[test]
+	fn redeclaration_with_other_type_is_rejected() {
+		let err = parse_str(
+			"server.port -> Integer\nserver.host -> String\nserver.port -> \
+			 String",
+		)
+		.unwrap_err();
+
+		match err {
+			ParseError::DuplicateKey { key, first_line, second_line, } => {
+				assert_eq!(key, "server.port");
+				assert_eq!(first_line, 1);
+				assert_eq!(second_line, 3);
+			},
+			other => panic!("unexpected error: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn parse_lines_reports_the_supplied_line_numbers() {
+		let err = parse_lines([
+			(4, "server.port -> Integer",),
+			(19, "server.port -> String",),
+		],)
+		.unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::DuplicateKey { first_line: 4, second_line: 19, .. }
+		));
+
+		let err =
+			parse_lines([(2, "# ports",), (8, "ports -> Integer -> Bool",),],)
+				.unwrap_err();
+		assert!(matches!(err, ParseError::InvalidDeclaration { line: 8, .. }));
+	}
+
+	#[test]
+	fn trailing_question_mark_marks_key_optional() {
+		let schema = parse_str(
+			"log.file -> String?\nlog.level -> String\nlimits -> (lo: \
+			 Integer, hi: Integer)?\ndb.password -> @Secret?\n",
+		)
+		.unwrap();
+		let leaf = |key| match schema.get(key,) {
+			Some(TreeValue::Scalar(leaf,),) => leaf.clone(),
+			other => panic!("unexpected value: {other:?}"),
+		};
+
+		assert!(leaf("log.file").optional);
+		assert_eq!(
+			leaf("log.file").ty,
+			Value::Single(SingleValueDiscriminants::String)
+		);
+		assert!(!leaf("log.level").optional);
+		assert!(leaf("limits").optional);
+		assert_eq!(leaf("limits").label(1), Some("hi"));
+		assert!(leaf("db.password").optional && leaf("db.password").resolvable);
+		assert!(parse_str("log.file -> String??",).is_err());
+	}
+
+	#[test]
+	fn brackets_declare_lists() {
+		let schema =
+			parse_str("ports -> [Integer]\nhosts -> [String]?\n",).unwrap();
+		let Some(TreeValue::Scalar(ports,),) = schema.get("ports",) else {
+			panic!("ports is missing");
+		};
+
+		assert!(ports.list && !ports.optional);
+		assert_eq!(
+			ports.element_kind(5),
+			Some(SingleValueDiscriminants::Integer)
+		);
+		assert!(matches!(
+			schema.get("hosts"),
+			Some(TreeValue::Scalar(leaf)) if leaf.list && leaf.optional
+		));
+		assert!(parse_str("ports -> [Integer, Bool]",).is_err());
+		assert!(parse_str("ports -> [Integer",).is_err());
+	}
+
+	#[test]
+	fn only_lists_are_repeatable() {
+		let schema = parse_str(
+			"allow -> [String] (repeatable)
+",
+		)
+		.unwrap();
+		assert!(matches!(
+			schema.get("allow"),
+			Some(TreeValue::Scalar(leaf)) if leaf.list && leaf.repeatable
+		));
+
+		let err = parse_str(
+			"port -> Integer (repeatable)
+",
+		)
+		.unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::InvalidDeclaration { ref key, line: 1, .. } if key == "port"
+		));
+	}
+
+	#[test]
+	fn redeclaration_with_same_type_warns() {
+		let (schema, warnings,) = parse_str_with_warnings(
+			"limits -> Integer,Integer\nlimits -> Integer, Integer",
+		)
+		.unwrap();
+
+		assert!(schema.get("limits").is_some());
+		assert_eq!(
+			warnings,
+			vec![Warning::RedundantDeclaration {
+				key:         "limits".to_string(),
+				first_line:  1,
+				second_line: 2,
+			}]
+		);
+	}
+
 	#[test]
 	fn display_for_value_discriminants_matches_variant_names() {
 		assert_eq!(ValueDiscriminants::Single.to_string(), "Single");
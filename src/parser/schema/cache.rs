@@ -0,0 +1,367 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::core::TreeValue;
+use crate::parser::diff::render_value;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaValue;
+use crate::parser::schema::ValueRange;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+const MAGIC: &[u8; 4] = b"DCSC";
+/// a cache is the magic, this version, then the tree. maps are a tag, an
+/// entry count and the entries, leaves a tag, the type names as text and the
+/// optional labels and flags. bump it whenever `SchemaLeaf` gains a field
+const FORMAT_VERSION: u8 = 9;
+
+const FLAG_RESOLVABLE: u8 = 1;
+const FLAG_OPTIONAL: u8 = 1 << 1;
+const FLAG_LIST: u8 = 1 << 2;
+/// followed by the declaration as written
+const FLAG_UNRECOGNIZED: u8 = 1 << 3;
+const FLAG_REPEATABLE: u8 = 1 << 4;
+/// followed by the count and the allowed literals
+const FLAG_ENUM: u8 = 1 << 5;
+/// followed by the minimum and the maximum, each a presence byte and the
+/// bound as text
+const FLAG_RANGE: u8 = 1 << 6;
+/// followed by a presence byte per tuple element and the range of each
+/// present one, as for [`FLAG_RANGE`]
+const FLAG_ELEMENT_RANGES: u8 = 1 << 7;
+
+const TAG_MAP: u8 = 0;
+const TAG_LEAF: u8 = 1;
+const TAG_SINGLE: u8 = 0;
+const TAG_COLLECTION: u8 = 1;
+
+impl SchemaMap {
+	/// compact encoding read back by [`SchemaMap::from_cache_bytes`]
+	pub fn to_cache_bytes(&self,) -> Vec<u8,> {
+		let mut bytes = MAGIC.to_vec();
+		bytes.push(FORMAT_VERSION,);
+		write_map(self, &mut bytes,);
+		bytes
+	}
+
+	/// fails on bytes which were not produced by the same format version of
+	/// [`SchemaMap::to_cache_bytes`]
+	pub fn from_cache_bytes(bytes: &[u8],) -> PRslt<SchemaMap,> {
+		let mut reader = Reader(bytes,);
+		if reader.take(MAGIC.len(),)? != MAGIC {
+			return Err(corrupt("not a schema cache",),);
+		}
+		if reader.byte()? != FORMAT_VERSION {
+			return Err(corrupt("unsupported schema cache version",),);
+		}
+
+		let map = read_map(&mut reader,)?;
+		if !reader.0.is_empty() {
+			return Err(corrupt("trailing bytes in schema cache",),);
+		}
+		Ok(SchemaMap::from_inner(map,),)
+	}
+}
+
+/// 64-bit fnv-1a. unlike `DefaultHasher` it is stable across releases, which
+/// matters for hashes written to disk
+pub(crate) fn fnv1a(bytes: &[u8],) -> u64 {
+	bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+		(hash ^ u64::from(*byte,)).wrapping_mul(0x0100_0000_01b3,)
+	},)
+}
+
+fn corrupt(reason: &str,) -> ParseError {
+	ParseError::Io(
+		std::io::Error::new(std::io::ErrorKind::InvalidData, reason,),
+	)
+}
+
+fn write_len(len: usize, bytes: &mut Vec<u8,>,) {
+	bytes.extend_from_slice(&(len as u32).to_le_bytes(),);
+}
+
+fn write_str(s: &str, bytes: &mut Vec<u8,>,) {
+	write_len(s.len(), bytes,);
+	bytes.extend_from_slice(s.as_bytes(),);
+}
+
+fn write_map(map: &BTreeMap<String, SchemaValue,>, bytes: &mut Vec<u8,>,) {
+	write_len(map.len(), bytes,);
+	for (key, value,) in map {
+		write_str(key, bytes,);
+		match value {
+			TreeValue::Map(children,) => {
+				bytes.push(TAG_MAP,);
+				write_map(children, bytes,);
+			},
+			TreeValue::Scalar(leaf,) => {
+				bytes.push(TAG_LEAF,);
+				write_leaf(leaf, bytes,);
+			},
+		}
+	}
+}
+
+fn write_leaf(leaf: &SchemaLeaf, bytes: &mut Vec<u8,>,) {
+	let kinds = match &leaf.ty {
+		Value::Single(kind,) => {
+			bytes.push(TAG_SINGLE,);
+			std::slice::from_ref(kind,)
+		},
+		Value::Collection(kinds,) => {
+			bytes.push(TAG_COLLECTION,);
+			kinds.as_slice()
+		},
+	};
+	write_len(kinds.len(), bytes,);
+	for kind in kinds {
+		write_str(&kind.to_string(), bytes,);
+	}
+
+	match &leaf.labels {
+		None => bytes.push(0,),
+		Some(labels,) => {
+			bytes.push(1,);
+			write_len(labels.len(), bytes,);
+			for label in labels {
+				write_str(label, bytes,);
+			}
+		},
+	}
+	let mut flags = 0;
+	if leaf.resolvable {
+		flags |= FLAG_RESOLVABLE;
+	}
+	if leaf.optional {
+		flags |= FLAG_OPTIONAL;
+	}
+	if leaf.list {
+		flags |= FLAG_LIST;
+	}
+	if leaf.unrecognized.is_some() {
+		flags |= FLAG_UNRECOGNIZED;
+	}
+	if leaf.repeatable {
+		flags |= FLAG_REPEATABLE;
+	}
+	if leaf.allowed.is_some() {
+		flags |= FLAG_ENUM;
+	}
+	if leaf.range.is_some() {
+		flags |= FLAG_RANGE;
+	}
+	if leaf.element_ranges.is_some() {
+		flags |= FLAG_ELEMENT_RANGES;
+	}
+	bytes.push(flags,);
+	if let Some(declaration,) = &leaf.unrecognized {
+		write_str(declaration, bytes,);
+	}
+	if let Some(allowed,) = &leaf.allowed {
+		write_len(allowed.len(), bytes,);
+		for literal in allowed {
+			write_str(literal, bytes,);
+		}
+	}
+	if let Some(range,) = &leaf.range {
+		write_range(range, bytes,);
+	}
+	for range in leaf.element_ranges.iter().flatten() {
+		match range {
+			None => bytes.push(0,),
+			Some(range,) => {
+				bytes.push(1,);
+				write_range(range, bytes,);
+			},
+		}
+	}
+}
+
+fn write_range(range: &ValueRange, bytes: &mut Vec<u8,>,) {
+	for bound in [&range.min, &range.max,] {
+		match bound {
+			None => bytes.push(0,),
+			Some(bound,) => {
+				bytes.push(1,);
+				write_str(
+					&render_value(&Value::Single(bound.clone(),),),
+					bytes,
+				);
+			},
+		}
+	}
+}
+
+struct Reader<'a,>(&'a [u8],);
+
+impl<'a,> Reader<'a,> {
+	fn take(&mut self, len: usize,) -> PRslt<&'a [u8],> {
+		if self.0.len() < len {
+			return Err(corrupt("truncated schema cache",),);
+		}
+		let (head, rest,) = self.0.split_at(len,);
+		self.0 = rest;
+		Ok(head,)
+	}
+
+	fn byte(&mut self,) -> PRslt<u8,> {
+		Ok(self.take(1,)?[0],)
+	}
+
+	fn len(&mut self,) -> PRslt<usize,> {
+		let bytes = self.take(4,)?;
+		Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3],],)
+			as usize,)
+	}
+
+	fn string(&mut self,) -> PRslt<String,> {
+		let len = self.len()?;
+		String::from_utf8(self.take(len,)?.to_vec(),)
+			.map_err(|_| corrupt("invalid utf-8 in schema cache",),)
+	}
+}
+
+fn read_map(reader: &mut Reader,) -> PRslt<BTreeMap<String, SchemaValue,>,> {
+	let mut map = BTreeMap::new();
+	for _ in 0..reader.len()? {
+		let key = reader.string()?;
+		let value = match reader.byte()? {
+			TAG_MAP => TreeValue::Map(read_map(reader,)?,),
+			TAG_LEAF => TreeValue::Scalar(read_leaf(reader,)?,),
+			_ => return Err(corrupt("unknown node in schema cache",),),
+		};
+		map.insert(key, value,);
+	}
+	Ok(map,)
+}
+
+fn read_leaf(reader: &mut Reader,) -> PRslt<SchemaLeaf,> {
+	let tag = reader.byte()?;
+	let kinds: Vec<SingleValueDiscriminants,> = (0..reader.len()?)
+		.map(|_| {
+			SingleValueDiscriminants::from_str(&reader.string()?,)
+				.map_err(|_| corrupt("unknown type in schema cache",),)
+		},)
+		.try_collect()?;
+
+	let ty = match (tag, kinds.as_slice(),) {
+		(TAG_SINGLE, [kind,],) => Value::Single(*kind,),
+		(TAG_COLLECTION, [_, ..,],) => Value::Collection(kinds,),
+		_ => return Err(corrupt("malformed type in schema cache",),),
+	};
+
+	let labels = match reader.byte()? {
+		0 => None,
+		1 => Some((0..reader.len()?).map(|_| reader.string(),).try_collect()?,),
+		_ => return Err(corrupt("malformed labels in schema cache",),),
+	};
+	// every bit of the byte is a flag
+	let flags = reader.byte()?;
+	let unrecognized = if flags & FLAG_UNRECOGNIZED != 0 {
+		Some(reader.string()?,)
+	} else {
+		None
+	};
+	let allowed = if flags & FLAG_ENUM != 0 {
+		Some((0..reader.len()?).map(|_| reader.string(),).try_collect()?,)
+	} else {
+		None
+	};
+	let range = if flags & FLAG_RANGE != 0 {
+		let kind = match &ty {
+			Value::Single(kind,) => *kind,
+			Value::Collection(kinds,) => kinds[0],
+		};
+		Some(read_range(reader, kind,)?,)
+	} else {
+		None
+	};
+	let element_ranges = match (&ty, flags & FLAG_ELEMENT_RANGES != 0,) {
+		(Value::Collection(kinds,), true,) => Some(
+			kinds
+				.iter()
+				.map(|kind| match reader.byte()? {
+					0 => Ok(None,),
+					1 => read_range(reader, *kind,).map(Some,),
+					_ => Err(corrupt("malformed range in schema cache",),),
+				},)
+				.try_collect()?,
+		),
+		(Value::Single(_,), true,) => {
+			return Err(corrupt("malformed range in schema cache",),);
+		},
+		(_, false,) => None,
+	};
+	Ok(SchemaLeaf {
+		ty,
+		labels,
+		resolvable: flags & FLAG_RESOLVABLE != 0,
+		optional: flags & FLAG_OPTIONAL != 0,
+		list: flags & FLAG_LIST != 0,
+		repeatable: flags & FLAG_REPEATABLE != 0,
+		unrecognized,
+		allowed,
+		range,
+		element_ranges,
+	},)
+}
+
+/// minimum and maximum of `kind` written by [`write_range`]
+fn read_range(
+	reader: &mut Reader,
+	kind: SingleValueDiscriminants,
+) -> PRslt<ValueRange,> {
+	let mut bound = || match reader.byte()? {
+		0 => Ok(None,),
+		1 => kind
+			.into_payload("", &reader.string()?, 0,)
+			.map(Some,)
+			.map_err(|_| corrupt("malformed range in schema cache",),),
+		_ => Err(corrupt("malformed range in schema cache",),),
+	};
+	Ok(ValueRange { min: bound()?, max: bound()?, },)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::schema;
+
+	fn sample() -> SchemaMap {
+		schema::parse_str_forward_compat(
+			"server.port -> Integer\nserver.host -> @String?\nlimits -> (min: \
+			 Integer, max: Integer)\nflags -> Bool, Bool?\nports -> \
+			 [Integer]\nprice -> Decimal(2)\nallow -> [String] \
+			 (repeatable)\nlevel -> Enum(debug, info)?\nport -> \
+			 Integer[1..65535]\nratios -> [Float[..1.5]]\nwindow -> \
+			 Integer[0..], Integer\nspan -> (lo: Long, hi: Long[..9])\n",
+		)
+		.unwrap()
+		.0
+	}
+
+	#[test]
+	fn cache_bytes_round_trip() {
+		let schema = sample();
+		let restored =
+			SchemaMap::from_cache_bytes(&schema.to_cache_bytes(),).unwrap();
+		assert_eq!(*restored, *schema);
+	}
+
+	#[test]
+	fn corrupt_bytes_are_rejected() {
+		let bytes = sample().to_cache_bytes();
+
+		assert!(
+			SchemaMap::from_cache_bytes(&bytes[..bytes.len() - 1],).is_err()
+		);
+		assert!(SchemaMap::from_cache_bytes(b"garbage",).is_err());
+
+		let mut other_version = bytes.clone();
+		other_version[MAGIC.len()] = FORMAT_VERSION + 1;
+		assert!(SchemaMap::from_cache_bytes(&other_version,).is_err());
+	}
+}
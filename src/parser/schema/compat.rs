@@ -0,0 +1,690 @@
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::core::TreeValue;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaValue;
+use crate::parser::schema::ValueRange;
+use crate::parser::schema::compare_numbers;
+use crate::parser::schema::declared;
+use crate::parser::schema::for_each_leaf;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq,)]
+pub enum Compatibility {
+	/// every conf valid under the old schema still parses
+	Compatible,
+	/// some conf valid under the old schema fails to parse
+	Breaking,
+}
+
+/// one difference between two schemas, at a dotted key
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub struct SchemaChange {
+	pub key:           String,
+	pub compatibility: Compatibility,
+	pub reason:        String,
+}
+
+/// `breaking: key: reason` or `compatible: key: reason`
+impl Display for SchemaChange {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		let label = match self.compatibility {
+			Compatibility::Compatible => "compatible",
+			Compatibility::Breaking => "breaking",
+		};
+		write!(f, "{label}: {}: {}", self.key, self.reason)
+	}
+}
+
+/// result of [`compat_check`], in key order
+#[derive(Debug, Clone, Default, PartialEq, Eq,)]
+pub struct CompatReport(Vec<SchemaChange,>,);
+
+impl CompatReport {
+	pub fn changes(&self,) -> &[SchemaChange] {
+		&self.0
+	}
+
+	pub fn breaking(&self,) -> impl Iterator<Item = &SchemaChange,> {
+		self.0
+			.iter()
+			.filter(|change| change.compatibility == Compatibility::Breaking,)
+	}
+
+	pub fn is_compatible(&self,) -> bool {
+		self.breaking().next().is_none()
+	}
+}
+
+/// one line per change
+impl Display for CompatReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		for change in &self.0 {
+			writeln!(f, "{change}")?;
+		}
+		Ok((),)
+	}
+}
+
+/// every difference between `old` and `new`, classified by whether a conf
+/// valid under `old` still parses under `new`, strictly parsed. removed keys
+/// and added keys which are not optional are breaking. a type change is
+/// compatible when the new type accepts every text the old one did, e.g.
+/// `Integer` to `Float` or anything to `String`
+pub fn compat_check(old: &SchemaMap, new: &SchemaMap,) -> CompatReport {
+	let mut changes = Vec::new();
+	compare_maps("", old, new, &mut changes,);
+	CompatReport(changes,)
+}
+
+fn compare_maps(
+	prefix: &str,
+	old: &BTreeMap<String, SchemaValue,>,
+	new: &BTreeMap<String, SchemaValue,>,
+	changes: &mut Vec<SchemaChange,>,
+) {
+	let keys: BTreeSet<&String,> = old.keys().chain(new.keys(),).collect();
+	for key in keys {
+		let path = if prefix.is_empty() {
+			key.clone()
+		} else {
+			format!("{prefix}.{key}")
+		};
+
+		match (old.get(key,), new.get(key,),) {
+			(Some(old,), Some(new,),) => compare(&path, old, new, changes,),
+			(Some(old,), None,) => for_each_leaf(&path, old, &mut |key, _| {
+				changes.push(change(key, Compatibility::Breaking, "removed",),)
+			},),
+			(None, Some(new,),) => {
+				for_each_leaf(&path, new, &mut |key, leaf| {
+					changes.push(if leaf.optional {
+						change(key, Compatibility::Compatible, "added",)
+					} else {
+						change(
+							key,
+							Compatibility::Breaking,
+							"added as required",
+						)
+					},)
+				},)
+			},
+			(None, None,) => unreachable!("key of neither map"),
+		}
+	}
+}
+
+fn compare(
+	path: &str,
+	old: &SchemaValue,
+	new: &SchemaValue,
+	changes: &mut Vec<SchemaChange,>,
+) {
+	match (old, new,) {
+		(TreeValue::Map(old,), TreeValue::Map(new,),) => {
+			compare_maps(path, old, new, changes,)
+		},
+		(TreeValue::Scalar(old,), TreeValue::Scalar(new,),) => {
+			compare_leaves(path, old, new, changes,)
+		},
+		(TreeValue::Scalar(_,), TreeValue::Map(_,),) => changes.push(change(
+			path,
+			Compatibility::Breaking,
+			"key became a section",
+		),),
+		(TreeValue::Map(_,), TreeValue::Scalar(_,),) => changes.push(change(
+			path,
+			Compatibility::Breaking,
+			"section became a key",
+		),),
+	}
+}
+
+fn compare_leaves(
+	path: &str,
+	old: &SchemaLeaf,
+	new: &SchemaLeaf,
+	changes: &mut Vec<SchemaChange,>,
+) {
+	if old.ty != new.ty || old.list != new.list {
+		// an enum only takes its literals, whatever the old type was
+		let (compatibility, verb,) =
+			if accepts_all(old, new,) && new.allowed.is_none() {
+				(Compatibility::Compatible, "widened",)
+			} else {
+				(Compatibility::Breaking, "changed",)
+			};
+		changes.push(change(
+			path,
+			compatibility,
+			&format!("type {verb} from {} to {}", declared(old), declared(new)),
+		),);
+	} else {
+		compare_literals(path, old, new, changes,);
+		let elements = match &old.ty {
+			Value::Collection(kinds,) if !old.list => kinds.len(),
+			_ => 1,
+		};
+		if (0..elements).any(|idx| old.range_of(idx,) != new.range_of(idx,),) {
+			let (compatibility, verb,) = if (0..elements).all(|idx| {
+				range_covers(new.range_of(idx,), old.range_of(idx,),)
+			},)
+			{
+				(Compatibility::Compatible, "widened",)
+			} else {
+				(Compatibility::Breaking, "narrowed",)
+			};
+			changes.push(change(
+				path,
+				compatibility,
+				&format!(
+					"range {verb} from {} to {}",
+					declared(old),
+					declared(new)
+				),
+			),);
+		}
+	}
+
+	match (old.resolvable, new.resolvable,) {
+		// a placeholder is then read like any other value
+		(true, false,) if accepts_any_text(&new.ty,) => changes.push(change(
+			path,
+			Compatibility::Compatible,
+			"placeholders are read as plain text",
+		),),
+		(true, false,) => changes.push(change(
+			path,
+			Compatibility::Breaking,
+			"placeholders are no longer accepted",
+		),),
+		(false, true,) => changes.push(change(
+			path,
+			Compatibility::Compatible,
+			"placeholders are accepted",
+		),),
+		_ => {},
+	}
+
+	match (old.optional, new.optional,) {
+		(true, false,) => changes.push(change(
+			path,
+			Compatibility::Breaking,
+			"became required",
+		),),
+		(false, true,) => changes.push(change(
+			path,
+			Compatibility::Compatible,
+			"became optional",
+		),),
+		_ => {},
+	}
+
+	// `+=` is then rejected, and a key assigned twice keeps one value only
+	match (old.repeatable, new.repeatable,) {
+		(true, false,) => changes.push(change(
+			path,
+			Compatibility::Breaking,
+			"no longer repeatable",
+		),),
+		(false, true,) => changes.push(change(
+			path,
+			Compatibility::Compatible,
+			"became repeatable",
+		),),
+		_ => {},
+	}
+
+	if old.labels != new.labels {
+		changes.push(change(
+			path,
+			Compatibility::Compatible,
+			"element labels changed",
+		),);
+	}
+}
+
+/// an enum is a string restricted to its literals, so of two leaves of the
+/// same type, only literals `old` accepts and `new` does not break confs
+fn compare_literals(
+	path: &str,
+	old: &SchemaLeaf,
+	new: &SchemaLeaf,
+	changes: &mut Vec<SchemaChange,>,
+) {
+	let missing = |from: &[String], to: &[String]| {
+		from.iter()
+			.filter(|literal| !to.contains(literal,),)
+			.cloned()
+			.collect::<Vec<_,>>()
+			.join(", ",)
+	};
+	match (&old.allowed, &new.allowed,) {
+		(None, Some(_,),) => changes.push(change(
+			path,
+			Compatibility::Breaking,
+			&format!("restricted to {}", declared(new)),
+		),),
+		(Some(_,), None,) => changes.push(change(
+			path,
+			Compatibility::Compatible,
+			&format!("no longer restricted to {}", declared(old)),
+		),),
+		(Some(old,), Some(new,),) => {
+			let removed = missing(old, new,);
+			if !removed.is_empty() {
+				changes.push(change(
+					path,
+					Compatibility::Breaking,
+					&format!("enum literals removed: {removed}"),
+				),);
+			}
+			let added = missing(new, old,);
+			if !added.is_empty() {
+				changes.push(change(
+					path,
+					Compatibility::Compatible,
+					&format!("enum literals added: {added}"),
+				),);
+			}
+		},
+		(None, None,) => {},
+	}
+}
+
+/// whether every value within `old` lies within `new`. no range takes any
+/// value
+fn range_covers(new: Option<&ValueRange,>, old: Option<&ValueRange,>,) -> bool {
+	let Some(new,) = new else {
+		return true;
+	};
+	let Some(old,) = old else {
+		return false;
+	};
+	let bound_covers =
+		|new: &Option<_,>, old: &Option<_,>, beyond| match (new, old,) {
+			(None, _,) => true,
+			(Some(_,), None,) => false,
+			(Some(new,), Some(old,),) => compare_numbers(old, new,)
+				.is_some_and(|ordering| ordering != beyond,),
+		};
+	bound_covers(&new.min, &old.min, Ordering::Less,)
+		&& bound_covers(&new.max, &old.max, Ordering::Greater,)
+}
+
+fn change(
+	key: &str,
+	compatibility: Compatibility,
+	reason: &str,
+) -> SchemaChange {
+	SchemaChange {
+		key: key.to_string(),
+		compatibility,
+		reason: reason.to_string(),
+	}
+}
+
+/// whether `new` accepts every value text `old` accepts. a tuple takes one
+/// value per element, so a single value never becomes one of several
+/// elements. lists reject empty elements, so only kinds whose texts never
+/// contain a comma can become a list
+fn accepts_all(old: &SchemaLeaf, new: &SchemaLeaf,) -> bool {
+	let kinds = |leaf: &SchemaLeaf| match &leaf.ty {
+		Value::Single(kind,) => std::slice::from_ref(kind,).to_vec(),
+		Value::Collection(kinds,) => kinds.clone(),
+	};
+
+	match (&old.ty, &new.ty,) {
+		(Value::Collection(old_kinds,), Value::Collection(new_kinds,),)
+			if old.list && new.list =>
+		{
+			kind_accepts(old_kinds[0], new_kinds[0],)
+		},
+		(_, Value::Collection(new_kinds,),) if new.list => kinds(old,)
+			.into_iter()
+			.all(|old| !is_text(old,) && kind_accepts(old, new_kinds[0],),),
+		(Value::Collection(_,), _,) if old.list => accepts_any_text(&new.ty,),
+		(Value::Single(old,), Value::Single(new,),) => {
+			kind_accepts(*old, *new,)
+		},
+		(Value::Collection(old,), Value::Collection(new,),) => {
+			old.len() == new.len()
+				&& old
+					.iter()
+					.zip(new,)
+					.all(|(old, new,)| kind_accepts(*old, *new,),)
+		},
+		(Value::Single(old,), Value::Collection(new,),) => {
+			new.len() == 1 && !is_text(*old,) && kind_accepts(*old, new[0],)
+		},
+		(Value::Collection(_,), Value::Single(_,),) => {
+			accepts_any_text(&new.ty,)
+		},
+	}
+}
+
+fn kind_accepts(
+	old: SingleValueDiscriminants,
+	new: SingleValueDiscriminants,
+) -> bool {
+	old == new
+		|| is_text(new,)
+		|| (old == SingleValueDiscriminants::Integer
+			&& matches!(
+				new,
+				SingleValueDiscriminants::Float
+					| SingleValueDiscriminants::Long
+			))
+}
+
+fn is_text(kind: SingleValueDiscriminants,) -> bool {
+	matches!(
+		kind,
+		SingleValueDiscriminants::String | SingleValueDiscriminants::Secret
+	)
+}
+
+fn accepts_any_text(ty: &Value<SingleValueDiscriminants,>,) -> bool {
+	matches!(ty, Value::Single(kind) if is_text(*kind))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::schema;
+
+	fn check(old: &str, new: &str,) -> Vec<String,> {
+		let report = compat_check(
+			&schema::parse_str(old,).unwrap(),
+			&schema::parse_str(new,).unwrap(),
+		);
+		report.changes().iter().map(ToString::to_string,).collect()
+	}
+
+	#[test]
+	fn identical_schemas_have_no_changes() {
+		let schema =
+			"server.port -> Integer\nlimits -> (lo: Float, hi: Float)\n";
+		assert!(check(schema, schema).is_empty());
+	}
+
+	#[test]
+	fn added_optional_keys_are_compatible_and_removed_ones_breaking() {
+		assert_eq!(
+			check(
+				"server.port -> Integer\nlog.file -> String\nlog.level -> \
+				 String\n",
+				"server.port -> Integer\nserver.host -> String?\nserver.tls \
+				 -> Bool\n",
+			),
+			vec![
+				"breaking: log.file: removed",
+				"breaking: log.level: removed",
+				"compatible: server.host: added",
+				"breaking: server.tls: added as required",
+			]
+		);
+	}
+
+	#[test]
+	fn making_keys_required_is_breaking() {
+		assert_eq!(
+			check("a -> Integer?\nb -> Integer", "a -> Integer\nb -> Float?",),
+			vec![
+				"breaking: a: became required",
+				"compatible: b: type widened from Integer to Float",
+				"compatible: b: became optional",
+			]
+		);
+	}
+
+	#[test]
+	fn widening_single_types_is_compatible() {
+		for (old, new,) in [
+			("Integer", "Float",),
+			("Integer", "Long",),
+			("Integer", "String",),
+			("Bool", "String",),
+			("Float", "Secret",),
+			("Secret", "String",),
+		] {
+			assert_eq!(
+				check(&format!("key -> {old}"), &format!("key -> {new}"),),
+				vec![format!(
+					"compatible: key: type widened from {old} to {new}"
+				)]
+			);
+		}
+	}
+
+	#[test]
+	fn narrowing_or_unrelated_single_types_is_breaking() {
+		for (old, new,) in [
+			("Float", "Integer",),
+			("String", "Integer",),
+			("String", "Bool",),
+			("Bool", "Integer",),
+			("Integer", "Bool",),
+		] {
+			assert_eq!(
+				check(&format!("key -> {old}"), &format!("key -> {new}"),),
+				vec![format!(
+					"breaking: key: type changed from {old} to {new}"
+				)]
+			);
+		}
+	}
+
+	#[test]
+	fn collections_compare_element_by_element() {
+		assert_eq!(
+			check("ports -> Integer, Integer", "ports -> Float, String",),
+			vec![
+				"compatible: ports: type widened from Integer, Integer to \
+				 Float, String"
+			]
+		);
+		assert_eq!(
+			check("ports -> Integer, String", "ports -> Integer, Integer",),
+			vec![
+				"breaking: ports: type changed from Integer, String to \
+				 Integer, Integer"
+			]
+		);
+		assert_eq!(
+			check(
+				"ports -> Integer, Integer",
+				"ports -> Integer, Integer, Integer",
+			),
+			vec![
+				"breaking: ports: type changed from Integer, Integer to \
+				 Integer, Integer, Integer"
+			]
+		);
+	}
+
+	#[test]
+	fn single_and_collection_convert_only_without_commas() {
+		// `80` is one element short
+		assert_eq!(
+			check("ports -> Integer", "ports -> Integer, Float",),
+			vec![
+				"breaking: ports: type changed from Integer to Integer, Float"
+			]
+		);
+		// `a,b` would be split
+		assert_eq!(
+			check("names -> String", "names -> String, String",),
+			vec!["breaking: names: type changed from String to String, String"]
+		);
+		assert_eq!(
+			check("ports -> Integer, Integer", "ports -> String",),
+			vec![
+				"compatible: ports: type widened from Integer, Integer to \
+				 String"
+			]
+		);
+		assert_eq!(
+			check("ports -> Integer, Integer", "ports -> Integer",),
+			vec![
+				"breaking: ports: type changed from Integer, Integer to \
+				 Integer"
+			]
+		);
+	}
+
+	#[test]
+	fn lists_accept_single_values_and_tuples_without_text() {
+		assert_eq!(
+			check(
+				"a -> Integer\nb -> Integer, Float\nc -> String\nd -> \
+				 [Integer]\ne -> [Integer]\nf -> [Integer]",
+				"a -> [Float]\nb -> [Float]\nc -> [String]\nd -> [String]\ne \
+				 -> String\nf -> Integer, Integer",
+			),
+			vec![
+				"compatible: a: type widened from Integer to [Float]",
+				"compatible: b: type widened from Integer, Float to [Float]",
+				"breaking: c: type changed from String to [String]",
+				"compatible: d: type widened from [Integer] to [String]",
+				"compatible: e: type widened from [Integer] to String",
+				"breaking: f: type changed from [Integer] to Integer, Integer",
+			]
+		);
+	}
+
+	#[test]
+	fn keys_turning_into_sections_are_breaking() {
+		assert_eq!(
+			check(
+				"log -> String\nnet.port -> Integer",
+				"log.file -> String\nnet -> String",
+			),
+			vec![
+				"breaking: log: key became a section",
+				"breaking: net: section became a key",
+			]
+		);
+	}
+
+	#[test]
+	fn placeholders_and_labels_are_classified() {
+		assert_eq!(
+			check(
+				"db.password -> @Secret\ndb.port -> @Integer\ndb.host -> \
+				 String\nlimits -> (lo: Integer, hi: Integer)",
+				"db.password -> Secret\ndb.port -> Integer\ndb.host -> \
+				 @String\nlimits -> (min: Integer, max: Integer)",
+			),
+			vec![
+				"compatible: db.host: placeholders are accepted",
+				"compatible: db.password: placeholders are read as plain text",
+				"breaking: db.port: placeholders are no longer accepted",
+				"compatible: limits: element labels changed",
+			]
+		);
+	}
+
+	#[test]
+	fn dropping_repeatable_is_breaking() {
+		assert_eq!(
+			check(
+				"allow -> [String] (repeatable)\ndeny -> [String]",
+				"allow -> [String]\ndeny -> [String] (repeatable)",
+			),
+			vec![
+				"breaking: allow: no longer repeatable",
+				"compatible: deny: became repeatable",
+			]
+		);
+	}
+
+	#[test]
+	fn enum_literals_are_classified() {
+		assert_eq!(
+			check(
+				"log.level -> Enum(debug, info, warn)
+log.format -> Enum(text)
+log.file -> String
+log.color -> Enum(auto)",
+				"log.level -> Enum(info, warn, error)
+log.format -> Enum(text, json)
+log.file -> Enum(stderr, stdout)
+log.color -> String",
+			),
+			vec![
+				"compatible: log.color: no longer restricted to Enum(auto)",
+				"breaking: log.file: restricted to Enum(stderr, stdout)",
+				"compatible: log.format: enum literals added: json",
+				"breaking: log.level: enum literals removed: debug",
+				"compatible: log.level: enum literals added: error",
+			]
+		);
+		assert_eq!(
+			check("port -> Integer", "port -> Enum(80, 443)",),
+			vec!["breaking: port: type changed from Integer to Enum(80, 443)"]
+		);
+	}
+
+	#[test]
+	fn narrowed_or_added_ranges_are_breaking() {
+		assert_eq!(
+			check(
+				"port -> Integer[1..65535]\nretries -> Integer\nratio -> \
+				 Float[0.0..1.0]\nsize -> Integer[1..]",
+				"port -> Integer[1..1024]\nretries -> Integer[1..10]\nratio \
+				 -> Float\nsize -> Integer[0..]",
+			),
+			vec![
+				"breaking: port: range narrowed from Integer[1..65535] to \
+				 Integer[1..1024]",
+				"compatible: ratio: range widened from Float[0..1] to Float",
+				"breaking: retries: range narrowed from Integer to \
+				 Integer[1..10]",
+				"compatible: size: range widened from Integer[1..] to \
+				 Integer[0..]",
+			]
+		);
+		assert_eq!(
+			check(
+				"limits -> Integer[1..10], Integer\nspan -> Long, Long",
+				"limits -> Integer[1..20], Integer\nspan -> Long, Long[..9]",
+			),
+			vec![
+				"compatible: limits: range widened from Integer[1..10], \
+				 Integer to Integer[1..20], Integer",
+				"breaking: span: range narrowed from Long, Long to Long, \
+				 Long[..9]",
+			]
+		);
+	}
+
+	#[test]
+	fn report_is_compatible_without_breaking_changes() {
+		let old = schema::parse_str("a -> Integer\nb -> String",).unwrap();
+		let widened =
+			schema::parse_str("a -> Float\nb -> String\nc -> Bool?",).unwrap();
+		let report = compat_check(&old, &widened,);
+		assert!(report.is_compatible());
+		assert_eq!(report.changes().len(), 2);
+
+		let report = compat_check(&widened, &old,);
+		assert!(!report.is_compatible());
+		assert_eq!(
+			report
+				.breaking()
+				.map(|change| change.key.as_str(),)
+				.collect::<Vec<_,>>(),
+			vec!["a", "c"]
+		);
+		assert_eq!(
+			report.to_string(),
+			"breaking: a: type changed from Float to Integer\nbreaking: c: \
+			 removed\n"
+		);
+	}
+}
@@ -0,0 +1,161 @@
+use crate::error::ParseError;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::schema::SchemaLeaf;
+use std::fmt::Debug;
+
+/// written in place of a secret by every renderer
+pub const REDACTED: &str = "***redacted***";
+
+/// payload of a `Secret` value. it never shows up in `Debug` output, and
+/// [`Secret::expose_secret`] is the only way to read it
+#[derive(Clone, Eq,)]
+pub struct Secret(String,);
+
+impl Secret {
+	pub fn new(value: impl Into<String,>,) -> Self {
+		Self(value.into(),)
+	}
+
+	pub fn expose_secret(&self,) -> &str {
+		&self.0
+	}
+}
+
+impl Debug for Secret {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		f.write_str(REDACTED,)
+	}
+}
+
+/// compares every byte regardless of where the first difference is, so the
+/// time taken says little about the contents
+impl PartialEq for Secret {
+	fn eq(&self, other: &Self,) -> bool {
+		let (l, r,) = (self.0.as_bytes(), other.0.as_bytes(),);
+		l.len() == r.len()
+			&& l.iter().zip(r,).fold(0, |diff, (l, r,)| diff | (l ^ r),) == 0
+	}
+}
+
+impl SingleValue {
+	/// the payload of a `Secret`, `None` for any other value
+	pub fn expose_secret(&self,) -> Option<&str,> {
+		match self {
+			SingleValue::Secret(secret,) => Some(secret.expose_secret(),),
+			_ => None,
+		}
+	}
+}
+
+impl SchemaLeaf {
+	/// whether any element of the value is declared a `Secret`
+	pub fn is_secret(&self,) -> bool {
+		match &self.ty {
+			Value::Single(kind,) => *kind == SingleValueDiscriminants::Secret,
+			Value::Collection(kinds,) => {
+				kinds.contains(&SingleValueDiscriminants::Secret,)
+			},
+		}
+	}
+}
+
+/// drops the offending text of `err` when it was raised for a key holding a
+/// secret, so the secret cannot leak into logs through error messages
+pub(crate) fn redact_for(
+	leaf: &SchemaLeaf, mut err: ParseError,
+) -> ParseError {
+	if !leaf.is_secret() {
+		return err;
+	}
+	match &mut err {
+		ParseError::InvalidValue { value, .. }
+		| ParseError::OutOfRange { value, .. } => *value = REDACTED.to_string(),
+		ParseError::LocalizedNumber { value, suggestion, .. } => {
+			*value = REDACTED.to_string();
+			*suggestion = REDACTED.to_string();
+		},
+		_ => {},
+	}
+	err
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::conf::ConfValue;
+	use crate::parser::schema;
+	use crate::show::ShowFmt;
+	use crate::show::ShowStyle;
+
+	const SCHEMA: &str = "db.password -> Secret\ndb.user -> String\n";
+	const CONF: &str = "db.password = hunter2\ndb.user = admin\n";
+
+	fn render(fmt: ShowFmt, style: &ShowStyle,) -> String {
+		let conf = conf::parse_str(CONF, schema::parse_str(SCHEMA,).unwrap(),)
+			.unwrap();
+		let mut output = Vec::new();
+		conf.write_fmt_streaming(fmt, style, &mut output,).unwrap();
+		String::from_utf8(output,).unwrap()
+	}
+
+	#[test]
+	fn secrets_are_redacted_by_every_format() {
+		for fmt in [
+			ShowFmt::Conf,
+			ShowFmt::Json,
+			ShowFmt::Toml,
+			ShowFmt::Debug,
+			ShowFmt::Env,
+		] {
+			let output = render(fmt, &ShowStyle::default(),);
+			assert!(!output.contains("hunter2"), "{fmt:?} leaked: {output}");
+			assert!(output.contains(REDACTED), "{fmt:?}: {output}");
+			assert!(output.contains("admin"), "{fmt:?}: {output}");
+		}
+	}
+
+	#[test]
+	fn reveal_secrets_is_explicit() {
+		let style = ShowStyle::default().reveal_secrets(true,);
+		assert!(render(ShowFmt::Conf, &style).contains("hunter2"));
+		// `Debug` has no style to consult and stays redacted
+		assert!(!render(ShowFmt::Debug, &style).contains("hunter2"));
+	}
+
+	#[test]
+	fn secret_payload_is_only_reachable_explicitly() {
+		let conf = conf::parse_str(CONF, schema::parse_str(SCHEMA,).unwrap(),)
+			.unwrap();
+		let Some(ConfValue::Scalar(Value::Single(password,),),) =
+			conf.get("db.password",)
+		else {
+			panic!("db.password is missing");
+		};
+
+		assert_eq!(password.expose_secret(), Some("hunter2"));
+		assert_eq!(format!("{password:?}"), "Secret(***redacted***)");
+		assert!(!format!("{conf:?}").contains("hunter2"));
+	}
+
+	#[test]
+	fn errors_on_secret_keys_do_not_echo_the_value() {
+		let err = conf::parse_str(
+			"token = hunter2, 1, 2\n",
+			schema::parse_str("token -> Secret, Integer\n",).unwrap(),
+		)
+		.unwrap_err();
+		assert!(err.is_arity_mismatch(), "{err:?}");
+		assert!(!err.to_string().contains("hunter2"), "{err}");
+		assert!(!format!("{err:#}").contains("hunter2"), "{err:#}");
+	}
+
+	#[test]
+	fn comparison_checks_every_byte() {
+		assert_eq!(Secret::new("hunter2"), Secret::new("hunter2"));
+		assert_ne!(Secret::new("hunter2"), Secret::new("hunter3"));
+		assert_ne!(Secret::new("hunter2"), Secret::new("hunter"));
+	}
+}
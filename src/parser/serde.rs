@@ -0,0 +1,1171 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::core::SegmentCharset;
+use crate::parser::core::TreeValue;
+use crate::parser::core::parse_key;
+use crate::parser::deferred::placeholder;
+use crate::parser::duration::render_duration;
+use crate::parser::secret::REDACTED;
+use ::serde::Deserialize;
+use ::serde::Deserializer;
+use ::serde::Serialize;
+use ::serde::Serializer;
+use ::serde::de;
+use ::serde::de::MapAccess;
+use ::serde::de::SeqAccess;
+use ::serde::de::Visitor;
+use ::serde::de::value::BorrowedStrDeserializer;
+use ::serde::forward_to_deserialize_any;
+use ::serde::ser;
+use ::serde::ser::Impossible;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+/// secrets are written redacted and placeholders as their text, the same as
+/// [`ConfMap::to_json_value`]
+impl Serialize for SingleValue {
+	fn serialize<S: Serializer,>(
+		&self,
+		serializer: S,
+	) -> Result<S::Ok, S::Error,> {
+		match self {
+			SingleValue::String(s,) => serializer.serialize_str(s,),
+			SingleValue::Bool(flag,) => serializer.serialize_bool(*flag,),
+			SingleValue::Integer(num,) => serializer.serialize_i32(*num,),
+			SingleValue::UInteger(num,) => serializer.serialize_u64(*num,),
+			SingleValue::Long(num,) => serializer.serialize_i64(*num,),
+			SingleValue::Float(num,) => serializer.serialize_f64(*num,),
+			SingleValue::Secret(_,) => serializer.serialize_str(REDACTED,),
+			SingleValue::Duration(duration,) => {
+				serializer.serialize_str(&render_duration(*duration,),)
+			},
+			SingleValue::IpAddr(addr,) => serializer.collect_str(addr,),
+			SingleValue::SocketAddr(addr,) => serializer.collect_str(addr,),
+			SingleValue::Path(path,) => {
+				serializer.serialize_str(&path.to_string_lossy(),)
+			},
+			SingleValue::Deferred { resolver, arg, } => {
+				serializer.serialize_str(&placeholder(resolver, arg,),)
+			},
+		}
+	}
+}
+
+impl Serialize for Value<SingleValue,> {
+	fn serialize<S: Serializer,>(
+		&self,
+		serializer: S,
+	) -> Result<S::Ok, S::Error,> {
+		match self {
+			Value::Single(single,) => single.serialize(serializer,),
+			Value::Collection(items,) => serializer.collect_seq(items,),
+		}
+	}
+}
+
+impl Serialize for ConfValue {
+	fn serialize<S: Serializer,>(
+		&self,
+		serializer: S,
+	) -> Result<S::Ok, S::Error,> {
+		match self {
+			TreeValue::Scalar(scalar,) => scalar.serialize(serializer,),
+			TreeValue::Map(children,) => serializer.collect_map(children,),
+		}
+	}
+}
+
+impl Serialize for ConfMap {
+	fn serialize<S: Serializer,>(
+		&self,
+		serializer: S,
+	) -> Result<S::Ok, S::Error,> {
+		serializer.collect_map(&**self,)
+	}
+}
+
+/// accepts strings, bools and numbers. integers beyond `i32` are rejected
+/// rather than turned into floats
+struct SingleVisitor;
+
+impl Visitor<'_,> for SingleVisitor {
+	type Value = SingleValue;
+
+	fn expecting(&self, f: &mut Formatter<'_,>,) -> std::fmt::Result {
+		write!(f, "a string, bool or number")
+	}
+
+	fn visit_bool<E: de::Error,>(self, v: bool,) -> Result<SingleValue, E,> {
+		Ok(SingleValue::Bool(v,),)
+	}
+
+	fn visit_i64<E: de::Error,>(self, v: i64,) -> Result<SingleValue, E,> {
+		i32::try_from(v,).map(SingleValue::Integer,).map_err(|_| {
+			E::invalid_value(de::Unexpected::Signed(v,), &"a 32-bit integer",)
+		},)
+	}
+
+	fn visit_u64<E: de::Error,>(self, v: u64,) -> Result<SingleValue, E,> {
+		i32::try_from(v,).map(SingleValue::Integer,).map_err(|_| {
+			E::invalid_value(de::Unexpected::Unsigned(v,), &"a 32-bit integer",)
+		},)
+	}
+
+	fn visit_f64<E: de::Error,>(self, v: f64,) -> Result<SingleValue, E,> {
+		SingleValue::try_from(v,).map_err(|_| {
+			E::invalid_value(de::Unexpected::Float(v,), &"a finite float",)
+		},)
+	}
+
+	fn visit_str<E: de::Error,>(self, v: &str,) -> Result<SingleValue, E,> {
+		Ok(SingleValue::String(v.to_string(),),)
+	}
+
+	fn visit_string<E: de::Error,>(
+		self, v: String,
+	) -> Result<SingleValue, E,> {
+		Ok(SingleValue::String(v,),)
+	}
+}
+
+impl<'de,> Deserialize<'de,> for SingleValue {
+	fn deserialize<D: Deserializer<'de,>,>(
+		deserializer: D,
+	) -> Result<Self, D::Error,> {
+		deserializer.deserialize_any(SingleVisitor,)
+	}
+}
+
+/// reads a value or a section. arrays become collections, which must not mix
+/// element types, and objects become sections
+struct ConfValueVisitor;
+
+impl<'de,> Visitor<'de,> for ConfValueVisitor {
+	type Value = ConfValue;
+
+	fn expecting(&self, f: &mut Formatter<'_,>,) -> std::fmt::Result {
+		write!(f, "a string, bool, number, array or object")
+	}
+
+	fn visit_bool<E: de::Error,>(self, v: bool,) -> Result<ConfValue, E,> {
+		SingleVisitor.visit_bool(v,).map(scalar,)
+	}
+
+	fn visit_i64<E: de::Error,>(self, v: i64,) -> Result<ConfValue, E,> {
+		SingleVisitor.visit_i64(v,).map(scalar,)
+	}
+
+	fn visit_u64<E: de::Error,>(self, v: u64,) -> Result<ConfValue, E,> {
+		SingleVisitor.visit_u64(v,).map(scalar,)
+	}
+
+	fn visit_f64<E: de::Error,>(self, v: f64,) -> Result<ConfValue, E,> {
+		SingleVisitor.visit_f64(v,).map(scalar,)
+	}
+
+	fn visit_str<E: de::Error,>(self, v: &str,) -> Result<ConfValue, E,> {
+		SingleVisitor.visit_str(v,).map(scalar,)
+	}
+
+	fn visit_string<E: de::Error,>(self, v: String,) -> Result<ConfValue, E,> {
+		SingleVisitor.visit_string(v,).map(scalar,)
+	}
+
+	fn visit_seq<A: SeqAccess<'de,>,>(
+		self,
+		mut seq: A,
+	) -> Result<ConfValue, A::Error,> {
+		let mut items: Vec<SingleValue,> = Vec::new();
+		while let Some(item,) = seq.next_element()? {
+			if let Some(first,) = items.first()
+				&& kind_of(first,) != kind_of(&item,)
+			{
+				return Err(de::Error::custom(format!(
+					"array mixes {} and {} elements",
+					kind_of(first,),
+					kind_of(&item,)
+				),),);
+			}
+			items.push(item,);
+		}
+		Ok(TreeValue::Scalar(Value::Collection(items,),),)
+	}
+
+	fn visit_map<A: MapAccess<'de,>,>(
+		self,
+		mut map: A,
+	) -> Result<ConfValue, A::Error,> {
+		let mut children = BTreeMap::new();
+		while let Some((key, value,),) = map.next_entry()? {
+			children.insert(key, value,);
+		}
+		Ok(TreeValue::Map(children,),)
+	}
+}
+
+fn scalar(single: SingleValue,) -> ConfValue {
+	TreeValue::Scalar(Value::Single(single,),)
+}
+
+fn kind_of(single: &SingleValue,) -> SingleValueDiscriminants {
+	SingleValueDiscriminants::from(single,)
+}
+
+impl<'de,> Deserialize<'de,> for ConfValue {
+	fn deserialize<D: Deserializer<'de,>,>(
+		deserializer: D,
+	) -> Result<Self, D::Error,> {
+		deserializer.deserialize_any(ConfValueVisitor,)
+	}
+}
+
+impl<'de,> Deserialize<'de,> for Value<SingleValue,> {
+	fn deserialize<D: Deserializer<'de,>,>(
+		deserializer: D,
+	) -> Result<Self, D::Error,> {
+		match ConfValue::deserialize(deserializer,)? {
+			TreeValue::Scalar(value,) => Ok(value,),
+			TreeValue::Map(_,) => Err(de::Error::invalid_type(
+				de::Unexpected::Map,
+				&"a string, bool, number or array",
+			),),
+		}
+	}
+}
+
+/// labels of labeled tuples and sources are not serialized, so a
+/// deserialized map has none
+impl<'de,> Deserialize<'de,> for ConfMap {
+	fn deserialize<D: Deserializer<'de,>,>(
+		deserializer: D,
+	) -> Result<Self, D::Error,> {
+		let entries =
+			BTreeMap::<String, ConfValue,>::deserialize(deserializer,)?;
+		Ok(ConfMap::from(&entries,),)
+	}
+}
+
+/// fills any `T: Deserialize` from `map`, field names matching key
+/// segments. sections are structs or maps, collections sequences and every
+/// other value the primitive it holds. durations, addresses, paths and
+/// placeholders are read as their text, and secrets as the text they hide.
+/// fields missing from `map` fail unless they are `Option`s or have a
+/// default, and entries without a field are ignored unless `T` denies
+/// unknown fields
+pub fn from_map<'a, T: Deserialize<'a,>,>(
+	map: &'a ConfMap,
+) -> Result<T, DeError,> {
+	T::deserialize(SectionDeserializer(map,),)
+}
+
+/// error of [`from_map`]. names the dotted key it is about, if any
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub struct DeError {
+	key:     String,
+	message: String,
+	missing: bool,
+}
+
+impl DeError {
+	/// dotted key of the field or value the error is about. empty for the
+	/// map as a whole
+	pub fn key(&self,) -> &str {
+		&self.key
+	}
+
+	fn under(mut self, segment: impl Display,) -> Self {
+		self.key = if self.key.is_empty() {
+			segment.to_string()
+		} else {
+			format!("{segment}.{}", self.key)
+		};
+		self
+	}
+}
+
+impl Display for DeError {
+	fn fmt(&self, f: &mut Formatter<'_,>,) -> std::fmt::Result {
+		match (self.missing, self.key.is_empty(),) {
+			(true, _,) => write!(f, "missing field `{}`", self.key),
+			(false, true,) => write!(f, "{}", self.message),
+			(false, false,) => write!(f, "{} at `{}`", self.message, self.key),
+		}
+	}
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+	fn custom<T: Display,>(msg: T,) -> Self {
+		Self {
+			key:     String::new(),
+			message: msg.to_string(),
+			missing: false,
+		}
+	}
+
+	fn missing_field(field: &'static str,) -> Self {
+		Self {
+			key:     field.to_string(),
+			message: String::new(),
+			missing: true,
+		}
+	}
+}
+
+/// a section, or the root of a [`ConfMap`], as a map
+struct SectionDeserializer<'a,>(&'a BTreeMap<String, ConfValue,>,);
+
+impl<'de,> Deserializer<'de,> for SectionDeserializer<'de,> {
+	type Error = DeError;
+
+	fn deserialize_any<V: Visitor<'de,>,>(
+		self,
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		visitor
+			.visit_map(SectionAccess { entries: self.0.iter(), value: None, },)
+	}
+
+	fn deserialize_option<V: Visitor<'de,>,>(
+		self,
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		visitor.visit_some(self,)
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf unit unit_struct newtype_struct seq tuple tuple_struct
+		map struct enum identifier ignored_any
+	}
+}
+
+struct SectionAccess<'a,> {
+	entries: std::collections::btree_map::Iter<'a, String, ConfValue,>,
+	value:   Option<(&'a str, &'a ConfValue,),>,
+}
+
+impl<'de,> MapAccess<'de,> for SectionAccess<'de,> {
+	type Error = DeError;
+
+	fn next_key_seed<K: de::DeserializeSeed<'de,>,>(
+		&mut self,
+		seed: K,
+	) -> Result<Option<K::Value,>, DeError,> {
+		let Some((key, value,),) = self.entries.next() else {
+			return Ok(None,);
+		};
+		self.value = Some((key, value,),);
+		seed.deserialize(BorrowedStrDeserializer::new(key,),).map(Some,)
+	}
+
+	fn next_value_seed<V: de::DeserializeSeed<'de,>,>(
+		&mut self,
+		seed: V,
+	) -> Result<V::Value, DeError,> {
+		let (key, value,) = self.value.take().ok_or_else(|| {
+			de::Error::custom("value asked for before its key",)
+		},)?;
+		seed.deserialize(ValueDeserializer(value,),)
+			.map_err(|err| err.under(key,),)
+	}
+}
+
+/// a section, a collection or a single value
+struct ValueDeserializer<'a,>(&'a ConfValue,);
+
+impl<'de,> Deserializer<'de,> for ValueDeserializer<'de,> {
+	type Error = DeError;
+
+	fn deserialize_any<V: Visitor<'de,>,>(
+		self,
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		match self.0 {
+			TreeValue::Map(children,) => {
+				SectionDeserializer(children,).deserialize_any(visitor,)
+			},
+			TreeValue::Scalar(Value::Single(single,),) => {
+				SingleDeserializer(single,).deserialize_any(visitor,)
+			},
+			TreeValue::Scalar(Value::Collection(items,),) => {
+				visitor.visit_seq(CollectionAccess {
+					items: items.iter().enumerate(),
+				},)
+			},
+		}
+	}
+
+	fn deserialize_option<V: Visitor<'de,>,>(
+		self,
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		visitor.visit_some(self,)
+	}
+
+	fn deserialize_enum<V: Visitor<'de,>,>(
+		self,
+		name: &'static str,
+		variants: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		match self.0 {
+			TreeValue::Scalar(Value::Single(single,),) => {
+				SingleDeserializer(single,)
+					.deserialize_enum(name, variants, visitor,)
+			},
+			_ => self.deserialize_any(visitor,),
+		}
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf unit unit_struct newtype_struct seq tuple tuple_struct
+		map struct identifier ignored_any
+	}
+}
+
+struct CollectionAccess<'a,> {
+	items: std::iter::Enumerate<std::slice::Iter<'a, SingleValue,>,>,
+}
+
+impl<'de,> SeqAccess<'de,> for CollectionAccess<'de,> {
+	type Error = DeError;
+
+	fn next_element_seed<T: de::DeserializeSeed<'de,>,>(
+		&mut self,
+		seed: T,
+	) -> Result<Option<T::Value,>, DeError,> {
+		let Some((idx, item,),) = self.items.next() else {
+			return Ok(None,);
+		};
+		seed.deserialize(SingleDeserializer(item,),)
+			.map(Some,)
+			.map_err(|err| err.under(idx,),)
+	}
+
+	fn size_hint(&self,) -> Option<usize,> {
+		Some(self.items.len(),)
+	}
+}
+
+struct SingleDeserializer<'a,>(&'a SingleValue,);
+
+impl<'de,> Deserializer<'de,> for SingleDeserializer<'de,> {
+	type Error = DeError;
+
+	fn deserialize_any<V: Visitor<'de,>,>(
+		self,
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		match self.0 {
+			SingleValue::String(s,) => visitor.visit_borrowed_str(s,),
+			SingleValue::Bool(flag,) => visitor.visit_bool(*flag,),
+			SingleValue::Integer(num,) => visitor.visit_i32(*num,),
+			SingleValue::UInteger(num,) => visitor.visit_u64(*num,),
+			SingleValue::Long(num,) => visitor.visit_i64(*num,),
+			SingleValue::Float(num,) => visitor.visit_f64(*num,),
+			SingleValue::Secret(secret,) => {
+				visitor.visit_borrowed_str(secret.expose_secret(),)
+			},
+			SingleValue::Duration(duration,) => {
+				visitor.visit_string(render_duration(*duration,),)
+			},
+			SingleValue::IpAddr(addr,) => {
+				visitor.visit_string(addr.to_string(),)
+			},
+			SingleValue::SocketAddr(addr,) => {
+				visitor.visit_string(addr.to_string(),)
+			},
+			SingleValue::Path(path,) => match path.to_str() {
+				Some(path,) => visitor.visit_borrowed_str(path,),
+				None => {
+					visitor.visit_string(path.to_string_lossy().into_owned(),)
+				},
+			},
+			SingleValue::Deferred { resolver, arg, } => {
+				visitor.visit_string(placeholder(resolver, arg,),)
+			},
+		}
+	}
+
+	fn deserialize_option<V: Visitor<'de,>,>(
+		self,
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		visitor.visit_some(self,)
+	}
+
+	/// unit variants, from the string naming them
+	fn deserialize_enum<V: Visitor<'de,>,>(
+		self,
+		_name: &'static str,
+		_variants: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value, DeError,> {
+		match self.0 {
+			SingleValue::String(s,) => {
+				visitor.visit_enum(BorrowedStrDeserializer::new(s,),)
+			},
+			_ => self.deserialize_any(visitor,),
+		}
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf unit unit_struct newtype_struct seq tuple tuple_struct
+		map struct identifier ignored_any
+	}
+}
+
+/// builds a conf map from `value`, a struct or a map with string keys.
+/// nested structs and maps become sections, sequences of plain values
+/// collections and unit variants the string naming them. `None` and unit
+/// fields are left out. anything else, like a nested sequence or an enum
+/// variant with a payload, fails with [`ParseError::Unserializable`] naming
+/// its dotted key
+pub fn to_map<T: Serialize + ?Sized,>(value: &T,) -> PRslt<ConfMap,> {
+	match value.serialize(ValueSerializer,)? {
+		Some(TreeValue::Map(entries,),) => Ok(ConfMap::from(&entries,),),
+		_ => Err(ser::Error::custom(
+			"only a struct or a map becomes a conf map",
+		),),
+	}
+}
+
+impl ser::Error for ParseError {
+	fn custom<T: Display,>(msg: T,) -> Self {
+		ParseError::Unserializable {
+			key:    String::new(),
+			reason: msg.to_string(),
+		}
+	}
+}
+
+/// `err` about the value at `segment` of the section or collection at hand
+fn under(err: ParseError, segment: impl Display,) -> ParseError {
+	match err {
+		ParseError::Unserializable { key, reason, } => {
+			let key = if key.is_empty() {
+				segment.to_string()
+			} else {
+				format!("{segment}.{key}")
+			};
+			ParseError::Unserializable { key, reason, }
+		},
+		other => other,
+	}
+}
+
+fn unsupported<T,>(what: &str,) -> PRslt<T,> {
+	Err(ser::Error::custom(format!("{what} are not supported"),),)
+}
+
+/// a value, or `None` for one which is left out of its section
+struct ValueSerializer;
+
+impl Serializer for ValueSerializer {
+	type Error = ParseError;
+	type Ok = Option<ConfValue,>;
+	type SerializeMap = SectionSerializer;
+	type SerializeSeq = CollectionSerializer;
+	type SerializeStruct = SectionSerializer;
+	type SerializeStructVariant = Impossible<Self::Ok, ParseError,>;
+	type SerializeTuple = CollectionSerializer;
+	type SerializeTupleStruct = CollectionSerializer;
+	type SerializeTupleVariant = Impossible<Self::Ok, ParseError,>;
+
+	fn serialize_bool(self, v: bool,) -> PRslt<Self::Ok,> {
+		Ok(Some(scalar(SingleValue::Bool(v,),),),)
+	}
+
+	fn serialize_i8(self, v: i8,) -> PRslt<Self::Ok,> {
+		self.serialize_i32(v.into(),)
+	}
+
+	fn serialize_i16(self, v: i16,) -> PRslt<Self::Ok,> {
+		self.serialize_i32(v.into(),)
+	}
+
+	fn serialize_i32(self, v: i32,) -> PRslt<Self::Ok,> {
+		Ok(Some(scalar(SingleValue::Integer(v,),),),)
+	}
+
+	/// an `Integer` when it fits one
+	fn serialize_i64(self, v: i64,) -> PRslt<Self::Ok,> {
+		let single = i32::try_from(v,)
+			.map_or(SingleValue::Long(v,), SingleValue::Integer,);
+		Ok(Some(scalar(single,),),)
+	}
+
+	fn serialize_u8(self, v: u8,) -> PRslt<Self::Ok,> {
+		self.serialize_i32(v.into(),)
+	}
+
+	fn serialize_u16(self, v: u16,) -> PRslt<Self::Ok,> {
+		self.serialize_i32(v.into(),)
+	}
+
+	fn serialize_u32(self, v: u32,) -> PRslt<Self::Ok,> {
+		self.serialize_u64(v.into(),)
+	}
+
+	/// an `Integer` when it fits one
+	fn serialize_u64(self, v: u64,) -> PRslt<Self::Ok,> {
+		let single = i32::try_from(v,)
+			.map_or(SingleValue::UInteger(v,), SingleValue::Integer,);
+		Ok(Some(scalar(single,),),)
+	}
+
+	fn serialize_f32(self, v: f32,) -> PRslt<Self::Ok,> {
+		self.serialize_f64(v.into(),)
+	}
+
+	fn serialize_f64(self, v: f64,) -> PRslt<Self::Ok,> {
+		if !v.is_finite() {
+			return unsupported("non-finite floats",);
+		}
+		Ok(Some(scalar(SingleValue::Float(v,),),),)
+	}
+
+	fn serialize_char(self, v: char,) -> PRslt<Self::Ok,> {
+		self.serialize_str(v.encode_utf8(&mut [0; 4],),)
+	}
+
+	fn serialize_str(self, v: &str,) -> PRslt<Self::Ok,> {
+		Ok(Some(scalar(SingleValue::String(v.to_string(),),),),)
+	}
+
+	fn serialize_bytes(self, _: &[u8],) -> PRslt<Self::Ok,> {
+		unsupported("byte strings",)
+	}
+
+	fn serialize_none(self,) -> PRslt<Self::Ok,> {
+		Ok(None,)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized,>(
+		self,
+		value: &T,
+	) -> PRslt<Self::Ok,> {
+		value.serialize(self,)
+	}
+
+	fn serialize_unit(self,) -> PRslt<Self::Ok,> {
+		Ok(None,)
+	}
+
+	fn serialize_unit_struct(self, _: &'static str,) -> PRslt<Self::Ok,> {
+		Ok(None,)
+	}
+
+	fn serialize_unit_variant(
+		self,
+		_: &'static str,
+		_: u32,
+		variant: &'static str,
+	) -> PRslt<Self::Ok,> {
+		self.serialize_str(variant,)
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized,>(
+		self,
+		_: &'static str,
+		value: &T,
+	) -> PRslt<Self::Ok,> {
+		value.serialize(self,)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized,>(
+		self,
+		_: &'static str,
+		_: u32,
+		_: &'static str,
+		_: &T,
+	) -> PRslt<Self::Ok,> {
+		unsupported("enum variants with payloads",)
+	}
+
+	fn serialize_seq(self, len: Option<usize,>,) -> PRslt<Self::SerializeSeq,> {
+		Ok(CollectionSerializer(Vec::with_capacity(len.unwrap_or_default(),),),)
+	}
+
+	fn serialize_tuple(self, len: usize,) -> PRslt<Self::SerializeTuple,> {
+		self.serialize_seq(Some(len,),)
+	}
+
+	fn serialize_tuple_struct(
+		self,
+		_: &'static str,
+		len: usize,
+	) -> PRslt<Self::SerializeTupleStruct,> {
+		self.serialize_seq(Some(len,),)
+	}
+
+	fn serialize_tuple_variant(
+		self,
+		_: &'static str,
+		_: u32,
+		_: &'static str,
+		_: usize,
+	) -> PRslt<Self::SerializeTupleVariant,> {
+		unsupported("enum variants with payloads",)
+	}
+
+	fn serialize_map(self, _: Option<usize,>,) -> PRslt<Self::SerializeMap,> {
+		Ok(SectionSerializer::default(),)
+	}
+
+	fn serialize_struct(
+		self,
+		_: &'static str,
+		_: usize,
+	) -> PRslt<Self::SerializeStruct,> {
+		Ok(SectionSerializer::default(),)
+	}
+
+	fn serialize_struct_variant(
+		self,
+		_: &'static str,
+		_: u32,
+		_: &'static str,
+		_: usize,
+	) -> PRslt<Self::SerializeStructVariant,> {
+		unsupported("enum variants with payloads",)
+	}
+}
+
+/// elements of a collection, each a plain value
+struct CollectionSerializer(Vec<SingleValue,>,);
+
+impl CollectionSerializer {
+	fn push<T: Serialize + ?Sized,>(&mut self, value: &T,) -> PRslt<(),> {
+		let idx = self.0.len();
+		match value.serialize(ValueSerializer,) {
+			Ok(Some(TreeValue::Scalar(Value::Single(single,),),),) => {
+				self.0.push(single,);
+				Ok((),)
+			},
+			Ok(Some(TreeValue::Scalar(Value::Collection(_,),),),) => {
+				unsupported("nested sequences",)
+			},
+			Ok(Some(TreeValue::Map(_,),),) => {
+				unsupported("sections inside sequences",)
+			},
+			Ok(None,) => unsupported("empty elements of sequences",),
+			Err(err,) => Err(err,),
+		}
+		.map_err(|err| under(err, idx,),)
+	}
+
+	fn finish(self,) -> PRslt<Option<ConfValue,>,> {
+		Ok(Some(TreeValue::Scalar(Value::Collection(self.0,),),),)
+	}
+}
+
+impl ser::SerializeSeq for CollectionSerializer {
+	type Error = ParseError;
+	type Ok = Option<ConfValue,>;
+
+	fn serialize_element<T: Serialize + ?Sized,>(
+		&mut self,
+		value: &T,
+	) -> PRslt<(),> {
+		self.push(value,)
+	}
+
+	fn end(self,) -> PRslt<Self::Ok,> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeTuple for CollectionSerializer {
+	type Error = ParseError;
+	type Ok = Option<ConfValue,>;
+
+	fn serialize_element<T: Serialize + ?Sized,>(
+		&mut self,
+		value: &T,
+	) -> PRslt<(),> {
+		self.push(value,)
+	}
+
+	fn end(self,) -> PRslt<Self::Ok,> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeTupleStruct for CollectionSerializer {
+	type Error = ParseError;
+	type Ok = Option<ConfValue,>;
+
+	fn serialize_field<T: Serialize + ?Sized,>(
+		&mut self,
+		value: &T,
+	) -> PRslt<(),> {
+		self.push(value,)
+	}
+
+	fn end(self,) -> PRslt<Self::Ok,> {
+		self.finish()
+	}
+}
+
+/// entries of a section. keys are single key segments
+#[derive(Default,)]
+struct SectionSerializer {
+	entries: BTreeMap<String, ConfValue,>,
+	key:     Option<String,>,
+}
+
+impl SectionSerializer {
+	fn insert<T: Serialize + ?Sized,>(
+		&mut self,
+		key: &str,
+		value: &T,
+	) -> PRslt<(),> {
+		if let Some(value,) =
+			value.serialize(ValueSerializer,).map_err(|err| under(err, key,),)?
+		{
+			self.entries.insert(key.to_string(), value,);
+		}
+		Ok((),)
+	}
+
+	fn finish(self,) -> PRslt<Option<ConfValue,>,> {
+		Ok(Some(TreeValue::Map(self.entries,),),)
+	}
+}
+
+impl ser::SerializeMap for SectionSerializer {
+	type Error = ParseError;
+	type Ok = Option<ConfValue,>;
+
+	fn serialize_key<T: Serialize + ?Sized,>(
+		&mut self, key: &T,
+	) -> PRslt<(),> {
+		let key = match key.serialize(ValueSerializer,)? {
+			Some(TreeValue::Scalar(Value::Single(SingleValue::String(
+				key,
+			),),),) => key,
+			_ => return unsupported("map keys other than strings",),
+		};
+		if !parse_key(&key, 0, 0, SegmentCharset::Permissive,)
+			.is_ok_and(|segments| segments.len() == 1,)
+		{
+			return Err(under(
+				ser::Error::custom("not a single key segment",),
+				key,
+			),);
+		}
+		self.key = Some(key,);
+		Ok((),)
+	}
+
+	fn serialize_value<T: Serialize + ?Sized,>(
+		&mut self,
+		value: &T,
+	) -> PRslt<(),> {
+		let key = self.key.take().ok_or_else(|| {
+			<ParseError as ser::Error>::custom("value given before its key",)
+		},)?;
+		self.insert(&key, value,)
+	}
+
+	fn end(self,) -> PRslt<Self::Ok,> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeStruct for SectionSerializer {
+	type Error = ParseError;
+	type Ok = Option<ConfValue,>;
+
+	fn serialize_field<T: Serialize + ?Sized,>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> PRslt<(),> {
+		self.insert(key, value,)
+	}
+
+	fn end(self,) -> PRslt<Self::Ok,> {
+		self.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sample_conf_map() -> ConfMap {
+		crate::conf_map! {
+			"endpoint" => "localhost:3000",
+			"debug" => true,
+			"ratio" => 0.5,
+			"log.file" => "/var/log/console.log",
+			"log.name" => "default.log",
+			"net.ipv4.ip_local_reserved_ports" => [8080, 9148],
+		}
+	}
+
+	#[test]
+	fn conf_map_serializes_as_nested_objects() {
+		assert_eq!(
+			serde_json::to_string(&sample_conf_map()).unwrap(),
+			r#"{"debug":true,"endpoint":"localhost:3000","log":{"file":"/var/log/console.log","name":"default.log"},"net":{"ipv4":{"ip_local_reserved_ports":[8080,9148]}},"ratio":0.5}"#
+		);
+	}
+
+	#[test]
+	fn serde_json_round_trip_rebuilds_the_tree() {
+		let conf = sample_conf_map();
+		let json = serde_json::to_string(&conf,).unwrap();
+		let restored: ConfMap = serde_json::from_str(&json,).unwrap();
+		assert_eq!(*restored, *conf);
+
+		let value = conf.get("net.ipv4.ip_local_reserved_ports",).unwrap();
+		let restored: ConfValue =
+			serde_json::from_str(&serde_json::to_string(value,).unwrap(),)
+				.unwrap();
+		assert_eq!(&restored, value);
+	}
+
+	#[derive(Debug, PartialEq, Deserialize,)]
+	struct Log {
+		file: String,
+		name: String,
+	}
+
+	#[derive(Debug, PartialEq, Deserialize,)]
+	struct Sample {
+		endpoint: String,
+		debug:    bool,
+		log:      Log,
+		ratio:    Option<f64,>,
+		level:    Option<String,>,
+	}
+
+	#[test]
+	fn from_map_fills_nested_structs() {
+		let conf = sample_conf_map();
+		let sample: Sample = from_map(&conf,).unwrap();
+		assert_eq!(
+			sample,
+			Sample {
+				endpoint: "localhost:3000".to_string(),
+				debug:    true,
+				log:      Log {
+					file: "/var/log/console.log".to_string(),
+					name: "default.log".to_string(),
+				},
+				ratio:    Some(0.5),
+				level:    None,
+			}
+		);
+
+		#[derive(Deserialize,)]
+		struct Net {
+			ipv4: BTreeMap<String, Vec<u16,>,>,
+		}
+		#[derive(Deserialize,)]
+		struct Ports {
+			net: Net,
+		}
+		let ports: Ports = from_map(&conf,).unwrap();
+		assert_eq!(ports.net.ipv4["ip_local_reserved_ports"], [8080, 9148]);
+	}
+
+	#[test]
+	fn from_map_reads_a_server_section_and_a_flag() {
+		#[derive(Debug, PartialEq, Deserialize,)]
+		struct Server {
+			host: String,
+			port: i32,
+		}
+		#[derive(Debug, PartialEq, Deserialize,)]
+		struct Config {
+			server: Server,
+			debug:  bool,
+		}
+
+		let conf = crate::conf_map! {
+			"server.host" => "localhost",
+			"server.port" => 8080,
+			"debug" => false,
+		};
+		assert_eq!(
+			from_map::<Config,>(&conf,),
+			Ok(Config {
+				server: Server { host: "localhost".to_string(), port: 8080, },
+				debug:  false,
+			})
+		);
+	}
+
+	#[test]
+	fn from_map_names_the_dotted_key_of_a_failure() {
+		#[derive(Debug, Deserialize,)]
+		#[allow(dead_code)]
+		struct Server {
+			host: String,
+			port: i32,
+		}
+		#[derive(Debug, Deserialize,)]
+		#[allow(dead_code)]
+		struct Missing {
+			log: Server,
+		}
+		let err = from_map::<Missing,>(&sample_conf_map(),).unwrap_err();
+		assert_eq!(err.to_string(), "missing field `log.host`");
+		assert_eq!(err.key(), "log.host");
+
+		#[derive(Debug, Deserialize,)]
+		#[allow(dead_code)]
+		struct Mistyped {
+			log: BTreeMap<String, i32,>,
+		}
+		let err = from_map::<Mistyped,>(&sample_conf_map(),).unwrap_err();
+		assert_eq!(err.key(), "log.file");
+		assert!(err.to_string().starts_with("invalid type: string"), "{err}");
+	}
+
+	#[test]
+	fn from_map_denies_unknown_fields_when_asked() {
+		#[derive(Debug, Deserialize,)]
+		#[serde(deny_unknown_fields)]
+		#[allow(dead_code)]
+		struct Strict {
+			file: String,
+		}
+		#[derive(Debug, Deserialize,)]
+		#[allow(dead_code)]
+		struct Root {
+			log: Strict,
+		}
+		let err = from_map::<Root,>(&sample_conf_map(),).unwrap_err();
+		assert!(err.to_string().starts_with("unknown field `name`"), "{err}");
+		assert_eq!(err.key(), "log");
+	}
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize,)]
+	enum Mode {
+		Fast,
+		Safe,
+	}
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize,)]
+	struct Limits {
+		ports:  Vec<i32,>,
+		burst:  u64,
+		ratio:  f64,
+		note:   Option<String,>,
+		labels: BTreeMap<String, String,>,
+	}
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize,)]
+	struct Service {
+		name:   String,
+		debug:  bool,
+		mode:   Mode,
+		limits: Limits,
+	}
+
+	fn service() -> Service {
+		Service {
+			name:   "api".to_string(),
+			debug:  true,
+			mode:   Mode::Safe,
+			limits: Limits {
+				ports:  vec![80, 443],
+				burst:  u64::MAX,
+				ratio:  0.25,
+				note:   None,
+				labels: BTreeMap::from([(
+					"tier".to_string(),
+					"web".to_string(),
+				),],),
+			},
+		}
+	}
+
+	#[test]
+	fn to_map_round_trips_a_struct() {
+		let conf = to_map(&service(),).unwrap();
+		assert_eq!(conf.get_str("name"), Some("api"));
+		assert_eq!(conf.get_str("mode"), Some("Safe"));
+		assert_eq!(conf.get_ints("limits.ports"), Some(vec![80, 443]));
+		assert_eq!(conf.get_uint("limits.burst"), Some(u64::MAX));
+		assert_eq!(conf.get_str("limits.labels.tier"), Some("web"));
+		assert!(conf.get("limits.note").is_none());
+
+		assert_eq!(from_map::<Service,>(&conf,), Ok(service()));
+	}
+
+	#[test]
+	fn to_map_rejects_shapes_a_conf_map_cannot_hold() {
+		let unserializable = |key: &str, reason: &str| {
+			Err(ParseError::Unserializable {
+				key:    key.to_string(),
+				reason: reason.to_string(),
+			},)
+		};
+
+		assert_eq!(
+			to_map(&BTreeMap::from([("grid", vec![vec![1]])],),),
+			unserializable("grid.0", "nested sequences are not supported",)
+		);
+		assert_eq!(
+			to_map(&BTreeMap::from([(1, true,)],),),
+			unserializable("", "map keys other than strings are not supported",)
+		);
+		assert_eq!(
+			to_map(&BTreeMap::from([("a.b", true,)],),),
+			unserializable("a.b", "not a single key segment",)
+		);
+		assert_eq!(
+			to_map(&7,),
+			unserializable("", "only a struct or a map becomes a conf map",)
+		);
+
+		#[derive(Serialize,)]
+		enum Shape {
+			Circle(f64,),
+		}
+		#[derive(Serialize,)]
+		struct Drawing {
+			shape: Shape,
+		}
+		assert_eq!(
+			to_map(&Drawing { shape: Shape::Circle(1.0,), },),
+			unserializable(
+				"shape",
+				"enum variants with payloads are not supported",
+			)
+		);
+	}
+
+	#[test]
+	fn mixed_arrays_and_wide_integers_are_rejected() {
+		let err = serde_json::from_str::<ConfMap,>(r#"{"pair":[1,true]}"#,)
+			.unwrap_err();
+		assert!(
+			err.to_string()
+				.starts_with("array mixes Integer and Bool elements")
+		);
+
+		assert!(
+			serde_json::from_str::<ConfMap,>(r#"{"big":4294967296}"#).is_err()
+		);
+		assert!(
+			serde_json::from_str::<Value<SingleValue,>,>(r#"{"a":1}"#).is_err()
+		);
+	}
+}
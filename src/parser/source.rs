@@ -0,0 +1,183 @@
+use crate::parser::conf::ConfMap;
+use crate::parser::core::MirObserver;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// where a value came from
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub enum Origin {
+	File(PathBuf,),
+	/// environment variable of this name
+	Env(String,),
+	/// set by the program itself
+	Override,
+	SchemaDefault,
+}
+
+impl Display for Origin {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			Origin::File(path,) => write!(f, "{}", path.display()),
+			Origin::Env(var,) => write!(f, "${var}"),
+			Origin::Override => write!(f, "override"),
+			Origin::SchemaDefault => write!(f, "schema default"),
+		}
+	}
+}
+
+/// one assignment of a key
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub struct Source {
+	pub origin: Origin,
+	/// 1-based, for origins which have lines
+	pub line:   Option<usize,>,
+}
+
+/// `path:line`, or the origin alone
+impl Display for Source {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match self.line {
+			Some(line,) => write!(f, "{}:{line}", self.origin),
+			None => write!(f, "{}", self.origin),
+		}
+	}
+}
+
+/// every assignment of every leaf, by dotted key, in the order they were
+/// applied. the last one is the effective source of the value
+#[derive(Debug, Clone, Default, PartialEq, Eq,)]
+pub struct SourceMap(BTreeMap<String, Vec<Source,>,>,);
+
+impl SourceMap {
+	pub fn new() -> Self {
+		Self(BTreeMap::new(),)
+	}
+
+	/// appends an assignment of `key`, which becomes its effective source
+	pub fn record(&mut self, key: &str, origin: Origin, line: Option<usize,>,) {
+		self.0
+			.entry(key.to_string(),)
+			.or_default()
+			.push(Source { origin, line, },);
+	}
+
+	pub fn source_of(&self, key: &str,) -> Option<&Source,> {
+		self.history_of(key,).last()
+	}
+
+	/// oldest first. empty for keys nothing was recorded for
+	pub fn history_of(&self, key: &str,) -> &[Source] {
+		self.0.get(key,).map_or(&[], Vec::as_slice,)
+	}
+
+	/// assignments of the keys starting with `prefix`, which is cut off them
+	pub(crate) fn strip_prefix(&self, prefix: &str,) -> SourceMap {
+		Self(
+			self.0
+				.iter()
+				.filter_map(|(key, history,)| {
+					Some((
+						key.strip_prefix(prefix,)?.to_string(),
+						history.clone(),
+					),)
+				},)
+				.collect(),
+		)
+	}
+
+	/// forgets every key `keep` rejects
+	pub(crate) fn retain(&mut self, mut keep: impl FnMut(&str,) -> bool,) {
+		self.0.retain(|key, _| keep(key,),);
+	}
+}
+
+impl ConfMap {
+	/// effective source of the leaf at the dotted `key`
+	pub fn source_of(&self, key: &str,) -> Option<&Source,> {
+		self.sources().source_of(key,)
+	}
+
+	/// every assignment of the leaf at the dotted `key`, oldest first
+	pub fn history_of(&self, key: &str,) -> &[Source] {
+		self.sources().history_of(key,)
+	}
+}
+
+/// records each assignment read while building the mir of a file, naming
+/// the included file for assignments read from one
+pub(crate) struct FileSources<'a,> {
+	pub(crate) sources:  &'a mut SourceMap,
+	pub(crate) path:     PathBuf,
+	/// files being included, innermost last
+	pub(crate) included: Vec<PathBuf,>,
+}
+
+impl MirObserver for FileSources<'_,> {
+	fn entry_parsed(&mut self, segments: &[String], line: usize,) {
+		let path = self.included.last().unwrap_or(&self.path,);
+		self.sources.record(
+			&segments.join(".",),
+			Origin::File(path.clone(),),
+			Some(line,),
+		);
+	}
+
+	fn include_entered(&mut self, path: &Path,) {
+		self.included.push(path.to_path_buf(),);
+	}
+
+	fn include_left(&mut self,) {
+		self.included.pop();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+
+	#[test]
+	fn history_lists_every_assignment_with_the_effective_one_last() {
+		let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"),)
+			.join("tests/examples/explain",);
+		let conf_path = dir.join("app.conf",);
+		let mut conf =
+			conf::parse_file(&conf_path, &dir.join("app.schema",),).unwrap();
+		conf.sources_mut().record(
+			"server.port",
+			Origin::Env("PORT".to_string(),),
+			None,
+		);
+
+		let file = |line| Source {
+			origin: Origin::File(conf_path.clone(),),
+			line:   Some(line,),
+		};
+		assert_eq!(
+			conf.history_of("server.port"),
+			[
+				file(1,),
+				file(3,),
+				Source {
+					origin: Origin::Env("PORT".to_string()),
+					line:   None,
+				},
+			]
+		);
+		assert_eq!(conf.source_of("server.host"), Some(&file(2,)));
+		assert_eq!(conf.source_of("server"), None);
+	}
+
+	#[test]
+	fn sources_render_as_location() {
+		let mut sources = SourceMap::new();
+		sources.record("a", Origin::File("etc/app.conf".into(),), Some(4,),);
+		sources.record("a", Origin::SchemaDefault, None,);
+
+		let rendered: Vec<String,> =
+			sources.history_of("a",).iter().map(ToString::to_string,).collect();
+		assert_eq!(rendered, vec!["etc/app.conf:4", "schema default"]);
+	}
+}
@@ -0,0 +1,146 @@
+use crate::parser::conf::ConfValue;
+use crate::parser::conf::Value;
+use crate::parser::core::MirObserver;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// figures collected by `conf::parse_str_instrumented`
+#[derive(Debug, Default, Clone, PartialEq, Eq,)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParseStats {
+	pub lines_scanned:       usize,
+	/// blank lines and comment lines
+	pub lines_skipped:       usize,
+	pub keys_parsed:         usize,
+	/// number of segments of the deepest key
+	pub max_depth:           usize,
+	/// assignments to a key which had already been assigned
+	pub overrides:           usize,
+	pub collection_elements: usize,
+	pub mir_time:            Duration,
+	pub typing_time:         Duration,
+}
+
+impl ParseStats {
+	pub(crate) fn count_collection_elements(
+		&mut self,
+		conf_map: &BTreeMap<String, ConfValue,>,
+	) {
+		for value in conf_map.values() {
+			match value {
+				ConfValue::Scalar(Value::Single(_,),) => {},
+				ConfValue::Scalar(Value::Collection(items,),) => {
+					self.collection_elements += items.len();
+				},
+				ConfValue::Map(children,) => {
+					self.count_collection_elements(children,)
+				},
+			}
+		}
+	}
+}
+
+impl MirObserver for ParseStats {
+	fn line_scanned(&mut self,) {
+		self.lines_scanned += 1;
+	}
+
+	fn line_skipped(&mut self,) {
+		self.lines_skipped += 1;
+	}
+
+	fn entry_parsed(&mut self, segments: &[String], _line: usize,) {
+		self.keys_parsed += 1;
+		self.max_depth = self.max_depth.max(segments.len(),);
+	}
+
+	fn entry_overridden(
+		&mut self,
+		_segments: &[String],
+		_previous: (&str, usize, usize,),
+		_current: (&str, usize, usize,),
+	) {
+		self.overrides += 1;
+	}
+}
+
+impl Display for ParseStats {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		write!(
+			f,
+			"{} lines scanned ({} skipped), {} keys parsed, max depth {}, {} \
+			 overrides, {} collection elements, mir {:?}, typing {:?}",
+			self.lines_scanned,
+			self.lines_skipped,
+			self.keys_parsed,
+			self.max_depth,
+			self.overrides,
+			self.collection_elements,
+			self.mir_time,
+			self.typing_time,
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf::SingleValue;
+
+	#[test]
+	fn observer_counts_entries_and_depth() {
+		let mut stats = ParseStats::default();
+		stats.line_scanned();
+		stats.line_scanned();
+		stats.line_skipped();
+		stats.entry_parsed(&["a".to_string(), "b".to_string(),], 2,);
+		stats.entry_overridden(
+			&["a".to_string(), "b".to_string(),],
+			("1", 1, 7,),
+			("2", 2, 7,),
+		);
+
+		assert_eq!(stats.lines_scanned, 2);
+		assert_eq!(stats.lines_skipped, 1);
+		assert_eq!(stats.keys_parsed, 1);
+		assert_eq!(stats.max_depth, 2);
+		assert_eq!(stats.overrides, 1);
+	}
+
+	#[test]
+	fn collection_elements_are_counted_recursively() {
+		let nested = BTreeMap::from([(
+			"ports".to_string(),
+			ConfValue::Scalar(Value::Collection(vec![
+				SingleValue::Integer(1,),
+				SingleValue::Integer(2,),
+			],),),
+		),],);
+		let root = BTreeMap::from([
+			(
+				"flag".to_string(),
+				ConfValue::Scalar(Value::Single(SingleValue::Bool(true,),),),
+			),
+			("net".to_string(), ConfValue::Map(nested,),),
+		],);
+
+		let mut stats = ParseStats::default();
+		stats.count_collection_elements(&root,);
+		assert_eq!(stats.collection_elements, 2);
+	}
+
+	#[test]
+	fn display_is_a_single_summary_line() {
+		let stats = ParseStats {
+			lines_scanned: 3,
+			keys_parsed: 2,
+			..Default::default()
+		};
+		let summary = stats.to_string();
+		assert!(
+			summary.starts_with("3 lines scanned (0 skipped), 2 keys parsed")
+		);
+		assert!(!summary.contains('\n'));
+	}
+}
@@ -0,0 +1,407 @@
+use crate::error::PRslt;
+use crate::error::ParseError;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::conf::check_constraints;
+use crate::parser::core::TreeValue;
+use crate::parser::deferred::placeholder;
+use crate::parser::duration::render_duration;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaValue;
+use crate::parser::secret::REDACTED;
+use crate::parser::secret::redact_for;
+use std::collections::BTreeMap;
+
+impl From<&SingleValue,> for ::toml::Value {
+	fn from(value: &SingleValue,) -> Self {
+		match value {
+			SingleValue::String(s,) => Self::String(s.clone(),),
+			SingleValue::Bool(flag,) => Self::Boolean(*flag,),
+			SingleValue::Integer(num,) => Self::Integer(i64::from(*num,),),
+			// toml integers are 64-bit signed, so larger ones are written as
+			// their digits
+			SingleValue::UInteger(num,) => i64::try_from(*num,)
+				.map_or_else(|_| Self::String(num.to_string(),), Self::Integer,),
+			SingleValue::Long(num,) => Self::Integer(*num,),
+			SingleValue::Float(num,) => Self::Float(*num,),
+			SingleValue::Secret(_,) => Self::String(REDACTED.to_string(),),
+			SingleValue::Duration(duration,) => {
+				Self::String(render_duration(*duration,),)
+			},
+			SingleValue::IpAddr(addr,) => Self::String(addr.to_string(),),
+			SingleValue::SocketAddr(addr,) => Self::String(addr.to_string(),),
+			SingleValue::Path(path,) => {
+				Self::String(path.to_string_lossy().into_owned(),)
+			},
+			SingleValue::Deferred { resolver, arg, } => {
+				Self::String(placeholder(resolver, arg,),)
+			},
+		}
+	}
+}
+
+impl From<&ConfValue,> for ::toml::Value {
+	fn from(value: &ConfValue,) -> Self {
+		match value {
+			ConfValue::Scalar(Value::Single(single,),) => single.into(),
+			// toml 1.0 arrays may mix types, so collections map one to one
+			ConfValue::Scalar(Value::Collection(items,),) => {
+				Self::Array(items.iter().map(Self::from,).collect(),)
+			},
+			ConfValue::Map(children,) => map_to_toml(children,),
+		}
+	}
+}
+
+fn map_to_toml(map: &BTreeMap<String, ConfValue,>,) -> ::toml::Value {
+	::toml::Value::Table(
+		map.iter().map(|(k, v,)| (k.clone(), v.into(),),).collect(),
+	)
+}
+
+impl ConfMap {
+	/// toml table of every entry, nested by key segment
+	pub fn to_toml_value(&self,) -> ::toml::Value {
+		map_to_toml(self,)
+	}
+}
+
+/// builds a conf map from a toml table typed against `schema`, the way
+/// [`ConfValue::try_from_toml`] reads a section
+pub fn from_toml(
+	value: &::toml::Value, schema: &SchemaMap,
+) -> PRslt<ConfMap,> {
+	map_from_toml("", value, schema,)
+		.map(|entries| ConfMap::from_typed(entries, schema,),)
+}
+
+impl ConfValue {
+	/// reads `value` back as the conf value described by `expected`.
+	///
+	/// datetimes have no conf counterpart and are rejected like any other
+	/// mistyped value
+	pub fn try_from_toml(
+		value: &::toml::Value,
+		expected: &SchemaValue,
+	) -> PRslt<ConfValue,> {
+		from_toml_at("", value, expected,)
+	}
+}
+
+fn from_toml_at(
+	key: &str,
+	value: &::toml::Value,
+	expected: &SchemaValue,
+) -> PRslt<ConfValue,> {
+	match expected {
+		TreeValue::Scalar(leaf,) => {
+			let leaf_value = leaf_from_toml(key, value, leaf,)
+				.map_err(|err| redact_for(leaf, err,),)?;
+			Ok(TreeValue::Scalar(leaf_value,),)
+		},
+		TreeValue::Map(schema_map,) => {
+			map_from_toml(key, value, schema_map,).map(TreeValue::Map,)
+		},
+	}
+}
+
+/// entries of the section at `key`
+fn map_from_toml(
+	key: &str,
+	value: &::toml::Value,
+	schema_map: &BTreeMap<String, SchemaValue,>,
+) -> PRslt<BTreeMap<String, ConfValue,>,> {
+	let ::toml::Value::Table(table,) = value else {
+		return Err(ParseError::ConflictingTypes {
+			key:    key.to_string(),
+			line:   0,
+			column: 0,
+			origin: None,
+		},);
+	};
+
+	let mut conf_map = BTreeMap::new();
+	for (child_key, child_value,) in table {
+		let dotted_key = if key.is_empty() {
+			child_key.clone()
+		} else {
+			format!("{key}.{child_key}")
+		};
+		let Some(child_schema,) = schema_map.get(child_key,) else {
+			return Err(ParseError::UnknownKey {
+				key:        dotted_key,
+				lines:      vec![],
+				suggestion: None,
+			},);
+		};
+
+		conf_map.insert(
+			child_key.clone(),
+			from_toml_at(&dotted_key, child_value, child_schema,)?,
+		);
+	}
+	Ok(conf_map,)
+}
+
+fn leaf_from_toml(
+	key: &str,
+	value: &::toml::Value,
+	leaf: &SchemaLeaf,
+) -> PRslt<Value<SingleValue,>,> {
+	match &leaf.ty {
+		Value::Single(kind,) => {
+			let single = single_from_toml(key, value, *kind, None,)?;
+			check_constraints(key, leaf, 0, &single, 0,)?;
+			Ok(Value::Single(single,),)
+		},
+		Value::Collection(kinds,) => {
+			let ::toml::Value::Array(items,) = value else {
+				return Err(invalid_value(key, value, kinds[0], None,),);
+			};
+			if !leaf.list && items.len() != kinds.len() {
+				return Err(invalid_value(key, value, kinds[0], None,),);
+			}
+
+			Ok(Value::Collection(
+				items
+					.iter()
+					.enumerate()
+					.map(|(idx, item,)| -> PRslt<SingleValue,> {
+						let kind = leaf.element_kind(idx,).unwrap_or(kinds[0],);
+						let single = single_from_toml(
+							key,
+							item,
+							kind,
+							leaf.label(idx,),
+						)?;
+						check_constraints(key, leaf, idx, &single, 0,)?;
+						Ok(single,)
+					},)
+					.try_collect()?,
+			),)
+		},
+	}
+}
+
+fn single_from_toml(
+	key: &str,
+	value: &::toml::Value,
+	kind: SingleValueDiscriminants,
+	element: Option<&str,>,
+) -> PRslt<SingleValue,> {
+	let single = match (kind, value,) {
+		(SingleValueDiscriminants::String, ::toml::Value::String(s,),) => {
+			Some(SingleValue::String(s.clone(),),)
+		},
+		// types written as text in conf files are read from the same text
+		(
+			SingleValueDiscriminants::Secret
+			| SingleValueDiscriminants::Duration
+			| SingleValueDiscriminants::IpAddr
+			| SingleValueDiscriminants::SocketAddr
+			| SingleValueDiscriminants::Path,
+			::toml::Value::String(s,),
+		) => kind.into_payload(key, s, 0,).ok(),
+		(SingleValueDiscriminants::Bool, ::toml::Value::Boolean(flag,),) => {
+			Some(SingleValue::Bool(*flag,),)
+		},
+		(SingleValueDiscriminants::Integer, ::toml::Value::Integer(num,),) => {
+			i32::try_from(*num,).ok().map(SingleValue::Integer,)
+		},
+		(SingleValueDiscriminants::UInteger, ::toml::Value::Integer(num,),) => {
+			u64::try_from(*num,).ok().map(SingleValue::UInteger,)
+		},
+		(SingleValueDiscriminants::UInteger, ::toml::Value::String(s,),) => {
+			s.parse().ok().map(SingleValue::UInteger,)
+		},
+		(SingleValueDiscriminants::Long, ::toml::Value::Integer(num,),) => {
+			Some(SingleValue::Long(*num,),)
+		},
+		(SingleValueDiscriminants::Float, ::toml::Value::Float(num,),) => {
+			num.is_finite().then_some(SingleValue::Float(*num,),)
+		},
+		_ => None,
+	};
+
+	single.ok_or_else(|| invalid_value(key, value, kind, element,),)
+}
+
+fn invalid_value(
+	key: &str,
+	value: &::toml::Value,
+	ty: SingleValueDiscriminants,
+	element: Option<&str,>,
+) -> ParseError {
+	ParseError::InvalidValue {
+		key: key.to_string(),
+		value: value.to_string(),
+		ty,
+		line: 0,
+		column: 0,
+		element: element.map(str::to_string,),
+		position: None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::conf;
+	use crate::parser::schema;
+
+	const SCHEMA: &str =
+		"debug -> Bool\nendpoint -> String\nlog.file -> String\nlog.name -> \
+		 String\nnet.ipv4.ip_local_reserved_ports -> Integer, Integer\n";
+	const CONF: &str = "debug = true\nendpoint = localhost:3000\nlog.file = \
+	                    /var/log/console.log\nlog.name = \
+	                    default.log\nnet.ipv4.ip_local_reserved_ports = \
+	                    8080,9148\n";
+
+	fn expected(schema: &str,) -> SchemaValue {
+		TreeValue::Map(schema::parse_str(schema,).unwrap().into_inner(),)
+	}
+
+	#[test]
+	fn conf_map_becomes_nested_tables() {
+		let conf = conf::parse_str(CONF, schema::parse_str(SCHEMA,).unwrap(),)
+			.unwrap();
+		let table = conf.to_toml_value();
+
+		assert_eq!(
+			table,
+			::toml::from_str::<::toml::Value,>(
+				"debug = true\nendpoint = \"localhost:3000\"\n[log]\nfile = \
+				 \"/var/log/console.log\"\nname = \
+				 \"default.log\"\n[net.ipv4]\nip_local_reserved_ports = \
+				 [8080, 9148]\n",
+			)
+			.unwrap()
+		);
+	}
+
+	#[test]
+	fn toml_round_trip_preserves_types() {
+		let conf = conf::parse_str(CONF, schema::parse_str(SCHEMA,).unwrap(),)
+			.unwrap();
+		let original = TreeValue::Map(conf.into_inner(),);
+
+		let toml = ::toml::Value::from(&original,);
+		let restored =
+			ConfValue::try_from_toml(&toml, &expected(SCHEMA,),).unwrap();
+		assert_eq!(restored, original);
+	}
+
+	#[test]
+	fn mixed_collection_against_other_schema_fails_cleanly() {
+		let mixed = conf::parse_str(
+			"pair = 1, true\n",
+			schema::parse_str("pair -> Integer, Bool\n",).unwrap(),
+		)
+		.unwrap();
+		let toml = mixed.to_toml_value();
+		assert_eq!(toml.to_string(), "{ pair = [1, true] }");
+
+		let err = ConfValue::try_from_toml(
+			&toml,
+			&expected("pair -> (lo: Integer, hi: Integer)\n",),
+		)
+		.unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"[E007] invalid value 'true' while expecting Integer for element \
+			 'hi' of 'pair' on line 0, column 0"
+		);
+	}
+
+	#[test]
+	fn from_toml_keeps_labels_and_repeatable_keys() {
+		let schema = schema::parse_str(
+			"limits -> (min: Integer, max: Integer)\nallow -> [String] \
+			 (repeatable)\n",
+		)
+		.unwrap();
+		let conf =
+			conf::parse_str("limits = 1, 10\nallow = a\nallow = b\n", &schema,)
+				.unwrap();
+
+		let built = from_toml(&conf.to_toml_value(), &schema,).unwrap();
+		assert_eq!(built, conf);
+		assert_eq!(
+			built.get_field("limits", "max"),
+			Some(&SingleValue::Integer(10))
+		);
+		assert!(built.is_repeatable("allow"));
+	}
+
+	#[test]
+	fn from_toml_reads_back_text_types() {
+		let schema = schema::parse_str(
+			"timeout -> Duration\nlisten -> SocketAddr\nhost -> Ip\nroot -> \
+			 Path\n",
+		)
+		.unwrap();
+		let conf = conf::parse_str(
+			"timeout = 1m30s\nlisten = 127.0.0.1:80\nhost = ::1\nroot = /srv\n",
+			&schema,
+		)
+		.unwrap();
+
+		let built = from_toml(&conf.to_toml_value(), &schema,).unwrap();
+		assert_eq!(*built, *conf);
+		assert_eq!(
+			built.get_duration("timeout"),
+			Some(std::time::Duration::from_secs(90))
+		);
+
+		let toml: ::toml::Value =
+			::toml::from_str("timeout = \"1h\"\n",).unwrap();
+		let built = from_toml(&toml, &schema,).unwrap();
+		assert_eq!(
+			built.get_duration("timeout"),
+			Some(std::time::Duration::from_secs(3600))
+		);
+	}
+
+	#[test]
+	fn from_toml_checks_ranges_and_enums() {
+		let schema = schema::parse_str(
+			"port -> Integer[1..1024]\nports -> [Integer[1..1024]]\nlevel -> \
+			 Enum(debug, info)\n",
+		)
+		.unwrap();
+		let from =
+			|text: &str| from_toml(&::toml::from_str(text,).unwrap(), &schema,);
+
+		assert!(
+			from("port = 80\nports = [80, 443]\nlevel = \"info\"\n").is_ok()
+		);
+		assert!(matches!(
+			from("port = 8080\n"),
+			Err(ParseError::OutOfRange { ref key, .. }) if key == "port"
+		));
+		assert!(matches!(
+			from("ports = [80, 0]\n"),
+			Err(ParseError::OutOfRange { ref key, .. }) if key == "ports"
+		));
+		assert!(matches!(
+			from("level = \"trace\"\n"),
+			Err(ParseError::InvalidEnumValue { ref key, .. }) if key == "level"
+		));
+	}
+
+	#[test]
+	fn values_without_conf_counterpart_are_rejected() {
+		let toml: ::toml::Value = ::toml::from_str("ratio = 0.5\n",).unwrap();
+		let err =
+			ConfValue::try_from_toml(&toml, &expected("ratio -> Integer",),)
+				.unwrap_err();
+		assert!(matches!(
+			err,
+			ParseError::InvalidValue { ref key, .. } if key == "ratio"
+		));
+	}
+}
@@ -0,0 +1,272 @@
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::Value;
+use crate::parser::core::TreeValue;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+
+/// read-only access shared by [`ConfMap`] and [`ConfMapRef`]. implemented
+/// for references, so everything handed out borrows from the map itself
+pub trait ConfView<'a,>: Copy {
+	fn entries(self,) -> &'a BTreeMap<String, ConfValue,>;
+
+	/// value at the dotted `key`. a top-level key which itself contains dots
+	/// is found as well, but only when no path through sections leads to
+	/// `key`
+	fn get(self, key: &str,) -> Option<&'a ConfValue,> {
+		TreeValue::get_dotted(self.entries(), key,)
+	}
+
+	/// section at the dotted `key`
+	fn subtree(self, key: &str,) -> Option<ConfMapRef<'a,>,> {
+		match self.get(key,)? {
+			ConfValue::Map(children,) => Some(ConfMapRef(children,),),
+			ConfValue::Scalar(_,) => None,
+		}
+	}
+
+	/// every value with its dotted key, in key order
+	fn iter_leaves(self,) -> Leaves<'a,> {
+		Leaves::new(self.entries(),)
+	}
+}
+
+/// borrowed counterpart of [`ConfMap`], e.g. a section of one
+#[derive(Debug, Clone, Copy, PartialEq,)]
+pub struct ConfMapRef<'a,>(&'a BTreeMap<String, ConfValue,>,);
+
+impl<'a,> ConfView<'a,> for ConfMapRef<'a,> {
+	fn entries(self,) -> &'a BTreeMap<String, ConfValue,> {
+		self.0
+	}
+}
+
+impl<'a,> ConfView<'a,> for &'a ConfMap {
+	fn entries(self,) -> &'a BTreeMap<String, ConfValue,> {
+		self
+	}
+}
+
+impl<'a,> From<&'a BTreeMap<String, ConfValue,>,> for ConfMapRef<'a,> {
+	fn from(entries: &'a BTreeMap<String, ConfValue,>,) -> Self {
+		Self(entries,)
+	}
+}
+
+impl ConfMap {
+	pub fn as_view(&self,) -> ConfMapRef<'_,> {
+		ConfMapRef(self,)
+	}
+}
+
+/// iterator of [`ConfView::iter_leaves`] and [`SchemaMap::iter_leaves`]. only
+/// the path to the current leaf is kept, never a copy of a section
+pub struct Leaves<'a, T = Value<SingleValue,>,> {
+	/// dotted key and remaining entries of each section being walked
+	stack: Vec<(String, btree_map::Iter<'a, String, TreeValue<T,>,>,),>,
+}
+
+impl<'a, T,> Leaves<'a, T,> {
+	pub(crate) fn new(entries: &'a BTreeMap<String, TreeValue<T,>,>,) -> Self {
+		Self { stack: vec![(String::new(), entries.iter(),)], }
+	}
+}
+
+impl<'a, T,> Iterator for Leaves<'a, T,> {
+	type Item = (String, &'a T,);
+
+	fn next(&mut self,) -> Option<Self::Item,> {
+		loop {
+			let (prefix, entries,) = self.stack.last_mut()?;
+			let Some((key, value,),) = entries.next() else {
+				self.stack.pop();
+				continue;
+			};
+
+			let dotted_key = dotted(prefix, key,);
+			match value {
+				TreeValue::Scalar(scalar,) => {
+					return Some((dotted_key, scalar,),);
+				},
+				TreeValue::Map(children,) => {
+					self.stack.push((dotted_key, children.iter(),),)
+				},
+			}
+		}
+	}
+}
+
+/// iterator of [`ConfMap::into_leaves`], the owning counterpart of [`Leaves`]
+pub struct IntoLeaves {
+	stack: Vec<(String, btree_map::IntoIter<String, ConfValue,>,),>,
+}
+
+impl Iterator for IntoLeaves {
+	type Item = (String, Value<SingleValue,>,);
+
+	fn next(&mut self,) -> Option<Self::Item,> {
+		loop {
+			let (prefix, entries,) = self.stack.last_mut()?;
+			let Some((key, value,),) = entries.next() else {
+				self.stack.pop();
+				continue;
+			};
+
+			let dotted_key = dotted(prefix, &key,);
+			match value {
+				TreeValue::Scalar(scalar,) => {
+					return Some((dotted_key, scalar,),);
+				},
+				TreeValue::Map(children,) => {
+					self.stack.push((dotted_key, children.into_iter(),),)
+				},
+			}
+		}
+	}
+}
+
+impl ConfMap {
+	/// every value with its dotted key, in key order, taking the map apart
+	pub fn into_leaves(self,) -> IntoLeaves {
+		IntoLeaves {
+			stack: vec![(String::new(), self.into_inner().into_iter(),)],
+		}
+	}
+}
+
+impl SchemaMap {
+	/// every declaration with its dotted key, in key order
+	pub fn iter_leaves(&self,) -> Leaves<'_, SchemaLeaf,> {
+		Leaves::new(self,)
+	}
+}
+
+fn dotted(prefix: &str, key: &str,) -> String {
+	if prefix.is_empty() { key.to_string() } else { format!("{prefix}.{key}") }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::show::ShowFmt;
+	use crate::show::ShowStyle;
+
+	fn sample() -> ConfMap {
+		crate::conf_map! {
+			"server.http.port" => 80,
+			"server.http.host" => "localhost",
+			"server.workers" => 4,
+			"debug" => true,
+		}
+	}
+
+	#[test]
+	fn subtrees_are_borrowed_views() {
+		let conf = sample();
+		let http =
+			conf.subtree("server",).and_then(|server| server.subtree("http",),);
+
+		let Some(http,) = http else {
+			panic!("server.http is missing");
+		};
+		assert!(std::ptr::eq(
+			http.entries(),
+			match conf.get("server.http",) {
+				Some(ConfValue::Map(children,),) => children,
+				other => panic!("unexpected value: {other:?}"),
+			}
+		));
+		assert_eq!(
+			http.get("port"),
+			Some(&ConfValue::Scalar(Value::Single(SingleValue::Integer(80))))
+		);
+		assert_eq!(conf.subtree("debug"), None);
+	}
+
+	#[test]
+	fn leaves_are_listed_in_key_order() {
+		let conf = sample();
+		let keys: Vec<String,> =
+			conf.iter_leaves().map(|(key, _,)| key,).collect();
+		assert_eq!(
+			keys,
+			vec![
+				"debug",
+				"server.http.host",
+				"server.http.port",
+				"server.workers"
+			]
+		);
+
+		let http: Vec<String,> = conf
+			.subtree("server.http",)
+			.unwrap()
+			.iter_leaves()
+			.map(|(key, _,)| key,)
+			.collect();
+		assert_eq!(http, vec!["host", "port"]);
+	}
+
+	#[test]
+	fn views_render_like_maps() {
+		let conf = sample();
+		let mut output = Vec::new();
+		conf.subtree("server",)
+			.unwrap()
+			.write_fmt_streaming(
+				ShowFmt::Conf,
+				&ShowStyle::default(),
+				&mut output,
+			)
+			.unwrap();
+		assert_eq!(
+			String::from_utf8(output,).unwrap(),
+			"http.host = localhost\nhttp.port = 80\nworkers = 4"
+		);
+	}
+
+	#[test]
+	fn owned_leaves_match_borrowed_ones() {
+		let conf = crate::conf_map! {
+			"net.ipv4.ip_local_reserved_ports" => [8080, 9148],
+			"net.ipv4.forward" => true,
+		};
+		let borrowed: Vec<(String, Value<SingleValue,>,),> = conf
+			.iter_leaves()
+			.map(|(key, value,)| (key, value.clone(),),)
+			.collect();
+		let owned: Vec<_,> = conf.into_leaves().collect();
+
+		assert_eq!(owned, borrowed);
+		assert_eq!(
+			owned[1],
+			(
+				"net.ipv4.ip_local_reserved_ports".to_string(),
+				Value::Collection(vec![
+					SingleValue::Integer(8080),
+					SingleValue::Integer(9148)
+				])
+			)
+		);
+		assert_eq!(ConfMap::new().into_leaves().count(), 0);
+		assert_eq!(ConfMap::new().iter_leaves().count(), 0);
+	}
+
+	#[test]
+	fn schema_leaves_are_listed_in_key_order() {
+		let schema = crate::parser::schema::parse_str(
+			"b.c.d -> Integer\na -> [String]\nb.c.e -> Bool\n",
+		)
+		.unwrap();
+		let keys: Vec<String,> =
+			schema.iter_leaves().map(|(key, _,)| key,).collect();
+		assert_eq!(keys, vec!["a", "b.c.d", "b.c.e"]);
+		assert!(
+			schema.iter_leaves().next().is_some_and(|(_, leaf,)| leaf.list)
+		);
+		assert_eq!(SchemaMap::new().iter_leaves().count(), 0);
+	}
+}
@@ -0,0 +1,217 @@
+use crate::error::PRslt;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::parse_file;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// knobs of [`watch_file_with`]. the default polls twice a second
+#[derive(Debug, Clone, PartialEq, Eq,)]
+pub struct WatchOptions {
+	/// time between two looks at the files
+	pub poll_interval: Duration,
+	/// how long the files have to stay unchanged after a change before they
+	/// are parsed, so a burst of writes is parsed once
+	pub debounce:      Duration,
+}
+
+impl Default for WatchOptions {
+	fn default() -> Self {
+		Self {
+			poll_interval: Duration::from_millis(500,),
+			debounce:      Duration::from_millis(500,),
+		}
+	}
+}
+
+impl WatchOptions {
+	pub fn poll_interval(self, poll_interval: Duration,) -> Self {
+		Self { poll_interval, ..self }
+	}
+
+	pub fn debounce(self, debounce: Duration,) -> Self {
+		Self { debounce, ..self }
+	}
+}
+
+/// thread watching a conf file, stopped by [`WatchHandle::stop`] or when the
+/// handle is dropped
+#[derive(Debug,)]
+pub struct WatchHandle {
+	stop:   Option<mpsc::Sender<(),>,>,
+	thread: Option<JoinHandle<(),>,>,
+}
+
+impl WatchHandle {
+	/// stops watching and waits for the thread to finish, the callback it
+	/// may be running included
+	pub fn stop(mut self,) {
+		self.shut_down();
+	}
+
+	fn shut_down(&mut self,) {
+		// a disconnected channel stops the thread as well
+		drop(self.stop.take(),);
+		if let Some(thread,) = self.thread.take() {
+			// a panicking callback already ended the watch
+			let _ = thread.join();
+		}
+	}
+}
+
+impl Drop for WatchHandle {
+	fn drop(&mut self,) {
+		self.shut_down();
+	}
+}
+
+/// same as [`watch_file_with`] with the default [`WatchOptions`]
+pub fn watch_file<P, F,>(
+	conf_path: P,
+	schema_path: P,
+	callback: F,
+) -> WatchHandle
+where
+	P: AsRef<Path,>,
+	F: FnMut(PRslt<ConfMap,>,) + Send + 'static,
+{
+	watch_file_with(conf_path, schema_path, WatchOptions::default(), callback,)
+}
+
+/// polls the modification time and size of `conf_path` and `schema_path` on
+/// a thread of its own, and calls `callback` with the result of
+/// [`parse_file`] once they have settled after a change. errors are passed
+/// on too, so the caller can keep its current map. the files are not parsed
+/// when watching starts
+pub fn watch_file_with<P, F,>(
+	conf_path: P,
+	schema_path: P,
+	options: WatchOptions,
+	mut callback: F,
+) -> WatchHandle
+where
+	P: AsRef<Path,>,
+	F: FnMut(PRslt<ConfMap,>,) + Send + 'static,
+{
+	let paths =
+		[conf_path.as_ref().to_path_buf(), schema_path.as_ref().to_path_buf(),];
+	let (stop, stopped,) = mpsc::channel();
+	let thread = std::thread::spawn(move || {
+		let mut seen = stamps(&paths,);
+		let mut changed_at = None;
+		while let Err(mpsc::RecvTimeoutError::Timeout,) =
+			stopped.recv_timeout(options.poll_interval,)
+		{
+			let now = stamps(&paths,);
+			if now != seen {
+				seen = now;
+				changed_at = Some(Instant::now(),);
+			} else if changed_at
+				.is_some_and(|at: Instant| at.elapsed() >= options.debounce,)
+			{
+				changed_at = None;
+				callback(parse_file(&paths[0], &paths[1],),);
+			}
+		}
+	},);
+
+	WatchHandle { stop: Some(stop,), thread: Some(thread,), }
+}
+
+/// modification time and size of each file, `None` for one which cannot be
+/// read
+fn stamps(paths: &[PathBuf; 2],) -> [Option<(SystemTime, u64,),>; 2] {
+	paths.each_ref().map(|path| {
+		let metadata = std::fs::metadata(path,).ok()?;
+		Some((metadata.modified().ok()?, metadata.len(),),)
+	},)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::mpsc::Receiver;
+
+	const POLL: Duration = Duration::from_millis(10,);
+	const DEBOUNCE: Duration = Duration::from_millis(50,);
+	/// long enough for a change to be seen, settle and be parsed
+	const SETTLED: Duration = Duration::from_millis(400,);
+
+	/// a schema and a conf file in a fresh directory
+	fn files(name: &str,) -> (PathBuf, PathBuf,) {
+		let dir = std::env::temp_dir()
+			.join(format!("dot_conf_watch_{name}_{}", std::process::id()),);
+		std::fs::create_dir_all(&dir,).unwrap();
+		let schema = dir.join("app.schema",);
+		let conf = dir.join("app.conf",);
+		std::fs::write(&schema, "port -> Integer\n",).unwrap();
+		std::fs::write(&conf, "port = 80\n",).unwrap();
+		(conf, schema,)
+	}
+
+	fn watch(
+		conf: &Path,
+		schema: &Path,
+	) -> (WatchHandle, Receiver<PRslt<ConfMap,>,>,) {
+		let (sender, results,) = mpsc::channel();
+		let options =
+			WatchOptions::default().poll_interval(POLL,).debounce(DEBOUNCE,);
+		let handle = watch_file_with(conf, schema, options, move |result| {
+			let _ = sender.send(result,);
+		},);
+		(handle, results,)
+	}
+
+	#[test]
+	fn changes_are_parsed_and_passed_on() {
+		let (conf, schema,) = files("change",);
+		let (handle, results,) = watch(&conf, &schema,);
+
+		std::thread::sleep(SETTLED,);
+		assert!(results.try_recv().is_err(), "parsed without a change");
+
+		std::fs::write(&conf, "port = 8080\n",).unwrap();
+		let reparsed = results.recv_timeout(SETTLED * 5,).unwrap().unwrap();
+		assert_eq!(reparsed.get_int("port"), Some(8080));
+
+		std::fs::write(&conf, "port = eighty\n",).unwrap();
+		let err = results.recv_timeout(SETTLED * 5,).unwrap().unwrap_err();
+		assert!(err.is_invalid_value(), "{err:?}");
+
+		handle.stop();
+		std::fs::remove_dir_all(conf.parent().unwrap(),).unwrap();
+	}
+
+	#[test]
+	fn a_burst_of_writes_is_parsed_once() {
+		let (conf, schema,) = files("burst",);
+		let (handle, results,) = watch(&conf, &schema,);
+
+		for port in 1000..1005 {
+			std::fs::write(&conf, format!("port = {port}\n"),).unwrap();
+			std::thread::sleep(Duration::from_millis(5,),);
+		}
+		let reparsed = results.recv_timeout(SETTLED * 5,).unwrap().unwrap();
+		assert_eq!(reparsed.get_int("port"), Some(1004));
+		std::thread::sleep(SETTLED,);
+		assert!(results.try_recv().is_err(), "parsed more than once");
+
+		handle.stop();
+		std::fs::remove_dir_all(conf.parent().unwrap(),).unwrap();
+	}
+
+	#[test]
+	fn stopping_ends_the_thread() {
+		let (conf, schema,) = files("stop",);
+		let (handle, results,) = watch(&conf, &schema,);
+
+		handle.stop();
+		// the callback, and the sender with it, is gone with the thread
+		assert_eq!(results.recv(), Err(mpsc::RecvError));
+		std::fs::remove_dir_all(conf.parent().unwrap(),).unwrap();
+	}
+}
@@ -2,121 +2,977 @@ use crate::parser::conf::ConfMap;
 use crate::parser::conf::ConfValue;
 use crate::parser::conf::SingleValue;
 use crate::parser::conf::Value;
+use crate::parser::core::ParseOptions;
+use crate::parser::core::TreeValue;
+use crate::parser::core::parse_value;
+use crate::parser::deferred::placeholder;
+use crate::parser::diff::ConfDiff;
+use crate::parser::duration::render_duration;
+use crate::parser::env::var_name;
+use crate::parser::secret::REDACTED;
+use crate::parser::view::ConfMapRef;
+use crate::parser::view::ConfView;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fmt::Debug;
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
 
 pub trait Show: Debug {
 	fn show(&self,) {
 		self.show_as(ShowFmt::default(),);
 	}
-	fn show_as(&self, fmt: ShowFmt,);
-}
 
-impl Show for ConfMap {
+	/// prints to stdout, followed by a line break
 	fn show_as(&self, fmt: ShowFmt,) {
-		let output = match fmt {
-			ShowFmt::Conf => conf_map_as_conf(self,),
-			ShowFmt::Json => conf_map_as_json(self,),
-			ShowFmt::Debug => conf_map_as_debug(self,),
-		};
+		let mut stdout = io::stdout().lock();
+		// stdout going away is not something `show` can report
+		let _ =
+			self.write_as(fmt, &mut stdout,).and_then(|_| writeln!(stdout),);
+	}
+
+	fn render_as(&self, fmt: ShowFmt,) -> String {
+		let mut output = Vec::new();
+		// writing into a `Vec` never fails, and renderers only write utf-8
+		let _ = self.write_as(fmt, &mut output,);
+		String::from_utf8_lossy(&output,).into_owned()
+	}
 
-		println!("{output}")
+	/// no line break is written after the last entry
+	fn write_as<W: io::Write,>(
+		&self,
+		fmt: ShowFmt,
+		w: &mut W,
+	) -> io::Result<(),>;
+}
+
+impl Show for ConfMap {
+	fn write_as<W: io::Write,>(
+		&self,
+		fmt: ShowFmt,
+		w: &mut W,
+	) -> io::Result<(),> {
+		self.write_fmt_streaming(fmt, &ShowStyle::default(), w,)
 	}
 }
 
-#[derive(Default,)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq,)]
 pub enum ShowFmt {
 	#[default]
 	Conf,
 	Json,
+	Toml,
 	Debug,
+	/// a `NAME=value` line per leaf, for shell `export` or env files. the
+	/// name is the dotted key in upper case with `_` for `.` and any other
+	/// character a shell name cannot hold, and with a leading `_` when it
+	/// would start with a digit. values are single quoted when the shell
+	/// would read them otherwise
+	Env,
+}
+
+type TransformFn = dyn Fn(&str, &SingleValue,) -> Option<String,> + Send + Sync;
+
+/// rewrites a value before it is rendered, given its dotted key. `None`
+/// renders the value as usual. a [`ConfMapRef`] passes keys relative to the
+/// viewed section
+#[derive(Clone,)]
+pub struct ValueTransform(Arc<TransformFn,>,);
+
+impl ValueTransform {
+	pub fn new(
+		transform: impl Fn(&str, &SingleValue,) -> Option<String,>
+		+ Send
+		+ Sync
+		+ 'static,
+	) -> Self {
+		Self(Arc::new(transform,),)
+	}
+
+	pub fn apply(&self, key: &str, value: &SingleValue,) -> Option<String,> {
+		(self.0)(key, value,)
+	}
+}
+
+impl Debug for ValueTransform {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		f.write_str("ValueTransform",)
+	}
+}
+
+/// knobs shared by every renderer
+#[derive(Debug, Clone,)]
+pub struct ShowStyle {
+	/// unit of indentation of nested json objects
+	pub indent:               String,
+	/// written between the elements of a collection. json arrays always use
+	/// `, `
+	pub collection_separator: String,
+	/// writes secrets in clear instead of redacted. for controlled dumps only
+	pub reveal_secrets:       bool,
+	/// applied to every value by every format, each element of a collection
+	/// on its own. it sees secrets before they are redacted
+	pub value_transform:      Option<ValueTransform,>,
+	/// levels of sections [`ShowFmt::Debug`] descends into. deeper sections
+	/// are shown as their number of entries
+	pub max_debug_depth:      Option<usize,>,
+	/// entries [`ShowFmt::Debug`] shows of each section, the rest being
+	/// counted
+	pub max_children_shown:   Option<usize,>,
+}
+
+impl Default for ShowStyle {
+	fn default() -> Self {
+		Self {
+			indent:               "\t".to_string(),
+			collection_separator: ",".to_string(),
+			reveal_secrets:       false,
+			value_transform:      None,
+			max_debug_depth:      Some(16,),
+			max_children_shown:   Some(100,),
+		}
+	}
+}
+
+impl ShowStyle {
+	pub fn reveal_secrets(self, reveal_secrets: bool,) -> Self {
+		Self { reveal_secrets, ..self }
+	}
+
+	pub fn value_transform(
+		self,
+		transform: impl Fn(&str, &SingleValue,) -> Option<String,>
+		+ Send
+		+ Sync
+		+ 'static,
+	) -> Self {
+		Self { value_transform: Some(ValueTransform::new(transform,),), ..self }
+	}
+
+	/// `None` for no limit
+	pub fn max_debug_depth(self, max_debug_depth: Option<usize,>,) -> Self {
+		Self { max_debug_depth, ..self }
+	}
+
+	/// `None` for no limit
+	pub fn max_children_shown(
+		self,
+		max_children_shown: Option<usize,>,
+	) -> Self {
+		Self { max_children_shown, ..self }
+	}
+}
+
+/// capacity of the buffer between the renderers and the writer
+const STREAM_BUFFER: usize = 8 * 1024;
+
+impl ConfMap {
+	/// renders leaf by leaf into `w`, so memory use does not grow with the
+	/// size of the map
+	pub fn write_fmt_streaming<W: io::Write,>(
+		&self,
+		fmt: ShowFmt,
+		style: &ShowStyle,
+		w: W,
+	) -> io::Result<(),> {
+		write_streaming(
+			self,
+			self.repeatable_keys(),
+			self.key_order(),
+			self,
+			fmt,
+			style,
+			w,
+		)
+	}
+}
+
+impl ConfMap {
+	/// the one text of this map: a `key = value` line per leaf in key order,
+	/// collection elements in declared order separated by `, `, and a trailing
+	/// newline. values are written the way they were typed, so `+5`, `05` and
+	/// `5` all become `5` and `0.50` becomes `0.5`. lists declared repeatable
+	/// get a line per element instead, and strings which would not read back
+	/// as themselves are quoted.
+	///
+	/// two maps parsed against the same schema are equal iff their canonical
+	/// texts are byte-equal. secrets are written in clear for that reason, so
+	/// the text is as sensitive as the map
+	pub fn canonicalize(&self,) -> String {
+		let style = ShowStyle {
+			collection_separator: ", ".to_string(),
+			..ShowStyle::default()
+		}
+		.reveal_secrets(true,);
+		let mut text = Vec::new();
+		self.write_fmt_streaming(ShowFmt::Conf, &style, &mut text,)
+			.expect("writing to a Vec never fails",);
+		let mut text =
+			String::from_utf8(text,).expect("renderers only write utf-8",);
+		if !text.is_empty() {
+			text.push('\n',);
+		}
+		text
+	}
+
+	/// 64-bit fnv-1a hash of [`Self::canonicalize`]. stable across builds and
+	/// platforms, but not collision resistant
+	pub fn fingerprint(&self,) -> u64 {
+		const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+		const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+		self.canonicalize().bytes().fold(OFFSET_BASIS, |hash, byte| {
+			(hash ^ u64::from(byte,)).wrapping_mul(PRIME,)
+		},)
+	}
 }
 
-fn render_single(value: &SingleValue,) -> String {
+impl ConfDiff {
+	/// the change as a one-line json object of `change`, `key` and either
+	/// `value` or `old` and `new`. values are typed the way
+	/// [`ShowFmt::Json`] writes them, secrets redacted
+	pub fn to_json(&self,) -> String {
+		let mut text = Vec::new();
+		self.write_json(&ShowStyle::default(), &mut text,)
+			.expect("writing to a Vec never fails",);
+		String::from_utf8(text,).expect("renderers only write utf-8",)
+	}
+
+	fn write_json(
+		&self,
+		style: &ShowStyle,
+		w: &mut impl io::Write,
+	) -> io::Result<(),> {
+		let (change, values,) = match self {
+			Self::Added { value, .. } => ("added", vec![("value", value,)],),
+			Self::Removed { value, .. } => {
+				("removed", vec![("value", value,)],)
+			},
+			Self::Changed { old, new, .. } => {
+				("changed", vec![("old", old,), ("new", new,)],)
+			},
+		};
+		write!(w, "{{\"change\":\"{change}\",\"key\":",)?;
+		write_quoted(self.key(), w,)?;
+		for (name, value,) in values {
+			write!(w, ",\"{name}\":")?;
+			write_typed_scalar(self.key(), value, ShowFmt::Json, style, w,)?;
+		}
+		w.write_all(b"}",)
+	}
+}
+
+impl ConfMapRef<'_,> {
+	/// same as [`ConfMap::write_fmt_streaming`], for the viewed entries
+	pub fn write_fmt_streaming<W: io::Write,>(
+		&self,
+		fmt: ShowFmt,
+		style: &ShowStyle,
+		w: W,
+	) -> io::Result<(),> {
+		write_streaming(
+			self.entries(),
+			&BTreeSet::new(),
+			&[],
+			self.entries(),
+			fmt,
+			style,
+			w,
+		)
+	}
+}
+
+/// `debug` is what [`ShowFmt::Debug`] prints, unless a value transform
+/// applies, the limits of `style` elide anything or the map is too large for
+/// `debug` to print in full. the entries are shown instead then
+fn write_streaming<W: io::Write,>(
+	entries: &BTreeMap<String, ConfValue,>,
+	repeatable: &BTreeSet<String,>,
+	order: &[String],
+	debug: &dyn Debug,
+	fmt: ShowFmt,
+	style: &ShowStyle,
+	w: W,
+) -> io::Result<(),> {
+	let mut w = io::BufWriter::with_capacity(STREAM_BUFFER, w,);
+	match fmt {
+		ShowFmt::Conf if !order.is_empty() => {
+			write_conf_ordered(entries, order, repeatable, style, &mut w,)?
+		},
+		ShowFmt::Conf => write_conf(
+			entries,
+			repeatable,
+			&mut String::new(),
+			&mut true,
+			style,
+			&mut w,
+		)?,
+		ShowFmt::Json => {
+			write_json(entries, &mut String::new(), 0, style, &mut w,)?
+		},
+		ShowFmt::Toml => {
+			write_toml(entries, &mut Vec::new(), &mut true, style, &mut w,)?
+		},
+		ShowFmt::Env => write_env(entries, style, &mut w,)?,
+		ShowFmt::Debug => {
+			let shown = DebugEntries {
+				entries,
+				prefix: String::new(),
+				level: 1,
+				style,
+			};
+			if style.value_transform.is_none()
+				&& !shown.elides()
+				&& ConfMapRef::from(entries,).iter_leaves().count()
+					<= DEBUG_SUMMARY_LEAVES
+			{
+				write!(w, "{debug:#?}")?
+			} else {
+				write!(w, "{shown:#?}")?
+			}
+		},
+	}
+	w.flush()
+}
+
+/// leaves beyond which the `Debug` of a [`ConfMap`] only summarizes it
+pub(crate) const DEBUG_SUMMARY_LEAVES: usize = 1000;
+
+/// entries of a section at `level`, top level being 1, within the limits of
+/// `style`. formatted like a map, with a marker counting elided entries.
+/// `prefix` is the dotted key of the section, for the value transform
+struct DebugEntries<'a,> {
+	entries: &'a BTreeMap<String, ConfValue,>,
+	prefix:  String,
+	level:   usize,
+	style:   &'a ShowStyle,
+}
+
+impl DebugEntries<'_,> {
+	fn shown(&self,) -> usize {
+		self.style.max_children_shown.unwrap_or(usize::MAX,)
+	}
+
+	fn descends(&self,) -> bool {
+		self.style.max_debug_depth.is_none_or(|max| self.level < max,)
+	}
+
+	/// whether any entry is left out
+	fn elides(&self,) -> bool {
+		self.entries.len() > self.shown()
+			|| self.entries.values().any(|value| match value {
+				ConfValue::Scalar(_,) => false,
+				ConfValue::Map(children,) => {
+					!self.descends()
+						|| DebugEntries {
+							entries: children,
+							prefix:  String::new(),
+							level:   self.level + 1,
+							style:   self.style,
+						}
+						.elides()
+				},
+			},)
+	}
+
+	fn dotted(&self, key: &str,) -> String {
+		if self.prefix.is_empty() {
+			key.to_string()
+		} else {
+			format!("{}.{key}", self.prefix)
+		}
+	}
+}
+
+impl Debug for DebugEntries<'_,> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		// a set of `key: value` entries renders exactly like a map
+		let mut set = f.debug_set();
+		for (key, value,) in self.entries.iter().take(self.shown(),) {
+			set.entry(&DebugEntry { key, value, entries: self, },);
+		}
+		let hidden = self.entries.len().saturating_sub(self.shown(),);
+		if hidden > 0 {
+			set.entry(&format_args!(
+				"… {hidden} more {}",
+				entries_noun(hidden)
+			),);
+		}
+		set.finish()
+	}
+}
+
+fn entries_noun(count: usize,) -> &'static str {
+	if count == 1 { "entry" } else { "entries" }
+}
+
+struct DebugEntry<'a,> {
+	key:     &'a String,
+	value:   &'a ConfValue,
+	/// section the entry belongs to
+	entries: &'a DebugEntries<'a,>,
+}
+
+impl Debug for DebugEntry<'_,> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		write!(f, "{:?}: ", self.key)?;
+		match self.value {
+			ConfValue::Scalar(scalar,) => {
+				match &self.entries.style.value_transform {
+					Some(transform,) => f
+						.debug_tuple("Scalar",)
+						.field(&transform_scalar(
+							&self.entries.dotted(self.key,),
+							scalar,
+							transform,
+						),)
+						.finish(),
+					None => f.debug_tuple("Scalar",).field(scalar,).finish(),
+				}
+			},
+			ConfValue::Map(children,) if self.entries.descends() => f
+				.debug_tuple("Map",)
+				.field(&DebugEntries {
+					entries: children,
+					prefix:  self.entries.dotted(self.key,),
+					level:   self.entries.level + 1,
+					style:   self.entries.style,
+				},)
+				.finish(),
+			ConfValue::Map(children,) => {
+				write!(
+					f,
+					"Map(… {} {})",
+					children.len(),
+					entries_noun(children.len())
+				)
+			},
+		}
+	}
+}
+
+fn write_single(
+	key: &str,
+	value: &SingleValue,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	if let Some(transformed,) =
+		style.value_transform.as_ref().and_then(|t| t.apply(key, value,),)
+	{
+		return w.write_all(transformed.as_bytes(),);
+	}
+
 	match value {
-		SingleValue::String(s,) => s.clone(),
-		SingleValue::Bool(flag,) => flag.to_string(),
-		SingleValue::Integer(num,) => num.to_string(),
+		SingleValue::String(s,) => w.write_all(s.as_bytes(),),
+		SingleValue::Bool(flag,) => write!(w, "{flag}"),
+		SingleValue::Integer(num,) => write!(w, "{num}"),
+		SingleValue::UInteger(num,) => write!(w, "{num}"),
+		SingleValue::Long(num,) => write!(w, "{num}"),
+		// `Display` of a float is the shortest text parsing back to it. -0
+		// equals 0, so both are written as `0`
+		SingleValue::Float(num,) if *num == 0.0 => w.write_all(b"0",),
+		SingleValue::Float(num,) => write!(w, "{num}"),
+		SingleValue::Secret(secret,) if style.reveal_secrets => {
+			w.write_all(secret.expose_secret().as_bytes(),)
+		},
+		SingleValue::Secret(_,) => w.write_all(REDACTED.as_bytes(),),
+		SingleValue::Duration(duration,) => {
+			w.write_all(render_duration(*duration,).as_bytes(),)
+		},
+		SingleValue::IpAddr(addr,) => write!(w, "{addr}"),
+		SingleValue::SocketAddr(addr,) => write!(w, "{addr}"),
+		SingleValue::Path(path,) => {
+			w.write_all(path.to_string_lossy().as_bytes(),)
+		},
+		SingleValue::Deferred { resolver, arg, } => {
+			w.write_all(placeholder(resolver, arg,).as_bytes(),)
+		},
+	}
+}
+
+/// [`write_single`], with text which would not read back as itself, because
+/// of comment markers, quotes, surrounding whitespace or a trailing backslash,
+/// written as a quoted string
+fn write_conf_single(
+	key: &str,
+	value: &SingleValue,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	let mut text = Vec::new();
+	write_single(key, value, style, &mut text,)?;
+	let text = String::from_utf8_lossy(&text,);
+	let is_text = matches!(
+		value,
+		SingleValue::String(_,)
+			| SingleValue::Secret(_,)
+			| SingleValue::Path(_,)
+	);
+	if !is_text
+		|| !text.starts_with('"',)
+			&& !text.ends_with('\\',)
+			&& parse_value(&text, 0, 0, &ParseOptions::default(),)
+				.is_ok_and(|parsed| parsed == text,)
+	{
+		return w.write_all(text.as_bytes(),);
 	}
+	write_conf_quoted(&text, w,)
 }
 
-fn render_scalar(value: &Value<SingleValue,>,) -> String {
+/// `text` between double quotes, escaped the way the conf format reads it
+fn write_conf_quoted(text: &str, w: &mut impl io::Write,) -> io::Result<(),> {
+	w.write_all(b"\"",)?;
+	for ch in text.chars() {
+		match ch {
+			'"' => w.write_all(b"\\\"",)?,
+			'\\' => w.write_all(b"\\\\",)?,
+			'\n' => w.write_all(b"\\n",)?,
+			'\t' => w.write_all(b"\\t",)?,
+			ch => write!(w, "{ch}")?,
+		}
+	}
+	w.write_all(b"\"",)
+}
+
+/// copy of the leaf at the dotted `key` where every value the transform
+/// rewrites is replaced by a string of its output
+fn transform_scalar(
+	key: &str,
+	value: &Value<SingleValue,>,
+	transform: &ValueTransform,
+) -> Value<SingleValue,> {
+	let transform_single = |value: &SingleValue| {
+		transform
+			.apply(key, value,)
+			.map_or_else(|| value.clone(), SingleValue::String,)
+	};
+
 	match value {
-		Value::Single(inner,) => render_single(inner,),
+		Value::Single(single,) => Value::Single(transform_single(single,),),
+		Value::Collection(items,) => {
+			Value::Collection(items.iter().map(transform_single,).collect(),)
+		},
+	}
+}
+
+/// `key` is the dotted key of `value`, for the value transform
+fn write_scalar(
+	key: &str,
+	value: &Value<SingleValue,>,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	match value {
+		Value::Single(inner,) => write_conf_single(key, inner, style, w,),
+		// the only way to write an empty list
+		Value::Collection(entries,) if entries.is_empty() => {
+			w.write_all(b"\"\"",)
+		},
 		Value::Collection(entries,) => {
-			entries.iter().map(render_single,).collect::<Vec<_,>>().join(",",)
+			for (idx, entry,) in entries.iter().enumerate() {
+				if idx > 0 {
+					w.write_all(style.collection_separator.as_bytes(),)?;
+				}
+				let mut text = Vec::new();
+				write_single(key, entry, style, &mut text,)?;
+				write_element(entry, &text, w,)?;
+				if entries.len() == 1 {
+					write_lone_terminator(&text, w,)?;
+				}
+			}
+			Ok((),)
 		},
 	}
 }
 
-fn conf_map_as_conf(conf_map: &ConfMap,) -> String {
-	fn collect_entries(
-		conf_map: &ConfMap,
-		prefix: &str,
-		output: &mut Vec<String,>,
+/// `text` of a collection element. text which would not read back as itself,
+/// opening with a quote or ending with a backslash which would escape the
+/// separator say, is written as a quoted string, and commas inside other text
+/// `\,` so they are not read as separators
+fn write_element(
+	value: &SingleValue,
+	text: &[u8],
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	if !matches!(
+		value,
+		SingleValue::String(_,)
+			| SingleValue::Secret(_,)
+			| SingleValue::Path(_,)
 	) {
-		for (key, value,) in conf_map.iter() {
-			match value {
-				ConfValue::Scalar(scalar,) => {
-					let full_key = if prefix.is_empty() {
-						key.clone()
-					} else {
-						format!("{prefix}.{key}")
-					};
-					output.push(format!(
-						"{full_key} = {}",
-						render_scalar(scalar,),
-					),);
-				},
-				ConfValue::Map(children,) => {
-					let nested_prefix = if prefix.is_empty() {
-						key.clone()
-					} else {
-						format!("{prefix}.{key}")
-					};
-					collect_entries(
-						&ConfMap::from(children,),
-						&nested_prefix,
-						output,
-					);
-				},
+		return w.write_all(text,);
+	}
+	let element = String::from_utf8_lossy(text,);
+	if element.starts_with('"',)
+		|| element.ends_with('\\',)
+		|| !parse_value(&element, 0, 0, &ParseOptions::default(),)
+			.is_ok_and(|parsed| parsed == element,)
+	{
+		return write_conf_quoted(&element, w,);
+	}
+	for part in text.split_inclusive(|byte| *byte == b',',) {
+		match part.strip_suffix(b",",) {
+			Some(part,) => {
+				w.write_all(part,)?;
+				w.write_all(b"\\,",)?;
+			},
+			None => w.write_all(part,)?,
+		}
+	}
+	Ok((),)
+}
+
+/// a lone empty element is written as `""`, which alone reads as an empty
+/// list, so it is followed by the trailing comma a list ignores
+fn write_lone_terminator(
+	text: &[u8],
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	if text.is_empty() { w.write_all(b",",) } else { Ok((),) }
+}
+
+/// `prefix` holds the dotted path of `map` and is restored before returning.
+/// `first` is cleared once a line has been written. lists whose dotted key is
+/// in `repeatable` get a line per element
+fn write_conf(
+	map: &BTreeMap<String, ConfValue,>,
+	repeatable: &BTreeSet<String,>,
+	prefix: &mut String,
+	first: &mut bool,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	for (key, value,) in map {
+		let prefix_len = prefix.len();
+		if !prefix.is_empty() {
+			prefix.push('.',);
+		}
+		prefix.push_str(key,);
+
+		match value {
+			ConfValue::Scalar(scalar,) => {
+				write_conf_leaf(prefix, scalar, repeatable, first, style, w,)?
+			},
+			ConfValue::Map(children,) => {
+				write_conf(children, repeatable, prefix, first, style, w,)?
+			},
+		}
+
+		prefix.truncate(prefix_len,);
+	}
+	Ok((),)
+}
+
+/// [`write_conf`] with the leaves whose dotted keys are in `order` first, in
+/// that order. keys of `order` which are no leaf of `map` are skipped
+fn write_conf_ordered(
+	map: &BTreeMap<String, ConfValue,>,
+	order: &[String],
+	repeatable: &BTreeSet<String,>,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	let mut first = true;
+	let mut written = BTreeSet::new();
+	for key in order {
+		if let Some(ConfValue::Scalar(scalar,),) =
+			TreeValue::get_dotted(map, key,)
+			&& written.insert(key.as_str(),)
+		{
+			write_conf_leaf(key, scalar, repeatable, &mut first, style, w,)?;
+		}
+	}
+	for (key, scalar,) in ConfMapRef::from(map,).iter_leaves() {
+		if !written.contains(key.as_str(),) {
+			write_conf_leaf(&key, scalar, repeatable, &mut first, style, w,)?;
+		}
+	}
+	Ok((),)
+}
+
+/// lines of the leaf at the dotted `key`, a line per element for lists in
+/// `repeatable`
+fn write_conf_leaf(
+	key: &str,
+	scalar: &Value<SingleValue,>,
+	repeatable: &BTreeSet<String,>,
+	first: &mut bool,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	match scalar {
+		Value::Collection(items,)
+			if !items.is_empty() && repeatable.contains(key,) =>
+		{
+			for item in items {
+				if !std::mem::take(first,) {
+					w.write_all(b"\n",)?;
+				}
+				write!(w, "{key} = ")?;
+				let mut text = Vec::new();
+				write_single(key, item, style, &mut text,)?;
+				write_element(item, &text, w,)?;
+				write_lone_terminator(&text, w,)?;
+			}
+		},
+		scalar => {
+			if !std::mem::take(first,) {
+				w.write_all(b"\n",)?;
 			}
+			write!(w, "{key} = ")?;
+			write_scalar(key, scalar, style, w,)?;
+		},
+	}
+	Ok((),)
+}
+
+fn write_env(
+	map: &BTreeMap<String, ConfValue,>,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	for (idx, (key, scalar,),) in
+		ConfMapRef::from(map,).iter_leaves().enumerate()
+	{
+		if idx > 0 {
+			w.write_all(b"\n",)?;
+		}
+		let name = var_name(&key,);
+		if name.starts_with(|c: char| c.is_ascii_digit(),) {
+			w.write_all(b"_",)?;
+		}
+		write!(w, "{name}=")?;
+
+		let mut text = Vec::new();
+		match scalar {
+			Value::Single(single,) => {
+				write_single(&key, single, style, &mut text,)?
+			},
+			Value::Collection(items,) => {
+				for (idx, item,) in items.iter().enumerate() {
+					if idx > 0 {
+						text.extend_from_slice(
+							style.collection_separator.as_bytes(),
+						);
+					}
+					write_single(&key, item, style, &mut text,)?;
+				}
+			},
 		}
+		write_shell_word(&String::from_utf8_lossy(&text,), w,)?;
 	}
+	Ok((),)
+}
 
-	let mut lines = Vec::new();
-	collect_entries(conf_map, "", &mut lines,);
-	lines.join("\n",)
+/// bare when every character is one a shell takes literally, single quoted
+/// otherwise with each `'` written `'\''`
+fn write_shell_word(s: &str, w: &mut impl io::Write,) -> io::Result<(),> {
+	if !s.is_empty()
+		&& s.chars()
+			.all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c,),)
+	{
+		return w.write_all(s.as_bytes(),);
+	}
+	w.write_all(b"'",)?;
+	w.write_all(s.replace('\'', r"'\''",).as_bytes(),)?;
+	w.write_all(b"'",)
 }
 
-fn conf_map_as_json(conf_map: &ConfMap,) -> String {
-	fn render_map(conf_map: &ConfMap, indent: usize,) -> String {
-		let indent_str = "\t".repeat(indent,);
-		let child_indent = "\t".repeat(indent + 1,);
-		let mut parts = Vec::new();
+/// `prefix` holds the dotted path of `map` and is restored before returning
+fn write_json(
+	map: &BTreeMap<String, ConfValue,>,
+	prefix: &mut String,
+	depth: usize,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	if map.is_empty() {
+		return w.write_all(b"{}",);
+	}
 
-		for (key, value,) in conf_map.iter() {
-			let rendered = match value {
-				ConfValue::Scalar(scalar,) => {
-					format!("{child_indent}{key}: {}", render_scalar(scalar,),)
-				},
-				ConfValue::Map(children,) => {
-					let nested = ConfMap::from(children,);
-					let nested_rendered = render_map(&nested, indent + 1,);
-					format!("{child_indent}{key}: {nested_rendered}")
-				},
-			};
-			parts.push(rendered,);
+	w.write_all(b"{\n",)?;
+	for (idx, (key, value,),) in map.iter().enumerate() {
+		if idx > 0 {
+			w.write_all(b",\n",)?;
 		}
+		write_indent(depth + 1, style, w,)?;
+		write_quoted(key, w,)?;
+		w.write_all(b": ",)?;
 
-		let body = parts.join(",\n",);
-		format!("{{\n{body}\n{indent_str}}}")
+		let prefix_len = prefix.len();
+		if !prefix.is_empty() {
+			prefix.push('.',);
+		}
+		prefix.push_str(key,);
+		match value {
+			ConfValue::Scalar(scalar,) => {
+				write_typed_scalar(prefix, scalar, ShowFmt::Json, style, w,)?
+			},
+			ConfValue::Map(children,) => {
+				write_json(children, prefix, depth + 1, style, w,)?
+			},
+		}
+		prefix.truncate(prefix_len,);
 	}
+	w.write_all(b"\n",)?;
+	write_indent(depth, style, w,)?;
+	w.write_all(b"}",)
+}
 
-	render_map(conf_map, 0,)
+/// `path` holds the segments of `map` and is restored before returning.
+/// `first` is cleared once anything has been written. the values of a table
+/// come before its subtables, which only get a header of their own when they
+/// hold values or are empty
+fn write_toml<'a,>(
+	map: &'a BTreeMap<String, ConfValue,>,
+	path: &mut Vec<&'a str,>,
+	first: &mut bool,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	let has_values =
+		map.values().any(|value| matches!(value, ConfValue::Scalar(_,)),);
+	if !path.is_empty() && (has_values || map.is_empty()) {
+		if !std::mem::take(first,) {
+			w.write_all(b"\n\n",)?;
+		}
+		w.write_all(b"[",)?;
+		for (idx, segment,) in path.iter().enumerate() {
+			if idx > 0 {
+				w.write_all(b".",)?;
+			}
+			write_toml_key(segment, w,)?;
+		}
+		w.write_all(b"]",)?;
+	}
+
+	for (key, value,) in map {
+		if let ConfValue::Scalar(scalar,) = value {
+			if !std::mem::take(first,) {
+				w.write_all(b"\n",)?;
+			}
+			write_toml_key(key, w,)?;
+			w.write_all(b" = ",)?;
+			path.push(key,);
+			let dotted_key = path.join(".",);
+			path.pop();
+			write_typed_scalar(&dotted_key, scalar, ShowFmt::Toml, style, w,)?;
+		}
+	}
+	for (key, value,) in map {
+		if let ConfValue::Map(children,) = value {
+			path.push(key,);
+			write_toml(children, path, first, style, w,)?;
+			path.pop();
+		}
+	}
+	Ok((),)
+}
+
+/// bare when it only consists of ascii letters, digits, `_` and `-`
+fn write_toml_key(key: &str, w: &mut impl io::Write,) -> io::Result<(),> {
+	if !key.is_empty()
+		&& key
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-',)
+	{
+		w.write_all(key.as_bytes(),)
+	} else {
+		write_quoted(key, w,)
+	}
+}
+
+/// a value as json and toml, `fmt`, write it. collections become arrays
+fn write_typed_scalar(
+	key: &str,
+	value: &Value<SingleValue,>,
+	fmt: ShowFmt,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	match value {
+		Value::Single(single,) => {
+			write_typed_single(key, single, fmt, style, w,)
+		},
+		Value::Collection(items,) => {
+			w.write_all(b"[",)?;
+			for (idx, item,) in items.iter().enumerate() {
+				if idx > 0 {
+					w.write_all(b", ",)?;
+				}
+				write_typed_single(key, item, fmt, style, w,)?;
+			}
+			w.write_all(b"]",)
+		},
+	}
 }
 
-fn conf_map_as_debug(conf_map: &ConfMap,) -> String {
-	format!("{conf_map:#?}")
+/// bools and numbers are written bare, floats always with a fraction, and
+/// everything else, transformed values included, as a string. toml integers
+/// are 64-bit signed, so larger ones are written as their digits there
+fn write_typed_single(
+	key: &str,
+	value: &SingleValue,
+	fmt: ShowFmt,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	let is_text = style
+		.value_transform
+		.as_ref()
+		.is_some_and(|t| t.apply(key, value,).is_some(),)
+		|| match value {
+			SingleValue::UInteger(num,) => {
+				fmt == ShowFmt::Toml && i64::try_from(*num,).is_err()
+			},
+			SingleValue::Bool(_,)
+			| SingleValue::Integer(_,)
+			| SingleValue::Long(_,)
+			| SingleValue::Float(_,) => false,
+			_ => true,
+		};
+	let mut text = Vec::new();
+	write_single(key, value, style, &mut text,)?;
+	if is_text {
+		write_quoted(&String::from_utf8_lossy(&text,), w,)
+	} else {
+		w.write_all(&text,)?;
+		if matches!(value, SingleValue::Float(_,)) && !text.contains(&b'.',) {
+			w.write_all(b".0",)?;
+		}
+		Ok((),)
+	}
+}
+
+/// quoted, with quotes, backslashes and control characters escaped the way
+/// both json strings and toml basic strings read them
+fn write_quoted(s: &str, w: &mut impl io::Write,) -> io::Result<(),> {
+	w.write_all(b"\"",)?;
+	for ch in s.chars() {
+		match ch {
+			'"' => w.write_all(b"\\\"",)?,
+			'\\' => w.write_all(b"\\\\",)?,
+			'\n' => w.write_all(b"\\n",)?,
+			'\r' => w.write_all(b"\\r",)?,
+			'\t' => w.write_all(b"\\t",)?,
+			ch if ch.is_control() => write!(w, "\\u{:04x}", ch as u32)?,
+			ch => write!(w, "{ch}")?,
+		}
+	}
+	w.write_all(b"\"",)
+}
+
+fn write_indent(
+	depth: usize,
+	style: &ShowStyle,
+	w: &mut impl io::Write,
+) -> io::Result<(),> {
+	for _ in 0..depth {
+		w.write_all(style.indent.as_bytes(),)?;
+	}
+	Ok((),)
 }
 
 #[cfg(test)]
@@ -126,46 +982,72 @@ mod tests {
 	use crate::parser::conf::SingleValue;
 	use crate::parser::conf::Value;
 
+	fn conf_map_as_conf(conf_map: &ConfMap,) -> String {
+		conf_map.render_as(ShowFmt::Conf,)
+	}
+
+	fn conf_map_as_json(conf_map: &ConfMap,) -> String {
+		conf_map.render_as(ShowFmt::Json,)
+	}
+
+	fn conf_map_as_toml(conf_map: &ConfMap,) -> String {
+		conf_map.render_as(ShowFmt::Toml,)
+	}
+
+	fn conf_map_as_env(conf_map: &ConfMap,) -> String {
+		conf_map.render_as(ShowFmt::Env,)
+	}
+
+	fn conf_map_as_debug(conf_map: &ConfMap,) -> String {
+		conf_map.render_as(ShowFmt::Debug,)
+	}
+
 	fn sample_conf_map() -> ConfMap {
-		let mut root = ConfMap::new();
-		root.insert(
-			"endpoint".to_string(),
-			ConfValue::Scalar(Value::Single(SingleValue::String(
-				"localhost:3000".to_string(),
-			),),),
-		);
-		root.insert(
-			"debug".to_string(),
-			ConfValue::Scalar(Value::Single(SingleValue::Bool(true,),),),
-		);
-		let mut log_map = ConfMap::new();
-		log_map.insert(
-			"file".to_string(),
-			ConfValue::Scalar(Value::Single(SingleValue::String(
-				"/var/log/console.log".to_string(),
-			),),),
-		);
-		log_map.insert(
-			"name".to_string(),
-			ConfValue::Scalar(Value::Single(SingleValue::String(
-				"default.log".to_string(),
-			),),),
-		);
-		root.insert("log".to_string(), ConfValue::Map(log_map.into_inner(),),);
-		let mut net_map = ConfMap::new();
-		let mut ipv4_map = ConfMap::new();
-		ipv4_map.insert(
-			"ip_local_reserved_ports".to_string(),
-			ConfValue::Scalar(Value::Collection(vec![
-				SingleValue::Integer(8080,),
-				SingleValue::Integer(9148,),
-			],),),
+		crate::conf_map! {
+			"endpoint" => "localhost:3000",
+			"debug" => true,
+			"log.file" => "/var/log/console.log",
+			"log.name" => "default.log",
+			"net.ipv4.ip_local_reserved_ports" => [8080, 9148],
+		}
+	}
+
+	#[test]
+	fn conf_map_as_env_formats_entries() {
+		let output = conf_map_as_env(&sample_conf_map(),);
+		assert_eq!(
+			r"DEBUG=true
+ENDPOINT=localhost:3000
+LOG_FILE=/var/log/console.log
+LOG_NAME=default.log
+NET_IPV4_IP_LOCAL_RESERVED_PORTS=8080,9148",
+			output
 		);
-		net_map
-			.insert("ipv4".to_string(), ConfValue::Map(ipv4_map.into_inner(),),);
-		root.insert("net".to_string(), ConfValue::Map(net_map.into_inner(),),);
+	}
 
-		root
+	#[test]
+	fn conf_map_as_env_quotes_values_and_fixes_names() {
+		let conf_map = crate::conf_map! {
+			"motd" => "it's a \"day\"",
+			"empty" => "",
+			"1st.max-conns" => 5,
+			"hosts" => ["a b", "c"],
+		};
+		let style = ShowStyle {
+			collection_separator: " ".to_string(),
+			..ShowStyle::default()
+		};
+		let mut output = Vec::new();
+		conf_map
+			.write_fmt_streaming(ShowFmt::Env, &style, &mut output,)
+			.unwrap();
+		assert_eq!(
+			String::from_utf8(output,).unwrap(),
+			r#"_1ST_MAX_CONNS=5
+EMPTY=''
+HOSTS='a b c'
+MOTD='it'\''s a "day"'"#
+		);
 	}
 
 	#[test]
@@ -181,27 +1063,214 @@ net.ipv4.ip_local_reserved_ports = 8080,9148",
 		);
 	}
 
+	#[test]
+	fn deep_wide_maps_render_every_leaf_once() {
+		// leaf `n` sits at `d<hundreds>.d<tens>.d<ones>.mid.low.leaf`, so
+		// there are 10 + 100 + 3 * 1000 sections below the top level
+		let mut entries = BTreeMap::new();
+		for n in 0..1000 {
+			let mut section = &mut entries;
+			for segment in [
+				format!("d{}", n / 100),
+				format!("d{}", n / 10 % 10),
+				format!("d{}", n % 10),
+				"mid".to_string(),
+				"low".to_string(),
+			] {
+				let ConfValue::Map(children,) = section
+					.entry(segment,)
+					.or_insert_with(|| ConfValue::Map(BTreeMap::new(),),)
+				else {
+					unreachable!("sections only")
+				};
+				section = children;
+			}
+			section.insert(
+				"leaf".to_string(),
+				ConfValue::Scalar(Value::Single(SingleValue::Integer(n,),),),
+			);
+		}
+		let conf = ConfMap::from(&entries,);
+
+		assert_eq!(conf_map_as_conf(&conf,).lines().count(), 1000);
+		// a line per leaf, and an opening and a closing line per section
+		// including the top level
+		assert_eq!(conf_map_as_json(&conf,).lines().count(), 1000 + 2 * 3111);
+	}
+
+	#[test]
+	fn conf_format_round_trips() {
+		let schema = crate::schema_map! {
+			"endpoint" => String,
+			"debug" => Bool,
+			"log.file" => String,
+			"log.name" => String,
+			"net.ipv4.ip_local_reserved_ports" => [Integer, Integer],
+		};
+		crate::testing::assert_round_trip(&sample_conf_map(), &schema,)
+			.unwrap_or_else(|report| panic!("{report}"),);
+	}
+
+	#[test]
+	fn inner_whitespace_survives_a_round_trip() {
+		let schema = crate::schema_map! { "motd" => String, "path" => String };
+		let conf = crate::parser::conf::parse_str(
+			"motd = Hello  world  \npath = C:\\My\tFiles\n",
+			&schema,
+		)
+		.unwrap();
+		assert_eq!(conf.get_str("motd"), Some("Hello  world"));
+
+		let output = conf_map_as_conf(&conf,);
+		assert_eq!(output, "motd = Hello  world\npath = C:\\My\tFiles");
+		assert_eq!(
+			crate::parser::conf::parse_str(&output, &schema,).unwrap(),
+			conf
+		);
+	}
+
 	#[test]
 	fn conf_map_as_json_nested_structure() {
 		let output = conf_map_as_json(&sample_conf_map(),);
 		assert_eq!(
-			r"{
-	debug: true,
-	endpoint: localhost:3000,
-	log: {
-		file: /var/log/console.log,
-		name: default.log
+			r#"{
+	"debug": true,
+	"endpoint": "localhost:3000",
+	"log": {
+		"file": "/var/log/console.log",
+		"name": "default.log"
 	},
-	net: {
-		ipv4: {
-			ip_local_reserved_ports: 8080,9148
+	"net": {
+		"ipv4": {
+			"ip_local_reserved_ports": [8080, 9148]
 		}
 	}
-}",
+}"#,
 			output
 		);
 	}
 
+	#[test]
+	fn conf_map_as_toml_emits_tables_after_values() {
+		let output = conf_map_as_toml(&sample_conf_map(),);
+		assert_eq!(
+			r#"debug = true
+endpoint = "localhost:3000"
+
+[log]
+file = "/var/log/console.log"
+name = "default.log"
+
+[net.ipv4]
+ip_local_reserved_ports = [8080, 9148]"#,
+			output
+		);
+	}
+
+	#[cfg(feature = "toml")]
+	#[test]
+	fn toml_format_parses_back_to_the_same_structure() {
+		let mut conf_map = sample_conf_map();
+		conf_map.insert(
+			"odd key".to_string(),
+			ConfValue::Scalar(Value::Single(SingleValue::String(
+				"say \"hi\"\n".to_string(),
+			),),),
+		);
+		conf_map.insert(
+			"ratio".to_string(),
+			ConfValue::Scalar(Value::Single(SingleValue::Float(3.0,),),),
+		);
+		conf_map.insert("empty".to_string(), ConfValue::Map(BTreeMap::new(),),);
+
+		let output = conf_map_as_toml(&conf_map,);
+		let parsed: ::toml::Value = ::toml::from_str(&output,).unwrap();
+		assert_eq!(parsed, conf_map.to_toml_value());
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn json_format_parses_back_to_the_same_structure() {
+		let mut conf_map = sample_conf_map();
+		conf_map.insert(
+			"quote".to_string(),
+			ConfValue::Scalar(Value::Single(SingleValue::String(
+				"say \"hi\" \\ \n\t\u{1}".to_string(),
+			),),),
+		);
+		conf_map.insert(
+			"ratio".to_string(),
+			ConfValue::Scalar(Value::Single(SingleValue::Float(-0.5,),),),
+		);
+		conf_map.insert("empty".to_string(), ConfValue::Map(BTreeMap::new(),),);
+
+		let output = conf_map_as_json(&conf_map,);
+		let parsed: serde_json::Value = serde_json::from_str(&output,).unwrap();
+		assert_eq!(parsed, conf_map.to_json_value());
+	}
+
+	#[test]
+	fn wide_integers_are_written_as_numbers() {
+		let mut conf_map = ConfMap::new();
+		for (key, value,) in [
+			("long", SingleValue::Long(-5_000_000_000,),),
+			("size", SingleValue::UInteger(4_000_000_000,),),
+			("max", SingleValue::UInteger(u64::MAX,),),
+		] {
+			conf_map.insert(
+				key.to_string(),
+				ConfValue::Scalar(Value::Single(value,),),
+			);
+		}
+
+		assert_eq!(
+			conf_map_as_json(&conf_map,),
+			"{\n\t\"long\": -5000000000,\n\t\"max\": \
+			 18446744073709551615,\n\t\"size\": 4000000000\n}"
+		);
+		assert_eq!(
+			conf_map_as_toml(&conf_map,),
+			"long = -5000000000\nmax = \"18446744073709551615\"\nsize = \
+			 4000000000"
+		);
+	}
+
+	#[test]
+	fn diffs_as_json_keep_value_types() {
+		let single = |value| Value::Single(value,);
+		let diffs = [
+			ConfDiff::Added {
+				key:   "debug".to_string(),
+				value: single(SingleValue::Bool(true,),),
+			},
+			ConfDiff::Removed {
+				key:   "ports".to_string(),
+				value: Value::Collection(vec![
+					SingleValue::Integer(80,),
+					SingleValue::Integer(443,),
+				],),
+			},
+			ConfDiff::Changed {
+				key: "motd".to_string(),
+				old: single(SingleValue::from("say \"hi\"",),),
+				new: single(SingleValue::Float(1.0,),),
+			},
+		];
+
+		let json: Vec<_,> = diffs.iter().map(ConfDiff::to_json,).collect();
+		assert_eq!(
+			json,
+			[
+				r#"{"change":"added","key":"debug","value":true}"#,
+				r#"{"change":"removed","key":"ports","value":[80, 443]}"#,
+				concat!(
+					r#"{"change":"changed","key":"motd","#,
+					r#""old":"say \"hi\"","new":1.0}"#,
+				),
+			]
+		);
+	}
+
 	#[test]
 	fn conf_map_as_debug_outputs_debug_string() {
 		let mut conf_map = sample_conf_map();
@@ -216,4 +1285,303 @@ net.ipv4.ip_local_reserved_ports = 8080,9148",
 
 		conf_map.show_as(ShowFmt::Debug,);
 	}
+
+	fn render_debug(conf_map: &ConfMap, style: &ShowStyle,) -> String {
+		let mut output = Vec::new();
+		conf_map
+			.write_fmt_streaming(ShowFmt::Debug, style, &mut output,)
+			.unwrap();
+		String::from_utf8(output,).unwrap()
+	}
+
+	#[test]
+	fn debug_of_small_maps_is_unchanged() {
+		let conf_map = sample_conf_map();
+		assert_eq!(conf_map_as_debug(&conf_map), format!("{conf_map:#?}"));
+		assert_eq!(
+			render_debug(
+				&conf_map,
+				&ShowStyle::default()
+					.max_debug_depth(None)
+					.max_children_shown(None),
+			),
+			format!("{conf_map:#?}")
+		);
+	}
+
+	#[test]
+	fn debug_elides_beyond_limits() {
+		let conf_map = sample_conf_map();
+
+		let narrow = render_debug(
+			&conf_map,
+			&ShowStyle::default().max_children_shown(Some(2,),),
+		);
+		assert!(narrow.contains("\"debug\": Scalar("));
+		assert!(narrow.contains("\"endpoint\": Scalar("));
+		assert!(!narrow.contains("\"log\""));
+		assert!(narrow.contains("    … 2 more entries,\n}"));
+
+		let shallow = render_debug(
+			&conf_map,
+			&ShowStyle::default().max_debug_depth(Some(2,),),
+		);
+		assert!(
+			shallow.contains("\"log\": Map(\n        {\n            \"file\"")
+		);
+		assert!(shallow.contains("\"ipv4\": Map(… 1 entry)"));
+	}
+
+	#[test]
+	fn debug_of_huge_maps_is_summarized() {
+		let mut conf_map = ConfMap::new();
+		for idx in 0..=DEBUG_SUMMARY_LEAVES {
+			conf_map.insert(
+				format!("key{idx}"),
+				ConfValue::Scalar(Value::Single(SingleValue::Integer(1,),),),
+			);
+		}
+		assert_eq!(
+			format!("{conf_map:?}"),
+			"ConfMap { leaves: 1001, top_level_keys: 1001, .. }"
+		);
+
+		let shown = conf_map_as_debug(&conf_map,);
+		assert!(shown.contains("    … 901 more entries,\n}"));
+	}
+
+	#[test]
+	fn write_as_fills_any_writer() {
+		let conf_map = sample_conf_map();
+		for fmt in [
+			ShowFmt::Conf,
+			ShowFmt::Json,
+			ShowFmt::Toml,
+			ShowFmt::Debug,
+			ShowFmt::Env,
+		] {
+			let mut output: Vec<u8,> = Vec::new();
+			conf_map.write_as(fmt, &mut output,).unwrap();
+			assert_eq!(
+				String::from_utf8(output,).unwrap(),
+				conf_map.render_as(fmt)
+			);
+		}
+
+		let mut output: Vec<u8,> = Vec::new();
+		conf_map.write_as(ShowFmt::Conf, &mut output,).unwrap();
+		assert!(
+			output.starts_with(b"debug = true\nendpoint = localhost:3000\n")
+		);
+	}
+	/// records the size of every write reaching the underlying writer
+	#[derive(Default,)]
+	struct ChunkTracker {
+		total:         usize,
+		largest_chunk: usize,
+	}
+
+	impl io::Write for ChunkTracker {
+		fn write(&mut self, buf: &[u8],) -> io::Result<usize,> {
+			self.total += buf.len();
+			self.largest_chunk = self.largest_chunk.max(buf.len(),);
+			Ok(buf.len(),)
+		}
+
+		fn flush(&mut self,) -> io::Result<(),> {
+			Ok((),)
+		}
+	}
+
+	#[test]
+	fn streaming_large_map_writes_bounded_chunks() {
+		let mut root = ConfMap::new();
+		for section in 0..200 {
+			let children = (0..200)
+				.map(|idx| {
+					(
+						format!("key{idx}"),
+						ConfValue::Scalar(Value::Collection(vec![
+							SingleValue::String("x".repeat(32,),),
+							SingleValue::Integer(idx,),
+						],),),
+					)
+				},)
+				.collect();
+			root.insert(format!("section{section}"), ConfValue::Map(children,),);
+		}
+
+		for fmt in [ShowFmt::Conf, ShowFmt::Json,] {
+			let mut tracker = ChunkTracker::default();
+			root.write_fmt_streaming(fmt, &ShowStyle::default(), &mut tracker,)
+				.unwrap();
+			assert!(tracker.total > 40 * STREAM_BUFFER);
+			assert!(tracker.largest_chunk <= STREAM_BUFFER);
+		}
+	}
+
+	#[test]
+	fn style_controls_separator_and_indent() {
+		let style = ShowStyle {
+			indent: "  ".to_string(),
+			collection_separator: ", ".to_string(),
+			..ShowStyle::default()
+		};
+		let mut output = Vec::new();
+		sample_conf_map()
+			.write_fmt_streaming(ShowFmt::Json, &style, &mut output,)
+			.unwrap();
+		let output = String::from_utf8(output,).unwrap();
+		assert!(
+			output.contains(
+				"\n      \"ip_local_reserved_ports\": [8080, 9148]\n"
+			)
+		);
+
+		let mut output = Vec::new();
+		sample_conf_map()
+			.write_fmt_streaming(ShowFmt::Conf, &style, &mut output,)
+			.unwrap();
+		let output = String::from_utf8(output,).unwrap();
+		assert!(
+			output.ends_with("\nnet.ipv4.ip_local_reserved_ports = 8080, 9148")
+		);
+	}
+
+	#[test]
+	fn deferred_values_render_as_placeholders() {
+		let conf = crate::parser::conf::parse_str(
+			"db.password = @secret(prod/db/password)\n",
+			crate::parser::schema::parse_str("db.password -> @String\n",)
+				.unwrap(),
+		)
+		.unwrap();
+		assert_eq!(
+			conf_map_as_conf(&conf),
+			"db.password = @secret(prod/db/password)"
+		);
+	}
+
+	#[test]
+	fn equivalent_inputs_canonicalize_identically() {
+		let schema = crate::parser::schema::parse_str(
+			"server.port -> Integer\nserver.host -> String\nratio -> \
+			 Float\nports -> Integer, Integer\ndebug -> Bool\n",
+		)
+		.unwrap();
+		let parse =
+			|input| crate::parser::conf::parse_str(input, &schema,).unwrap();
+
+		let tidy = parse(
+			"debug = true\nports = 80, 443\nratio = 0.5\nserver.host = \
+			 a\nserver.port = 5\n",
+		);
+		let messy = parse(
+			"server.port   =+5\n  ratio = 0.50\nports=080 ,+443\ndebug = \
+			 true\nserver.host = a\n",
+		);
+		assert_eq!(
+			tidy.canonicalize(),
+			"debug = true\nports = 80, 443\nratio = 0.5\nserver.host = \
+			 a\nserver.port = 5\n"
+		);
+		assert_eq!(messy.canonicalize(), tidy.canonicalize());
+		assert_eq!(messy.fingerprint(), tidy.fingerprint());
+
+		let other = parse("debug = true\nports = 443, 80\n",);
+		assert_ne!(other.fingerprint(), tidy.fingerprint());
+		assert_eq!(ConfMap::new().canonicalize(), "");
+	}
+
+	#[test]
+	fn value_transform_applies_to_every_format() {
+		let conf = crate::conf_map! {
+			"db.password" => "hunter2",
+			"db.port" => 5432,
+			"cache.password" => "swordfish",
+			"peers" => ["alpha", "beta"],
+		};
+		let style = ShowStyle::default().value_transform(|key, value| {
+			if key.ends_with(".password",) {
+				return Some("<hidden>".to_string(),);
+			}
+			match value {
+				SingleValue::String(s,) if s.len() > 4 => {
+					Some(format!("{}...", &s[..4]),)
+				},
+				_ => None,
+			}
+		},);
+
+		for fmt in [
+			ShowFmt::Conf,
+			ShowFmt::Json,
+			ShowFmt::Toml,
+			ShowFmt::Debug,
+			ShowFmt::Env,
+		] {
+			let mut output = Vec::new();
+			conf.write_fmt_streaming(fmt, &style, &mut output,).unwrap();
+			let output = String::from_utf8(output,).unwrap();
+
+			assert!(!output.contains("hunter2"), "{fmt:?}: {output}");
+			assert!(!output.contains("swordfish"), "{fmt:?}: {output}");
+			assert_eq!(output.matches("<hidden>").count(), 2, "{fmt:?}");
+			assert!(output.contains("alph..."), "{fmt:?}: {output}");
+			assert!(output.contains("beta"), "{fmt:?}: {output}");
+			assert!(output.contains("5432"), "{fmt:?}: {output}");
+		}
+	}
+
+	#[test]
+	fn sections_render_alike_borrowed_or_owned() {
+		let conf = crate::conf_map! {
+			"a.b.c.d" => "deep",
+			"a.b.c.n" => 4,
+			"a.b.x" => [1, 2],
+			"a.top" => true,
+		};
+		let ConfValue::Map(section,) = conf.get("a",).unwrap() else {
+			panic!("expected a section");
+		};
+		let style = ShowStyle::default().value_transform(|key, _| {
+			(key == "b.c.d").then(|| "*".to_string(),)
+		},);
+
+		for fmt in [
+			ShowFmt::Conf,
+			ShowFmt::Json,
+			ShowFmt::Toml,
+			ShowFmt::Debug,
+			ShowFmt::Env,
+		] {
+			for style in [&ShowStyle::default(), &style,] {
+				// the plain `Debug` of a map shows its labels and sources too
+				if fmt == ShowFmt::Debug && style.value_transform.is_none() {
+					continue;
+				}
+				let mut borrowed = Vec::new();
+				ConfMapRef::from(section,)
+					.write_fmt_streaming(fmt, style, &mut borrowed,)
+					.unwrap();
+				let mut owned = Vec::new();
+				ConfMap::from(section,)
+					.write_fmt_streaming(fmt, style, &mut owned,)
+					.unwrap();
+				assert_eq!(
+					String::from_utf8(borrowed,).unwrap(),
+					String::from_utf8(owned,).unwrap(),
+					"{fmt:?}"
+				);
+			}
+		}
+
+		let mut output = Vec::new();
+		ConfMapRef::from(section,)
+			.write_fmt_streaming(ShowFmt::Debug, &style, &mut output,)
+			.unwrap();
+		let output = String::from_utf8(output,).unwrap();
+		assert!(output.contains("\"*\""), "{output}");
+		assert!(!output.contains("deep"), "{output}");
+	}
 }
@@ -0,0 +1,735 @@
+use crate::error::ParseError;
+use crate::parser::conf;
+use crate::parser::conf::ConfMap;
+use crate::parser::conf::ConfValue;
+use crate::parser::conf::SingleValue;
+use crate::parser::conf::SingleValueDiscriminants;
+use crate::parser::conf::Value;
+use crate::parser::core::TreeValue;
+use crate::parser::diff::ConfDiff;
+use crate::parser::schema::SchemaLeaf;
+use crate::parser::schema::SchemaMap;
+use crate::parser::schema::SchemaRef;
+use crate::parser::schema::SchemaValue;
+use crate::parser::schema::ValueRange;
+use crate::parser::secret::Secret;
+use crate::show::ShowFmt;
+use crate::show::ShowStyle;
+use proptest::prelude::Strategy;
+use proptest::test_runner::TestCaseError;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// return type of tests mixing parse errors and [`Mismatch`]es with `?`
+pub type TestResult = Result<(), Box<dyn Error,>,>;
+
+/// builds a [`ConfMap`] from dotted keys. a `[...]` value becomes a
+/// collection, anything else a single value. panics on a float which is not
+/// finite
+///
+/// ```
+/// # use dot_conf_parser::conf_map;
+/// let conf = conf_map! { "server.port" => 8080, "tags" => ["a", "b"] };
+/// assert!(conf.get("server.port").is_some());
+/// ```
+#[macro_export]
+macro_rules! conf_map {
+	(@insert $map:ident;) => {};
+	(@insert $map:ident; $key:expr => [$($item:expr),* $(,)?] $(, $($rest:tt)*)?) => {
+		$crate::testing::insert_dotted(
+			&mut *$map,
+			$key,
+			$crate::parser::conf::Value::Collection(vec![
+				$($crate::parser::conf::SingleValue::try_from($item)
+					.expect("conf_map! values are finite")),*
+			]),
+		);
+		$crate::conf_map!(@insert $map; $($($rest)*)?);
+	};
+	(@insert $map:ident; $key:expr => $value:expr $(, $($rest:tt)*)?) => {
+		$crate::testing::insert_dotted(
+			&mut *$map,
+			$key,
+			$crate::parser::conf::Value::Single(
+				$crate::parser::conf::SingleValue::try_from($value)
+					.expect("conf_map! values are finite"),
+			),
+		);
+		$crate::conf_map!(@insert $map; $($($rest)*)?);
+	};
+	($($entries:tt)*) => {{
+		let mut map = $crate::parser::conf::ConfMap::new();
+		$crate::conf_map!(@insert map; $($entries)*);
+		map
+	}};
+}
+
+/// builds a [`SchemaMap`](crate::parser::schema::SchemaMap) from dotted keys
+/// and type names. `[...]` declares a collection
+///
+/// ```
+/// # use dot_conf_parser::schema_map;
+/// let schema = schema_map! { "server.port" => Integer, "tags" => [String, String] };
+/// assert!(schema.get("tags").is_some());
+/// ```
+#[macro_export]
+macro_rules! schema_map {
+	(@insert $map:ident;) => {};
+	(@insert $map:ident; $key:expr => [$($ty:ident),* $(,)?] $(, $($rest:tt)*)?) => {
+		$crate::testing::insert_dotted(
+			&mut *$map,
+			$key,
+			$crate::parser::schema::SchemaLeaf::from(
+				$crate::parser::conf::Value::Collection(vec![
+					$($crate::parser::conf::SingleValueDiscriminants::$ty),*
+				]),
+			),
+		);
+		$crate::schema_map!(@insert $map; $($($rest)*)?);
+	};
+	(@insert $map:ident; $key:expr => $ty:ident $(, $($rest:tt)*)?) => {
+		$crate::testing::insert_dotted(
+			&mut *$map,
+			$key,
+			$crate::parser::schema::SchemaLeaf::from(
+				$crate::parser::conf::Value::Single(
+					$crate::parser::conf::SingleValueDiscriminants::$ty,
+				),
+			),
+		);
+		$crate::schema_map!(@insert $map; $($($rest)*)?);
+	};
+	($($entries:tt)*) => {{
+		let mut map = $crate::parser::schema::SchemaMap::new();
+		$crate::schema_map!(@insert map; $($entries)*);
+		map
+	}};
+}
+
+/// like `assert_eq!`, but reports the differing leaves instead of two
+/// debug dumps
+#[macro_export]
+macro_rules! assert_conf_eq {
+	($left:expr, $right:expr $(,)?) => {
+		if let Err(report,) = $crate::testing::conf_eq(&$left, &$right,) {
+			panic!("{report}");
+		}
+	};
+}
+
+/// `Err` holds one line per differing leaf, `-` for `left` and `+` for
+/// `right`
+pub fn conf_eq(left: &ConfMap, right: &ConfMap,) -> Result<(), String,> {
+	let diffs = left.diff(right,);
+	if diffs.is_empty() {
+		return Ok((),);
+	}
+
+	let mut report = "conf maps differ (- left, + right):".to_string();
+	for diff in diffs {
+		report.push_str(&format!("\n  {diff}"),);
+	}
+	Err(report,)
+}
+
+/// inserts `value` at the dotted `key`, creating intermediate maps
+///
+/// # Panics
+///
+/// when a prefix of `key` already holds a value
+#[doc(hidden)]
+pub fn insert_dotted<T,>(
+	map: &mut BTreeMap<String, TreeValue<T,>,>,
+	key: &str,
+	value: T,
+) {
+	let mut segments: Vec<&str,> = key.split('.',).collect();
+	let last = segments.pop().expect("split yields at least one segment",);
+
+	let mut current = map;
+	for segment in segments {
+		let entry = current
+			.entry(segment.to_string(),)
+			.or_insert_with(|| TreeValue::Map(BTreeMap::new(),),);
+		current = match entry {
+			TreeValue::Map(children,) => children,
+			TreeValue::Scalar(_,) => {
+				panic!("'{segment}' of '{key}' is a value")
+			},
+		};
+	}
+	current.insert(last.to_string(), TreeValue::Scalar(value,),);
+}
+
+/// a typed lookup which found nothing or something of another type
+#[derive(Debug, Clone, PartialEq,)]
+pub struct Mismatch {
+	pub key:      String,
+	pub expected: &'static str,
+	pub found:    Option<ConfValue,>,
+}
+
+impl Display for Mismatch {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match &self.found {
+			Some(found,) => write!(
+				f,
+				"expected {} at '{}', found {found:?}",
+				self.expected, self.key
+			),
+			None => write!(
+				f,
+				"expected {} at '{}', found nothing",
+				self.expected, self.key
+			),
+		}
+	}
+}
+
+impl Error for Mismatch {}
+
+fn lookup<'a, T,>(
+	conf: &'a ConfMap,
+	key: &str,
+	expected: &'static str,
+	extract: impl FnOnce(&'a ConfValue,) -> Option<T,>,
+) -> Result<T, Mismatch,> {
+	let found = conf.get(key,);
+	found.and_then(extract,).ok_or_else(|| Mismatch {
+		key: key.to_string(),
+		expected,
+		found: found.cloned(),
+	},)
+}
+
+pub fn get_str<'a,>(
+	conf: &'a ConfMap,
+	key: &str,
+) -> Result<&'a str, Mismatch,> {
+	lookup(conf, key, "a string", ConfValue::as_str,)
+}
+
+pub fn get_bool(conf: &ConfMap, key: &str,) -> Result<bool, Mismatch,> {
+	lookup(conf, key, "a bool", ConfValue::as_bool,)
+}
+
+pub fn get_int(conf: &ConfMap, key: &str,) -> Result<i32, Mismatch,> {
+	lookup(conf, key, "an integer", ConfValue::as_int,)
+}
+
+pub fn get_float(conf: &ConfMap, key: &str,) -> Result<f64, Mismatch,> {
+	lookup(conf, key, "a float", ConfValue::as_float,)
+}
+
+pub fn get_ints(conf: &ConfMap, key: &str,) -> Result<Vec<i32,>, Mismatch,> {
+	lookup(conf, key, "a collection of integers", ConfValue::as_ints,)
+}
+
+/// why a map did not survive [`assert_round_trip`]
+#[derive(Debug,)]
+pub enum RoundTripFailure {
+	/// the rendered text is not a valid conf file
+	Reparse(Box<ParseError,>,),
+	/// the rendered text reads back as another map
+	Differs(Vec<ConfDiff,>,),
+}
+
+/// failed round trip, with the text it went through
+#[derive(Debug,)]
+pub struct RoundTripReport {
+	pub text:    String,
+	pub failure: RoundTripFailure,
+}
+
+impl Display for RoundTripReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_,>,) -> std::fmt::Result {
+		match &self.failure {
+			RoundTripFailure::Reparse(err,) => {
+				write!(f, "rendered conf does not parse: {err}")?
+			},
+			RoundTripFailure::Differs(diffs,) => {
+				write!(
+					f,
+					"rendered conf parses differently (- map, + reparsed):"
+				)?;
+				for diff in diffs {
+					write!(f, "\n  {diff}")?;
+				}
+			},
+		}
+		write!(f, "\nrendered conf:\n{}", self.text)
+	}
+}
+
+impl Error for RoundTripReport {}
+
+/// renders `map` as a conf file, secrets included, and parses it back
+/// against `schema`. the two maps must not differ in any leaf
+pub fn assert_round_trip(
+	map: &ConfMap,
+	schema: &SchemaMap,
+) -> Result<(), RoundTripReport,> {
+	let mut output = Vec::new();
+	map.write_fmt_streaming(
+		ShowFmt::Conf,
+		&ShowStyle::default().reveal_secrets(true,),
+		&mut output,
+	)
+	.expect("writing to a Vec never fails",);
+	let text = String::from_utf8(output,).expect("renderers only write utf-8",);
+
+	let failure = match conf::parse_str(&text, schema,) {
+		Err(err,) => RoundTripFailure::Reparse(Box::new(err,),),
+		Ok(reparsed,) => {
+			let diffs = map.diff(&reparsed,);
+			if diffs.is_empty() {
+				return Ok((),);
+			}
+			RoundTripFailure::Differs(diffs,)
+		},
+	};
+	Err(RoundTripReport { text, failure, },)
+}
+
+/// strategy of [`generate`]d maps for `schema`, and the check every one of
+/// them must pass, for use in `proptest!` blocks:
+///
+/// ```
+/// # use dot_conf_parser::parser::schema;
+/// # use dot_conf_parser::testing::round_trip_property;
+/// let schema = schema::parse_str("port -> Integer\n").unwrap().into_shared();
+/// let (maps, check) = round_trip_property(schema);
+/// proptest::proptest!(|(map in maps)| check(&map)?);
+/// ```
+pub fn round_trip_property(
+	schema: SchemaRef,
+) -> (
+	impl Strategy<Value = ConfMap,>,
+	impl Fn(&ConfMap,) -> Result<(), TestCaseError,>,
+) {
+	let strategy_schema = schema.clone();
+	let maps = proptest::prelude::any::<u64,>().prop_map(move |seed| {
+		generate(&strategy_schema, seed, &GenOpts::default(),)
+	},);
+	let check = move |map: &ConfMap| {
+		assert_round_trip(map, &schema,)
+			.map_err(|report| TestCaseError::fail(report.to_string(),),)
+	};
+	(maps, check,)
+}
+
+/// bounds of the values made up by [`generate`]
+#[derive(Debug, Clone,)]
+pub struct GenOpts {
+	/// bounds of every number, floats and integers of any width alike.
+	/// bounds beyond the range of a type, or the range declared for a key,
+	/// are cut to it, so unsigned integers start at 0 at the least
+	pub integers:   RangeInclusive<i32,>,
+	/// lengths below 1 are raised to 1, as a conf value cannot be empty
+	pub string_len: RangeInclusive<usize,>,
+}
+
+impl Default for GenOpts {
+	fn default() -> Self {
+		Self { integers: i32::MIN..=i32::MAX, string_len: 1..=16, }
+	}
+}
+
+/// characters of generated strings. none of them has a meaning to the
+/// parser, so the rendered text reads back as the same value
+const STRING_ALPHABET: &[u8] =
+	b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_-/";
+
+/// conf map setting every key of `schema` to a random value of the declared
+/// type, within its declared range or among its `Enum` literals. the same
+/// seed always yields the same map
+pub fn generate(schema: &SchemaMap, seed: u64, opts: &GenOpts,) -> ConfMap {
+	fn generate_map(
+		schema: &BTreeMap<String, SchemaValue,>,
+		rng: &mut SplitMix64,
+		opts: &GenOpts,
+	) -> BTreeMap<String, ConfValue,> {
+		schema
+			.iter()
+			.map(|(key, value,)| {
+				let value = match value {
+					TreeValue::Scalar(leaf,) => {
+						TreeValue::Scalar(match &leaf.ty {
+							Value::Single(kind,) => Value::Single(
+								generate_single(*kind, leaf, 0, rng, opts,),
+							),
+							Value::Collection(kinds,) if leaf.list => {
+								let len = rng.below(4,) as usize;
+								Value::Collection(
+									(0..len)
+										.map(|_| {
+											generate_single(
+												kinds[0], leaf, 0, rng, opts,
+											)
+										},)
+										.collect(),
+								)
+							},
+							Value::Collection(kinds,) => Value::Collection(
+								kinds
+									.iter()
+									.enumerate()
+									.map(|(idx, kind,)| {
+										generate_single(
+											*kind, leaf, idx, rng, opts,
+										)
+									},)
+									.collect(),
+							),
+						},)
+					},
+					TreeValue::Map(children,) => {
+						TreeValue::Map(generate_map(children, rng, opts,),)
+					},
+				};
+				(key.clone(), value,)
+			},)
+			.collect()
+	}
+
+	let mut conf = ConfMap::new();
+	conf.extend(generate_map(schema, &mut SplitMix64(seed,), opts,),);
+	conf
+}
+
+/// [`generate`]d map rendered as a conf file, secrets included
+pub fn generate_conf_text(
+	schema: &SchemaMap,
+	seed: u64,
+	opts: &GenOpts,
+) -> String {
+	let mut output = Vec::new();
+	generate(schema, seed, opts,)
+		.write_fmt_streaming(
+			ShowFmt::Conf,
+			&ShowStyle::default().reveal_secrets(true,),
+			&mut output,
+		)
+		.expect("writing to a Vec never fails",);
+	let mut text =
+		String::from_utf8(output,).expect("renderers only write utf-8",);
+	if !text.is_empty() {
+		text.push('\n',);
+	}
+	text
+}
+
+/// value of `kind` meeting the constraints `leaf` declares for its `idx`th
+/// element
+fn generate_single(
+	kind: SingleValueDiscriminants,
+	leaf: &SchemaLeaf,
+	idx: usize,
+	rng: &mut SplitMix64,
+	opts: &GenOpts,
+) -> SingleValue {
+	let range = leaf.range_of(idx,);
+	match kind {
+		SingleValueDiscriminants::String => match &leaf.allowed {
+			Some(allowed,) if !allowed.is_empty() => SingleValue::String(
+				allowed[rng.below(allowed.len() as u64,) as usize].clone(),
+			),
+			_ => SingleValue::String(generate_string(rng, opts,),),
+		},
+		SingleValueDiscriminants::Float => {
+			let bound = |bound: Option<&SingleValue,>, default: f64| match bound
+			{
+				Some(SingleValue::Float(num,),) => *num,
+				_ => default,
+			};
+			let (min, max,) =
+				range.map_or((f64::NEG_INFINITY, f64::INFINITY,), |range| {
+					(
+						bound(range.min.as_ref(), f64::NEG_INFINITY,),
+						bound(range.max.as_ref(), f64::INFINITY,),
+					)
+				},);
+			let start = f64::from(*opts.integers.start(),).max(min,).min(max,);
+			let end = f64::from(*opts.integers.end(),).max(min,).min(max,);
+			let unit = rng.next_u64() as f64 / u64::MAX as f64;
+			SingleValue::Float(start + unit * (end - start),)
+		},
+		SingleValueDiscriminants::Secret => {
+			SingleValue::Secret(Secret::new(generate_string(rng, opts,),),)
+		},
+		SingleValueDiscriminants::Bool => {
+			SingleValue::Bool(rng.below(2,) == 1,)
+		},
+		SingleValueDiscriminants::Duration => {
+			SingleValue::Duration(Duration::from_millis(rng.below(1 << 40,),),)
+		},
+		SingleValueDiscriminants::IpAddr => {
+			SingleValue::IpAddr(generate_ip(rng,),)
+		},
+		SingleValueDiscriminants::UInteger => SingleValue::UInteger(
+			generate_whole(rng, opts, range, 0, u64::MAX.into(),) as u64,
+		),
+		SingleValueDiscriminants::Long => SingleValue::Long(generate_whole(
+			rng,
+			opts,
+			range,
+			i64::MIN.into(),
+			i64::MAX.into(),
+		) as i64,),
+		SingleValueDiscriminants::Path => {
+			SingleValue::Path(PathBuf::from(generate_string(rng, opts,),),)
+		},
+		SingleValueDiscriminants::SocketAddr => SingleValue::SocketAddr(
+			SocketAddr::new(generate_ip(rng,), rng.below(1 << 16,) as u16,),
+		),
+		SingleValueDiscriminants::Integer => SingleValue::Integer(
+			generate_whole(rng, opts, range, i32::MIN.into(), i32::MAX.into(),)
+				as i32,
+		),
+		// not declarable, see `SingleValue::Deferred`, so only a schema built
+		// in code has it, and a placeholder is all it may stand for
+		SingleValueDiscriminants::Deferred => SingleValue::Deferred {
+			resolver: "env".to_string(),
+			arg:      generate_string(rng, opts,),
+		},
+	}
+}
+
+/// whole number within `opts.integers`, cut to the declared `range` and to
+/// `min..=max`, the range of the type generated
+fn generate_whole(
+	rng: &mut SplitMix64,
+	opts: &GenOpts,
+	range: Option<&ValueRange,>,
+	min: i128,
+	max: i128,
+) -> i128 {
+	let bound = |bound: Option<&SingleValue,>, default: i128| match bound {
+		Some(SingleValue::Integer(num,),) => i128::from(*num,),
+		Some(SingleValue::UInteger(num,),) => i128::from(*num,),
+		Some(SingleValue::Long(num,),) => i128::from(*num,),
+		_ => default,
+	};
+	let (min, max,) = range.map_or((min, max,), |range| {
+		(
+			bound(range.min.as_ref(), min,).max(min,),
+			bound(range.max.as_ref(), max,).min(max,),
+		)
+	},);
+	let start = i128::from(*opts.integers.start(),).max(min,).min(max,);
+	let end = i128::from(*opts.integers.end(),).max(min,).min(max,);
+	if end < start {
+		return start;
+	}
+	match u64::try_from(end - start + 1,) {
+		Ok(span,) => start + i128::from(rng.below(span,),),
+		// the whole range of a 64-bit type
+		Err(_,) => start + i128::from(rng.next_u64(),),
+	}
+}
+
+/// IPv4 or IPv6, evenly
+fn generate_ip(rng: &mut SplitMix64,) -> IpAddr {
+	if rng.below(2,) == 0 {
+		IpAddr::from((rng.next_u64() as u32).to_be_bytes(),)
+	} else {
+		let bits =
+			u128::from(rng.next_u64(),) << 64 | u128::from(rng.next_u64(),);
+		IpAddr::from(bits.to_be_bytes(),)
+	}
+}
+
+fn generate_string(rng: &mut SplitMix64, opts: &GenOpts,) -> String {
+	let min = (*opts.string_len.start()).max(1,);
+	let max = (*opts.string_len.end()).max(min,);
+	let len = min + rng.below((max - min + 1) as u64,) as usize;
+	(0..len)
+		.map(|_| {
+			char::from(
+				STRING_ALPHABET
+					[rng.below(STRING_ALPHABET.len() as u64,) as usize],
+			)
+		},)
+		.collect()
+}
+
+/// small, seedable and stable across releases, unlike the std hashers
+struct SplitMix64(u64,);
+
+impl SplitMix64 {
+	fn next_u64(&mut self,) -> u64 {
+		self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15,);
+		let mut z = self.0;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9,);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb,);
+		z ^ (z >> 31)
+	}
+
+	/// in `0..bound`. `bound` must not be 0
+	fn below(&mut self, bound: u64,) -> u64 {
+		self.next_u64() % bound
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn conf_map_macro_nests_dotted_keys() {
+		let conf = crate::conf_map! {
+			"server.port" => 8080,
+			"server.host" => "localhost",
+			"debug" => true,
+			"ports" => [80, 443],
+		};
+
+		assert_eq!(get_int(&conf, "server.port"), Ok(8080));
+		assert_eq!(get_str(&conf, "server.host"), Ok("localhost"));
+		assert_eq!(get_bool(&conf, "debug"), Ok(true));
+		assert_eq!(get_ints(&conf, "ports"), Ok(vec![80, 443]));
+		assert!(matches!(conf.get("server"), Some(ConfValue::Map(_))));
+	}
+
+	#[test]
+	fn extraction_reports_what_was_found() {
+		let conf = crate::conf_map! { "debug" => true };
+
+		let err = get_int(&conf, "debug",).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"expected an integer at 'debug', found Scalar(Single(Bool(true)))"
+		);
+		let err = get_int(&conf, "missing",).unwrap_err();
+		assert_eq!(err.found, None);
+	}
+
+	#[test]
+	fn schema_map_macro_declares_types() {
+		let schema = crate::schema_map! {
+			"server.port" => Integer,
+			"limits" => [Integer, Integer],
+		};
+		let conf = crate::parser::conf::parse_str(
+			"server.port = 1\nlimits = 1, 2\n",
+			schema,
+		)
+		.unwrap();
+
+		crate::assert_conf_eq!(
+			conf,
+			crate::conf_map! { "server.port" => 1, "limits" => [1, 2] }
+		);
+	}
+
+	#[test]
+	fn conf_eq_lists_differing_leaves() {
+		let report = conf_eq(
+			&crate::conf_map! { "a" => 1, "b" => "x" },
+			&crate::conf_map! { "a" => 2, "c" => false },
+		)
+		.unwrap_err();
+
+		assert_eq!(
+			report,
+			"conf maps differ (- left, + right):\n  ~ a = 1 -> 2\n  - b = x\n  \
+			 + c = false"
+		);
+	}
+
+	const GENERATED_SCHEMA: &str =
+		"server.port -> Integer\nserver.host -> @String\nlimits -> (min: \
+		 Integer, max: Integer)\nflags -> Bool, Bool, Bool\ntoken -> \
+		 Secret\nname -> String\nratio -> Float\nports -> [Integer]\ntags -> \
+		 [String]\ntimeouts -> [Duration]\nlisten -> SocketAddr\nallow -> \
+		 [Ip]\nlog -> Path\nquota -> UInt\nepoch -> Long\nworkers -> \
+		 Integer[1..10]\nmode -> Enum(a, b)\nweights -> \
+		 [Float[0..1]]\nretries -> UInt[2..5]\nshard -> Long[-5..]\n";
+
+	#[test]
+	fn generation_is_deterministic() {
+		let schema =
+			crate::parser::schema::parse_str(GENERATED_SCHEMA,).unwrap();
+		let opts = GenOpts::default();
+		assert_eq!(
+			generate_conf_text(&schema, 7, &opts,),
+			generate_conf_text(&schema, 7, &opts,)
+		);
+		assert_ne!(
+			generate_conf_text(&schema, 7, &opts,),
+			generate_conf_text(&schema, 8, &opts,)
+		);
+	}
+
+	#[test]
+	fn generation_respects_bounds() {
+		let schema =
+			crate::parser::schema::parse_str(GENERATED_SCHEMA,).unwrap();
+		let opts = GenOpts { integers: -3..=3, string_len: 0..=2, };
+		for seed in 0..64 {
+			let conf = generate(&schema, seed, &opts,);
+			assert!(
+				(-3..=3).contains(&get_int(&conf, "server.port",).unwrap())
+			);
+			assert!(matches!(
+				conf.get("quota"),
+				Some(ConfValue::Scalar(Value::Single(SingleValue::UInteger(
+					0..=3
+				))))
+			));
+			assert!(matches!(
+				conf.get("epoch"),
+				Some(ConfValue::Scalar(Value::Single(SingleValue::Long(
+					-3..=3
+				))))
+			));
+			let name = get_str(&conf, "name",).unwrap();
+			assert!((1..=2).contains(&name.len()), "{name:?}");
+		}
+	}
+
+	#[test]
+	fn generation_meets_declared_constraints() {
+		let schema =
+			crate::parser::schema::parse_str(GENERATED_SCHEMA,).unwrap();
+		for opts in [
+			GenOpts::default(),
+			GenOpts { integers: 20..=30, string_len: 1..=4, },
+		] {
+			for seed in 0..64 {
+				let conf = generate(&schema, seed, &opts,);
+				crate::parser::conf::validate(&conf, &schema,)
+					.unwrap_or_else(|errors| panic!("{errors:?}"),);
+			}
+		}
+	}
+
+	#[test]
+	fn generated_maps_round_trip() {
+		let schema = crate::parser::schema::parse_str(GENERATED_SCHEMA,)
+			.unwrap()
+			.into_shared();
+		let (maps, check,) = round_trip_property(schema,);
+		proptest::proptest!(|(map in maps)| check(&map)?);
+	}
+
+	#[test]
+	fn round_trip_report_names_differing_keys() {
+		let schema = crate::schema_map! { "pair" => [String, Integer] };
+		let numeric_text = crate::conf_map! { "pair" => ["x", "08"] };
+
+		let report = assert_round_trip(&numeric_text, &schema,).unwrap_err();
+		assert!(matches!(report.failure, RoundTripFailure::Differs(_)));
+		assert_eq!(
+			report.to_string(),
+			"rendered conf parses differently (- map, + reparsed):\n  ~ pair \
+			 = x,08 -> x,8\nrendered conf:\npair = x,08"
+		);
+	}
+}
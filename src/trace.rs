@@ -0,0 +1,29 @@
+//! wrappers over `tracing` which expand to nothing unless the `tracing`
+//! feature is enabled. every event is emitted under the `dot_conf_parser`
+//! target
+
+macro_rules! event {
+	($level:ident, $($arg:tt)+) => {
+		#[cfg(feature = "tracing")]
+		::tracing::$level!(target: "dot_conf_parser", $($arg)+);
+	};
+}
+
+macro_rules! span {
+	($name:literal, $($arg:tt)*) => {{
+		#[cfg(feature = "tracing")]
+		let guard =
+			::tracing::debug_span!(target: "dot_conf_parser", $name, $($arg)*)
+				.entered();
+		#[cfg(not(feature = "tracing"))]
+		let guard = $crate::trace::NoSpan;
+		guard
+	}};
+}
+
+/// stands in for an entered span when the `tracing` feature is disabled
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+pub(crate) use event;
+pub(crate) use span;
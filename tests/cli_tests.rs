@@ -0,0 +1,294 @@
+use std::process::Command;
+use std::process::Output;
+
+fn dot_conf(args: &[&str],) -> Output {
+	Command::new(env!("CARGO_BIN_EXE_dot-conf"),)
+		.args(args,)
+		.current_dir(env!("CARGO_MANIFEST_DIR"),)
+		.output()
+		.expect("spawn dot-conf",)
+}
+
+#[test]
+fn scaffold_prints_placeholders_for_schema() {
+	let output =
+		dot_conf(&["scaffold", "tests/examples/sysctl_sample.schema",],);
+
+	assert_eq!(output.status.code(), Some(0));
+	let stdout = String::from_utf8(output.stdout,).expect("utf8 output",);
+	assert!(stdout.contains("# kernel.hostname = <String>\n"));
+	assert!(stdout.contains("# vm.swappiness = <Integer>\n"));
+	assert_eq!(stdout.lines().count(), 10);
+}
+
+#[test]
+fn scaffold_reports_missing_schema_file() {
+	let output = dot_conf(&["scaffold", "tests/examples/missing.schema",],);
+
+	assert_eq!(output.status.code(), Some(1));
+	let stderr = String::from_utf8(output.stderr,).expect("utf8 output",);
+	assert!(
+		stderr.starts_with("tests/examples/missing.schema: [E001] I/O error")
+	);
+}
+
+#[test]
+fn unknown_subcommand_prints_usage() {
+	let output = dot_conf(&["frobnicate",],);
+
+	assert_eq!(output.status.code(), Some(2));
+	let stderr = String::from_utf8(output.stderr,).expect("utf8 output",);
+	assert!(stderr.starts_with("usage: dot-conf"));
+}
+
+fn diff_against(proposed: &str, extra: &[&str],) -> Output {
+	let proposed = format!("tests/examples/diff/{proposed}");
+	let mut args = vec!["diff", "--schema", "tests/examples/diff/app.schema"];
+	args.extend_from_slice(extra,);
+	args.extend(["tests/examples/diff/running.conf", proposed.as_str(),],);
+	dot_conf(&args,)
+}
+
+#[test]
+fn diff_of_identical_files_exits_zero() {
+	let output = diff_against("running.conf", &[],);
+
+	assert_eq!(output.status.code(), Some(0));
+	assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn diff_reports_added_key() {
+	let output = diff_against("added_key.conf", &[],);
+
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(String::from_utf8(output.stdout,).unwrap(), "+ workers = 4\n");
+}
+
+#[test]
+fn diff_reports_removed_key() {
+	let output = diff_against("removed_key.conf", &[],);
+
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(
+		String::from_utf8(output.stdout,).unwrap(),
+		"- log.file = /var/log/app.log\n"
+	);
+}
+
+#[test]
+fn diff_reports_changed_value_as_json() {
+	let output = diff_against("changed_value.conf", &["--format", "json",],);
+
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(
+		String::from_utf8(output.stdout,).unwrap(),
+		"[{\"change\":\"changed\",\"key\":\"server.port\",\"old\":8080,\"new\"\
+		 :9090}]\n"
+	);
+}
+
+#[test]
+fn diff_rejects_collection_of_other_length() {
+	let output = diff_against("collection_length.conf", &[],);
+
+	assert_eq!(output.status.code(), Some(2));
+	let stderr = String::from_utf8(output.stderr,).unwrap();
+	assert!(stderr.starts_with(
+		"tests/examples/diff/collection_length.conf: [E029] expected 2 \
+		 elements for 'limits' on line 3, found 3\n"
+	));
+	assert!(output.stdout.is_empty());
+}
+
+fn explain(key: &str,) -> Output {
+	dot_conf(&[
+		"explain",
+		"--schema",
+		"tests/examples/explain/app.schema",
+		"tests/examples/explain/app.conf",
+		key,
+	],)
+}
+
+#[test]
+fn explain_lists_every_assignment_of_a_key() {
+	let output = explain("server.port",);
+
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(
+		String::from_utf8(output.stdout,).expect("utf8 output",),
+		"tests/examples/explain/app.conf:1 \
+		 (overridden)\ntests/examples/explain/app.conf:3 (effective)\n"
+	);
+}
+
+#[test]
+fn explain_reports_unset_key() {
+	let output = explain("server.tls",);
+
+	assert_eq!(output.status.code(), Some(1));
+	let stderr = String::from_utf8(output.stderr,).expect("utf8 output",);
+	assert!(stderr.contains("'server.tls' is not set"));
+}
+
+fn schema_compat(old: &str, new: &str,) -> Output {
+	dot_conf(&[
+		"schema-compat",
+		&format!("tests/examples/compat/{old}"),
+		&format!("tests/examples/compat/{new}"),
+	],)
+}
+
+#[test]
+fn schema_compat_accepts_widened_schema() {
+	let output = schema_compat("v1.schema", "v2.schema",);
+
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(
+		String::from_utf8(output.stdout,).expect("utf8 output",),
+		"compatible: log.file: added\ncompatible: ratio: type widened from \
+		 Integer to Float\n"
+	);
+}
+
+#[test]
+fn schema_compat_fails_on_breaking_change() {
+	let output = schema_compat("v2.schema", "v3.schema",);
+
+	assert_eq!(output.status.code(), Some(1));
+	assert_eq!(
+		String::from_utf8(output.stdout,).expect("utf8 output",),
+		"breaking: log.file: removed\nbreaking: log.level: \
+		 removed\ncompatible: server.port: type widened from Integer to \
+		 String\n"
+	);
+}
+
+#[test]
+fn schema_compat_reports_unreadable_schema() {
+	let output = schema_compat("v1.schema", "missing.schema",);
+
+	assert_eq!(output.status.code(), Some(2));
+	let stderr = String::from_utf8(output.stderr,).expect("utf8 output",);
+	assert!(
+		stderr.starts_with("tests/examples/compat/missing.schema: [E001] I/O")
+	);
+}
+
+const SYSCTL_SCHEMA: &str = "tests/examples/sysctl_sample.schema";
+const SYSCTL_CONF: &str = "tests/examples/sysctl_sample.conf";
+
+#[test]
+fn check_accepts_a_valid_conf_silently() {
+	let output = dot_conf(&["check", "--schema", SYSCTL_SCHEMA, SYSCTL_CONF,],);
+
+	assert_eq!(output.status.code(), Some(0));
+	assert!(output.stdout.is_empty());
+	assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn check_reports_every_error_with_its_line() {
+	let output = dot_conf(&[
+		"check",
+		"--schema",
+		SYSCTL_SCHEMA,
+		"tests/examples/check/broken.conf",
+	],);
+
+	assert_eq!(output.status.code(), Some(1));
+	assert!(output.stdout.is_empty());
+	let stderr = String::from_utf8(output.stderr,).expect("utf8 output",);
+	let lines: Vec<&str,> = stderr.lines().collect();
+	assert_eq!(lines.len(), 3, "{stderr}");
+	assert!(lines[0].contains("[E007] invalid value 'high'"));
+	assert!(lines[0].contains("on line 2"));
+	assert!(lines[1].contains("unknown key 'kernel.hostnme' on line [3]"));
+	assert!(lines[2].ends_with(
+		"[E002] missing delimiter on line 4, column 1; expected '='"
+	));
+}
+
+#[test]
+fn check_follows_includes() {
+	let output = dot_conf(&[
+		"check",
+		"--schema",
+		SYSCTL_SCHEMA,
+		"tests/examples/check/with_include.conf",
+	],);
+	assert_eq!(output.status.code(), Some(0));
+	assert!(output.stderr.is_empty());
+
+	let output = dot_conf(&[
+		"check",
+		"--schema",
+		SYSCTL_SCHEMA,
+		"tests/examples/check/bad_include.conf",
+	],);
+	assert_eq!(output.status.code(), Some(1));
+	let stderr = String::from_utf8(output.stderr,).expect("utf8 output",);
+	assert_eq!(stderr.lines().count(), 1, "{stderr}");
+	assert!(stderr.contains("conf.d/bad.conf"), "{stderr}");
+	assert!(stderr.contains("[E007] invalid value 'high'"), "{stderr}");
+}
+
+#[test]
+fn show_renders_in_the_requested_format() {
+	let output = dot_conf(&[
+		"show",
+		"--schema",
+		SYSCTL_SCHEMA,
+		"--format",
+		"json",
+		SYSCTL_CONF,
+	],);
+
+	assert_eq!(output.status.code(), Some(0));
+	let stdout = String::from_utf8(output.stdout,).expect("utf8 output",);
+	assert!(stdout.starts_with("{\n"));
+	assert!(stdout.contains("\"swappiness\": 10"));
+
+	let output = dot_conf(&["show", "--schema", SYSCTL_SCHEMA, SYSCTL_CONF,],);
+	let stdout = String::from_utf8(output.stdout,).expect("utf8 output",);
+	assert!(stdout.contains("service.mode = maintenance\n"));
+}
+
+#[test]
+fn show_rejects_unknown_formats() {
+	let output = dot_conf(&[
+		"show",
+		"--schema",
+		SYSCTL_SCHEMA,
+		"--format",
+		"xml",
+		SYSCTL_CONF,
+	],);
+
+	assert_eq!(output.status.code(), Some(2));
+	assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn get_prints_a_value_or_a_section() {
+	let get =
+		|key| dot_conf(&["get", "--schema", SYSCTL_SCHEMA, SYSCTL_CONF, key,],);
+
+	let output = get("vm.swappiness",);
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(output.stdout, b"10\n");
+
+	let output = get("net.ipv4",);
+	assert_eq!(output.status.code(), Some(0));
+	assert_eq!(
+		String::from_utf8(output.stdout,).expect("utf8 output",),
+		"conf.all.rp_filter = 1\nconf.default.rp_filter = 1\nip_forward = 0\n"
+	);
+
+	let output = get("vm.overcommit",);
+	assert_eq!(output.status.code(), Some(1));
+	assert!(output.stdout.is_empty());
+	let stderr = String::from_utf8(output.stderr,).expect("utf8 output",);
+	assert!(stderr.contains("'vm.overcommit' is not set"));
+}
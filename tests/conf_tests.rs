@@ -1,80 +1,204 @@
 use dot_conf_parser::error::PRslt;
 use dot_conf_parser::error::ParseError;
+use dot_conf_parser::parser::conf::BuildConf;
 use dot_conf_parser::parser::conf::ConfValue;
+use dot_conf_parser::parser::conf::DuplicateKeyPolicy;
+use dot_conf_parser::parser::conf::KeyCase;
+use dot_conf_parser::parser::conf::ParseOptions;
+use dot_conf_parser::parser::conf::PathRequirement;
+use dot_conf_parser::parser::conf::SegmentCharset;
 use dot_conf_parser::parser::conf::SingleValue;
+use dot_conf_parser::parser::conf::SingleValueDiscriminants;
 use dot_conf_parser::parser::conf::Value;
 use dot_conf_parser::parser::conf::{self};
+use dot_conf_parser::parser::merge::MergePolicy;
+use dot_conf_parser::parser::mount::SchemaMounts;
+use dot_conf_parser::parser::raw;
+use dot_conf_parser::parser::raw::TreeValue;
 use dot_conf_parser::parser::schema;
+use dot_conf_parser::parser::schema::SchemaLeaf;
+use dot_conf_parser::parser::schema::SchemaMap;
+use dot_conf_parser::parser::source::Origin;
+use dot_conf_parser::testing::GenOpts;
+use dot_conf_parser::testing::TestResult;
+use dot_conf_parser::testing::conf_eq;
+use dot_conf_parser::testing::generate;
+use dot_conf_parser::testing::get_bool;
+use dot_conf_parser::testing::get_int;
+use dot_conf_parser::testing::get_ints;
+use dot_conf_parser::testing::get_str;
 use proptest::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
 
-fn expect_string(value: &ConfValue,) -> &str {
-	match value {
-		ConfValue::Scalar(Value::Single(SingleValue::String(s,),),) => s,
-		other => panic!("expected string payload, got {other:?}"),
-	}
+#[test]
+fn conf_overwrites_duplicate_scalar_values() -> TestResult {
+	let schema = schema::parse_str("name -> String\n",)?;
+	let conf = conf::parse_str("name = original\nname = updated\n", schema,)?;
+
+	assert_eq!(get_str(&conf, "name")?, "updated");
+	Ok((),)
 }
 
-fn expect_bool(value: &ConfValue,) -> bool {
-	match value {
-		ConfValue::Scalar(Value::Single(SingleValue::Bool(flag,),),) => *flag,
-		other => panic!("expected bool payload, got {other:?}"),
-	}
+#[test]
+fn conf_value_keeps_everything_after_first_equals_sign() -> TestResult {
+	let schema = schema::parse_str(
+		"query -> String\njvm.opts -> String\na -> String\n",
+	)?;
+	let conf = conf::parse_str(
+		"query = a=b&c=d\njvm.opts = -Dfoo=bar -Dbaz=qux\na=b.c = d\n",
+		schema,
+	)?;
+
+	assert_eq!(get_str(&conf, "query")?, "a=b&c=d");
+	assert_eq!(get_str(&conf, "jvm.opts")?, "-Dfoo=bar -Dbaz=qux");
+	// a key cannot contain '=', so this assigns `a`
+	assert_eq!(get_str(&conf, "a")?, "b.c = d");
+	Ok((),)
 }
 
-fn expect_int(value: &ConfValue,) -> i32 {
-	match value {
-		ConfValue::Scalar(Value::Single(SingleValue::Integer(v,),),) => *v,
-		other => panic!("expected integer payload, got {other:?}"),
-	}
+#[test]
+fn conf_supports_inline_comments() -> TestResult {
+	let schema = schema::parse_str("net.port -> Integer\n",)?;
+	let conf = conf::parse_str("net.port = 443 # https\n", schema,)?;
+
+	assert_eq!(get_int(&conf, "net.port")?, 443);
+	Ok((),)
 }
 
-fn expect_ints(value: &ConfValue,) -> Vec<i32,> {
-	match value {
-		ConfValue::Scalar(Value::Collection(items,),) => items
-			.iter()
-			.map(|entry| match entry {
-				SingleValue::Integer(v,) => *v,
-				other => panic!("expected integer, got {other:?}"),
-			},)
-			.collect(),
-		other => panic!("expected collection, got {other:?}"),
+#[test]
+fn conf_keeps_comment_markers_inside_quotes() -> TestResult {
+	let schema = schema::parse_str("password -> String\nprefix -> String\n",)?;
+	let conf = conf::parse_str(
+		"password = \"abc#123 ; still part of value\" ; rotated\nprefix = \
+		 \"\"\n",
+		&schema,
+	)?;
+
+	assert_eq!(get_str(&conf, "password")?, "abc#123 ; still part of value");
+	// unlike a bare empty value, an empty quoted string is a value
+	assert_eq!(get_str(&conf, "prefix")?, "");
+	assert!(matches!(
+		conf::parse_str("prefix =\n", &schema),
+		Err(ParseError::EmptyValue { line: 1, .. })
+	));
+	assert!(matches!(
+		conf::parse_str("password = \"abc#123\n", &schema),
+		Err(ParseError::UnterminatedString { line: 1, })
+	));
+	Ok((),)
+}
+
+#[test]
+fn strict_parsing_lists_every_missing_key() -> TestResult {
+	let schema = schema::parse_str(
+		"service.mode -> String\nserver.port -> Integer\nserver.host -> \
+		 String\nlimits -> Integer, Integer\n",
+	)?;
+	let input = "server.host = localhost\n";
+
+	// keys are allowed, not required, unless parsing strictly
+	let conf = conf::parse_str(input, &schema,)?;
+	assert_eq!(get_str(&conf, "server.host")?, "localhost");
+
+	match conf::parse_str_strict(input, &schema,) {
+		Err(ParseError::MissingKey { keys, },) => {
+			assert_eq!(keys, vec!["limits", "server.port", "service.mode"])
+		},
+		other => panic!("unexpected result: {other:?}"),
 	}
+
+	let conf = conf::parse_str_strict(
+		"service.mode = fast\nserver.port = 80\nserver.host = a\nlimits = 1, \
+		 2\n",
+		&schema,
+	)?;
+	conf.require_keys(&schema,)?;
+	Ok((),)
 }
 
 #[test]
-fn conf_overwrites_duplicate_scalar_values() -> PRslt<(),> {
-	let schema = schema::parse_str("name -> String\n",)?;
-	let conf = conf::parse_str("name = original\nname = updated\n", schema,)?;
+fn strict_parsing_skips_optional_keys() -> TestResult {
+	let schema = schema::parse_str(
+		"server.port -> Integer\nserver.tls.cert -> String?\nserver.tls.key \
+		 -> @Secret ?\n",
+	)?;
+
+	let conf = conf::parse_str_strict("server.port = 443\n", &schema,)?;
+	assert!(conf.get("server.tls").is_none());
+
+	let conf = conf::parse_str_strict(
+		"server.port = 443\nserver.tls.cert = /etc/cert.pem\n",
+		&schema,
+	)?;
+	assert_eq!(get_str(&conf, "server.tls.cert")?, "/etc/cert.pem");
+
+	match conf::parse_str_strict("server.tls.cert = /etc/cert.pem\n", &schema,)
+	{
+		Err(ParseError::MissingKey { keys, },) => {
+			assert_eq!(keys, vec!["server.port"])
+		},
+		other => panic!("unexpected result: {other:?}"),
+	}
+	Ok((),)
+}
 
-	assert_eq!(expect_string(conf.get("name").expect("name entry")), "updated");
+#[test]
+fn optional_marker_stays_out_of_error_messages() -> TestResult {
+	let schema = schema::parse_str("server.port -> Integer?\n",)?;
+	let err = conf::parse_str("server.port = x\n", &schema,)
+		.expect_err("expected invalid value",);
+	assert_eq!(
+		err.to_string(),
+		"[E007] invalid value 'x' while expecting Integer for 'server.port' \
+		 on line 1, column 15"
+	);
 	Ok((),)
 }
 
 #[test]
-fn conf_supports_inline_comments() -> PRslt<(),> {
-	let schema = schema::parse_str("net.port -> Integer\n",)?;
-	let conf = conf::parse_str("net.port = 443 # https\n", schema,)?;
+fn localized_numbers_get_a_targeted_error() -> TestResult {
+	let schema = schema::parse_str(
+		"max -> Integer\nrate -> Float\nhost -> String\nport -> Integer\n",
+	)?;
 
-	assert_eq!(expect_int(conf.get("net.port").expect("net.port entry")), 443);
+	let err = conf::parse_str("max = 1.000\n", &schema,)
+		.expect_err("expected localized number",);
+	assert_eq!(
+		err.to_string(),
+		"[E009] value '1.000' for 'max' on line 1 looks like it uses a \
+		 thousands separator; write 1000"
+	);
+
+	let err = conf::parse_str("host = a\nrate = 3,14\n", &schema,)
+		.expect_err("expected localized number",);
+	assert_eq!(
+		err.to_string(),
+		"[E009] value '3,14' for 'rate' on line 2 looks like it uses a \
+		 decimal comma; write 3.14"
+	);
+
+	// only failed numeric conversions get the hint
+	let conf = conf::parse_str(
+		"host = 192.168.1.1\nrate = 1.000\nmax = +1000\n",
+		&schema,
+	)?;
+	assert_eq!(get_str(&conf, "host")?, "192.168.1.1");
+	assert_eq!(get_int(&conf, "max")?, 1000);
+	assert!(matches!(
+		conf::parse_str("port = 80.80.80\n", &schema),
+		Err(ParseError::InvalidValue { .. })
+	));
 	Ok((),)
 }
 
 #[test]
-fn conf_trims_key_segments() -> PRslt<(),> {
+fn conf_trims_key_segments() -> TestResult {
 	let schema = schema::parse_str("outer.inner -> String\n",)?;
 	let conf = conf::parse_str("outer . inner = spaced\n", schema,)?;
 
-	assert_eq!(
-		expect_string(
-			conf.get("outer")
-				.and_then(|m| match m {
-					ConfValue::Map(map,) => map.get("inner"),
-					other => panic!("expected nested map, got {other:?}"),
-				})
-				.expect("inner entry")
-		),
-		"spaced"
-	);
+	assert!(matches!(conf.get("outer"), Some(ConfValue::Map(_))));
+	assert_eq!(get_str(&conf, "outer.inner")?, "spaced");
 
 	Ok((),)
 }
@@ -85,10 +209,7 @@ fn conf_reports_empty_value_after_comment() -> PRslt<(),> {
 	let err = conf::parse_str("service.enabled =   ; no value\n", schema,)
 		.expect_err("expected empty value error",);
 
-	match err {
-		ParseError::EmptyValue { line, } => assert_eq!(line, 1),
-		other => panic!("unexpected error: {other}"),
-	}
+	assert_eq!(err, ParseError::EmptyValue { line: 1, column: 18, });
 
 	Ok((),)
 }
@@ -103,7 +224,7 @@ fn conf_reports_unknown_keys_with_all_lines() -> PRslt<(),> {
 	.expect_err("expected unknown key error",);
 
 	match err {
-		ParseError::UnknownKey { key, lines, } => {
+		ParseError::UnknownKey { key, lines, .. } => {
 			assert_eq!(key, "unknown");
 			assert_eq!(lines, vec![2]);
 		},
@@ -114,14 +235,11 @@ fn conf_reports_unknown_keys_with_all_lines() -> PRslt<(),> {
 }
 
 #[test]
-fn conf_builds_collections_from_schema() -> PRslt<(),> {
+fn conf_builds_collections_from_schema() -> TestResult {
 	let schema = schema::parse_str("limits -> Integer, Integer\n",)?;
-	let conf = conf::parse_str("limits = 7\n", schema,)?;
+	let conf = conf::parse_str("limits = 7, 9\n", schema,)?;
 
-	assert_eq!(
-		expect_ints(conf.get("limits").expect("limits entry")),
-		vec![7, 7]
-	);
+	assert_eq!(get_ints(&conf, "limits")?, vec![7, 9]);
 
 	Ok((),)
 }
@@ -133,11 +251,20 @@ fn conf_rejects_invalid_integer_values() -> PRslt<(),> {
 		.expect_err("expected invalid value error",);
 
 	match err {
-		ParseError::InvalidValue { key, value, ty, line, } => {
+		ParseError::InvalidValue {
+			key,
+			value,
+			ty,
+			line,
+			column,
+			element,
+			position,
+		} => {
 			assert_eq!(key, "retry.count");
+			assert_eq!((element, position), (None, None));
 			assert_eq!(value, "not-a-number");
 			assert_eq!(ty.to_string(), "Integer");
-			assert_eq!(line, 1);
+			assert_eq!((line, column), (1, 15));
 		},
 		other => panic!("unexpected error: {other}"),
 	}
@@ -151,11 +278,37 @@ fn conf_reports_missing_delimiter() -> PRslt<(),> {
 	let err = conf::parse_str("name value without equals\n", schema,)
 		.expect_err("expected missing delimiter error",);
 
-	match err {
-		ParseError::MissingDelimiter { line, } => assert_eq!(line, 1),
-		other => panic!("unexpected error: {other}"),
-	}
+	assert_eq!(
+		err,
+		ParseError::MissingDelimiter {
+			line:     1,
+			column:   1,
+			expected: vec!["=".to_string()],
+		}
+	);
+
+	Ok((),)
+}
 
+#[test]
+fn readers_parse_like_the_text_they_yield() -> PRslt<(),> {
+	let schema_text = "server.port -> Integer\nserver.host -> String\n";
+	let schema =
+		schema::parse_reader(std::io::Cursor::new(schema_text.as_bytes(),),)?;
+	assert_eq!(schema, schema::parse_str(schema_text,)?);
+
+	let input = "[server]\nport = 80\nhost = example.com";
+	let conf =
+		conf::parse_reader(std::io::Cursor::new(input.as_bytes(),), &schema,)?;
+	assert_eq!(conf, conf::parse_str(input, &schema,)?);
+	assert_eq!(conf.get_str("server.host"), Some("example.com"));
+
+	let err = conf::parse_reader(
+		std::io::Cursor::new(&b"server.port = x\n"[..],),
+		&schema,
+	)
+	.unwrap_err();
+	assert!(matches!(err, ParseError::InvalidValue { line: 1, .. }));
 	Ok((),)
 }
 
@@ -170,7 +323,7 @@ fn conf_reports_unknown_nested_key_with_all_lines() -> PRslt<(),> {
 	.expect_err("expected unknown key error",);
 
 	match err {
-		ParseError::UnknownKey { key, lines, } => {
+		ParseError::UnknownKey { key, lines, .. } => {
 			assert_eq!(key, "unknown");
 			assert_eq!(lines, vec![2, 3]);
 		},
@@ -188,7 +341,7 @@ fn conf_reports_latest_line_for_overwritten_unknown_leaf() -> PRslt<(),> {
 			.expect_err("expected unknown key error",);
 
 	match err {
-		ParseError::UnknownKey { key, lines, } => {
+		ParseError::UnknownKey { key, lines, .. } => {
 			assert_eq!(key, "unknown");
 			assert_eq!(lines, vec![2]);
 		},
@@ -198,6 +351,63 @@ fn conf_reports_latest_line_for_overwritten_unknown_leaf() -> PRslt<(),> {
 	Ok((),)
 }
 
+#[test]
+fn unknown_key_one_edit_away_suggests_the_declared_one() -> PRslt<(),> {
+	let schema = schema::parse_str("service -> String\nport -> Integer\n",)?;
+	let err = conf::parse_str("sevice = web\nport = 80\n", schema,)
+		.expect_err("expected unknown key error",);
+
+	match &err {
+		ParseError::UnknownKey { key, suggestion, .. } => {
+			assert_eq!(key, "sevice");
+			assert_eq!(suggestion.as_deref(), Some("service"));
+		},
+		other => panic!("unexpected error: {other}"),
+	}
+	assert_eq!(
+		err.to_string(),
+		"[E008] unknown key 'sevice' on line [1]; did you mean 'service'?"
+	);
+
+	Ok((),)
+}
+
+#[test]
+fn unrelated_unknown_key_gets_no_suggestion() -> PRslt<(),> {
+	let schema = schema::parse_str("service -> String\n",)?;
+	let err = conf::parse_str("service = web\ntimeout = 5\n", schema,)
+		.expect_err("expected unknown key error",);
+
+	match err {
+		ParseError::UnknownKey { key, suggestion, .. } => {
+			assert_eq!(key, "timeout");
+			assert_eq!(suggestion, None);
+		},
+		other => panic!("unexpected error: {other}"),
+	}
+
+	Ok((),)
+}
+
+#[test]
+fn nested_unknown_key_suggests_within_its_section() -> PRslt<(),> {
+	let schema =
+		schema::parse_str("server.mode -> String\nserver.port -> Integer\n",)?;
+	let err =
+		conf::parse_str("server.mdoe = fast\nserver.port = 80\n", schema,)
+			.expect_err("expected unknown key error",);
+
+	match err {
+		ParseError::UnknownKey { key, suggestion, .. } => {
+			assert_eq!(key, "server.mdoe");
+			assert_eq!(suggestion.as_deref(), Some("server.mode"));
+		},
+		other => panic!("unexpected error: {other}"),
+	}
+
+	Ok((),)
+}
+
 #[test]
 fn conf_rejects_scalar_after_nested_map() -> PRslt<(),> {
 	let schema = schema::parse_str("service.mode -> String\n",)?;
@@ -208,7 +418,7 @@ fn conf_rejects_scalar_after_nested_map() -> PRslt<(),> {
 	.expect_err("expected conflicting type error",);
 
 	match err {
-		ParseError::ConflictingTypes { key, line, } => {
+		ParseError::ConflictingTypes { key, line, .. } => {
 			assert_eq!(key, "service");
 			assert_eq!(line, 2);
 		},
@@ -226,7 +436,7 @@ fn conf_rejects_nested_assignment_after_scalar() -> PRslt<(),> {
 			.expect_err("expected conflicting type error",);
 
 	match err {
-		ParseError::ConflictingTypes { key, line, } => {
+		ParseError::ConflictingTypes { key, line, .. } => {
 			assert_eq!(key, "service");
 			assert_eq!(line, 2);
 		},
@@ -237,60 +447,73 @@ fn conf_rejects_nested_assignment_after_scalar() -> PRslt<(),> {
 }
 
 #[test]
-fn conf_supports_semicolon_inline_comments() -> PRslt<(),> {
+fn conf_supports_semicolon_inline_comments() -> TestResult {
 	let schema = schema::parse_str("path -> String\n",)?;
 	let conf = conf::parse_str("path = /tmp/data ; keep last\n", schema,)
 		.expect("conf parse",);
 
-	assert_eq!(
-		expect_string(conf.get("path").expect("path entry")),
-		"/tmp/data"
-	);
+	assert_eq!(get_str(&conf, "path")?, "/tmp/data");
 
 	Ok((),)
 }
 
 #[test]
-fn conf_supports_negative_integers() -> PRslt<(),> {
+fn conf_supports_negative_integers() -> TestResult {
 	let schema = schema::parse_str("retry.count -> Integer\n",)?;
 	let conf = conf::parse_str("retry.count = -42\n", schema,)?;
 
-	assert_eq!(
-		expect_int(conf.get("retry.count").expect("retry.count entry")),
-		-42
-	);
+	assert_eq!(get_int(&conf, "retry.count")?, -42);
 
 	Ok((),)
 }
 
 #[test]
-fn conf_trims_trailing_whitespace_in_values() -> PRslt<(),> {
+fn conf_trims_trailing_whitespace_in_values() -> TestResult {
 	let schema = schema::parse_str("path -> String\n",)?;
 	let conf = conf::parse_str("path = /var/log/app   \n", schema,)?;
 
-	assert_eq!(
-		expect_string(conf.get("path").expect("path entry")),
-		"/var/log/app"
-	);
+	assert_eq!(get_str(&conf, "path")?, "/var/log/app");
 
 	Ok((),)
 }
 
 #[test]
-fn conf_ignores_blank_and_comment_lines() -> PRslt<(),> {
+fn conf_ignores_blank_and_comment_lines() -> TestResult {
 	let schema = schema::parse_str("service.name -> String\n",)?;
 	let conf_src =
 		"\n# skipped comment\n; another comment\nservice.name = running\n";
 	let conf = conf::parse_str(conf_src, schema,)?;
 
-	assert_eq!(
-		expect_string(conf.get("service.name").expect("service.name entry")),
-		"running"
-	);
+	assert_eq!(get_str(&conf, "service.name")?, "running");
 
 	Ok((),)
 }
 
+#[test]
+fn deferred_type_built_in_code_is_an_unknown_type() {
+	let schema = SchemaMap::from([(
+		"token".to_string(),
+		TreeValue::Scalar(SchemaLeaf::from(Value::Single(
+			SingleValueDiscriminants::Deferred,
+		),),),
+	),],);
+	let unknown = || ParseError::UnknownType {
+		key:  "token".to_string(),
+		name: "Deferred".to_string(),
+		line: 1,
+	};
+
+	assert_eq!(conf::parse_str("token = x\n", &schema,), Err(unknown()));
+	assert_eq!(
+		schema::parse_str(&schema::to_string(&schema,),),
+		Err(unknown())
+	);
+	assert!(matches!(
+		generate(&schema, 7, &GenOpts::default(),).get("token"),
+		Some(ConfValue::Scalar(Value::Single(SingleValue::Deferred { .. },),),)
+	));
+}
+
 #[test]
 fn conf_rejects_empty_key() -> PRslt<(),> {
 	let schema = schema::parse_str("service.name -> String\n",)?;
@@ -298,7 +521,7 @@ fn conf_rejects_empty_key() -> PRslt<(),> {
 		.expect_err("expected empty key error",);
 
 	match err {
-		ParseError::EmptyKey { line, } => assert_eq!(line, 1),
+		ParseError::EmptyKey { line, .. } => assert_eq!(line, 1),
 		other => panic!("unexpected error: {other}"),
 	}
 
@@ -312,7 +535,7 @@ fn conf_rejects_invalid_key_segment() -> PRslt<(),> {
 		.expect_err("expected invalid key segment",);
 
 	match err {
-		ParseError::InvalidKeySegment { segment, line, } => {
+		ParseError::InvalidKeySegment { segment, line, .. } => {
 			assert!(segment.is_empty());
 			assert_eq!(line, 1);
 		},
@@ -323,7 +546,299 @@ fn conf_rejects_invalid_key_segment() -> PRslt<(),> {
 }
 
 #[test]
-fn conf_parses_from_file() -> PRslt<(),> {
+fn strings_with_comment_markers_and_edge_whitespace_survive_serialization()
+-> TestResult {
+	let schema = schema::parse_str(
+		"a -> String\nb -> String\nc -> String\nd -> String\ne -> String\n",
+	)?;
+	let conf = conf::parse_str(
+		"a = \"#not a comment\"\nb = \"x; y\"\nc = \"  padded\t\"\nd = \
+		 \"\\\"quoted\\\" \\\\ \"\ne = plain text ; comment\n",
+		&schema,
+	)?;
+	assert_eq!(get_str(&conf, "c")?, "  padded\t");
+
+	let text = conf::to_string(&conf,);
+	assert_eq!(
+		text,
+		"a = \"#not a comment\"\nb = \"x; y\"\nc = \"  padded\\t\"\nd = \
+		 \"\\\"quoted\\\" \\\\ \"\ne = plain text\n"
+	);
+	assert_eq!(conf::parse_str(&text, &schema,)?, conf);
+	Ok((),)
+}
+
+#[test]
+fn written_conf_file_parses_back() -> TestResult {
+	let schema = schema::parse_str(
+		"app.port -> Integer\napp.tags -> [String]\napp.motd -> String\n",
+	)?;
+	let conf = conf::parse_str(
+		"app.port = 9000\napp.tags = web, api\napp.motd = \" hi # all\"\n",
+		&schema,
+	)?;
+
+	let mut path = std::env::temp_dir();
+	path.push(format!("write_file_test_{}.conf", std::process::id()),);
+	conf::write_file(&conf, &path,)?;
+	let written = std::fs::read_to_string(&path,)?;
+	std::fs::remove_file(&path,)?;
+
+	assert_eq!(
+		written,
+		"app.motd = \" hi # all\"\napp.port = 9000\napp.tags = web, api\n"
+	);
+	assert_eq!(conf::parse_str(&written, &schema,)?, conf);
+	Ok((),)
+}
+
+#[test]
+fn plugins_mounted_under_prefixes_share_one_file() -> TestResult {
+	let web = schema::parse_str(
+		"port -> Integer\nallow -> [String] (repeatable)\n",
+	)?;
+	let metrics = schema::parse_str("port -> Integer\ninterval -> Float\n",)?;
+
+	let mut mounts = SchemaMounts::new();
+	mounts.mount("web", "plugins.web", web,)?;
+	mounts.mount("metrics", "plugins.metrics", metrics,)?;
+	let schema = mounts.into_schema();
+
+	let conf = conf::parse_str(
+		"plugins.web.port = 8080\nplugins.metrics.port = \
+		 9100\nplugins.web.allow = alice\nplugins.metrics.interval = \
+		 0.5\nplugins.web.allow = bob\n",
+		&schema,
+	)?;
+
+	let web = conf.strip_prefix("plugins.web",).expect("web section",);
+	assert_eq!(web.get_int("port"), Some(8080));
+	assert!(web.is_repeatable("allow"));
+	assert_eq!(
+		conf::to_string(&web,),
+		"allow = alice\nallow = bob\nport = 8080\n"
+	);
+
+	let metrics =
+		conf.strip_prefix("plugins.metrics",).expect("metrics section",);
+	assert_eq!(conf::to_string(&metrics,), "interval = 0.5\nport = 9100\n");
+	assert!(conf.strip_prefix("plugins.cache").is_none());
+	Ok((),)
+}
+
+#[test]
+fn nested_plugin_prefixes_are_rejected_at_mount_time() -> TestResult {
+	let mut mounts = SchemaMounts::new();
+	mounts.mount(
+		"web",
+		"plugins.web",
+		schema::parse_str("port -> Integer\n",)?,
+	)?;
+
+	for (plugin, prefix,) in [
+		("web2", "plugins.web",),
+		("sub", "plugins.web.tls",),
+		("all", "plugins",),
+	] {
+		let err = mounts
+			.mount(plugin, prefix, schema::parse_str("port -> Integer\n",)?,)
+			.unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			format!(
+				"[E025] '{plugin}' mounted at '{prefix}' collides with 'web' \
+				 mounted at 'plugins.web'"
+			)
+		);
+	}
+
+	mounts.mount(
+		"webhooks",
+		"plugins.webhooks",
+		schema::parse_str("url -> String\n",)?,
+	)?;
+	Ok((),)
+}
+
+#[test]
+fn layered_files_merge_section_by_section() -> TestResult {
+	let dir = std::env::temp_dir()
+		.join(format!("merge_test_{}", std::process::id()),);
+	std::fs::create_dir_all(&dir,)?;
+	let schema_path = dir.join("app.schema",);
+	let base = dir.join("base.conf",);
+	let prod = dir.join("prod.conf",);
+	std::fs::write(
+		&schema_path,
+		"server.http.port -> Integer\nserver.http.host -> \
+		 String\nserver.workers -> Integer\nlog.level -> String\n",
+	)?;
+	std::fs::write(
+		&base,
+		"server.http.port = 80\nserver.http.host = localhost\nserver.workers \
+		 = 2\n",
+	)?;
+	std::fs::write(
+		&prod,
+		"server.http.host = example.com\nlog.level = warn\n",
+	)?;
+
+	let paths = [base.clone(), prod.clone(),];
+	let merged = conf::parse_files_merged(
+		&paths,
+		&schema_path,
+		MergePolicy::OverrideWithOther,
+	)?;
+	let kept = conf::parse_files_merged(
+		&paths,
+		&schema_path,
+		MergePolicy::KeepExisting,
+	)?;
+	let conflict = conf::parse_files_merged(
+		&paths,
+		&schema_path,
+		MergePolicy::ErrorOnConflict,
+	)
+	.unwrap_err();
+	std::fs::remove_dir_all(&dir,)?;
+
+	assert_eq!(
+		conf::to_string(&merged,),
+		"log.level = warn\nserver.http.host = example.com\nserver.http.port = \
+		 80\nserver.workers = 2\n"
+	);
+	assert_eq!(merged.history_of("server.http.host").len(), 2);
+	assert_eq!(
+		merged.source_of("log.level").map(ToString::to_string),
+		Some(format!("{}:2", prod.display()))
+	);
+	assert_eq!(kept.get_str("server.http.host"), Some("localhost"));
+	assert!(matches!(
+		conflict,
+		ParseError::MergeConflict { ref key } if key == "server.http.host"
+	));
+	Ok((),)
+}
+
+#[test]
+fn merging_a_value_over_a_section_conflicts() {
+	let mut base = dot_conf_parser::conf_map! { "server.port" => 80 };
+	let before = base.clone();
+	let err = base
+		.merge(
+			dot_conf_parser::conf_map! { "server" => "localhost" },
+			MergePolicy::OverrideWithOther,
+		)
+		.unwrap_err();
+
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { ref key, .. } if key == "server"
+	));
+	assert_eq!(base, before);
+}
+
+/// fresh directory under the temp dir, removed by the caller
+fn include_dir(name: &str,) -> std::io::Result<std::path::PathBuf,> {
+	let dir = std::env::temp_dir()
+		.join(format!("include_test_{name}_{}", std::process::id()),);
+	std::fs::create_dir_all(dir.join("conf.d",),)?;
+	Ok(dir,)
+}
+
+#[test]
+fn includes_are_spliced_in_relative_to_the_including_file() -> TestResult {
+	let dir = include_dir("nested",)?;
+	std::fs::write(
+		dir.join("app.schema",),
+		"@include conf.d/server.schema\nlog.level -> String\n",
+	)?;
+	std::fs::write(
+		dir.join("conf.d/server.schema",),
+		"server.port -> Integer\nserver.host -> String\n",
+	)?;
+	std::fs::write(
+		dir.join("app.conf",),
+		"server.port = 80\n@include conf.d/server.conf\nlog.level = warn\n",
+	)?;
+	std::fs::write(
+		dir.join("conf.d/server.conf",),
+		"server.host = a\n@include tls.conf\n",
+	)?;
+	std::fs::write(
+		dir.join("conf.d/tls.conf",),
+		"server.port = 443\nserver.host = b\n",
+	)?;
+
+	let conf =
+		conf::parse_file(dir.join("app.conf",), dir.join("app.schema",),);
+	std::fs::remove_dir_all(&dir,)?;
+	let conf = conf?;
+
+	assert_eq!(
+		conf::to_string(&conf,),
+		"log.level = warn\nserver.host = b\nserver.port = 443\n"
+	);
+	let source = conf.source_of("server.port",).expect("server.port source",);
+	assert_eq!(source.line, Some(1));
+	assert!(source.to_string().contains("tls.conf"));
+	Ok((),)
+}
+
+#[test]
+fn errors_in_included_files_name_the_file_and_its_line() -> TestResult {
+	let dir = include_dir("error",)?;
+	std::fs::write(dir.join("app.schema",), "port -> Integer\n",)?;
+	std::fs::write(dir.join("app.conf",), "\n\n@include conf.d/bad.conf\n",)?;
+	std::fs::write(dir.join("conf.d/bad.conf",), "\nport = http\n",)?;
+
+	let err = conf::parse_file(dir.join("app.conf",), dir.join("app.schema",),)
+		.unwrap_err();
+	let direct = conf::parse_file(
+		dir.join("conf.d/bad.conf",),
+		dir.join("app.schema",),
+	);
+	std::fs::remove_dir_all(&dir,)?;
+
+	let ParseError::InFile { path, error, } = err else {
+		panic!("unexpected error: {err}");
+	};
+	assert!(path.ends_with("conf.d/bad.conf"));
+	assert!(matches!(
+		*error,
+		ParseError::InvalidValue { ref key, line: 2, .. } if key == "port"
+	));
+	assert!(matches!(direct, Err(ParseError::InvalidValue { line: 2, .. })));
+	Ok((),)
+}
+
+#[test]
+fn include_cycles_are_reported() -> TestResult {
+	let dir = include_dir("cycle",)?;
+	std::fs::write(dir.join("app.schema",), "a -> Integer\nb -> Integer\n",)?;
+	std::fs::write(dir.join("a.conf",), "a = 1\n@include conf.d/b.conf\n",)?;
+	std::fs::write(
+		dir.join("conf.d/b.conf",),
+		"b = 2\n\n@include ../a.conf\n",
+	)?;
+
+	let err = conf::parse_file(dir.join("a.conf",), dir.join("app.schema",),)
+		.unwrap_err();
+	std::fs::remove_dir_all(&dir,)?;
+
+	let ParseError::InFile { path, error, } = err else {
+		panic!("unexpected error: {err}");
+	};
+	assert!(path.ends_with("conf.d/b.conf"));
+	assert!(matches!(
+		*error,
+		ParseError::CircularInclude { ref path, line: 3 } if path.ends_with("a.conf")
+	));
+	Ok((),)
+}
+
+#[test]
+fn conf_parses_from_file() -> TestResult {
 	let mut schema_path = std::env::temp_dir();
 	let mut conf_path = std::env::temp_dir();
 	let unique = format!(
@@ -340,10 +855,7 @@ fn conf_parses_from_file() -> PRslt<(),> {
 	std::fs::write(&conf_path, "app.port = 9000\n",)?;
 
 	let conf_map = conf::parse_file(&conf_path, &schema_path,)?;
-	assert_eq!(
-		expect_int(conf_map.get("app.port").expect("app.port entry")),
-		9000
-	);
+	assert_eq!(get_int(&conf_map, "app.port")?, 9000);
 
 	std::fs::remove_file(&schema_path,)?;
 	std::fs::remove_file(&conf_path,)?;
@@ -353,15 +865,25 @@ fn conf_parses_from_file() -> PRslt<(),> {
 
 proptest! {
 	#[test]
-	fn bool_payload_matches_true_literal(input in prop::string::string_regex("[A-Za-z0-9]+").unwrap()) {
+	fn bool_payload_accepts_only_exact_literals(input in prop::string::string_regex("[A-Za-z0-9]+").unwrap()) {
 		prop_assume!(!input.is_empty());
 
 		let schema = schema::parse_str("feature.enabled -> Bool\n").expect("schema parse");
 		let conf_string = format!("feature.enabled = {}\n", input);
-		let conf = conf::parse_str(&conf_string, schema).expect("conf parse");
-
-		let value = expect_bool(conf.get("feature.enabled").expect("feature.enabled entry"));
-		prop_assert_eq!(value, input == "true");
+		match conf::parse_str(&conf_string, schema) {
+			Ok(conf) => {
+				prop_assert!(input == "true" || input == "false");
+				prop_assert_eq!(get_bool(&conf, "feature.enabled")?, input == "true");
+			},
+			Err(ParseError::InvalidValue { key, value, ty, line, .. }) => {
+				prop_assert!(input != "true" && input != "false");
+				prop_assert_eq!(key, "feature.enabled");
+				prop_assert_eq!(value, input);
+				prop_assert_eq!(ty, SingleValueDiscriminants::Bool);
+				prop_assert_eq!(line, 1);
+			},
+			Err(other) => prop_assert!(false, "unexpected error: {}", other),
+		}
 	}
 
 	#[test]
@@ -370,52 +892,1831 @@ proptest! {
 		let conf_string = format!("retry.count = {}\n", input);
 		let conf = conf::parse_str(&conf_string, schema).expect("conf parse");
 
-		let value = expect_int(conf.get("retry.count").expect("retry.count entry"));
+		let value = get_int(&conf, "retry.count")?;
 		prop_assert_eq!(value, input);
 	}
+
+	#[test]
+	fn uint_payload_round_trips(input in any::<u64>()) {
+		let schema = schema::parse_str("quota.bytes -> UInt\n").expect("schema parse");
+		let conf = conf::parse_str(&format!("quota.bytes = {input}\n"), &schema).expect("conf parse");
+
+		prop_assert_eq!(conf.get_uint("quota.bytes"), Some(input));
+		prop_assert_eq!(conf::parse_str(&conf::to_string(&conf), &schema).expect("reparse"), conf);
+	}
+
+	#[test]
+	fn long_payload_round_trips(input in any::<i64>()) {
+		let schema = schema::parse_str("epoch -> Long\n").expect("schema parse");
+		let conf = conf::parse_str(&format!("epoch = {input}\n"), &schema).expect("conf parse");
+
+		prop_assert_eq!(conf.get_long("epoch"), Some(input));
+		prop_assert_eq!(conf::parse_str(&conf::to_string(&conf), &schema).expect("reparse"), conf);
+	}
+
+	#[test]
+	fn serialized_conf_parses_back_equal(
+		name in "[ -~\t]{0,12}",
+		ports in prop::collection::vec(any::<i32>(), 1..4),
+		ratio in -1.0e6f64..1.0e6,
+	) {
+		let schema = schema::parse_str(
+			"name -> String\nports -> [Integer]\nratio -> Float\n",
+		).expect("schema parse");
+		let escaped = name
+			.replace('\\', "\\\\")
+			.replace('"', "\\\"")
+			.replace('\t', "\\t");
+		let ports: Vec<String> = ports.iter().map(ToString::to_string).collect();
+		let input = format!(
+			"name = \"{escaped}\"\nports = {}\nratio = {ratio}\n",
+			ports.join(", ")
+		);
+
+		let conf = conf::parse_str(&input, &schema).expect("conf parse");
+		let text = conf::to_string(&conf);
+		prop_assert_eq!(conf::parse_str(&text, &schema).expect("reparse"), conf);
+	}
 }
 
 #[test]
-fn conf_parses_bool_true_literal() -> PRslt<(),> {
+fn conf_parses_bool_true_literal() -> TestResult {
 	let schema = schema::parse_str("feature.enabled -> Bool\n",)?;
 	let conf = conf::parse_str("feature.enabled = true\n", schema,)?;
 
-	assert!(expect_bool(
-		conf.get("feature.enabled").expect("feature.enabled entry")
-	));
+	assert!(get_bool(&conf, "feature.enabled")?);
 
 	Ok((),)
 }
 
 #[test]
-fn conf_parses_bool_false_literal() -> PRslt<(),> {
+fn conf_parses_bool_false_literal() -> TestResult {
 	let schema = schema::parse_str("feature.enabled -> Bool\n",)?;
 	let conf = conf::parse_str("feature.enabled = false\n", schema,)?;
 
-	assert!(!expect_bool(
-		conf.get("feature.enabled").expect("feature.enabled entry"),
+	assert!(!get_bool(&conf, "feature.enabled")?);
+
+	Ok((),)
+}
+
+#[test]
+fn error_columns_count_chars_and_tabs() -> TestResult {
+	let options =
+		ParseOptions::default().segment_charset(SegmentCharset::UnicodeIdent,);
+	let schema = schema::parse_str_with("café.port -> Integer\n", &options,)?;
+	let parse = |input: &str| conf::parse_str_with(input, &schema, &options,);
+	let err = parse("café.port = eighty\n",).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue { line: 1, column: 13, .. }
+	));
+	assert_eq!((err.line(), err.column()), (Some(1), Some(13)));
+
+	let err = parse("\tcafé.port\t=\tx\n",).unwrap_err();
+	assert!(matches!(err, ParseError::InvalidValue { column: 14, .. }));
+
+	let err = parse("  café..port = 1\n",).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidKeySegment { line: 1, column: 8, .. }
+	));
+	let err = parse("café.port\n\t = 1\n",).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::MissingDelimiter { line: 1, column: 1, .. }
 	));
+	let err = parse("\t = 1\n",).unwrap_err();
+	assert!(matches!(err, ParseError::EmptyKey { line: 1, column: 3, }));
+	let err = parse("café.port = 1\ncafé.port.x = 2\n",).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { line: 2, column: 1, .. }
+	));
+	Ok((),)
+}
 
+#[test]
+fn conf_rejects_bool_lookalikes() -> TestResult {
+	let schema = schema::parse_str("feature.enabled -> Bool\n",)?;
+	for input in ["yes", "ture", "True", "FALSE", "1",] {
+		let err =
+			conf::parse_str(&format!("feature.enabled = {input}\n"), &schema,)
+				.expect_err("expected invalid value",);
+		assert_eq!(
+			err.to_string(),
+			format!(
+				"[E007] invalid value '{input}' while expecting Bool for \
+				 'feature.enabled' on line 1, column 19"
+			)
+		);
+	}
 	Ok((),)
 }
 
 #[test]
 fn conf_supports_boolean_collections() -> PRslt<(),> {
 	let schema = schema::parse_str("feature.flags -> Bool, Bool\n",)?;
-	let conf = conf::parse_str("feature.flags = true\n", schema,)?;
+	let conf = conf::parse_str("feature.flags = true, false\n", schema,)?;
 
 	match conf.get("feature.flags",).expect("feature.flags entry",) {
 		ConfValue::Scalar(Value::Collection(items,),) => {
-			assert_eq!(items.len(), 2);
-			for item in items {
-				match item {
-					SingleValue::Bool(flag,) => assert!(*flag),
-					other => panic!("expected bool payload, got {other:?}"),
-				}
-			}
+			assert_eq!(
+				items,
+				&vec![SingleValue::Bool(true), SingleValue::Bool(false)]
+			);
 		},
 		other => panic!("expected collection payload, got {other:?}"),
 	}
 
 	Ok((),)
 }
+
+#[test]
+fn threads_parse_against_one_shared_schema() -> TestResult {
+	let schema =
+		schema::parse_str("worker.id -> Integer\nworker.name -> String\n",)?
+			.into_shared();
+
+	let handles: Vec<_,> = (0..4)
+		.map(|id| {
+			let schema = schema.clone();
+			std::thread::spawn(move || {
+				let input = format!("worker.id = {id}\nworker.name = w{id}\n");
+				conf::parse_str(&input, &schema,)
+			},)
+		},)
+		.collect();
+
+	for (id, handle,) in handles.into_iter().enumerate() {
+		let conf = handle.join().expect("worker thread",)?;
+		assert_eq!(get_int(&conf, "worker.id")?, id as i32);
+		assert_eq!(get_str(&conf, "worker.name")?, format!("w{id}"));
+	}
+
+	Ok((),)
+}
+
+#[test]
+fn parsed_maps_equal_maps_built_by_hand() -> TestResult {
+	let schema = schema::parse_str(
+		"server.port -> Integer\nserver.host -> String\nratio -> Float\nports \
+		 -> Integer, Integer\n",
+	)?;
+	assert_eq!(
+		schema,
+		dot_conf_parser::schema_map! {
+			"server.port" => Integer,
+			"server.host" => String,
+			"ratio" => Float,
+			"ports" => [Integer, Integer],
+		}
+	);
+
+	let conf = conf::parse_str(
+		"ratio = 0.50\nserver.port = +80\nserver.host = localhost\nports = 1, \
+		 2\n",
+		&schema,
+	)?;
+	let expected = dot_conf_parser::conf_map! {
+		"server.port" => 80,
+		"server.host" => "localhost",
+		"ratio" => 0.5,
+		"ports" => [1, 2],
+	};
+	assert_eq!(conf, expected);
+
+	let pristine = conf.clone();
+	let mut edited = conf;
+	edited.remove("ratio",);
+	assert_ne!(edited, pristine);
+	assert_eq!(pristine, expected);
+	Ok((),)
+}
+
+#[test]
+fn insert_dotted_creates_sections_and_overwrites_leaves() -> TestResult {
+	let schema = schema::parse_str(
+		"server.port -> Integer\nserver.tls.cert -> String\n",
+	)?;
+	let mut conf = conf::parse_str("server.port = 80\n", &schema,)?;
+
+	let port =
+		|num| ConfValue::Scalar(Value::Single(SingleValue::Integer(num,),),);
+	assert_eq!(conf.insert_dotted("server.port", port(8080),)?, Some(port(80)));
+	let cert = ConfValue::Scalar(Value::Single(SingleValue::String(
+		"a.pem".to_string(),
+	),),);
+	assert_eq!(conf.insert_dotted("server.tls.cert", cert,)?, None);
+
+	assert_eq!(conf.get_int("server.port"), Some(8080));
+	assert_eq!(conf.get_str("server.tls.cert"), Some("a.pem"));
+	assert_eq!(
+		conf::to_string(&conf,),
+		"server.port = 8080\nserver.tls.cert = a.pem\n"
+	);
+	conf_eq(&conf::parse_str(&conf::to_string(&conf,), &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn insert_dotted_rejects_paths_through_values() -> TestResult {
+	let schema = schema::parse_str("server.port -> Integer\n",)?;
+	let mut conf = conf::parse_str("server.port = 80\n", &schema,)?;
+	let flag = ConfValue::Scalar(Value::Single(SingleValue::Bool(true,),),);
+
+	let err =
+		conf.insert_dotted("server.port.open", flag.clone(),).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { ref key, .. } if key == "server.port"
+	));
+	let err = conf.insert_dotted("server", flag,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { ref key, .. } if key == "server"
+	));
+	assert_eq!(conf.get_int("server.port"), Some(80));
+	Ok((),)
+}
+
+#[test]
+fn sections_win_over_top_level_keys_holding_dots() -> TestResult {
+	let schema = schema::parse_str("a.b.c -> Integer\n",)?;
+	let mut conf = conf::parse_str("a.b.c = 1\n", &schema,)?;
+	let int =
+		|num| ConfValue::Scalar(Value::Single(SingleValue::Integer(num,),),);
+	conf.insert("a.b".to_string(), int(2,),);
+	conf.insert(
+		"x.y".to_string(),
+		ConfValue::Map([("z".to_string(), int(3,),),].into(),),
+	);
+
+	assert_eq!(conf.get_int("a.b.c"), Some(1));
+	assert!(matches!(conf.get("a.b"), Some(ConfValue::Map(_))));
+	assert_eq!(conf.get_int("x.y.z"), Some(3));
+	assert!(matches!(conf.get("x.y"), Some(ConfValue::Map(_))));
+	assert_eq!(conf.get("x"), None);
+	Ok((),)
+}
+
+#[test]
+fn entry_dotted_puts_each_segment_in_its_section() -> TestResult {
+	let schema = schema::parse_str("server.port -> Integer\n",)?;
+	let mut conf = conf::parse_str("server.port = 80\n", &schema,)?;
+	let port =
+		|num| ConfValue::Scalar(Value::Single(SingleValue::Integer(num,),),);
+
+	conf.entry_dotted("server.tls.port",)?.or_insert(port(443,),);
+	*conf.entry_dotted("server.port",)?.or_insert(port(1,),) = port(8080,);
+	assert_eq!(conf.get_int("server.tls.port"), Some(443));
+	assert_eq!(conf.get_int("server.port"), Some(8080));
+	assert_eq!(conf.keys().collect::<Vec<_,>>(), ["server"]);
+
+	let err = conf.entry_dotted("server.port.open",).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { ref key, .. } if key == "server.port"
+	));
+	Ok((),)
+}
+
+#[test]
+fn remove_dotted_prunes_emptied_sections() -> TestResult {
+	let schema = schema::parse_str(
+		"a.b.c -> Integer\na.d -> Integer\nlog.file -> String\n",
+	)?;
+	let mut conf =
+		conf::parse_str("a.b.c = 1\na.d = 2\nlog.file = x\n", &schema,)?;
+
+	assert_eq!(
+		conf.remove_dotted("a.b.c"),
+		Some(ConfValue::Scalar(Value::Single(SingleValue::Integer(1,),),))
+	);
+	assert!(conf.get("a.b").is_none());
+	assert_eq!(conf.get_int("a.d"), Some(2));
+
+	assert!(conf.remove_dotted("a.d").is_some());
+	assert!(conf.get("a").is_none());
+	assert!(conf.remove_dotted("a.d").is_none());
+	assert!(conf.remove_dotted("log.file.x").is_none());
+	assert_eq!(conf::to_string(&conf,), "log.file = x\n");
+	Ok((),)
+}
+
+#[test]
+fn duplicate_key_policies_pick_or_reject_repeated_keys() -> TestResult {
+	let schema =
+		schema::parse_str("port -> Integer\nserver.host -> String\n",)?;
+	let input = "port = 80\nserver.host = a\nport = 81\nserver.host = b\n";
+	let parse = |duplicate_keys| {
+		conf::parse_str_with(
+			input,
+			&schema,
+			&ParseOptions::default().duplicate_keys(duplicate_keys,),
+		)
+	};
+
+	let conf = parse(DuplicateKeyPolicy::LastWins,)?;
+	assert_eq!(
+		(conf.get_int("port"), conf.get_str("server.host")),
+		(Some(81), Some("b"))
+	);
+	assert_eq!(conf, conf::parse_str(input, &schema,)?);
+
+	let conf = parse(DuplicateKeyPolicy::FirstWins,)?;
+	assert_eq!(
+		(conf.get_int("port"), conf.get_str("server.host")),
+		(Some(80), Some("a"))
+	);
+
+	let err = parse(DuplicateKeyPolicy::Error,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::DuplicateKey { ref key, first_line: 1, second_line: 3 } if key == "port"
+	));
+	let err = conf::parse_str_with(
+		"server.host = a\nport = 80\nserver.host = b\n",
+		&schema,
+		&ParseOptions::default().duplicate_keys(DuplicateKeyPolicy::Error,),
+	)
+	.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::DuplicateKey { ref key, first_line: 1, second_line: 3 }
+			if key == "server.host"
+	));
+	Ok((),)
+}
+
+#[test]
+fn duplicate_key_error_lets_repeatable_lists_repeat() -> TestResult {
+	let schema = schema::parse_str(
+		"allow -> [String] (repeatable)\nport -> Integer\n",
+	)?;
+	let options =
+		ParseOptions::default().duplicate_keys(DuplicateKeyPolicy::Error,);
+	let input = "allow = a\nport = 80\nallow = b\nallow += c\n";
+	let conf = conf::parse_str_with(input, &schema, &options,)?;
+	assert_eq!(conf, conf::parse_str(input, &schema,)?);
+	assert_eq!(conf.get_int("port"), Some(80));
+
+	let err = conf::parse_str_with(
+		"allow = a\nport = 80\nallow = b\nport = 81\n",
+		&schema,
+		&options,
+	)
+	.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::DuplicateKey { ref key, first_line: 2, second_line: 4 }
+			if key == "port"
+	));
+	Ok((),)
+}
+
+#[test]
+fn every_entry_point_parses_as_options_ask() -> TestResult {
+	let options = ParseOptions::default()
+		.comment_chars(['!',],)
+		.duplicate_keys(DuplicateKeyPolicy::Error,);
+	let schema_text = "! note\nname -> String\n";
+	let input = "! note\nname = a#b\n";
+	let schema = schema::parse_lines_with(
+		schema_text.lines().enumerate().map(|(idx, line,)| (idx + 1, line,),),
+		&options,
+	)?;
+	assert_eq!(
+		schema,
+		schema::parse_reader_with(schema_text.as_bytes(), &options,)?
+	);
+	assert_eq!(
+		schema,
+		schema::parse_str_all_errors_with(schema_text, &options,).unwrap()
+	);
+
+	let dir = include_dir("options",)?;
+	std::fs::write(dir.join("app.schema",), "name -> String\n",)?;
+	std::fs::write(dir.join("options.schema",), schema_text,)?;
+	std::fs::write(dir.join("app.conf",), input,)?;
+	let from_file =
+		schema::parse_file_with(dir.join("options.schema",), &options,)
+			.and_then(|from_file| {
+				assert_eq!(from_file, schema);
+				conf::parse_file_with(
+					dir.join("app.conf",),
+					dir.join("app.schema",),
+					&options,
+				)
+			},);
+	let all_from_file = conf::parse_file_all_errors_with(
+		dir.join("app.conf",),
+		&schema,
+		&options,
+	);
+	std::fs::remove_dir_all(&dir,)?;
+
+	let expected = conf::parse_str_with(input, &schema, &options,)?;
+	assert_eq!(get_str(&expected, "name")?, "a#b");
+	assert_eq!(from_file?, expected);
+	assert_eq!(all_from_file.unwrap(), expected);
+	assert_eq!(
+		conf::parse_reader_with(input.as_bytes(), &schema, &options,)?,
+		expected
+	);
+	assert_eq!(
+		conf::parse_lines_with(
+			[(1, "! note",), (2, "name = a#b",),],
+			&schema,
+			&options,
+		)?,
+		expected
+	);
+	assert_eq!(
+		conf::parse_str_collecting_with(input, &schema, &options,).unwrap(),
+		expected
+	);
+	assert_eq!(
+		conf::parse_str_all_errors_with(input, &schema, &options,).unwrap(),
+		expected
+	);
+
+	let duplicate = || {
+		vec![ParseError::DuplicateKey {
+			key:         "name".to_string(),
+			first_line:  1,
+			second_line: 2,
+		}]
+	};
+	let input = "name = a\nname = b\n";
+	assert_eq!(
+		conf::parse_str_collecting_with(input, &schema, &options,),
+		Err(duplicate())
+	);
+	assert_eq!(
+		conf::parse_str_all_errors_with(input, &schema, &options,),
+		Err(duplicate())
+	);
+	Ok((),)
+}
+
+#[test]
+fn comment_chars_option_replaces_the_comment_markers() -> TestResult {
+	let schema = schema::parse_str("name -> String\n",)?;
+	let options = ParseOptions::default().comment_chars(['!',],);
+	let input = "! name = ignored\nname = a#b;c ! note\n";
+	let conf = conf::parse_str_with(input, &schema, &options,)?;
+	assert_eq!(get_str(&conf, "name")?, "a#b;c");
+
+	let err = conf::parse_str_with("# note\n", &schema, &options,).unwrap_err();
+	assert!(matches!(err, ParseError::MissingDelimiter { line: 1, .. }));
+	Ok((),)
+}
+
+#[test]
+fn delimiters_option_accepts_alternative_delimiters() -> TestResult {
+	let schema = schema::parse_str(
+		"url -> String\nquery -> String\nport -> Integer\n",
+	)?;
+	let options = ParseOptions::default().delimiters(["=", ":",],);
+	let input = "url: http://x:8080/a=b\nquery = a:b\nport:80\n";
+	let conf = conf::parse_str_with(input, &schema, &options,)?;
+	assert_eq!(get_str(&conf, "url")?, "http://x:8080/a=b");
+	assert_eq!(get_str(&conf, "query")?, "a:b");
+	assert_eq!(get_int(&conf, "port")?, 80);
+
+	// the earliest delimiter wins, so a key cannot contain either
+	let err =
+		conf::parse_str_with("port:x = 80\n", &schema, &options,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue { ref key, ref value, .. }
+			if key == "port" && value == "x = 80"
+	));
+
+	let err =
+		conf::parse_str_with("port 80\n", &schema, &options,).unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"[E002] missing delimiter on line 1, column 1; expected '=' or ':'"
+	);
+
+	let schema = schema::parse_str_with(
+		"port: Integer\n",
+		&ParseOptions::default().delimiters([":",],),
+	)?;
+	assert!(schema.get("port").is_some());
+	Ok((),)
+}
+
+#[test]
+fn segment_charset_option_decides_what_keys_are_made_of() -> TestResult {
+	let charset = |charset| ParseOptions::default().segment_charset(charset,);
+	let schema = schema::parse_str_with(
+		"my key.x -> Integer\n🦀.x -> Integer\nnet . port -> Integer\n",
+		&charset(SegmentCharset::Permissive,),
+	)?;
+
+	let err = conf::parse_str("my key.x = 1\n", &schema,).unwrap_err();
+	assert_eq!(
+		err,
+		ParseError::InvalidKeySegment {
+			segment: "my key".to_string(),
+			line:    1,
+			column:  1,
+		}
+	);
+	let conf = conf::parse_str_with(
+		"my key.x = 1\n",
+		&schema,
+		&charset(SegmentCharset::Permissive,),
+	)?;
+	assert_eq!(get_int(&conf, "my key.x")?, 1);
+
+	// spaces around dots are not part of any segment
+	let conf = conf::parse_str("net . port = 80\n", &schema,)?;
+	assert_eq!(get_int(&conf, "net.port")?, 80);
+
+	for (preset, accepted,) in [
+		(SegmentCharset::AsciiIdent, false,),
+		(SegmentCharset::UnicodeIdent, false,),
+		(SegmentCharset::Permissive, true,),
+	] {
+		let parsed =
+			conf::parse_str_with("🦀.x = 1\n", &schema, &charset(preset,),);
+		match parsed {
+			Ok(conf,) => {
+				assert!(accepted, "{preset:?}");
+				assert_eq!(get_int(&conf, "🦀.x")?, 1);
+			},
+			Err(err,) => assert!(
+				!accepted && err.is_invalid_key_segment(),
+				"{preset:?}: {err:?}"
+			),
+		}
+	}
+	Ok((),)
+}
+
+#[test]
+fn allow_empty_values_option_assigns_empty_strings() -> TestResult {
+	let schema = schema::parse_str(
+		"name -> String\nport -> Integer\ndebug -> Bool\nports -> [Integer]\n",
+	)?;
+	let options = ParseOptions::default().allow_empty_values(true,);
+	let conf = conf::parse_str_with("name =   # unset\n", &schema, &options,)?;
+	assert_eq!(get_str(&conf, "name")?, "");
+	let conf = conf::parse_str_with("name =", &schema, &options,)?;
+	assert_eq!(get_str(&conf, "name")?, "");
+
+	let conf = conf::parse_str_with("ports =\n", &schema, &options,)?;
+	assert_eq!(get_ints(&conf, "ports")?, Vec::<i32,>::new());
+	for input in ["port =\n", "name = a\ndebug = ; off\n",] {
+		let err = conf::parse_str_with(input, &schema, &options,).unwrap_err();
+		assert!(
+			matches!(err, ParseError::EmptyValue { .. }),
+			"{input:?}: {err:?}"
+		);
+	}
+	let err = conf::parse_str_with("name = a\ndebug =\n", &schema, &options,)
+		.unwrap_err();
+	assert_eq!(err.to_string(), "[E004] empty value on line 2, column 8");
+	assert!(matches!(
+		conf::parse_str("name = # unset\n", &schema,),
+		Err(ParseError::EmptyValue { line: 1, .. })
+	));
+	Ok((),)
+}
+
+#[test]
+fn trim_values_option_keeps_surrounding_whitespace() -> TestResult {
+	let schema = schema::parse_str("name -> String\n",)?;
+	let options = ParseOptions::default().trim_values(false,);
+	let conf = conf::parse_str_with(
+		"name =  padded\tvalue  # c\n",
+		&schema,
+		&options,
+	)?;
+	assert_eq!(get_str(&conf, "name")?, "  padded\tvalue  ");
+	Ok((),)
+}
+
+#[test]
+fn lowercase_key_case_option_folds_keys() -> TestResult {
+	let options = ParseOptions::default().key_case(KeyCase::Lowercase,);
+	let schema = schema::parse_str_with(
+		"Server.Port -> Integer\nserver.host -> String\n",
+		&options,
+	)?;
+	let input = "SERVER.port = 80\n[Server]\nHost = a\n";
+	let conf = conf::parse_str_with(input, &schema, &options,)?;
+	assert_eq!(get_int(&conf, "server.port")?, 80);
+	assert_eq!(get_str(&conf, "server.host")?, "a");
+
+	let err = conf::parse_str_with(input, &schema, &ParseOptions::default(),)
+		.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::UnknownKey { ref key, .. } if key == "SERVER"
+	));
+	Ok((),)
+}
+
+#[test]
+fn first_wins_reports_unknown_keys_at_the_kept_line() -> TestResult {
+	let schema = schema::parse_str("port -> Integer\n",)?;
+	let options =
+		ParseOptions::default().duplicate_keys(DuplicateKeyPolicy::FirstWins,);
+	let err = conf::parse_str_with(
+		"legacy.port = 1\nport = 80\nlegacy.port = 2\n",
+		&schema,
+		&options,
+	)
+	.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::UnknownKey { ref key, ref lines, .. } if key == "legacy" && *lines == [1]
+	));
+	Ok((),)
+}
+
+#[test]
+fn maps_compare_by_entries_labels_and_repeatable_keys() -> TestResult {
+	let schema = schema::parse_str(
+		"limits -> (min: Integer, max: Integer)\nallow -> [String] \
+		 (repeatable)\nname -> String\n",
+	)?;
+	let input = "name = app\nallow = a\nlimits = 1, 10\n";
+	let conf = conf::parse_str(input, &schema,)?;
+
+	let mut ordered = conf::parse_str_with(
+		input,
+		&schema,
+		&ParseOptions::default().preserve_order(true,),
+	)?;
+	ordered.sources_mut().record("name", Origin::Override, None,);
+	assert_eq!(ordered, conf);
+
+	let unlabeled = dot_conf_parser::conf_map! {
+		"name" => "app",
+		"allow" => ["a"],
+		"limits" => [1, 10],
+	};
+	assert_ne!(unlabeled, conf);
+
+	assert!(SingleValue::try_from(f64::NAN).is_err());
+	assert!(SingleValue::try_from(f64::INFINITY).is_err());
+	assert_eq!(SingleValue::try_from(0.5)?, SingleValue::Float(0.5));
+	Ok((),)
+}
+
+#[test]
+fn conf_reads_lists_of_any_length() -> TestResult {
+	let schema = schema::parse_str("ports -> [Integer]\nhosts -> [String]\n",)?;
+	let conf =
+		conf::parse_str("ports = 8080, 8443, 9000,\nhosts = \"\"\n", &schema,)?;
+	assert_eq!(get_ints(&conf, "ports")?, vec![8080, 8443, 9000]);
+	assert_eq!(
+		conf.get("hosts"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![])))
+	);
+
+	let conf = conf::parse_str("ports = 80\n", &schema,)?;
+	assert_eq!(get_ints(&conf, "ports")?, vec![80]);
+	Ok((),)
+}
+
+#[test]
+fn repeated_assignments_append_to_repeatable_lists() -> TestResult {
+	let schema = schema::parse_str(
+		"allow -> [String] (repeatable)\nports -> [Integer]\nname -> String\n",
+	)?;
+	let input = "allow = alice\nports = 80\nname = a\nallow = bob, \
+	             carol\nports = 443\nname = b\nallow = dave\n";
+	let conf = conf::parse_str(input, &schema,)?;
+
+	let allow = ["alice", "bob", "carol", "dave",]
+		.map(|name| SingleValue::String(name.to_string(),),);
+	assert_eq!(
+		conf.get("allow"),
+		Some(&ConfValue::Scalar(Value::Collection(allow.to_vec())))
+	);
+	assert_eq!(get_ints(&conf, "ports")?, vec![443]);
+	assert_eq!(conf.get_str("name"), Some("b"));
+	assert!(conf.is_repeatable("allow") && !conf.is_repeatable("ports"));
+
+	let canonical = conf.canonicalize();
+	assert_eq!(
+		canonical,
+		"allow = alice\nallow = bob\nallow = carol\nallow = dave\nname = \
+		 b\nports = 443\n"
+	);
+	conf_eq(&conf::parse_str(&canonical, &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn plus_equals_appends_to_repeatable_lists_only() -> TestResult {
+	let schema = schema::parse_str(
+		"allow -> [String] (repeatable)\nports -> [Integer]\n",
+	)?;
+	let input = "allow += alice\nports = 80\nallow = bob\nallow += carol, \
+	             dave\nallow+=erin\n";
+	let conf = conf::parse_str(input, &schema,)?;
+
+	let allow = ["alice", "bob", "carol", "dave", "erin",]
+		.map(|name| SingleValue::String(name.to_string(),),);
+	assert_eq!(
+		conf.get("allow"),
+		Some(&ConfValue::Scalar(Value::Collection(allow.to_vec())))
+	);
+
+	assert_eq!(
+		conf::parse_str("ports = 80\nports += 443\n", &schema,),
+		Err(ParseError::NotRepeatable { key: "ports".to_string(), line: 2, })
+	);
+	Ok((),)
+}
+
+#[test]
+fn conf_types_tuple_elements_by_position() -> TestResult {
+	let schema = schema::parse_str("endpoint -> String, Integer\n",)?;
+
+	let conf = conf::parse_str("endpoint = local host , 8080\n", &schema,)?;
+	assert_eq!(
+		conf.get("endpoint"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::String("local host".to_string()),
+			SingleValue::Integer(8080),
+		])))
+	);
+
+	let err =
+		conf::parse_str("endpoint = 8080, localhost\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue {
+			ref value,
+			ty: SingleValueDiscriminants::Integer,
+			position: Some((2, 2)),
+			..
+		} if value == "localhost"
+	));
+	Ok((),)
+}
+
+#[test]
+fn escaped_commas_stay_inside_their_element() -> TestResult {
+	let schema =
+		schema::parse_str("greeting -> String, Integer\nnames -> [String]\n",)?;
+	let input = "greeting = hello\\, world, 3\nnames = a\\,b, c\n";
+	let conf = conf::parse_str(input, &schema,)?;
+
+	assert_eq!(
+		conf.get("greeting"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::String("hello, world".to_string()),
+			SingleValue::Integer(3),
+		])))
+	);
+	assert_eq!(
+		conf.get("names"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::from("a,b"),
+			SingleValue::from("c"),
+		])))
+	);
+
+	let canonical = conf.canonicalize();
+	assert_eq!(canonical, input);
+	conf_eq(&conf::parse_str(&canonical, &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn quoted_elements_may_end_with_a_backslash() -> TestResult {
+	let schema =
+		schema::parse_str("dirs -> [String]\npair -> String, String\n",)?;
+	let input =
+		"dirs = \"C:\\\\\", D:\\,E:, \"a\\\\,b\"\npair = \"x\\\\\", y\n";
+	let conf = conf::parse_str(input, &schema,)?;
+
+	assert_eq!(
+		conf.get("dirs"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::from("C:\\"),
+			SingleValue::from("D:,E:"),
+			SingleValue::from("a\\,b"),
+		])))
+	);
+	assert_eq!(
+		conf.get("pair"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::from("x\\"),
+			SingleValue::from("y"),
+		])))
+	);
+
+	let canonical = conf.canonicalize();
+	assert_eq!(
+		canonical,
+		"dirs = \"C:\\\\\", D:\\,E:, a\\\\,b\npair = \"x\\\\\", y\n"
+	);
+	conf_eq(&conf::parse_str(&canonical, &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn quoted_elements_are_decoded_before_typing() -> TestResult {
+	let schema = schema::parse_str(
+		"ports -> [Integer]\nnames -> [String]\npair -> String, String\nmotd \
+		 -> String\n",
+	)?;
+	let input = "ports = \"80\", \"443\"\nnames = \"a b\", c, \"d # e\" # \
+	             note\npair = \"a, b\", \"c\"\nmotd = \"\\\"hi\\\" there\"\n";
+	let conf = conf::parse_str(input, &schema,)?;
+
+	assert_eq!(
+		conf.get("ports"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::Integer(80),
+			SingleValue::Integer(443),
+		])))
+	);
+	assert_eq!(
+		conf.get("names"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::from("a b"),
+			SingleValue::from("c"),
+			SingleValue::from("d # e"),
+		])))
+	);
+	assert_eq!(
+		conf.get("pair"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::from("a, b"),
+			SingleValue::from("c"),
+		])))
+	);
+	assert_eq!(
+		conf.get("motd"),
+		Some(&ConfValue::Scalar(Value::Single(SingleValue::from(
+			"\"hi\" there"
+		))))
+	);
+
+	conf_eq(&conf::parse_str(&conf.canonicalize(), &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn quoted_empty_elements_are_kept_anywhere() -> TestResult {
+	let schema = schema::parse_str(
+		"first -> [String]\nlast -> [String]\nlone -> [String]\nnone -> \
+		 [String]\nallow -> [String] (repeatable)\nname -> (name: String)\n",
+	)?;
+	let input = "first = \"\", x\nlast = x, \"\"\nlone = \"\",\nnone = \
+	             \"\"\nallow = \"\",\nallow = y\nname = \"\",\n";
+	let conf = conf::parse_str(input, &schema,)?;
+
+	let strings = |items: &[&str]| {
+		Some(ConfValue::Scalar(Value::Collection(
+			items.iter().map(|item| SingleValue::from(*item,),).collect(),
+		),),)
+	};
+	assert_eq!(conf.get("first").cloned(), strings(&["", "x"]));
+	assert_eq!(conf.get("last").cloned(), strings(&["x", ""]));
+	assert_eq!(conf.get("lone").cloned(), strings(&[""]));
+	assert_eq!(conf.get("none").cloned(), strings(&[]));
+	assert_eq!(conf.get("allow").cloned(), strings(&["", "y"]));
+	assert_eq!(conf.get("name").cloned(), strings(&[""]));
+
+	let canonical = conf.canonicalize();
+	assert_eq!(
+		canonical,
+		"allow = \"\",\nallow = y\nfirst = \"\", x\nlast = x, \"\"\nlone = \
+		 \"\",\nname = \"\",\nnone = \"\"\n"
+	);
+	conf_eq(&conf::parse_str(&canonical, &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn comma_after_a_quoted_scalar_is_trailing_text() -> TestResult {
+	let schema = schema::parse_str("name -> String\nports -> [Integer]\n",)?;
+
+	let err =
+		conf::parse_str("name = \"prod\", extra text\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::TrailingCharacters { line: 1, column: 14, ref text }
+			if text == ", extra text"
+	));
+
+	let err =
+		conf::parse_str("ports = \"80\", \"443\" x\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::TrailingCharacters { line: 1, column: 21, ref text }
+			if text == "x"
+	));
+
+	let err = conf::parse_str("ports = 80, \"443\n", &schema,).unwrap_err();
+	assert!(matches!(err, ParseError::UnterminatedString { line: 1, }));
+	Ok((),)
+}
+
+#[test]
+fn conf_names_the_bad_element_of_a_list() -> TestResult {
+	let schema = schema::parse_str("ports -> [Integer]\n",)?;
+
+	let err = conf::parse_str("ports = 80, http, 443\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue {
+			ty: SingleValueDiscriminants::Integer,
+			element: None,
+			position: Some((2, 3)),
+			..
+		}
+	));
+
+	let err = conf::parse_str("ports = 80, , 443\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue { ref value, position: Some((2, 3)), .. }
+			if value.is_empty()
+	));
+	Ok((),)
+}
+
+#[test]
+fn conf_names_the_bad_element_of_a_tuple() -> TestResult {
+	let schema = schema::parse_str(
+		"retries -> Integer\nlimits -> Integer, Integer, Integer\n",
+	)?;
+
+	let err = conf::parse_str("retries = 3\nlimits = x, 2, 3\n", &schema,)
+		.unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"[E007] invalid value 'x' (element 1 of 3) while expecting Integer \
+		 for 'limits' on line 2, column 10"
+	);
+
+	let err = conf::parse_str("retries = 3\nlimits = 1, x, 3\n", &schema,)
+		.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue {
+			ref value,
+			element: None,
+			position: Some((2, 3)),
+			..
+		} if value == "x"
+	));
+	Ok((),)
+}
+
+#[test]
+fn conf_reports_arity_mismatch_of_a_tuple() -> TestResult {
+	let schema = schema::parse_str("limits -> Integer, Integer, Integer\n",)?;
+
+	for (value, found,) in [("1", 1,), ("1, 2", 2,), ("1, 2, 3, 4", 4,),] {
+		let err = conf::parse_str(&format!("limits = {value}\n"), &schema,)
+			.unwrap_err();
+		assert_eq!(
+			err,
+			ParseError::ArityMismatch {
+				key: "limits".to_string(),
+				expected: 3,
+				found,
+				line: 1,
+			},
+			"{value}"
+		);
+	}
+	Ok((),)
+}
+
+#[test]
+fn collecting_reports_key_and_value_errors_of_one_line() -> TestResult {
+	let schema = schema::parse_str("bad.key -> String\nport -> Integer\n",)?;
+	let input = "bad..key = \nport = 80\n= x\n";
+
+	let errors = conf::parse_str_collecting(input, &schema,).unwrap_err();
+	assert_eq!(
+		errors.iter().map(ToString::to_string,).collect::<Vec<_,>>(),
+		vec![
+			"[E005] invalid key segment '' on line 1, column 5",
+			"[E004] empty value on line 1, column 11",
+			"[E003] empty key on line 3, column 1",
+		]
+	);
+
+	assert!(matches!(
+		conf::parse_str(input, &schema,),
+		Err(ParseError::InvalidKeySegment { line: 1, .. })
+	));
+	Ok((),)
+}
+
+#[test]
+fn collecting_caps_errors_per_line() -> TestResult {
+	let schema = schema::parse_str("port -> Integer\n",)?;
+	let errors = conf::parse_str_collecting(
+		"a->1.b->2.c->3.d->4.e..f = \nport = 80\n",
+		&schema,
+	)
+	.unwrap_err();
+	assert_eq!(errors.len(), conf::LINE_ERROR_BUDGET);
+
+	let conf = conf::parse_str_collecting("port = 80\n", &schema,)
+		.map_err(|mut errors| errors.remove(0,),)?;
+	assert_eq!(get_int(&conf, "port")?, 80);
+	Ok((),)
+}
+
+#[test]
+fn all_errors_reports_independent_mistakes_by_line() -> TestResult {
+	let schema =
+		schema::parse_str("port -> Integer\nserver.host -> String\n",)?;
+	let input = "server.mode = fast\nserver.host = a\nport 80\nport = eighty\n";
+	let errors = conf::parse_str_all_errors(input, &schema,).unwrap_err();
+
+	assert_eq!(errors.len(), 3, "{errors:?}");
+	assert!(matches!(
+		&errors[0],
+		ParseError::UnknownKey { key, lines, .. } if key == "server.mode" && *lines == [1]
+	));
+	assert!(matches!(errors[1], ParseError::MissingDelimiter { line: 3, .. }));
+	assert!(matches!(errors[2], ParseError::InvalidValue { line: 4, .. }));
+	assert_eq!(
+		errors.iter().map(ParseError::line,).collect::<Vec<_,>>(),
+		[Some(1), Some(3), Some(4)]
+	);
+
+	let conf = conf::parse_str_all_errors("port = 80\n", &schema,)
+		.map_err(|mut errors| errors.remove(0,),)?;
+	assert_eq!(get_int(&conf, "port")?, 80);
+	Ok((),)
+}
+
+#[test]
+fn value_assigned_where_the_schema_has_a_section_conflicts() -> TestResult {
+	let schema = schema::parse_str("server.host -> String\n",)?;
+	let err = conf::parse_str("server = a\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { ref key, line: 1, .. } if key == "server"
+	));
+	assert!(
+		format!("{err:#}")
+			.contains("'server' is used both as a value and as a section")
+	);
+
+	let schema = schema::parse_str("server -> String\n",)?;
+	let err = conf::parse_str("server.host = a\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { ref key, line: 1, .. } if key == "server"
+	));
+	assert_eq!(
+		err.to_string(),
+		"[E006] conflicting definitions for 'server' on line 1, column 15"
+	);
+	Ok((),)
+}
+
+#[test]
+fn section_headers_prefix_the_keys_below_them() -> TestResult {
+	let schema = schema::parse_str(
+		"debug -> Bool\nserver.tls.cert -> String\nserver.tls.key.path -> \
+		 String\nlog.file -> String\n",
+	)?;
+	let input = "debug = true\n[server.tls] # tls only\ncert = \
+	             /etc/cert.pem\nkey.path = /etc/key.pem\n\n[log]\nfile = \
+	             a.log\n";
+	let conf = conf::parse_str(input, &schema,)?;
+	assert!(get_bool(&conf, "debug")?);
+	assert_eq!(get_str(&conf, "server.tls.cert")?, "/etc/cert.pem");
+	assert_eq!(get_str(&conf, "server.tls.key.path")?, "/etc/key.pem");
+	assert_eq!(get_str(&conf, "log.file")?, "a.log");
+
+	for (input, line,) in
+		[("[]\n", 1,), ("debug = true\n[server\n", 2,), ("[a..b]\n", 1,),]
+	{
+		let err = conf::parse_str(input, &schema,).unwrap_err();
+		assert!(
+			matches!(err, ParseError::InvalidSectionHeader { line: l } if l == line),
+			"{input:?}: {err:?}"
+		);
+	}
+
+	let err =
+		conf::parse_str("[server.tls]\nciphers = all\n", &schema,).unwrap_err();
+	assert!(matches!(
+		&err,
+		ParseError::UnknownKey { key, lines, .. } if key == "server.tls.ciphers" && *lines == [2]
+	));
+	Ok((),)
+}
+
+#[test]
+fn trailing_backslash_continues_the_line() -> TestResult {
+	let schema = schema::parse_str(
+		"ciphers -> String\npath -> String\nport -> Integer\ndebug -> Bool\n",
+	)?;
+	let input = "ciphers = AES128, \\\n    AES256 \\ # strong ones\n  \
+	             ChaCha20\npath = C:\\\\\nport = 80 \\\n\ndebug = true \\";
+	let conf = conf::parse_str(input, &schema,)?;
+	assert_eq!(get_str(&conf, "ciphers")?, "AES128, AES256 ChaCha20");
+	assert_eq!(get_str(&conf, "path")?, "C:\\");
+	// a blank line or the end of the input ends the continuation
+	assert_eq!(get_int(&conf, "port")?, 80);
+	assert!(get_bool(&conf, "debug")?);
+
+	let err =
+		conf::parse_str("debug = \\\n  yes\nport = 1\n", &schema,).unwrap_err();
+	assert!(matches!(err, ParseError::InvalidValue { line: 1, .. }), "{err:?}");
+	Ok((),)
+}
+
+#[test]
+fn lowercase_key_case_matches_keys_and_reports_them_as_written() -> TestResult {
+	let schema =
+		schema::parse_str("Server.Port -> Integer\nname -> String\n",)?;
+	let options = ParseOptions::default().key_case(KeyCase::Lowercase,);
+	let input = "SERVER.PORT = 80\nserver.port = 81\nServer.Port = 82\n";
+	let conf = conf::parse_str_with(input, &schema, &options,)?;
+	assert_eq!(conf.len(), 1);
+	assert_eq!(get_int(&conf, "server.port")?, 82);
+	assert_eq!(conf.get_ci("SERVER.Port"), conf.get("server.port"));
+	assert!(conf.get("Server.Port").is_none());
+
+	let err =
+		conf::parse_str_with("Server.Port = eighty\n", &schema, &options,)
+			.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue { ref key, .. } if key == "Server.Port"
+	));
+	let err = conf::parse_str_with("[Server]\nHost = a\n", &schema, &options,)
+		.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::UnknownKey { ref key, ref lines, .. } if key == "Server.Host" && *lines == [2]
+	));
+	let err = conf::parse_str_with("Logs.File = a\n", &schema, &options,)
+		.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::UnknownKey { ref key, .. } if key == "Logs"
+	));
+	Ok((),)
+}
+
+#[test]
+fn raw_conf_is_typed_against_several_schemas() -> TestResult {
+	let raw = raw::parse_raw_str(
+		"server.port = 8080\nserver.host = prod-1\nports = 1\nports = 2\n",
+	)?;
+	assert_eq!(raw.value_of("server.port"), Some("8080"));
+	assert_eq!(raw.value_of("server"), None);
+	assert!(matches!(raw.get("server"), Some(TreeValue::Map(_))));
+	assert_eq!(raw.lines_of("server"), [1, 2]);
+	assert_eq!(raw.lines_of("missing"), Vec::<usize,>::new());
+
+	let dev = schema::parse_str(
+		"server.port -> Integer\nserver.host -> String\nports -> [Integer] \
+		 (repeatable)\n",
+	)?;
+	let conf = raw.clone().into_conf(&dev,)?;
+	assert_eq!(get_int(&conf, "server.port")?, 8080);
+	assert_eq!(get_ints(&conf, "ports")?, [1, 2]);
+
+	let prod = schema::parse_str(
+		"server.port -> Integer\nserver.host -> Integer\nports -> [Integer]\n",
+	)?;
+	let err = raw.into_conf(&prod,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue { ref key, line: 2, column: 15, .. } if key == "server.host"
+	));
+	Ok((),)
+}
+
+fn validation_schema() -> PRslt<schema::SchemaMap,> {
+	schema::parse_str(
+		"server.port -> Integer\nserver.host -> String?\nlimits -> Integer, \
+		 Integer\n",
+	)
+}
+
+#[test]
+fn validate_accepts_a_conf_fitting_its_schema() -> TestResult {
+	let schema = validation_schema()?;
+	let mut conf =
+		conf::parse_str("server.port = 80\nlimits = 1, 2\n", &schema,)?;
+	conf.insert_dotted(
+		"server.host",
+		ConfValue::Scalar(Value::Single(SingleValue::from("localhost",),),),
+	)?;
+
+	assert!(conf::validate(&conf, &schema).is_ok());
+	Ok((),)
+}
+
+#[test]
+fn validate_reports_unknown_keys_by_dotted_path() -> TestResult {
+	let schema = validation_schema()?;
+	let mut conf =
+		conf::parse_str("server.port = 80\nlimits = 1, 2\n", &schema,)?;
+	conf.insert_dotted(
+		"server.tls",
+		ConfValue::Scalar(Value::Single(SingleValue::Bool(true,),),),
+	)?;
+
+	let errors = conf::validate(&conf, &schema,).unwrap_err();
+	assert!(matches!(
+		errors.as_slice(),
+		[ParseError::UnknownKey { key, lines, .. }]
+			if key == "server.tls" && lines.is_empty()
+	));
+	Ok((),)
+}
+
+#[test]
+fn validate_reports_missing_required_keys() -> TestResult {
+	let schema = validation_schema()?;
+	let mut conf =
+		conf::parse_str("server.port = 80\nlimits = 1, 2\n", &schema,)?;
+	conf.remove_dotted("server.port",);
+	conf.remove_dotted("limits",);
+
+	let errors = conf::validate(&conf, &schema,).unwrap_err();
+	assert!(matches!(
+		errors.as_slice(),
+		[ParseError::MissingKey { keys }]
+			if keys == &["limits", "server.port"]
+	));
+	Ok((),)
+}
+
+#[test]
+fn validate_reports_values_of_the_wrong_type() -> TestResult {
+	let schema = validation_schema()?;
+	let mut conf =
+		conf::parse_str("server.port = 80\nlimits = 1, 2\n", &schema,)?;
+	conf.insert_dotted(
+		"server.port",
+		ConfValue::Scalar(Value::Single(SingleValue::from("eighty",),),),
+	)?;
+
+	let errors = conf::validate(&conf, &schema,).unwrap_err();
+	assert!(matches!(
+		errors.as_slice(),
+		[ParseError::InvalidValue { key, value, ty, line: 0, column: 0, .. }]
+			if key == "server.port"
+				&& value == "eighty"
+				&& *ty == SingleValueDiscriminants::Integer
+	));
+	Ok((),)
+}
+
+#[test]
+fn validate_reports_collections_of_the_wrong_shape() -> TestResult {
+	let schema = validation_schema()?;
+	let mut conf =
+		conf::parse_str("server.port = 80\nlimits = 1, 2\n", &schema,)?;
+
+	conf.insert_dotted(
+		"limits",
+		ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::Integer(1,),
+			SingleValue::Integer(2,),
+			SingleValue::Integer(3,),
+		],),),
+	)?;
+	let errors = conf::validate(&conf, &schema,).unwrap_err();
+	assert!(matches!(
+		errors.as_slice(),
+		[ParseError::ArityMismatch { key, expected: 2, found: 3, .. }]
+			if key == "limits"
+	));
+
+	conf.insert_dotted(
+		"limits",
+		ConfValue::Scalar(Value::Single(SingleValue::Integer(1,),),),
+	)?;
+	let errors = conf::validate(&conf, &schema,).unwrap_err();
+	assert!(matches!(
+		errors.as_slice(),
+		[ParseError::InvalidValue { key, value, .. }]
+			if key == "limits" && value == "1"
+	));
+	Ok((),)
+}
+
+#[test]
+fn durations_read_units_and_plain_seconds() -> TestResult {
+	let schema = schema::parse_str(
+		"timeout -> Duration\nretry -> Duration\nbackoff -> Duration, \
+		 Duration, Duration\n",
+	)?;
+	let conf = conf::parse_str(
+		"timeout = 1h30m\nretry = 90\nbackoff = 250ms, 5s, 1m\n",
+		&schema,
+	)?;
+
+	assert_eq!(conf.get_duration("timeout"), Some(Duration::from_secs(5_400)));
+	assert_eq!(conf.get_duration("retry"), Some(Duration::from_secs(90)));
+	assert_eq!(
+		conf.get("backoff"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::Duration(Duration::from_millis(250)),
+			SingleValue::Duration(Duration::from_secs(5)),
+			SingleValue::Duration(Duration::from_secs(60)),
+		])))
+	);
+	assert_eq!(
+		conf::to_string(&conf),
+		"backoff = 250ms, 5s, 1m\nretry = 1m30s\ntimeout = 1h30m\n"
+	);
+	conf_eq(&conf::parse_str(&conf::to_string(&conf,), &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn malformed_durations_are_invalid_values() -> TestResult {
+	let schema = schema::parse_str("timeout -> Duration\n",)?;
+	for value in ["-5s", "5x", "1.5s", "30s5m",] {
+		let err = conf::parse_str(&format!("timeout = {value}\n"), &schema,)
+			.unwrap_err();
+		assert!(
+			matches!(
+				&err,
+				ParseError::InvalidValue { key, value: written, ty, .. }
+					if key == "timeout"
+						&& written == value
+						&& *ty == SingleValueDiscriminants::Duration
+			),
+			"{value}: {err:?}"
+		);
+	}
+	Ok((),)
+}
+
+#[test]
+fn addresses_read_ipv4_and_ipv6_forms() -> TestResult {
+	let schema = schema::parse_str(
+		"listen.ip -> Ip\nlisten.ip6 -> Ip\nlisten.addr -> \
+		 SocketAddr\nlisten.addr6 -> SocketAddr\nallow -> [Ip]\n",
+	)?;
+	let conf = conf::parse_str(
+		"listen.ip = 10.0.0.1\nlisten.ip6 = fe80::1 ; link local\nlisten.addr \
+		 = 127.0.0.1:80\nlisten.addr6 = [::1]:8080 # loopback\nallow = \
+		 10.0.0.2, ::1\n",
+		&schema,
+	)?;
+
+	let ip = |text: &str| SingleValue::IpAddr(text.parse().unwrap(),);
+	let single = |value| ConfValue::Scalar(Value::Single(value,),);
+	assert_eq!(conf.get("listen.ip"), Some(&single(ip("10.0.0.1"))));
+	assert_eq!(conf.get("listen.ip6"), Some(&single(ip("fe80::1"))));
+	assert_eq!(
+		conf.get("listen.addr6"),
+		Some(&single(SingleValue::SocketAddr("[::1]:8080".parse().unwrap())))
+	);
+	assert_eq!(
+		conf.get("allow"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			ip("10.0.0.2"),
+			ip("::1")
+		])))
+	);
+	assert_eq!(
+		conf::to_string(&conf),
+		"allow = 10.0.0.2, ::1\nlisten.addr = 127.0.0.1:80\nlisten.addr6 = \
+		 [::1]:8080\nlisten.ip = 10.0.0.1\nlisten.ip6 = fe80::1\n"
+	);
+	conf_eq(&conf::parse_str(&conf::to_string(&conf,), &schema,)?, &conf,)?;
+	Ok((),)
+}
+
+#[test]
+fn malformed_addresses_are_invalid_values() -> TestResult {
+	let schema =
+		schema::parse_str("listen.ip -> Ip\nlisten.addr -> SocketAddr\n",)?;
+	for (conf_text, bad_key, bad_value, bad_ty,) in [
+		("listen.ip = 10.0.0.256\n", "listen.ip", "10.0.0.256", "Ip",),
+		("listen.ip = 1.2.3.4:80\n", "listen.ip", "1.2.3.4:80", "Ip",),
+		(
+			"listen.ip = ::1\nlisten.addr = ::1:8080\n",
+			"listen.addr",
+			"::1:8080",
+			"SocketAddr",
+		),
+	] {
+		let err = conf::parse_str(conf_text, &schema,).unwrap_err();
+		assert!(
+			matches!(
+				&err,
+				ParseError::InvalidValue { key, value, ty, line, .. }
+					if key == bad_key
+						&& value == bad_value
+						&& ty.to_string() == bad_ty
+						&& *line == conf_text.lines().count()
+			),
+			"{conf_text}: {err:?}"
+		);
+	}
+	Ok((),)
+}
+
+#[test]
+fn paths_are_kept_as_written() -> TestResult {
+	let schema = schema::parse_str("log.file -> Path\nlog.dirs -> [Path]\n",)?;
+	let conf = conf::parse_str(
+		"log.file = C:\\Program Files\\My App\\log.txt ; windows\nlog.dirs = \
+		 /var/log, ~/logs\n",
+		&schema,
+	)?;
+
+	assert_eq!(
+		conf.get("log.file"),
+		Some(&ConfValue::Scalar(Value::Single(SingleValue::Path(
+			PathBuf::from("C:\\Program Files\\My App\\log.txt")
+		))))
+	);
+	assert_eq!(
+		conf.get("log.dirs"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::Path(PathBuf::from("/var/log")),
+			SingleValue::Path(PathBuf::from("~/logs")),
+		])))
+	);
+	conf_eq(&conf::parse_str(&conf::to_string(&conf,), &schema,)?, &conf,)?;
+
+	let err = conf::parse_str_with(
+		"log.file =\nlog.dirs = /var/log\n",
+		&schema,
+		&ParseOptions::default().allow_empty_values(true,),
+	)
+	.unwrap_err();
+	assert!(matches!(err, ParseError::EmptyValue { line: 1, .. }), "{err:?}");
+	Ok((),)
+}
+
+#[test]
+fn preserve_order_option_writes_keys_as_they_came() -> TestResult {
+	let schema = schema::parse_str(
+		"alpha -> Bool\nmid.a -> String\nmid.b -> String\nzeta -> Integer\n",
+	)?;
+	let input = "zeta = 1\nmid.b = x\nmid.a = y\nalpha = true\n";
+
+	let sorted =
+		conf::parse_str_with(input, &schema, &ParseOptions::default(),)?;
+	assert_eq!(
+		conf::to_string(&sorted,),
+		"alpha = true\nmid.a = y\nmid.b = x\nzeta = 1\n"
+	);
+
+	let options = ParseOptions::default().preserve_order(true,);
+	let mut ordered = conf::parse_str_with(input, &schema, &options,)?;
+	assert_eq!(conf::to_string(&ordered,), input);
+	assert_eq!(ordered.key_order(), ["zeta", "mid.b", "mid.a", "alpha"]);
+
+	// a reassigned key stays where it was first assigned, and keys added
+	// later follow in key order
+	let reassigned = conf::parse_str_with(
+		"zeta = 1\nalpha = true\nzeta = 2\n",
+		&schema,
+		&options,
+	)?;
+	assert_eq!(conf::to_string(&reassigned,), "zeta = 2\nalpha = true\n");
+	ordered.remove_dotted("mid.b",);
+	ordered.insert_dotted(
+		"mid.b",
+		ConfValue::Scalar(
+			Value::Single(SingleValue::String("z".to_string(),),),
+		),
+	)?;
+	assert_eq!(
+		conf::to_string(&ordered,),
+		"zeta = 1\nmid.a = y\nalpha = true\nmid.b = z\n"
+	);
+	Ok((),)
+}
+
+#[test]
+fn expand_home_option_replaces_a_leading_tilde() -> TestResult {
+	let Some(home,) = std::env::var_os("HOME",) else {
+		return Ok((),);
+	};
+	let schema = schema::parse_str("log.file -> Path\nlog.dirs -> [Path]\n",)?;
+	let conf = conf::parse_str_with(
+		"log.file = ~/logs/app.log\nlog.dirs = ~, /tmp/~\n",
+		&schema,
+		&ParseOptions::default().expand_home(true,),
+	)?;
+
+	let home = PathBuf::from(home,);
+	assert_eq!(
+		conf.get("log.file"),
+		Some(&ConfValue::Scalar(Value::Single(SingleValue::Path(
+			home.join("logs/app.log")
+		))))
+	);
+	assert_eq!(
+		conf.get("log.dirs"),
+		Some(&ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::Path(home),
+			SingleValue::Path(PathBuf::from("/tmp/~")),
+		])))
+	);
+	Ok((),)
+}
+
+#[test]
+fn validate_paths_reports_each_unmet_requirement() -> TestResult {
+	let dir = std::env::temp_dir()
+		.join(format!("dot_conf_parser_paths_{}", std::process::id()),);
+	std::fs::create_dir_all(&dir,)?;
+	let file = dir.join("app.log",);
+	std::fs::write(&file, "",)?;
+	let missing = dir.join("missing",);
+
+	let schema = schema::parse_str(
+		"file -> Path\nsub.dir -> Path\nmissing -> [Path]\n",
+	)?;
+	let conf = conf::parse_str(
+		&format!(
+			"file = {}\nsub.dir = {}\nmissing = {}\n",
+			file.display(),
+			dir.display(),
+			missing.display()
+		),
+		&schema,
+	)?;
+	let failing = |requirement| match conf::validate_paths(&conf, requirement,)
+	{
+		Ok((),) => Vec::new(),
+		Err(errors,) => errors
+			.into_iter()
+			.map(|err| match err {
+				ParseError::UnmetPathRequirement {
+					key,
+					requirement: met,
+					..
+				} => {
+					assert_eq!(met, requirement);
+					key
+				},
+				other => panic!("unexpected {other:?}"),
+			},)
+			.collect(),
+	};
+
+	assert_eq!(failing(PathRequirement::None), Vec::<String,>::new());
+	assert_eq!(failing(PathRequirement::Exists), ["missing"]);
+	assert_eq!(failing(PathRequirement::IsFile), ["missing", "sub.dir"]);
+	assert_eq!(failing(PathRequirement::IsDir), ["file", "missing"]);
+
+	std::fs::remove_dir_all(&dir,)?;
+	Ok((),)
+}
+
+#[test]
+fn integers_beyond_their_type_are_out_of_range() -> TestResult {
+	let schema = schema::parse_str(
+		"count -> Integer\nquota.bytes -> UInt\nepoch -> Long\n",
+	)?;
+	let conf = conf::parse_str(
+		"count = 1\nquota.bytes = 10737418240\nepoch = -62135596800\n",
+		&schema,
+	)?;
+	assert_eq!(conf.get_uint("quota.bytes"), Some(10_737_418_240));
+	assert_eq!(conf.get_long("epoch"), Some(-62_135_596_800));
+
+	for (conf_text, bad_key, bad_value,) in [
+		("count = 10737418240\n", "count", "10737418240",),
+		("quota.bytes = -1\n", "quota.bytes", "-1",),
+		("epoch = 9223372036854775808\n", "epoch", "9223372036854775808",),
+	] {
+		let err = conf::parse_str(conf_text, &schema,).unwrap_err();
+		assert!(
+			matches!(
+				&err,
+				ParseError::OutOfRange { key, value, line: 1, .. }
+					if key == bad_key && value == bad_value
+			),
+			"{conf_text}: {err:?}"
+		);
+		assert!(err.to_string().contains("out of range"), "{err}");
+	}
+
+	let err = conf::parse_str("quota.bytes = lots\n", &schema,).unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::InvalidValue { ty: SingleValueDiscriminants::UInteger, .. }
+	));
+	Ok((),)
+}
+
+#[test]
+fn enum_values_must_be_one_of_the_literals() -> TestResult {
+	let schema = schema::parse_str("log.level -> Enum(debug, info, warn)\n",)?;
+	let conf = conf::parse_str("log.level = warn\n", &schema,)?;
+	assert_eq!(get_str(&conf, "log.level")?, "warn");
+
+	let err = conf::parse_str("\nlog.level = Warn\n", &schema,).unwrap_err();
+	match err {
+		ParseError::InvalidEnumValue { key, value, allowed, line, } => {
+			assert_eq!(key, "log.level");
+			assert_eq!(value, "Warn");
+			assert_eq!(allowed, ["debug", "info", "warn"]);
+			assert_eq!(line, 2);
+		},
+		other => panic!("unexpected error: {other:?}"),
+	}
+	Ok((),)
+}
+
+#[test]
+fn ranges_accept_their_bounds_and_reject_beyond() -> TestResult {
+	let schema = schema::parse_str(
+		"port -> Integer[1..65535]\nratio -> Float[0..1]\nretries -> \
+		 UInt[..5]\nports -> [Integer[1024..]]\n",
+	)?;
+	for (port, ratio, retries,) in [("1", "0", "0",), ("65535", "1", "5",),] {
+		let conf = conf::parse_str(
+			&format!(
+				"port = {port}\nratio = {ratio}\nretries = {retries}\nports = \
+				 1024, 8080\n"
+			),
+			&schema,
+		)?;
+		assert_eq!(get_int(&conf, "port")?.to_string(), port);
+		assert_eq!(get_ints(&conf, "ports")?, [1024, 8080]);
+	}
+
+	for (conf_text, bad_key, bad_value, bounds,) in [
+		("port = 0\n", "port", "0", (Some("1",), Some("65535",),),),
+		("port = 65536\n", "port", "65536", (Some("1",), Some("65535",),),),
+		("ratio = 1.5\n", "ratio", "1.5", (Some("0",), Some("1",),),),
+		("retries = 6\n", "retries", "6", (None, Some("5",),),),
+		("ports = 8080, 80\n", "ports", "80", (Some("1024",), None,),),
+	] {
+		let err = conf::parse_str(conf_text, &schema,).unwrap_err();
+		assert!(
+			matches!(
+				&err,
+				ParseError::OutOfRange { key, value, min, max, line: 1, .. }
+					if key == bad_key
+						&& value == bad_value
+						&& (min.as_deref(), max.as_deref()) == bounds
+			),
+			"{conf_text}: {err:?}"
+		);
+	}
+	Ok((),)
+}
+
+#[test]
+fn tuple_elements_have_ranges_of_their_own() -> TestResult {
+	let schema = schema::parse_str(
+		"limits -> Integer[1..10], Integer[1..10]\nspan -> (lo: Long[0..], \
+		 hi: Long)\n",
+	)?;
+	let conf = conf::parse_str("limits = 1, 10\nspan = 0, -5\n", &schema,)?;
+	assert_eq!(get_ints(&conf, "limits")?, [1, 10]);
+
+	for (conf_text, bad_key, bad_value,) in [
+		("limits = 3, 11\n", "limits", "11",),
+		("limits = 0, 3\n", "limits", "0",),
+		("span = -1, 5\n", "span", "-1",),
+	] {
+		let err = conf::parse_str(conf_text, &schema,).unwrap_err();
+		assert!(
+			matches!(
+				&err,
+				ParseError::OutOfRange { key, value, line: 1, .. }
+					if key == bad_key && value == bad_value
+			),
+			"{conf_text}: {err:?}"
+		);
+	}
+	Ok((),)
+}
+
+#[test]
+fn conf_values_convert_into_primitives() -> PRslt<(),> {
+	let schema = schema::parse_str(
+		"server.port -> Integer\nserver.host -> String\ndebug -> Bool\nports \
+		 -> [Integer]\nhosts -> [String]\nflags -> [Bool]\n",
+	)?;
+	let conf = conf::parse_str(
+		"server.port = 80\nserver.host = localhost\ndebug = true\nports = 80, \
+		 443\nhosts = a, b\nflags = true, false\n",
+		schema,
+	)?;
+
+	let port: i32 = conf.get("server.port",).unwrap().try_into()?;
+	let host: String = conf.get("server.host",).unwrap().try_into()?;
+	let debug: bool = conf.get("debug",).unwrap().try_into()?;
+	let ports: Vec<i32,> = conf.get("ports",).unwrap().try_into()?;
+	let hosts: Vec<String,> = conf.get("hosts",).unwrap().try_into()?;
+	let flags: Vec<bool,> = conf.get("flags",).unwrap().try_into()?;
+	assert_eq!(port, 80);
+	assert_eq!(host, "localhost");
+	assert!(debug);
+	assert_eq!(ports, [80, 443]);
+	assert_eq!(hosts, ["a", "b"]);
+	assert_eq!(flags, [true, false]);
+
+	let owned: String = conf.get("server.host",).unwrap().clone().try_into()?;
+	assert_eq!(owned, "localhost");
+	let owned: Vec<i32,> = conf.get("ports",).unwrap().clone().try_into()?;
+	assert_eq!(owned, [80, 443]);
+	Ok((),)
+}
+
+#[test]
+fn conf_value_conversions_reject_other_shapes() {
+	let invalid = |value: &str, ty| ParseError::InvalidValue {
+		key: String::new(),
+		value: value.to_string(),
+		ty,
+		line: 0,
+		column: 0,
+		element: None,
+		position: None,
+	};
+	let int = ConfValue::Scalar(Value::Single(SingleValue::Integer(80,),),);
+
+	assert_eq!(
+		String::try_from(&int,),
+		Err(invalid("80", SingleValueDiscriminants::String,))
+	);
+	assert_eq!(
+		bool::try_from(int.clone(),),
+		Err(invalid("80", SingleValueDiscriminants::Bool,))
+	);
+	assert_eq!(
+		Vec::<i32,>::try_from(&int,),
+		Err(invalid("80", SingleValueDiscriminants::Integer,))
+	);
+
+	let section = ConfValue::Map(Default::default(),);
+	assert!(i32::try_from(&section,).unwrap_err().is_conflicting_types());
+	assert!(
+		Vec::<String,>::try_from(section,).unwrap_err().is_conflicting_types()
+	);
+}
+
+#[test]
+fn collection_with_a_stray_element_does_not_convert() {
+	let mixed = ConfValue::Scalar(Value::Collection(vec![
+		SingleValue::Integer(1,),
+		SingleValue::Bool(true,),
+		SingleValue::Integer(3,),
+	],),);
+
+	let err = Vec::<i32,>::try_from(&mixed,).unwrap_err();
+	assert_eq!(
+		err,
+		ParseError::InvalidValue {
+			key:      String::new(),
+			value:    "true".to_string(),
+			ty:       SingleValueDiscriminants::Integer,
+			line:     0,
+			column:   0,
+			element:  None,
+			position: None,
+		}
+	);
+	assert!(Vec::<i32,>::try_from(mixed,).is_err());
+	assert!(
+		Vec::<bool,>::try_from(ConfValue::Scalar(Value::Collection(vec![
+			SingleValue::Bool(true,),
+			SingleValue::from("yes",)
+		],),),)
+		.is_err()
+	);
+}
+
+#[test]
+fn single_values_convert_into_primitives() {
+	assert_eq!(SingleValue::from("a",).try_into_string(), Ok("a".to_string()));
+	assert_eq!(SingleValue::from(true,).try_into_bool(), Ok(true));
+	assert_eq!(SingleValue::from(7,).try_into_int(), Ok(7));
+	assert!(
+		SingleValue::from(7,).try_into_string().unwrap_err().is_invalid_value()
+	);
+	assert!(
+		SingleValue::from("7",).try_into_int().unwrap_err().is_invalid_value()
+	);
+	assert!(
+		SingleValue::from(1,).try_into_bool().unwrap_err().is_invalid_value()
+	);
+}
@@ -1,4 +1,7 @@
+use dot_conf_parser::error::ErrorKind;
 use dot_conf_parser::error::ParseError;
+use dot_conf_parser::error::Separator;
+use dot_conf_parser::parser::conf::PathRequirement;
 use dot_conf_parser::parser::conf::SingleValueDiscriminants;
 use dot_conf_parser::parser::conf::ValueDiscriminants;
 use dot_conf_parser::parser::conf::{self};
@@ -8,61 +11,358 @@ use std::path::PathBuf;
 
 #[test]
 fn parse_error_display_covers_all_variants() {
-	let io_err =
-		ParseError::Io(std::io::Error::new(std::io::ErrorKind::Other, "io",),);
-	assert!(format!("{io_err}").starts_with("I/O error"));
+	let io_err = ParseError::Io(std::io::Error::other("io",),);
+	assert!(format!("{io_err}").starts_with("[E001] I/O error"));
 
-	let missing = ParseError::MissingDelimiter { line: 7, };
-	assert_eq!(format!("{missing}"), "missing delimiter on line 7");
+	let missing = ParseError::MissingDelimiter {
+		line:     7,
+		column:   1,
+		expected: vec!["=".to_string()],
+	};
+	assert_eq!(
+		format!("{missing}"),
+		"[E002] missing delimiter on line 7, column 1; expected '='"
+	);
+	let missing_any = ParseError::MissingDelimiter {
+		line:     7,
+		column:   1,
+		expected: ["=", ":", "=>",].map(str::to_string,).to_vec(),
+	};
+	assert_eq!(
+		format!("{missing_any}"),
+		"[E002] missing delimiter on line 7, column 1; expected '=', ':' or \
+		 '=>'"
+	);
 
-	let empty_key = ParseError::EmptyKey { line: 3, };
-	assert_eq!(format!("{empty_key}"), "empty key on line 3");
+	let empty_key = ParseError::EmptyKey { line: 3, column: 2, };
+	assert_eq!(format!("{empty_key}"), "[E003] empty key on line 3, column 2");
 
-	let empty_value = ParseError::EmptyValue { line: 4, };
-	assert_eq!(format!("{empty_value}"), "empty value on line 4");
+	let empty_value = ParseError::EmptyValue { line: 4, column: 6, };
+	assert_eq!(
+		format!("{empty_value}"),
+		"[E004] empty value on line 4, column 6"
+	);
 
-	let invalid_segment =
-		ParseError::InvalidKeySegment { segment: "".to_string(), line: 9, };
+	let invalid_segment = ParseError::InvalidKeySegment {
+		segment: "".to_string(),
+		line:    9,
+		column:  3,
+	};
 	assert_eq!(
 		format!("{invalid_segment}"),
-		"invalid key segment '' on line 9"
+		"[E005] invalid key segment '' on line 9, column 3"
 	);
 
 	let conflict = ParseError::ConflictingTypes {
-		key:  "server.port".to_string(),
-		line: 11,
+		key:    "server.port".to_string(),
+		line:   11,
+		column: 1,
+		origin: None,
 	};
 	assert_eq!(
 		format!("{conflict}"),
-		"conflicting definitions for 'server.port' on line 11"
+		"[E006] conflicting definitions for 'server.port' on line 11, column 1"
+	);
+
+	let across_files = ParseError::ConflictingTypes {
+		key:    "net.port".to_string(),
+		line:   0,
+		column: 0,
+		origin: Some(("a.schema".into(), "b.schema".into(),),),
+	};
+	assert_eq!(
+		format!("{across_files}"),
+		"[E006] conflicting definitions for 'net.port' in 'a.schema' and \
+		 'b.schema'"
 	);
 
 	let invalid_value = ParseError::InvalidValue {
-		key:   "service.mode".to_string(),
-		value: "maybe".to_string(),
-		ty:    SingleValueDiscriminants::Bool,
-		line:  5,
+		key:      "service.mode".to_string(),
+		value:    "maybe".to_string(),
+		ty:       SingleValueDiscriminants::Bool,
+		line:     5,
+		column:   15,
+		element:  None,
+		position: None,
 	};
 	let invalid_string = format!("{invalid_value}");
+	assert!(invalid_string.ends_with("on line 5, column 15"));
 	assert!(invalid_string.contains("invalid value 'maybe'"));
 	assert!(invalid_string.contains("Bool"));
 	assert!(invalid_string.contains("service.mode"));
 
+	let invalid_element = ParseError::InvalidValue {
+		key:      "limits".to_string(),
+		value:    "x".to_string(),
+		ty:       SingleValueDiscriminants::Integer,
+		line:     4,
+		column:   10,
+		element:  None,
+		position: Some((2, 2,),),
+	};
+	assert_eq!(
+		format!("{invalid_element}"),
+		"[E007] invalid value 'x' (element 2 of 2) while expecting Integer \
+		 for 'limits' on line 4, column 10"
+	);
+
 	let unknown = ParseError::UnknownKey {
-		key:   "unknown".to_string(),
-		lines: vec![2, 4],
+		key:        "unknown".to_string(),
+		lines:      vec![2, 4],
+		suggestion: None,
+	};
+	assert_eq!(
+		format!("{unknown}"),
+		"[E008] unknown key 'unknown' on line [2, 4]"
+	);
+
+	let misspelt = ParseError::UnknownKey {
+		key:        "sevice".to_string(),
+		lines:      vec![1],
+		suggestion: Some("service".to_string(),),
+	};
+	assert_eq!(
+		format!("{misspelt}"),
+		"[E008] unknown key 'sevice' on line [1]; did you mean 'service'?"
+	);
+
+	let localized = ParseError::LocalizedNumber {
+		key:        "max".to_string(),
+		value:      "1.000".to_string(),
+		ty:         SingleValueDiscriminants::Integer,
+		line:       3,
+		separator:  Separator::Thousands,
+		suggestion: "1000".to_string(),
+	};
+	assert_eq!(
+		format!("{localized}"),
+		"[E009] value '1.000' for 'max' on line 3 looks like it uses a \
+		 thousands separator; write 1000"
+	);
+
+	let declaration = ParseError::InvalidDeclaration {
+		key:         "limits".to_string(),
+		declaration: "(min: Integer, Integer)".to_string(),
+		line:        3,
+	};
+	assert_eq!(
+		format!("{declaration}"),
+		"[E012] invalid declaration '(min: Integer, Integer)' for 'limits' on \
+		 line 3"
+	);
+
+	let unknown_type = ParseError::UnknownType {
+		key:  "price".to_string(),
+		name: "Decimal(2)".to_string(),
+		line: 4,
+	};
+	assert_eq!(
+		format!("{unknown_type}"),
+		"[E013] unknown type 'Decimal(2)' for 'price' on line 4; valid types \
+		 are: String, Bool, Integer, UInt, Long, Float, Secret, Duration, Ip, \
+		 SocketAddr, Path"
+	);
+
+	let duplicate = ParseError::DuplicateKey {
+		key:         "server.port".to_string(),
+		first_line:  2,
+		second_line: 40,
+	};
+	assert_eq!(
+		format!("{duplicate}"),
+		"[E022] duplicate key 'server.port' on lines 2 and 40"
+	);
+
+	let circular =
+		ParseError::CircularInclude { path: "etc/a.conf".into(), line: 3, };
+	assert_eq!(
+		format!("{circular}"),
+		"[E023] circular include of 'etc/a.conf' on line 3"
+	);
+
+	let in_file = ParseError::InFile {
+		path:  "etc/b.conf".into(),
+		error: Box::new(ParseError::EmptyValue { line: 2, column: 5, },),
+	};
+	assert_eq!(
+		format!("{in_file}"),
+		"[E004] empty value on line 2, column 5 in 'etc/b.conf'"
+	);
+
+	let merge = ParseError::MergeConflict { key: "server.port".to_string(), };
+	assert_eq!(
+		format!("{merge}"),
+		"[E024] 'server.port' is set by both merged maps"
+	);
+
+	let mount = ParseError::MountConflict {
+		plugin:       "metrics".to_string(),
+		prefix:       "web.metrics".to_string(),
+		other:        "web".to_string(),
+		other_prefix: "web".to_string(),
+	};
+	assert_eq!(
+		format!("{mount}"),
+		"[E025] 'metrics' mounted at 'web.metrics' collides with 'web' \
+		 mounted at 'web'"
+	);
+
+	let range = ParseError::OutOfRange {
+		key:   "port".to_string(),
+		value: "0".to_string(),
+		ty:    SingleValueDiscriminants::Integer,
+		min:   Some("1".to_string(),),
+		max:   None,
+		line:  2,
+	};
+	assert_eq!(
+		format!("{range:#}"),
+		"[E010] value '0' for 'port' on line 2 is out of range for \
+		 Integer[1..]\n  --> line 2\n  = note: both bounds are included"
+	);
+
+	let level = ParseError::InvalidEnumValue {
+		key:     "log.level".to_string(),
+		value:   "verbose".to_string(),
+		allowed: vec!["debug".to_string(), "info".to_string()],
+		line:    3,
+	};
+	assert_eq!(
+		format!("{level}"),
+		"[E011] invalid value 'verbose' for 'log.level' on line 3, expected \
+		 one of debug, info"
+	);
+
+	let path = ParseError::UnmetPathRequirement {
+		key:         "log.dir".to_string(),
+		path:        PathBuf::from("/var/log/app",),
+		requirement: PathRequirement::IsDir,
+	};
+	assert_eq!(
+		format!("{path}"),
+		"[E026] '/var/log/app' for 'log.dir' must be a directory"
+	);
+
+	let nested = ParseError::Unserializable {
+		key:    "server.tags.0".to_string(),
+		reason: "nested sequence".to_string(),
+	};
+	assert_eq!(
+		format!("{nested}"),
+		"[E027] cannot serialize 'server.tags.0' into a conf map: nested \
+		 sequence"
+	);
+	let whole = ParseError::Unserializable {
+		key:    String::new(),
+		reason: "expected a struct or map, found i32".to_string(),
+	};
+	assert_eq!(
+		format!("{whole}"),
+		"[E027] cannot serialize into a conf map: expected a struct or map, \
+		 found i32"
+	);
+
+	let null = ParseError::NullValue { key: "net.port".to_string(), };
+	assert_eq!(format!("{null}"), "[E028] null value for 'net.port'");
+
+	let count = ParseError::ArityMismatch {
+		key:      "limits".to_string(),
+		expected: 2,
+		found:    3,
+		line:     4,
+	};
+	assert_eq!(
+		format!("{count}"),
+		"[E029] expected 2 elements for 'limits' on line 4, found 3"
+	);
+
+	let append =
+		ParseError::NotRepeatable { key: "ports".to_string(), line: 5, };
+	assert_eq!(
+		format!("{append}"),
+		"[E030] '+=' on line 5 appends to 'ports', which is not a repeatable \
+		 list"
+	);
+
+	let unterminated = ParseError::UnterminatedString { line: 8, };
+	assert_eq!(
+		format!("{unterminated}"),
+		"[E014] unterminated string on line 8"
+	);
+
+	let header = ParseError::InvalidSectionHeader { line: 4, };
+	assert_eq!(format!("{header}"), "[E015] invalid section header on line 4");
+
+	let trailing = ParseError::TrailingCharacters {
+		line:   6,
+		column: 15,
+		text:   "extra text".to_string(),
 	};
-	assert_eq!(format!("{unknown}"), "unknown key 'unknown' on line [2, 4]");
+	assert_eq!(
+		format!("{trailing}"),
+		"[E016] unexpected 'extra text' after closing quote on line 6, column \
+		 15"
+	);
+
+	let failed = ParseError::ResolverFailed {
+		key:      "db.password".to_string(),
+		resolver: "secret".to_string(),
+		reason:   "vault sealed".to_string(),
+	};
+	assert_eq!(
+		format!("{failed}"),
+		"[E017] resolver 'secret' failed for 'db.password': vault sealed"
+	);
+
+	let unresolved = ParseError::Unresolved {
+		keys: vec!["db.password".to_string(), "db.host".to_string()],
+	};
+	assert_eq!(
+		format!("{unresolved}"),
+		"[E018] unresolved placeholders for db.password, db.host"
+	);
+
+	let missing = ParseError::MissingKey {
+		keys: vec!["server.port".to_string(), "service.mode".to_string()],
+	};
+	assert_eq!(
+		format!("{missing}"),
+		"[E019] missing keys server.port, service.mode"
+	);
+
+	let conflict = ParseError::MigrationConflict {
+		old_key:  "net.port".to_string(),
+		old_line: 2,
+		new_key:  "server.port".to_string(),
+		new_line: 5,
+	};
+	assert_eq!(
+		format!("{conflict}"),
+		"[E020] 'net.port' on line 2 and its replacement 'server.port' on \
+		 line 5 are both set"
+	);
+
+	let invalid = ParseError::InvalidMigration {
+		rule: "copy a -> b".to_string(),
+		line: 3,
+	};
+	assert_eq!(
+		format!("{invalid}"),
+		"[E021] invalid migration rule 'copy a -> b' on line 3"
+	);
 }
 
 #[test]
 fn parse_error_source_only_wraps_io() {
-	let io_err =
-		ParseError::Io(std::io::Error::new(std::io::ErrorKind::Other, "io",),);
+	let io_err = ParseError::Io(std::io::Error::other("io",),);
 	let source = io_err.source().expect("io source",);
 	assert_eq!(source.to_string(), "io");
 
-	let missing = ParseError::MissingDelimiter { line: 1, };
+	let missing = ParseError::MissingDelimiter {
+		line:     1,
+		column:   1,
+		expected: Vec::new(),
+	};
 	assert!(missing.source().is_none());
 }
 
@@ -98,7 +398,372 @@ fn parse_file_reports_io_errors() {
 		.expect_err("conf parse should surface IO errors",);
 	assert!(matches!(err, ParseError::Io(_)));
 
-	let schema_err = schema::parse_file(PathBuf::from(missing_path,),)
+	let schema_err = schema::parse_file(missing_path,)
 		.expect_err("schema parse should surface IO errors",);
 	assert!(matches!(schema_err, ParseError::Io(_)));
 }
+
+/// one representative of every variant, with line breaks in every text field
+fn every_variant() -> Vec<ParseError,> {
+	let text = "first\nsecond\r\n";
+	let errors = vec![
+		ParseError::Io(std::io::Error::other(text,),),
+		ParseError::MissingDelimiter {
+			line:     1,
+			column:   1,
+			expected: vec![text.to_string()],
+		},
+		ParseError::EmptyKey { line: 2, column: 1, },
+		ParseError::EmptyValue { line: 3, column: 1, },
+		ParseError::InvalidKeySegment {
+			segment: text.to_string(),
+			line:    4,
+			column:  1,
+		},
+		ParseError::ConflictingTypes {
+			key:    text.to_string(),
+			line:   5,
+			column: 1,
+			origin: None,
+		},
+		ParseError::InvalidValue {
+			key:      text.to_string(),
+			value:    text.to_string(),
+			ty:       SingleValueDiscriminants::Integer,
+			line:     6,
+			column:   1,
+			element:  Some(text.to_string(),),
+			position: Some((1, 2,),),
+		},
+		ParseError::UnknownKey {
+			key:        text.to_string(),
+			lines:      vec![7, 8],
+			suggestion: Some(text.to_string(),),
+		},
+		ParseError::LocalizedNumber {
+			key:        text.to_string(),
+			value:      text.to_string(),
+			ty:         SingleValueDiscriminants::Float,
+			line:       17,
+			separator:  Separator::DecimalComma,
+			suggestion: text.to_string(),
+		},
+		ParseError::InvalidDeclaration {
+			key:         text.to_string(),
+			declaration: text.to_string(),
+			line:        9,
+		},
+		ParseError::UnknownType {
+			key:  text.to_string(),
+			name: text.to_string(),
+			line: 18,
+		},
+		ParseError::UnterminatedString { line: 16, },
+		ParseError::InvalidSectionHeader { line: 21, },
+		ParseError::TrailingCharacters {
+			line:   10,
+			column: 3,
+			text:   text.to_string(),
+		},
+		ParseError::ResolverFailed {
+			key:      text.to_string(),
+			resolver: text.to_string(),
+			reason:   text.to_string(),
+		},
+		ParseError::Unresolved { keys: vec![text.to_string()], },
+		ParseError::MissingKey { keys: vec![text.to_string()], },
+		ParseError::MigrationConflict {
+			old_key:  text.to_string(),
+			old_line: 13,
+			new_key:  text.to_string(),
+			new_line: 14,
+		},
+		ParseError::InvalidMigration { rule: text.to_string(), line: 15, },
+		ParseError::DuplicateKey {
+			key:         text.to_string(),
+			first_line:  11,
+			second_line: 12,
+		},
+		ParseError::CircularInclude { path: text.into(), line: 19, },
+		ParseError::InFile {
+			path:  text.into(),
+			error: Box::new(ParseError::EmptyKey { line: 20, column: 1, },),
+		},
+		ParseError::MergeConflict { key: text.to_string(), },
+		ParseError::MountConflict {
+			plugin:       text.to_string(),
+			prefix:       text.to_string(),
+			other:        text.to_string(),
+			other_prefix: text.to_string(),
+		},
+		ParseError::OutOfRange {
+			key:   text.to_string(),
+			value: text.to_string(),
+			ty:    SingleValueDiscriminants::Long,
+			min:   Some(text.to_string(),),
+			max:   Some(text.to_string(),),
+			line:  1,
+		},
+		ParseError::InvalidEnumValue {
+			key:     text.to_string(),
+			value:   text.to_string(),
+			allowed: vec![text.to_string()],
+			line:    1,
+		},
+		ParseError::UnmetPathRequirement {
+			key:         text.to_string(),
+			path:        PathBuf::from(text,),
+			requirement: PathRequirement::IsFile,
+		},
+		ParseError::Unserializable {
+			key:    text.to_string(),
+			reason: text.to_string(),
+		},
+		ParseError::NullValue { key: text.to_string(), },
+		ParseError::ArityMismatch {
+			key:      text.to_string(),
+			expected: 2,
+			found:    3,
+			line:     22,
+		},
+		ParseError::NotRepeatable { key: text.to_string(), line: 23, },
+	];
+
+	// fails to compile once a variant is added, so it gets a representative
+	// above
+	for err in &errors {
+		match err {
+			ParseError::Io(_,)
+			| ParseError::MissingDelimiter { .. }
+			| ParseError::EmptyKey { .. }
+			| ParseError::EmptyValue { .. }
+			| ParseError::InvalidKeySegment { .. }
+			| ParseError::ConflictingTypes { .. }
+			| ParseError::InvalidValue { .. }
+			| ParseError::UnknownKey { .. }
+			| ParseError::LocalizedNumber { .. }
+			| ParseError::InvalidDeclaration { .. }
+			| ParseError::UnknownType { .. }
+			| ParseError::UnterminatedString { .. }
+			| ParseError::InvalidSectionHeader { .. }
+			| ParseError::TrailingCharacters { .. }
+			| ParseError::ResolverFailed { .. }
+			| ParseError::Unresolved { .. }
+			| ParseError::MissingKey { .. }
+			| ParseError::MigrationConflict { .. }
+			| ParseError::InvalidMigration { .. }
+			| ParseError::DuplicateKey { .. }
+			| ParseError::CircularInclude { .. }
+			| ParseError::InFile { .. }
+			| ParseError::MergeConflict { .. }
+			| ParseError::MountConflict { .. }
+			| ParseError::UnmetPathRequirement { .. }
+			| ParseError::Unserializable { .. }
+			| ParseError::NullValue { .. }
+			| ParseError::ArityMismatch { .. }
+			| ParseError::NotRepeatable { .. }
+			| ParseError::OutOfRange { .. }
+			| ParseError::InvalidEnumValue { .. } => {},
+		}
+	}
+	errors
+}
+
+#[test]
+fn plain_display_never_spans_lines() {
+	for err in every_variant() {
+		let rendered = format!("{err}");
+		assert!(
+			!rendered.contains(['\n', '\r']),
+			"{err:?} rendered {rendered:?}"
+		);
+	}
+}
+
+#[test]
+fn every_variant_has_its_kind_code_line_and_key() {
+	use ErrorKind::*;
+	let text = "first\nsecond\r\n";
+	let expected = [
+		(Io, "E001", None, false,),
+		(Syntax, "E002", Some(1,), false,),
+		(Syntax, "E003", Some(2,), false,),
+		(Syntax, "E004", Some(3,), false,),
+		(Syntax, "E005", Some(4,), false,),
+		(Conflict, "E006", Some(5,), true,),
+		(Type, "E007", Some(6,), true,),
+		(Schema, "E008", Some(7,), true,),
+		(Type, "E009", Some(17,), true,),
+		(Schema, "E012", Some(9,), true,),
+		(Schema, "E013", Some(18,), true,),
+		(Syntax, "E014", Some(16,), false,),
+		(Syntax, "E015", Some(21,), false,),
+		(Syntax, "E016", Some(10,), false,),
+		(Resolve, "E017", None, true,),
+		(Resolve, "E018", None, false,),
+		(Missing, "E019", None, false,),
+		(Conflict, "E020", Some(13,), true,),
+		(Syntax, "E021", Some(15,), false,),
+		(Conflict, "E022", Some(11,), true,),
+		(Syntax, "E023", Some(19,), false,),
+		(Syntax, "E003", None, false,),
+		(Conflict, "E024", None, true,),
+		(Conflict, "E025", None, false,),
+		(Type, "E010", Some(1,), true,),
+		(Type, "E011", Some(1,), true,),
+		(Type, "E026", None, true,),
+		(Type, "E027", None, true,),
+		(Type, "E028", None, true,),
+		(Type, "E029", Some(22,), true,),
+		(Schema, "E030", Some(23,), true,),
+	];
+
+	let errors = every_variant();
+	assert_eq!(errors.len(), expected.len());
+	for (err, (kind, code, line, has_key,),) in errors.iter().zip(expected,) {
+		assert_eq!(err.kind(), kind, "{err:?}");
+		assert_eq!(err.code(), code, "{err:?}");
+		assert_eq!(err.line(), line, "{err:?}");
+		assert_eq!(err.key(), has_key.then_some(text), "{err:?}");
+		assert!(err.to_string().starts_with(&format!("[{code}] ")), "{err}");
+	}
+}
+
+#[test]
+fn codes_differ_between_variants() {
+	let mut codes: Vec<_,> = every_variant()
+		.iter()
+		.filter(|err| !matches!(err, ParseError::InFile { .. }),)
+		.map(ParseError::code,)
+		.collect();
+	let count = codes.len();
+	codes.sort_unstable();
+	codes.dedup();
+	assert_eq!(codes.len(), count);
+}
+
+#[test]
+fn in_file_errors_take_the_key_and_code_of_the_error_inside() {
+	let err = ParseError::InFile {
+		path:  "etc/b.conf".into(),
+		error: Box::new(ParseError::UnknownKey {
+			key:        "port".to_string(),
+			lines:      vec![4, 2],
+			suggestion: None,
+		},),
+	};
+	assert_eq!(err.kind(), ErrorKind::Schema);
+	assert_eq!(err.code(), "E008");
+	assert_eq!(err.key(), Some("port"));
+	assert_eq!(err.line(), None);
+	assert!(err.to_string().starts_with("[E008] unknown key 'port'"));
+}
+
+#[test]
+fn alternate_display_starts_with_plain_headline() {
+	for err in every_variant() {
+		let verbose = format!("{err:#}");
+		let plain =
+			format!("{err}").replace("\\r", "\r",).replace("\\n", "\n",);
+		assert!(verbose.starts_with(&plain), "{verbose:?}");
+		assert!(verbose.len() > plain.len(), "{err:?} has no details");
+	}
+}
+
+#[test]
+fn render_with_source_underlines_invalid_value() -> Result<(), ParseError,> {
+	let schema = schema::parse_str("port -> Integer\nhost -> String\n",)?;
+	let source = "host = a\nport = eighty # http\n";
+	let err = conf::parse_str(source, &schema,).unwrap_err();
+	assert_eq!(
+		err.render_with_source(source),
+		"[E007] invalid value 'eighty' while expecting Integer for 'port' on \
+		 line 2, column 8\n  |\n2 | port = eighty # http\n  |        ^^^^^^"
+	);
+	Ok((),)
+}
+
+#[test]
+fn render_with_source_underlines_line_missing_its_delimiter() {
+	let source = "a -> Integer\n\t\tport 80\n";
+	let err = schema::parse_str(source,).unwrap_err();
+	assert_eq!(
+		err.render_with_source(source),
+		"[E002] missing delimiter on line 2, column 3; expected '->'\n  |\n2 \
+		 | \t\tport 80\n  | \t\t^^^^^^^"
+	);
+}
+
+#[test]
+fn render_with_source_copes_with_lines_it_cannot_point_into() {
+	let err = ParseError::EmptyValue { line: 1, column: 40, };
+	assert_eq!(
+		err.render_with_source("key ="),
+		"[E004] empty value on line 1, column 40\n  |\n1 | key =\n  |      ^"
+	);
+
+	for err in [
+		ParseError::EmptyValue { line: 3, column: 1, },
+		ParseError::ConflictingTypes {
+			key:    "port".to_string(),
+			line:   0,
+			column: 0,
+			origin: None,
+		},
+		ParseError::MissingKey { keys: vec!["port".to_string()], },
+	] {
+		assert_eq!(err.render_with_source("key =\n"), err.to_string());
+	}
+}
+
+#[test]
+fn errors_compare_by_variant_and_fields() {
+	let empty = ParseError::EmptyValue { line: 1, column: 5, };
+	assert_eq!(empty, ParseError::EmptyValue { line: 1, column: 5, });
+	assert_ne!(empty, ParseError::EmptyValue { line: 2, column: 5, });
+	assert_ne!(empty, ParseError::EmptyKey { line: 1, column: 5, });
+
+	let in_file = |line| ParseError::InFile {
+		path:  "a.conf".into(),
+		error: Box::new(ParseError::EmptyValue { line, column: 5, },),
+	};
+	assert_eq!(in_file(1), in_file(1));
+	assert_ne!(in_file(1), in_file(2));
+	assert_ne!(in_file(1), empty);
+}
+
+#[test]
+fn io_errors_compare_by_kind_and_message() {
+	let io = |kind, message: &str| {
+		ParseError::from(std::io::Error::new(kind, message,),)
+	};
+	assert_eq!(
+		io(std::io::ErrorKind::NotFound, "gone"),
+		io(std::io::ErrorKind::NotFound, "gone")
+	);
+	assert_ne!(
+		io(std::io::ErrorKind::NotFound, "gone"),
+		io(std::io::ErrorKind::NotFound, "missing")
+	);
+	assert_ne!(
+		io(std::io::ErrorKind::NotFound, "gone"),
+		io(std::io::ErrorKind::PermissionDenied, "gone")
+	);
+}
+
+#[test]
+fn predicates_name_the_variant() {
+	let err = ParseError::UnknownKey {
+		key:        "port".to_string(),
+		lines:      vec![1],
+		suggestion: None,
+	};
+	assert!(err.is_unknown_key());
+	assert!(!err.is_io());
+	assert!(ParseError::from(std::io::Error::other("boom"),).is_io());
+
+	let in_file =
+		ParseError::InFile { path: "a.conf".into(), error: Box::new(err,), };
+	assert!(in_file.is_in_file());
+	assert!(!in_file.is_unknown_key());
+}
@@ -1,8 +1,12 @@
+use dot_conf_parser::error::PRslt;
 use dot_conf_parser::error::ParseError;
 use dot_conf_parser::parser::conf::SingleValueDiscriminants;
 use dot_conf_parser::parser::conf::Value;
+use dot_conf_parser::parser::conf::{self};
+use dot_conf_parser::parser::schema::SchemaLeaf;
 use dot_conf_parser::parser::schema::SchemaValue;
 use dot_conf_parser::parser::schema::{self};
+use proptest::prelude::*;
 
 #[test]
 fn schema_parses_collection_values() {
@@ -10,7 +14,10 @@ fn schema_parses_collection_values() {
 		schema::parse_str("limits -> Integer, Bool\n",).expect("schema parse",);
 
 	match schema.get("limits",).expect("missing limits entry",) {
-		SchemaValue::Scalar(Value::Collection(items,),) => {
+		SchemaValue::Scalar(SchemaLeaf {
+			ty: Value::Collection(items,),
+			..
+		},) => {
 			assert_eq!(
 				items,
 				&vec![
@@ -31,7 +38,9 @@ fn schema_strips_inline_comments() {
 	.expect("schema parse",);
 
 	match schema.get("log.level",).expect("missing log.level entry",) {
-		SchemaValue::Scalar(Value::Single(kind,),) => {
+		SchemaValue::Scalar(SchemaLeaf {
+			ty: Value::Single(kind,), ..
+		},) => {
 			assert_eq!(kind, &SingleValueDiscriminants::String);
 		},
 		other => panic!("expected single value schema, got {other:?}"),
@@ -43,10 +52,15 @@ fn schema_reports_missing_delimiter() {
 	let err = schema::parse_str("log.level String\n",)
 		.expect_err("expected delimiter error",);
 
-	match err {
-		ParseError::MissingDelimiter { line, } => assert_eq!(line, 1),
-		other => panic!("unexpected error: {other}"),
-	}
+	assert_eq!(
+		err,
+		ParseError::MissingDelimiter {
+			line:     1,
+			column:   1,
+			expected: vec!["->".to_string()],
+		}
+	);
+	assert!(err.is_missing_delimiter());
 }
 
 #[test]
@@ -55,7 +69,7 @@ fn schema_reports_empty_key() {
 		.expect_err("expected empty key error",);
 
 	match err {
-		ParseError::EmptyKey { line, } => assert_eq!(line, 1),
+		ParseError::EmptyKey { line, .. } => assert_eq!(line, 1),
 		other => panic!("unexpected error: {other}"),
 	}
 }
@@ -66,7 +80,7 @@ fn schema_reports_empty_value() {
 		.expect_err("expected empty value error",);
 
 	match err {
-		ParseError::EmptyValue { line, } => assert_eq!(line, 1),
+		ParseError::EmptyValue { line, .. } => assert_eq!(line, 1),
 		other => panic!("unexpected error: {other}"),
 	}
 }
@@ -77,7 +91,7 @@ fn schema_reports_invalid_segment() {
 		.expect_err("expected invalid segment error",);
 
 	match err {
-		ParseError::InvalidKeySegment { segment, line, } => {
+		ParseError::InvalidKeySegment { segment, line, .. } => {
 			assert!(segment.is_empty());
 			assert_eq!(line, 1);
 		},
@@ -136,12 +150,478 @@ fn schema_parses_from_file() {
 #[test]
 fn schema_rejects_unknown_value_type() {
 	let err = schema::parse_str("feature.flag -> Unknown\n",)
-		.expect_err("expected invalid value error",);
+		.expect_err("expected unknown type error",);
 
 	match err {
-		ParseError::InvalidValue { ty, .. } => {
-			assert_eq!(ty.to_string(), "Bool");
+		ParseError::UnknownType { key, name, line, } => {
+			assert_eq!(key, "feature.flag");
+			assert_eq!(name, "Unknown");
+			assert_eq!(line, 1);
 		},
 		other => panic!("unexpected error: {other}"),
 	}
 }
+
+#[test]
+fn schema_reports_unknown_element_type_of_collection() {
+	let err = schema::parse_str(
+		"retry.count -> Integer\nlimits -> Integer, Bogus\n",
+	)
+	.expect_err("expected unknown type error",);
+
+	assert_eq!(
+		err,
+		ParseError::UnknownType {
+			key:  "limits".to_string(),
+			name: "Bogus".to_string(),
+			line: 2,
+		}
+	);
+	assert!(
+		err.to_string().ends_with(
+			"; valid types are: String, Bool, Integer, UInt, Long, Float, \
+			 Secret, Duration, Ip, SocketAddr, Path"
+		),
+		"{err}"
+	);
+}
+
+#[test]
+fn forward_compat_keeps_declarations_of_newer_versions() -> PRslt<(),> {
+	let input =
+		"price -> Decimal(2)\nport -> Integer\nretries -> Integer where > 0?\n";
+
+	match schema::parse_str(input,) {
+		Err(ParseError::UnknownType { key, name, line, },) => {
+			assert_eq!(
+				(key.as_str(), name.as_str(), line,),
+				("price", "Decimal(2)", 1,)
+			);
+		},
+		other => panic!("unexpected result: {other:?}"),
+	}
+
+	let (schema, warnings,) = schema::parse_str_forward_compat(input,)?;
+	assert_eq!(
+		warnings.iter().map(ToString::to_string,).collect::<Vec<_,>>(),
+		vec![
+			"'Decimal(2)' for 'price' on line 1 is not understood, any value \
+			 is accepted",
+			"'Integer where > 0?' for 'retries' on line 3 is not understood, \
+			 any value is accepted",
+		]
+	);
+	match schema.get("retries",) {
+		Some(SchemaValue::Scalar(leaf,),) => {
+			assert_eq!(
+				leaf.unrecognized.as_deref(),
+				Some("Integer where > 0?")
+			);
+			assert!(leaf.optional);
+		},
+		other => panic!("unexpected value: {other:?}"),
+	}
+
+	let conf = conf::parse_str("price = 12.50 EUR\nport = 80\n", &schema,)?;
+	assert_eq!(conf.get_str("price"), Some("12.50 EUR"));
+	assert_eq!(conf.get_int("port"), Some(80));
+	Ok((),)
+}
+
+fn scratch_dir(name: &str,) -> std::path::PathBuf {
+	let mut dir = std::env::temp_dir();
+	dir.push(format!(
+		"{name}_{}_{}",
+		std::process::id(),
+		std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.expect("time")
+			.as_nanos()
+	),);
+	std::fs::create_dir_all(&dir,).expect("scratch dir",);
+	dir
+}
+
+#[test]
+fn schema_files_merge_into_one_tree() -> PRslt<(),> {
+	let dir = scratch_dir("schema_files",);
+	let network = dir.join("network.schema",);
+	let logging = dir.join("logging.schema",);
+	let app = dir.join("app.schema",);
+	std::fs::write(&network, "net.port -> Integer\nlog.level -> String\n",)
+		.expect("write schema",);
+	std::fs::write(&logging, "log.level -> String\nlog.file -> Path?\n",)
+		.expect("write schema",);
+	std::fs::write(&app, "net.host -> String\nname -> String\n",)
+		.expect("write schema",);
+
+	let schema = schema::parse_files(&[&network, &logging, &app,],)?;
+	assert_eq!(
+		schema.keys_flat(),
+		["log.file", "log.level", "name", "net.host", "net.port"]
+	);
+	let Some(SchemaValue::Map(net,),) = schema.get("net",) else {
+		panic!("net should be a section");
+	};
+	assert_eq!(net.len(), 2);
+
+	std::fs::write(&app, "net.port -> String\n",).expect("write schema",);
+	let err = schema::parse_files(&[&network, &logging, &app,],).unwrap_err();
+	match &err {
+		ParseError::ConflictingTypes {
+			key,
+			origin: Some((first, second,),),
+			..
+		} => {
+			assert_eq!(key, "net.port");
+			assert_eq!((first, second), (&network, &app));
+		},
+		other => panic!("unexpected error: {other:?}"),
+	}
+	assert!(err.to_string().contains("app.schema"));
+
+	std::fs::remove_dir_all(dir,).expect("cleanup",);
+	Ok((),)
+}
+
+#[test]
+fn schema_sections_win_over_top_level_keys_holding_dots() -> PRslt<(),> {
+	let mut schema = schema::parse_str("a.b.c -> Integer\n",)?;
+	let string =
+		schema::parse_str("s -> String\n",)?.get("s",).cloned().unwrap();
+	schema.insert("a.b".to_string(), string.clone(),);
+	schema.insert(
+		"x.y".to_string(),
+		SchemaValue::Map([("z".to_string(), string,),].into(),),
+	);
+
+	assert_eq!(
+		schema.type_of("a.b.c"),
+		Some(&Value::Single(SingleValueDiscriminants::Integer,),)
+	);
+	assert!(matches!(schema.get("a.b"), Some(SchemaValue::Map(_))));
+	assert_eq!(
+		schema.type_of("x.y.z"),
+		Some(&Value::Single(SingleValueDiscriminants::String,),)
+	);
+	assert_eq!(schema.get("x"), None);
+	Ok((),)
+}
+
+#[test]
+fn schema_merge_rejects_a_leaf_meeting_a_section() -> PRslt<(),> {
+	let mut schema = schema::parse_str("net -> String\n",)?;
+	let before = schema.clone();
+	let err = schema
+		.merge(schema::parse_str("net.port -> Integer\n",)?,)
+		.unwrap_err();
+	assert!(matches!(
+		err,
+		ParseError::ConflictingTypes { ref key, origin: None, .. } if key == "net"
+	));
+	assert_eq!(schema, before);
+	Ok((),)
+}
+
+#[test]
+fn cached_schema_is_rebuilt_when_source_changes() {
+	let dir = scratch_dir("schema_cache",);
+	let schema_path = dir.join("app.schema",);
+	let cache_dir = dir.join("cache",);
+
+	std::fs::write(&schema_path, "port -> Integer\n",).expect("write schema",);
+	let first = schema::parse_file_cached(&schema_path, &cache_dir,)
+		.expect("first load",);
+	assert!(first.get("port").is_some());
+	assert_eq!(std::fs::read_dir(&cache_dir,).expect("cache dir",).count(), 1);
+
+	// served from the cache while the source is unchanged
+	let second = schema::parse_file_cached(&schema_path, &cache_dir,)
+		.expect("cached load",);
+	assert_eq!(*second, *first);
+
+	std::fs::write(&schema_path, "host -> String\n",).expect("write schema",);
+	let third = schema::parse_file_cached(&schema_path, &cache_dir,)
+		.expect("stale load",);
+	assert!(third.get("port").is_none());
+	assert!(third.get("host").is_some());
+
+	std::fs::remove_dir_all(dir,).expect("cleanup",);
+}
+
+#[test]
+fn corrupt_cache_falls_back_to_parsing() {
+	let dir = scratch_dir("schema_cache_corrupt",);
+	let schema_path = dir.join("app.schema",);
+	let cache_dir = dir.join("cache",);
+
+	std::fs::write(&schema_path, "port -> Integer\n",).expect("write schema",);
+	schema::parse_file_cached(&schema_path, &cache_dir,).expect("first load",);
+	for entry in std::fs::read_dir(&cache_dir,).expect("cache dir",) {
+		let path = entry.expect("cache entry",).path();
+		let mut bytes = std::fs::read(&path,).expect("read cache",);
+		bytes.truncate(bytes.len() / 2,);
+		std::fs::write(path, bytes,).expect("corrupt cache",);
+	}
+
+	let schema = schema::parse_file_cached(&schema_path, &cache_dir,)
+		.expect("fallback load",);
+	assert!(schema.get("port").is_some());
+
+	std::fs::remove_dir_all(dir,).expect("cleanup",);
+}
+
+#[test]
+fn all_errors_reports_every_bad_declaration() {
+	let input = "port -> Integer\nhost -> Strnig\nlimits -> (lo: Integer, \
+	             Integer)\nport -> String\ntimeout Integer\n";
+	let errors = schema::parse_str_all_errors(input,).unwrap_err();
+
+	assert_eq!(
+		errors.iter().map(ParseError::line,).collect::<Vec<_,>>(),
+		[Some(1), Some(2), Some(3), Some(5)],
+		"{errors:?}"
+	);
+	assert!(matches!(
+		errors[0],
+		ParseError::DuplicateKey { first_line: 1, second_line: 4, .. }
+	));
+	assert!(matches!(errors[1], ParseError::UnknownType { .. }));
+	assert!(matches!(errors[2], ParseError::InvalidDeclaration { .. }));
+	assert!(matches!(errors[3], ParseError::MissingDelimiter { line: 5, .. }));
+	assert!(schema::parse_str_all_errors("port -> Integer\n").is_ok());
+}
+
+#[test]
+fn section_headers_prefix_the_declarations_below_them() -> PRslt<(),> {
+	let sectioned = schema::parse_str(
+		"debug -> Bool\n[server.tls]\ncert -> String\nkey.path -> String\n",
+	)?;
+	let dotted = schema::parse_str(
+		"debug -> Bool\nserver.tls.cert -> String\nserver.tls.key.path -> \
+		 String\n",
+	)?;
+	assert_eq!(sectioned, dotted);
+	assert!(matches!(
+		schema::parse_str("[server\nport -> Integer\n"),
+		Err(ParseError::InvalidSectionHeader { line: 1, })
+	));
+	Ok((),)
+}
+
+#[test]
+fn declarations_continue_after_a_trailing_backslash() -> PRslt<(),> {
+	assert_eq!(
+		schema::parse_str("port -> \\\n  Integer\n")?,
+		schema::parse_str("port -> Integer\n")?
+	);
+	Ok((),)
+}
+
+#[test]
+fn parse_str_with_reads_declarations_as_options_ask() -> PRslt<(),> {
+	let options = conf::ParseOptions::default().comment_chars(['%',],);
+	let schema = schema::parse_str_with(
+		"% port -> Float\nport -> Integer % net\n",
+		&options,
+	)?;
+	assert_eq!(schema, schema::parse_str("port -> Integer\n")?);
+	Ok((),)
+}
+
+#[test]
+fn schema_lists_its_keys_and_their_types() -> PRslt<(),> {
+	let schema = schema::parse_str(
+		"server.port -> Integer\nserver.tls.ciphers -> [String]\nlimits -> \
+		 Integer, Integer\ndebug -> Bool\n",
+	)?;
+	assert_eq!(
+		schema.keys_flat(),
+		["debug", "limits", "server.port", "server.tls.ciphers"]
+	);
+	assert_eq!(
+		schema.type_of("server.port"),
+		Some(&Value::Single(SingleValueDiscriminants::Integer))
+	);
+	assert_eq!(
+		schema.type_of("server.tls.ciphers"),
+		Some(&Value::Collection(vec![SingleValueDiscriminants::String]))
+	);
+	assert_eq!(
+		schema.type_of("limits"),
+		Some(&Value::Collection(vec![
+			SingleValueDiscriminants::Integer,
+			SingleValueDiscriminants::Integer
+		]))
+	);
+	assert_eq!(schema.type_of("server.tls"), None);
+	assert_eq!(schema.type_of("server.host"), None);
+
+	for path in ["server", "server.tls", "server.tls.ciphers", "debug",] {
+		assert!(schema.contains_path(path), "{path}");
+	}
+	for path in ["serv", "server.tls.ciphers.x", "debug.x", "",] {
+		assert!(!schema.contains_path(path), "{path}");
+	}
+	Ok((),)
+}
+
+#[test]
+fn schema_to_string_declares_every_leaf() -> PRslt<(),> {
+	let text = "server.port -> @Integer?\nserver.tls.ciphers -> [String] \
+	            (repeatable)\nlimits -> (min: Integer, max: Integer)\npair -> \
+	            Bool, Float\n";
+	let schema = schema::parse_str(text,)?;
+
+	assert_eq!(
+		schema::to_string(&schema),
+		"limits -> (min: Integer, max: Integer)\npair -> Bool, \
+		 Float\nserver.port -> @Integer?\nserver.tls.ciphers -> [String] \
+		 (repeatable)\n"
+	);
+	Ok((),)
+}
+
+#[test]
+fn schema_write_file_reads_back() -> PRslt<(),> {
+	let schema =
+		schema::parse_str("app.name -> String\napp.port -> Integer\n",)?;
+	let path = std::env::temp_dir().join(format!(
+		"dot_conf_parser_schema_write_{}.schema",
+		std::process::id()
+	),);
+
+	schema::write_file(&schema, &path,)?;
+	let read_back = schema::parse_file(&path,);
+	std::fs::remove_file(&path,)?;
+
+	assert_eq!(read_back?, schema);
+	Ok((),)
+}
+
+fn declaration_line() -> impl Strategy<Value = String,> {
+	let kind = prop::sample::select(vec![
+		"String", "Bool", "Integer", "Float", "Secret", "Duration",
+	],);
+	let shape =
+		(kind.clone(), kind, 0..5usize,).prop_map(
+			|(a, b, shape,)| match shape {
+				0 => a.to_string(),
+				1 => format!("[{a}]"),
+				2 => format!("[{a}] (repeatable)"),
+				3 => format!("{a}, {b}"),
+				_ => format!("(lo: {a}, hi: {b})"),
+			},
+		);
+	(
+		prop::string::string_regex("[a-d]{1,3}(\\.[a-d]{1,3})?",).unwrap(),
+		any::<bool,>(),
+		shape,
+		any::<bool,>(),
+	)
+		.prop_map(|(key, resolvable, shape, optional,)| {
+			let resolvable = if resolvable { "@" } else { "" };
+			let optional = if optional { "?" } else { "" };
+			format!("{key} -> {resolvable}{shape}{optional}\n")
+		},)
+}
+
+proptest! {
+	#[test]
+	fn schema_to_string_round_trips(lines in prop::collection::vec(declaration_line(), 1..6)) {
+		let parsed = schema::parse_str(&lines.concat());
+		prop_assume!(parsed.is_ok());
+		let schema = parsed.unwrap();
+
+		let text = schema::to_string(&schema);
+		prop_assert_eq!(schema::parse_str(&text).expect("reparse"), schema);
+	}
+}
+
+#[test]
+fn enum_declarations_list_their_literals() -> PRslt<(),> {
+	let schema = schema::parse_str(
+		"log.level -> Enum( debug ,info, warn , error )?\n",
+	)?;
+
+	match schema.get("log.level",).expect("missing log.level entry",) {
+		SchemaValue::Scalar(leaf,) => {
+			assert_eq!(
+				leaf.ty,
+				Value::Single(SingleValueDiscriminants::String)
+			);
+			assert_eq!(
+				leaf.allowed.as_deref(),
+				Some(&["debug", "info", "warn", "error"].map(String::from)[..])
+			);
+			assert!(leaf.optional);
+		},
+		other => panic!("expected a leaf, got {other:?}"),
+	}
+	assert_eq!(
+		schema::to_string(&schema),
+		"log.level -> Enum(debug, info, warn, error)?\n"
+	);
+	assert_eq!(schema::parse_str(&schema::to_string(&schema),)?, schema);
+	Ok((),)
+}
+
+#[test]
+fn enum_declarations_reject_empty_and_repeated_literals() {
+	for declaration in [
+		"Enum()",
+		"Enum( )",
+		"Enum(debug, , info)",
+		"Enum(debug, info, debug)",
+	] {
+		let err = schema::parse_str(&format!("log.level -> {declaration}\n"),)
+			.unwrap_err();
+		assert!(
+			matches!(
+				&err,
+				ParseError::InvalidDeclaration { key, line: 1, .. }
+					if key == "log.level"
+			),
+			"{declaration}: {err:?}"
+		);
+	}
+}
+
+#[test]
+fn range_declarations_read_back_as_written() -> PRslt<(),> {
+	let text = "limit -> Long[-5..]\nlimits -> Integer[1..10], \
+	            Integer[1..10]\nport -> Integer[1..65535]\nratios -> \
+	            [Float[0..1.5]]\nspan -> (lo: Long[0..], hi: Long)\n";
+	let schema = schema::parse_str(text,)?;
+
+	assert_eq!(schema::to_string(&schema), text);
+	assert_eq!(
+		schema.type_of("port"),
+		Some(&Value::Single(SingleValueDiscriminants::Integer))
+	);
+	Ok((),)
+}
+
+#[test]
+fn malformed_ranges_are_rejected_at_schema_parse_time() {
+	for declaration in [
+		"Integer[10..5]",
+		"Float[1..0.5]",
+		"Integer[..]",
+		"Integer[1-5]",
+		"Integer[a..5]",
+		"String[1..5]",
+		"[Bool[0..1]]",
+	] {
+		let err = schema::parse_str(&format!("port -> {declaration}\n"),)
+			.unwrap_err();
+		assert!(
+			matches!(
+				&err,
+				ParseError::InvalidDeclaration { key, declaration: written, line: 1 }
+					if key == "port" && written == declaration
+			),
+			"{declaration}: {err:?}"
+		);
+	}
+}
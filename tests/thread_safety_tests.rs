@@ -0,0 +1,50 @@
+use dot_conf_parser::error::ParseError;
+use dot_conf_parser::error::Warning;
+use dot_conf_parser::parser::conf::ConfMap;
+use dot_conf_parser::parser::conf::ConfValue;
+use dot_conf_parser::parser::conf::SingleValue;
+use dot_conf_parser::parser::deferred::Resolvers;
+use dot_conf_parser::parser::diff::ConfDiff;
+use dot_conf_parser::parser::migrate::Migrations;
+use dot_conf_parser::parser::mount::SchemaMounts;
+use dot_conf_parser::parser::schema::CompatReport;
+use dot_conf_parser::parser::schema::SchemaMap;
+use dot_conf_parser::parser::schema::SchemaRef;
+use dot_conf_parser::parser::schema::SchemaValue;
+use dot_conf_parser::parser::secret::Secret;
+use dot_conf_parser::parser::source::SourceMap;
+use dot_conf_parser::parser::stats::ParseStats;
+use dot_conf_parser::parser::view::ConfMapRef;
+use dot_conf_parser::show::ShowStyle;
+
+fn assert_send_sync<T: Send + Sync,>() {}
+
+#[test]
+fn parsed_values_are_send_and_sync() {
+	assert_send_sync::<ConfMap,>();
+	assert_send_sync::<ConfValue,>();
+	assert_send_sync::<SingleValue,>();
+	assert_send_sync::<Secret,>();
+	assert_send_sync::<SourceMap,>();
+	assert_send_sync::<ConfMapRef<'static,>,>();
+	assert_send_sync::<ConfDiff,>();
+	assert_send_sync::<ParseStats,>();
+}
+
+#[test]
+fn schemas_and_errors_are_send_and_sync() {
+	assert_send_sync::<SchemaMap,>();
+	assert_send_sync::<SchemaValue,>();
+	assert_send_sync::<SchemaRef,>();
+	assert_send_sync::<CompatReport,>();
+	assert_send_sync::<ParseError,>();
+	assert_send_sync::<Warning,>();
+}
+
+#[test]
+fn extension_points_are_send_and_sync() {
+	assert_send_sync::<Resolvers,>();
+	assert_send_sync::<Migrations,>();
+	assert_send_sync::<SchemaMounts,>();
+	assert_send_sync::<ShowStyle,>();
+}
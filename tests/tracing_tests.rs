@@ -0,0 +1,120 @@
+#![cfg(feature = "tracing")]
+
+use dot_conf_parser::error::PRslt;
+use dot_conf_parser::parser::conf;
+use dot_conf_parser::parser::migrate::Migrations;
+use dot_conf_parser::parser::schema;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tracing::Event;
+use tracing::Metadata;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+
+#[derive(Debug, Default,)]
+struct CapturedEvent {
+	target: String,
+	level:  String,
+	fields: Vec<(String, String,),>,
+}
+
+impl CapturedEvent {
+	fn field(&self, name: &str,) -> Option<&str,> {
+		self.fields.iter().find(|(k, _,)| k == name,).map(|(_, v,)| v.as_str(),)
+	}
+}
+
+impl Visit for CapturedEvent {
+	fn record_debug(&mut self, field: &Field, value: &dyn Debug,) {
+		self.fields.push((field.name().to_string(), format!("{value:?}"),),);
+	}
+}
+
+/// records every event, ignores spans
+#[derive(Clone, Default,)]
+struct Capture(Arc<Mutex<Vec<CapturedEvent,>,>,>,);
+
+impl tracing::Subscriber for Capture {
+	fn enabled(&self, _: &Metadata<'_,>,) -> bool {
+		true
+	}
+
+	fn new_span(&self, _: &span::Attributes<'_,>,) -> span::Id {
+		span::Id::from_u64(1,)
+	}
+
+	fn record(&self, _: &span::Id, _: &span::Record<'_,>,) {}
+
+	fn record_follows_from(&self, _: &span::Id, _: &span::Id,) {}
+
+	fn event(&self, event: &Event<'_,>,) {
+		let mut captured = CapturedEvent {
+			target: event.metadata().target().to_string(),
+			level: event.metadata().level().to_string(),
+			..Default::default()
+		};
+		event.record(&mut captured,);
+		self.0.lock().unwrap().push(captured,);
+	}
+
+	fn enter(&self, _: &span::Id,) {}
+
+	fn exit(&self, _: &span::Id,) {}
+}
+
+#[test]
+fn duplicate_key_emits_override_event() -> PRslt<(),> {
+	let capture = Capture::default();
+	let schema = schema::parse_str("server.port -> Integer\n",)?;
+
+	tracing::subscriber::with_default(capture.clone(), || {
+		conf::parse_str("server.port = 80\nserver.port = 8080\n", schema,)
+	},)?;
+
+	let events = capture.0.lock().unwrap();
+	let overridden = events
+		.iter()
+		.find(|event| event.field("message",) == Some("value overridden",),)
+		.expect("override event",);
+	assert_eq!(overridden.target, "dot_conf_parser");
+	assert_eq!(overridden.level, "INFO");
+	assert_eq!(overridden.field("key"), Some("server.port"));
+	assert_eq!(overridden.field("previous_line"), Some("1"));
+	assert_eq!(overridden.field("line"), Some("2"));
+
+	let accepted = events
+		.iter()
+		.filter(|event| event.field("message",) == Some("entry accepted",),)
+		.count();
+	assert_eq!(accepted, 1);
+
+	Ok((),)
+}
+
+#[test]
+fn every_warning_emits_a_warn_event() -> PRslt<(),> {
+	let capture = Capture::default();
+	let migrations =
+		Migrations::parse_str("rename net.port -> server.port\n",)?;
+
+	tracing::subscriber::with_default(capture.clone(), || {
+		let (schema, _,) = schema::parse_str_with_warnings(
+			"server.port -> Integer\nserver.port -> Integer\n",
+		)?;
+		conf::parse_str_with_migrations("net.port = 80\n", &schema, &migrations,)
+	},)?;
+
+	let events = capture.0.lock().unwrap();
+	let warned = |message| {
+		events.iter().find(|event| {
+			event.level == "WARN" && event.field("message",) == Some(message,)
+		},)
+	};
+	let redundant = warned("redundant declaration",).expect("schema warning",);
+	assert_eq!(redundant.field("second_line"), Some("2"));
+	let migrated = warned("key migrated",).expect("migration warning",);
+	assert_eq!(migrated.field("from"), Some("net.port"));
+	Ok((),)
+}